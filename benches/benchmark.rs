@@ -9,6 +9,18 @@ fn bench_main(c: &mut Criterion) {
                 black_box(stack);
             })
         });
+        g.bench_function("SmallAny New Inline", |b| {
+            b.iter(|| {
+                let small = stack_any::SmallAny::<1>::new(127u8);
+                black_box(small);
+            })
+        });
+        g.bench_function("SmallAny New Boxed", |b| {
+            b.iter(|| {
+                let small = stack_any::SmallAny::<0>::new(127u8);
+                black_box(small);
+            })
+        });
         g.bench_function("Box New", |b| {
             b.iter(|| {
                 let heap: Box<dyn std::any::Any> = Box::new(127u8);
@@ -26,6 +38,13 @@ fn bench_main(c: &mut Criterion) {
                 black_box(stack);
             })
         });
+        g.bench_function("StackAny Mut Unchecked", |b| {
+            b.iter(|| {
+                let mut stack = stack_any::stack_any!(u8, 127u8);
+                *unsafe { stack.downcast_mut_unchecked() } = 63u8;
+                black_box(stack);
+            })
+        });
         g.bench_function("Box Mut", |b| {
             b.iter(|| {
                 let mut heap: Box<dyn std::any::Any> = Box::new(127u8);
@@ -44,6 +63,19 @@ fn bench_main(c: &mut Criterion) {
                 black_box(v);
             })
         });
+        g.bench_function("StackAny Get Unchecked", |b| {
+            b.iter(|| {
+                let v = unsafe { stack.downcast_ref_unchecked::<u8>() };
+                black_box(v);
+            })
+        });
+        let small = stack_any::SmallAny::<1>::new(127u8);
+        g.bench_function("SmallAny Get Inline", |b| {
+            b.iter(|| {
+                let v = small.downcast_ref::<u8>().unwrap();
+                black_box(v);
+            })
+        });
         let heap: Box<dyn std::any::Any> = Box::new(127u8);
         g.bench_function("Box Get", |b| {
             b.iter(|| {
@@ -52,6 +84,111 @@ fn bench_main(c: &mut Criterion) {
             })
         });
     }
+
+    {
+        let mut g = c.benchmark_group("Fn");
+        let offset = black_box(1u32);
+        g.bench_function("StackFn New", |b| {
+            b.iter(|| {
+                let f = stack_any::StackFn::<u32, u32, 4>::new(move |x| x + offset);
+                black_box(f);
+            })
+        });
+        g.bench_function("Box Fn New", |b| {
+            b.iter(|| {
+                let f: Box<dyn Fn(u32) -> u32> = Box::new(move |x| x + offset);
+                let _ = black_box(f);
+            })
+        });
+        let f = stack_any::StackFn::<u32, u32, 4>::new(move |x| x + offset);
+        g.bench_function("StackFn Call", |b| {
+            b.iter(|| {
+                let v = f.call(black_box(127));
+                black_box(v);
+            })
+        });
+        let heap: Box<dyn Fn(u32) -> u32> = Box::new(move |x| x + offset);
+        g.bench_function("Box Fn Call", |b| {
+            b.iter(|| {
+                let v = heap(black_box(127));
+                black_box(v);
+            })
+        });
+    }
+
+    {
+        use std::hash::Hasher;
+
+        let mut g = c.benchmark_group("Hasher");
+        let bytes = black_box(vec![5u8; 4096]);
+        g.bench_function("StackHasher Write", |b| {
+            b.iter(|| {
+                let mut hasher = stack_any::StackHasher::<72>::new(
+                    std::collections::hash_map::DefaultHasher::new(),
+                );
+                for chunk in bytes.chunks(8) {
+                    hasher.write(chunk);
+                }
+                black_box(hasher.finish());
+            })
+        });
+        g.bench_function("Box Hasher Write", |b| {
+            b.iter(|| {
+                let mut hasher: Box<dyn Hasher> =
+                    Box::new(std::collections::hash_map::DefaultHasher::new());
+                for chunk in bytes.chunks(8) {
+                    hasher.write(chunk);
+                }
+                black_box(hasher.finish());
+            })
+        });
+        g.bench_function("Concrete Hasher Write", |b| {
+            b.iter(|| {
+                let mut hasher = std::collections::hash_map::DefaultHasher::new();
+                for chunk in bytes.chunks(8) {
+                    hasher.write(chunk);
+                }
+                black_box(hasher.finish());
+            })
+        });
+    }
+
+    {
+        let mut g = c.benchmark_group("Drop");
+        g.bench_function("StackAny Drop u64", |b| {
+            b.iter_batched(
+                || -> Vec<stack_any::StackAny<8>> {
+                    (0..10_000u64)
+                        .map(|i| stack_any::stack_any!(u64, i))
+                        .collect()
+                },
+                drop,
+                criterion::BatchSize::LargeInput,
+            )
+        });
+        g.bench_function("StackAny Drop String", |b| {
+            b.iter_batched(
+                || -> Vec<stack_any::StackAny<24>> {
+                    (0..10_000)
+                        .map(|_| stack_any::stack_any!(String, String::new()))
+                        .collect()
+                },
+                drop,
+                criterion::BatchSize::LargeInput,
+            )
+        });
+        g.bench_function("Box Drop u64", |b| {
+            b.iter_batched(
+                || -> Vec<Box<dyn std::any::Any>> {
+                    (0..10_000u64)
+                        .map(|i| Box::new(i) as Box<dyn std::any::Any>)
+                        .collect()
+                },
+                drop,
+                criterion::BatchSize::LargeInput,
+            )
+        });
+    }
 }
 
 criterion_group!(benches, bench_main);
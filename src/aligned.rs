@@ -0,0 +1,373 @@
+use crate::vtable::{Capture, Plain};
+use crate::{any_ptr, fits, value_ptr, CapacityError, VTable};
+
+/// A marker that selects an alignment of `A` bytes.
+#[derive(Debug)]
+pub struct Align<const A: usize>;
+
+/// A trait implemented by every [`Align`] whose alignment is supported.
+pub trait Alignment {
+    /// A zero-sized type whose alignment is equal to the selected alignment.
+    type Type: Copy;
+}
+
+macro_rules! impl_alignment {
+    ($($name:ident = $align:literal),*) => {
+        $(
+            #[doc(hidden)]
+            #[derive(Clone, Copy)]
+            #[repr(align($align))]
+            pub struct $name;
+
+            impl Alignment for Align<$align> {
+                type Type = $name;
+            }
+        )*
+    };
+}
+
+impl_alignment!(
+    Align1 = 1,
+    Align2 = 2,
+    Align4 = 4,
+    Align8 = 8,
+    Align16 = 16,
+    Align32 = 32,
+    Align64 = 64,
+    Align128 = 128,
+    Align256 = 256,
+    Align512 = 512,
+    Align1024 = 1024,
+    Align2048 = 2048,
+    Align4096 = 4096
+);
+
+/// Byte storage whose alignment is selected by `A`.
+#[repr(C)]
+struct AlignedBuffer<const N: usize, const A: usize>
+where
+    Align<A>: Alignment,
+{
+    _align: [<Align<A> as Alignment>::Type; 0],
+    bytes: [core::mem::MaybeUninit<u8>; N],
+}
+
+/// A convertible type that owns a stack allocation of `N` size aligned to `ALIGN` bytes.
+///
+/// # Examples
+///
+/// ```
+/// #[repr(align(32))]
+/// struct Simd([f32; 8]);
+///
+/// let stacks = [
+///     stack_any::StackAnyAligned::<32, 32>::try_new(Simd([1.0; 8])).unwrap(),
+///     stack_any::StackAnyAligned::<32, 32>::try_new(1.0f32).unwrap(),
+/// ];
+///
+/// assert_eq!(stacks[0].downcast_ref::<Simd>().unwrap().0, [1.0; 8]);
+/// assert_eq!(stacks[1].downcast_ref::<f32>(), Some(&1.0));
+/// ```
+///
+/// Since any type can be placed into it, `StackAnyAligned` is neither `Send` nor `Sync`.
+///
+/// ```compile_fail
+/// fn assert_send<T: Send>() {}
+/// assert_send::<stack_any::StackAnyAligned<8, 8>>();
+/// ```
+///
+/// ```compile_fail
+/// fn assert_sync<T: Sync>() {}
+/// assert_sync::<stack_any::StackAnyAligned<8, 8>>();
+/// ```
+pub struct StackAnyAligned<const N: usize, const ALIGN: usize>
+where
+    Align<ALIGN>: Alignment,
+{
+    type_id: core::any::TypeId,
+    bytes: AlignedBuffer<N, ALIGN>,
+    vtable: &'static VTable,
+    _marker: core::marker::PhantomData<*mut ()>,
+}
+
+impl<const N: usize, const ALIGN: usize> StackAnyAligned<N, ALIGN>
+where
+    Align<ALIGN>: Alignment,
+{
+    /// Allocates N-size memory aligned to ALIGN bytes on the stack and then places `value` into it.
+    ///
+    /// Whether `T` fits is checked at compile time, so this never fails at runtime.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// #[repr(align(32))]
+    /// struct Simd([f32; 8]);
+    ///
+    /// let simd = stack_any::StackAnyAligned::<32, 32>::new(Simd([1.0; 8]));
+    /// assert!(simd.downcast_ref::<Simd>().is_some());
+    /// ```
+    ///
+    /// ```compile_fail
+    /// #[repr(align(32))]
+    /// struct Simd([f32; 8]);
+    ///
+    /// let simd = stack_any::StackAnyAligned::<32, 16>::new(Simd([1.0; 8]));
+    /// ```
+    pub fn new<T>(value: T) -> Self
+    where
+        T: core::any::Any,
+    {
+        const { assert!(Self::fits::<T>(), "value does not fit in the stack") };
+
+        match Self::try_new_checked(value) {
+            Ok(stack) => stack,
+            Err(_) => unreachable!(),
+        }
+    }
+
+    /// Returns true if a value of `T` can be placed into N-size memory aligned to ALIGN bytes.
+    ///
+    /// As in [`StackAny::fits`](crate::StackAny::fits), zero-sized `T` fits regardless of its alignment.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// #[repr(align(64))]
+    /// struct Marker;
+    ///
+    /// assert!(stack_any::StackAnyAligned::<8, 8>::fits::<u64>());
+    /// assert!(!stack_any::StackAnyAligned::<8, 4>::fits::<u64>());
+    /// assert!(stack_any::StackAnyAligned::<0, 1>::fits::<Marker>());
+    /// assert!(stack_any::StackAny::<0>::fits::<Marker>());
+    ///
+    /// let marker = stack_any::StackAnyAligned::<0, 1>::new(Marker);
+    /// assert!(marker.downcast_ref::<Marker>().is_some());
+    /// ```
+    pub const fn fits<T>() -> bool {
+        fits(
+            core::mem::size_of::<T>(),
+            core::mem::align_of::<T>(),
+            N,
+            ALIGN,
+        )
+    }
+
+    /// Allocates N-size memory aligned to ALIGN bytes on the stack and then places `value` into it.
+    /// Returns None if `T` size is larger than N or `T` alignment is larger than ALIGN.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// #[repr(align(32))]
+    /// struct Simd([f32; 8]);
+    ///
+    /// let simd = stack_any::StackAnyAligned::<32, 32>::try_new(Simd([1.0; 8]));
+    /// assert!(simd.is_some());
+    ///
+    /// let simd = stack_any::StackAnyAligned::<32, 16>::try_new(Simd([1.0; 8]));
+    /// assert!(simd.is_none());
+    /// ```
+    pub fn try_new<T>(value: T) -> Option<Self>
+    where
+        T: core::any::Any,
+    {
+        Self::try_new_checked(value).ok()
+    }
+
+    /// Allocates N-size memory aligned to ALIGN bytes on the stack and then places `value` into it.
+    /// Returns [`CapacityError`] carrying `value` back if `T` size is larger than N
+    /// or `T` alignment is larger than ALIGN.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let err = stack_any::StackAnyAligned::<16, 4>::try_new_checked(5u64).unwrap_err();
+    /// assert_eq!(err.to_string(), "value of type u64 needs alignment 8 but alignment is 4");
+    /// ```
+    pub fn try_new_checked<T>(value: T) -> Result<Self, CapacityError<T>>
+    where
+        T: core::any::Any,
+    {
+        let type_id = core::any::TypeId::of::<T>();
+        let size = core::mem::size_of::<T>();
+
+        if !Self::fits::<T>() {
+            return Err(CapacityError::new(value, N, ALIGN));
+        }
+
+        let mut bytes = AlignedBuffer {
+            _align: [],
+            bytes: [core::mem::MaybeUninit::uninit(); N],
+        };
+
+        let src = &value as *const _ as *const _;
+        let dst = bytes.bytes.as_mut_ptr();
+        unsafe { core::ptr::copy_nonoverlapping(src, dst, size) };
+
+        core::mem::forget(value);
+
+        Ok(Self {
+            type_id,
+            bytes,
+            vtable: <Plain as Capture<T>>::REF,
+            _marker: core::marker::PhantomData,
+        })
+    }
+
+    /// Attempt to return reference to the inner value as a concrete type.
+    /// Returns None if `T` is not equal to contained value type.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let five = stack_any::stack_any_aligned!(i32, 5);
+    /// assert_eq!(five.downcast_ref::<i32>(), Some(&5));
+    /// assert_eq!(five.downcast_ref::<i64>(), None);
+    /// ```
+    pub fn downcast_ref<T>(&self) -> Option<&T>
+    where
+        T: core::any::Any,
+    {
+        if core::any::TypeId::of::<T>() != self.type_id {
+            return None;
+        }
+
+        let ptr = value_ptr::<T>(self.bytes.bytes.as_ptr() as *mut _);
+        Some(unsafe { &*ptr })
+    }
+
+    /// Attempt to return mutable reference to the inner value as a concrete type.
+    /// Returns None if `T` is not equal to contained value type.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut five = stack_any::stack_any_aligned!(i32, 5);
+    /// assert_eq!(five.downcast_mut::<i32>(), Some(&mut 5));
+    /// assert_eq!(five.downcast_mut::<i64>(), None);
+    /// ```
+    pub fn downcast_mut<T>(&mut self) -> Option<&mut T>
+    where
+        T: core::any::Any,
+    {
+        if core::any::TypeId::of::<T>() != self.type_id {
+            return None;
+        }
+
+        let ptr = value_ptr::<T>(self.bytes.bytes.as_mut_ptr());
+        Some(unsafe { &mut *ptr })
+    }
+
+    /// Attempt to downcast the stack to a concrete type.
+    /// Returns the stack back if `T` is not equal to contained value type.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let five = stack_any::stack_any_aligned!(i32, 5);
+    /// assert_eq!(five.downcast::<i32>().ok(), Some(5));
+    /// ```
+    pub fn downcast<T>(self) -> Result<T, Self>
+    where
+        T: core::any::Any,
+    {
+        if core::any::TypeId::of::<T>() != self.type_id {
+            return Err(self);
+        }
+
+        let mut stack = core::mem::ManuallyDrop::new(self);
+        let ptr = value_ptr::<T>(stack.bytes.bytes.as_mut_ptr());
+        Ok(unsafe { core::ptr::read(ptr) })
+    }
+}
+
+/// Formats the metadata of the contained value as `StackAny` does, that is the name of its type
+/// with the `type-name` feature, or its `TypeId` without it, followed by its size.
+///
+/// # Examples
+///
+/// ```
+/// let stack = stack_any::stack_any_aligned!(u64, 5);
+/// let debug = format!("{:?}", stack);
+///
+/// assert!(debug.starts_with("StackAnyAligned { capacity: 8, align: 8, type"));
+/// assert!(debug.ends_with("size: 8 }"));
+/// # #[cfg(feature = "type-name")]
+/// # assert_eq!(debug, "StackAnyAligned { capacity: 8, align: 8, type: \"u64\", size: 8 }");
+/// ```
+impl<const N: usize, const ALIGN: usize> core::fmt::Debug for StackAnyAligned<N, ALIGN>
+where
+    Align<ALIGN>: Alignment,
+{
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let mut debug = f.debug_struct("StackAnyAligned");
+        debug.field("capacity", &N).field("align", &ALIGN);
+        #[cfg(feature = "type-name")]
+        debug.field("type", &self.vtable.type_name());
+        #[cfg(not(feature = "type-name"))]
+        debug.field("type_id", &self.type_id);
+        debug.field("size", &self.vtable.size).finish()
+    }
+}
+
+/// Drops the contained value by the destructor of its type found in the shared [`VTable`],
+/// or skips the call if the type does not [need drop](VTable::needs_drop).
+///
+/// # Examples
+///
+/// ```
+/// use std::rc::Rc;
+///
+/// let counter = Rc::new(());
+///
+/// let stack = stack_any::StackAnyAligned::<8, 8>::new(counter.clone());
+/// assert_eq!(Rc::strong_count(&counter), 2);
+/// drop(stack);
+/// assert_eq!(Rc::strong_count(&counter), 1);
+///
+/// let stack = stack_any::StackAnyAligned::<8, 8>::new(counter.clone());
+/// let taken = stack.downcast::<Rc<()>>().unwrap();
+/// assert_eq!(Rc::strong_count(&counter), 2);
+/// drop(taken);
+/// assert_eq!(Rc::strong_count(&counter), 1);
+///
+/// let five = stack_any::StackAnyAligned::<8, 8>::new(5u64);
+/// drop(five);
+/// ```
+impl<const N: usize, const ALIGN: usize> Drop for StackAnyAligned<N, ALIGN>
+where
+    Align<ALIGN>: Alignment,
+{
+    fn drop(&mut self) {
+        if self.vtable.needs_drop {
+            let ptr = any_ptr(
+                self.vtable.meta,
+                self.vtable.size,
+                self.bytes.bytes.as_mut_ptr(),
+            );
+            unsafe { core::ptr::drop_in_place(ptr) };
+        }
+    }
+}
+
+/// Allocates aligned memory on the stack and then places value based on given type and value.
+///
+/// # Examples
+///
+/// ```
+/// #[repr(align(32))]
+/// struct Simd([f32; 8]);
+///
+/// let simd = stack_any::stack_any_aligned!(Simd, Simd([1.0; 8]));
+/// assert!(simd.downcast_ref::<Simd>().is_some());
+/// ```
+#[macro_export]
+macro_rules! stack_any_aligned {
+    ($type:ty, $init:expr) => {
+        $crate::StackAnyAligned::<
+            { ::core::mem::size_of::<$type>() },
+            { ::core::mem::align_of::<$type>() },
+        >::new::<$type>($init)
+    };
+}
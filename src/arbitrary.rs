@@ -0,0 +1,164 @@
+//! Construction of [`StackAny`] from fuzzer input, enabled by the `arbitrary` feature.
+//!
+//! # Examples
+//!
+//! ```
+//! let mut u = arbitrary::Unstructured::new(&[1, 5, 0, 0, 0]);
+//!
+//! let stack = stack_any::arbitrary::any_of::<(u8, u32, String), 64>(&mut u).unwrap();
+//! assert_eq!(stack.downcast_ref::<u32>(), Some(&5));
+//! ```
+
+use crate::StackAny;
+
+/// A tuple of candidate types, one of which is picked by [`any_of`].
+pub trait Candidates<'a> {
+    /// Picks one of the candidate types that fit in N-size memory,
+    /// and then places an arbitrary value of it into the stack.
+    fn arbitrary_stack_any<const N: usize>(
+        u: &mut ::arbitrary::Unstructured<'a>,
+    ) -> ::arbitrary::Result<StackAny<N>>;
+}
+
+macro_rules! impl_candidates {
+    ($($type:ident),+) => {
+        impl<'a, $($type),+> Candidates<'a> for ($($type,)+)
+        where
+            $($type: ::arbitrary::Arbitrary<'a> + core::any::Any),+
+        {
+            #[allow(unused_assignments)]
+            fn arbitrary_stack_any<const N: usize>(
+                u: &mut ::arbitrary::Unstructured<'a>,
+            ) -> ::arbitrary::Result<StackAny<N>> {
+                let count = 0 $(+ StackAny::<N>::fits::<$type>() as usize)+;
+                if count == 0 {
+                    return Err(::arbitrary::Error::IncorrectFormat);
+                }
+
+                let mut index = u.choose_index(count)?;
+                $(
+                    if StackAny::<N>::fits::<$type>() {
+                        if index == 0 {
+                            let value = <$type as ::arbitrary::Arbitrary>::arbitrary(u)?;
+                            return Ok(StackAny::try_new(value).unwrap());
+                        }
+                        index -= 1;
+                    }
+                )+
+                unreachable!()
+            }
+        }
+    };
+}
+
+impl_candidates!(A);
+impl_candidates!(A, B);
+impl_candidates!(A, B, C);
+impl_candidates!(A, B, C, D);
+impl_candidates!(A, B, C, D, E);
+impl_candidates!(A, B, C, D, E, F);
+impl_candidates!(A, B, C, D, E, F, G);
+impl_candidates!(A, B, C, D, E, F, G, H);
+
+/// Picks one of the types in `C` that fit in N-size memory,
+/// and then places an arbitrary value of it into the stack.
+///
+/// Fails with `IncorrectFormat` if none of the types fit.
+///
+/// # Examples
+///
+/// ```
+/// let data: Vec<u8> = (0..4096u32).map(|i| (i.wrapping_mul(2654435761) >> 13) as u8).collect();
+/// let mut u = arbitrary::Unstructured::new(&data);
+///
+/// for _ in 0..2000 {
+///     let stack = stack_any::arbitrary::any_of::<(u8, u32, String, [u64; 16]), 24>(&mut u).unwrap();
+///
+///     assert!(stack.downcast_ref::<[u64; 16]>().is_none());
+///     if let Some(value) = stack.downcast_ref::<String>().cloned() {
+///         assert_eq!(stack.downcast::<String>().ok(), Some(value));
+///     } else if let Some(&value) = stack.downcast_ref::<u32>() {
+///         assert_eq!(stack.downcast::<u32>().ok(), Some(value));
+///     } else {
+///         assert!(stack.downcast::<u8>().is_ok());
+///     }
+/// }
+///
+/// let mut u = arbitrary::Unstructured::new(&data);
+/// assert!(stack_any::arbitrary::any_of::<([u64; 16],), 24>(&mut u).is_err());
+/// ```
+pub fn any_of<'a, C, const N: usize>(
+    u: &mut ::arbitrary::Unstructured<'a>,
+) -> ::arbitrary::Result<StackAny<N>>
+where
+    C: Candidates<'a>,
+{
+    C::arbitrary_stack_any(u)
+}
+
+/// A [`StackAny`] that implements `Arbitrary` by [`any_of`] the candidate types `C`,
+/// so that it can be used in `#[derive(Arbitrary)]` types.
+///
+/// # Examples
+///
+/// ```
+/// use arbitrary::{Arbitrary, Unstructured};
+/// use stack_any::arbitrary::ArbitraryStackAny;
+///
+/// #[derive(Arbitrary)]
+/// struct Message {
+///     id: u16,
+///     payload: ArbitraryStackAny<(u8, u64, String), 24>,
+/// }
+///
+/// let mut u = Unstructured::new(&[7, 0, 1, 5, 0, 0, 0, 0, 0, 0, 0]);
+/// let message = Message::arbitrary(&mut u).unwrap();
+///
+/// assert_eq!(message.id, 7);
+/// assert_eq!(message.payload.into_inner().downcast_ref::<u64>(), Some(&5));
+/// ```
+pub struct ArbitraryStackAny<C, const N: usize> {
+    stack: StackAny<N>,
+    _marker: core::marker::PhantomData<fn() -> C>,
+}
+
+impl<C, const N: usize> ArbitraryStackAny<C, N> {
+    /// Returns the generated stack.
+    pub fn into_inner(self) -> StackAny<N> {
+        self.stack
+    }
+}
+
+impl<C, const N: usize> core::fmt::Debug for ArbitraryStackAny<C, N> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_tuple("ArbitraryStackAny")
+            .field(&self.stack)
+            .finish()
+    }
+}
+
+impl<C, const N: usize> core::ops::Deref for ArbitraryStackAny<C, N> {
+    type Target = StackAny<N>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.stack
+    }
+}
+
+impl<C, const N: usize> core::ops::DerefMut for ArbitraryStackAny<C, N> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.stack
+    }
+}
+
+impl<'a, C, const N: usize> ::arbitrary::Arbitrary<'a> for ArbitraryStackAny<C, N>
+where
+    C: Candidates<'a>,
+{
+    fn arbitrary(u: &mut ::arbitrary::Unstructured<'a>) -> ::arbitrary::Result<Self> {
+        Ok(Self {
+            stack: any_of::<C, N>(u)?,
+            _marker: core::marker::PhantomData,
+        })
+    }
+}
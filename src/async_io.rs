@@ -0,0 +1,741 @@
+use core::pin::Pin;
+use core::task::{Context, Poll};
+use std::io::{IoSlice, IoSliceMut};
+
+use futures_io::{AsyncRead, AsyncWrite};
+
+use crate::{value_ptr, Buffer, CapacityError, StackAny};
+
+type PollRead = unsafe fn(Ptr, &mut Context<'_>, &mut [u8]) -> Poll<std::io::Result<usize>>;
+type PollReadVectored =
+    unsafe fn(Ptr, &mut Context<'_>, &mut [IoSliceMut<'_>]) -> Poll<std::io::Result<usize>>;
+type PollWrite = unsafe fn(Ptr, &mut Context<'_>, &[u8]) -> Poll<std::io::Result<usize>>;
+type PollWriteVectored =
+    unsafe fn(Ptr, &mut Context<'_>, &[IoSlice<'_>]) -> Poll<std::io::Result<usize>>;
+type PollFlush = unsafe fn(Ptr, &mut Context<'_>) -> Poll<std::io::Result<()>>;
+type Ptr = *mut core::mem::MaybeUninit<u8>;
+
+/// A stack allocation of `N` size whose contained value is structurally pinned.
+struct Pinned<const N: usize> {
+    type_id: core::any::TypeId,
+    bytes: Buffer<N>,
+    drop_fn: Option<unsafe fn(Ptr)>,
+    _marker: core::marker::PhantomData<*mut ()>,
+    _pinned: core::marker::PhantomPinned,
+}
+
+impl<const N: usize> Pinned<N> {
+    fn try_new<T>(value: T) -> Result<Self, CapacityError<T>>
+    where
+        T: core::any::Any,
+    {
+        if !StackAny::<N>::fits::<T>() {
+            return Err(CapacityError::new(value, N, StackAny::<N>::ALIGN));
+        }
+
+        let mut bytes = Buffer([core::mem::MaybeUninit::uninit(); N]);
+
+        let ptr = value_ptr::<T>(bytes.0.as_mut_ptr());
+        unsafe { core::ptr::write(ptr, value) };
+
+        let drop_fn: Option<unsafe fn(_)> = match core::mem::needs_drop::<T>() {
+            true => Some(|ptr| unsafe { core::ptr::drop_in_place(value_ptr::<T>(ptr)) }),
+            false => None,
+        };
+
+        Ok(Self {
+            type_id: core::any::TypeId::of::<T>(),
+            bytes,
+            drop_fn,
+            _marker: core::marker::PhantomData,
+            _pinned: core::marker::PhantomPinned,
+        })
+    }
+
+    /// Returns the pointer to the buffer without borrowing it as a whole,
+    /// which would invalidate references into a self-referential value.
+    fn as_ptr(&mut self) -> Ptr {
+        core::ptr::addr_of_mut!(self.bytes).cast()
+    }
+
+    fn downcast_ref<T>(&self) -> Option<&T>
+    where
+        T: core::any::Any,
+    {
+        if core::any::TypeId::of::<T>() != self.type_id {
+            return None;
+        }
+
+        let ptr = value_ptr::<T>(core::ptr::addr_of!(self.bytes) as *mut _);
+        Some(unsafe { &*ptr })
+    }
+
+    fn downcast<T>(self) -> Result<T, Self>
+    where
+        T: core::any::Any,
+    {
+        if core::any::TypeId::of::<T>() != self.type_id {
+            return Err(self);
+        }
+
+        let mut this = core::mem::ManuallyDrop::new(self);
+        Ok(unsafe { core::ptr::read(value_ptr::<T>(this.as_ptr())) })
+    }
+}
+
+impl<const N: usize> Drop for Pinned<N> {
+    fn drop(&mut self) {
+        if let Some(drop_fn) = self.drop_fn {
+            unsafe { drop_fn(self.as_ptr()) };
+        }
+    }
+}
+
+/// Returns the pinned `T` placed at `ptr`.
+///
+/// # Safety
+///
+/// `ptr` must point to `T` which is structurally pinned.
+unsafe fn pinned<'a, T>(ptr: Ptr) -> Pin<&'a mut T> {
+    unsafe { Pin::new_unchecked(&mut *value_ptr::<T>(ptr)) }
+}
+
+/// A type that owns a stack allocation of `N` size containing an async reader, and is an async reader itself.
+///
+/// `poll_read` and `poll_read_vectored` are delegated to the contained reader through the functions
+/// captured at construction. As [`StackFuture`](crate::StackFuture), `StackAsyncRead` is never `Unpin`,
+/// so the contained reader is never moved once it is polled.
+///
+/// # Examples
+///
+/// ```
+/// use futures::executor::block_on;
+/// use futures::io::{AsyncReadExt, Cursor};
+///
+/// use stack_any::StackAsyncRead;
+///
+/// fn source(chained: bool) -> StackAsyncRead<80> {
+///     match chained {
+///         true => StackAsyncRead::new(Cursor::new(b"ab".to_vec()).chain(Cursor::new(b"cd".to_vec()))),
+///         false => StackAsyncRead::new(Cursor::new(b"xyz".to_vec())),
+///     }
+/// }
+///
+/// let mut text = String::new();
+/// block_on(Box::pin(source(true)).read_to_string(&mut text)).unwrap();
+/// assert_eq!(text, "abcd");
+///
+/// let mut text = String::new();
+/// block_on(Box::pin(source(false)).read_to_string(&mut text)).unwrap();
+/// assert_eq!(text, "xyz");
+/// ```
+pub struct StackAsyncRead<const N: usize> {
+    inner: Pinned<N>,
+    poll_read_fn: PollRead,
+    poll_read_vectored_fn: PollReadVectored,
+}
+
+impl<const N: usize> StackAsyncRead<N> {
+    /// Allocates N-size memory on the stack and then places `reader` into it.
+    ///
+    /// Whether `R` fits is checked at compile time, as in [`StackAny::new`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let reader = stack_any::StackAsyncRead::<16>::new(futures::io::empty());
+    /// ```
+    ///
+    /// ```compile_fail
+    /// let reader = stack_any::StackAsyncRead::<16>::new(futures::io::Cursor::new(vec![5u8]));
+    /// ```
+    pub fn new<R>(reader: R) -> Self
+    where
+        R: AsyncRead + 'static,
+    {
+        const {
+            assert!(
+                StackAny::<N>::fits::<R>(),
+                "value does not fit in the stack"
+            )
+        };
+
+        match Self::try_new_checked(reader) {
+            Ok(stack) => stack,
+            Err(_) => unreachable!(),
+        }
+    }
+
+    /// Allocates N-size memory on the stack and then places `reader` into it.
+    /// Returns None if `R` size is larger than N or `R` alignment is larger than [`StackAny::ALIGN`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let reader = stack_any::StackAsyncRead::<16>::try_new(futures::io::Cursor::new(vec![5u8]));
+    /// assert!(reader.is_none());
+    /// ```
+    pub fn try_new<R>(reader: R) -> Option<Self>
+    where
+        R: AsyncRead + 'static,
+    {
+        Self::try_new_checked(reader).ok()
+    }
+
+    /// Allocates N-size memory on the stack and then places `reader` into it.
+    /// Returns [`CapacityError`] carrying `reader` back if `R` size is larger than N
+    /// or `R` alignment is larger than [`StackAny::ALIGN`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let reader = futures::io::Cursor::new(vec![5u8]);
+    ///
+    /// let err = stack_any::StackAsyncRead::<16>::try_new_checked(reader).unwrap_err();
+    /// assert_eq!(err.into_value().into_inner(), [5]);
+    /// ```
+    pub fn try_new_checked<R>(reader: R) -> Result<Self, CapacityError<R>>
+    where
+        R: AsyncRead + 'static,
+    {
+        Ok(Self {
+            inner: Pinned::try_new(reader)?,
+            poll_read_fn: |ptr, cx, buf| unsafe { pinned::<R>(ptr) }.poll_read(cx, buf),
+            poll_read_vectored_fn: |ptr, cx, bufs| {
+                unsafe { pinned::<R>(ptr) }.poll_read_vectored(cx, bufs)
+            },
+        })
+    }
+
+    /// Attempt to return reference to the inner reader as a concrete type.
+    /// Returns None if `R` is not equal to contained reader type.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let reader = stack_any::StackAsyncRead::<16>::new(futures::io::empty());
+    /// assert!(reader.downcast_ref::<futures::io::Empty>().is_some());
+    /// ```
+    pub fn downcast_ref<R>(&self) -> Option<&R>
+    where
+        R: AsyncRead + 'static,
+    {
+        self.inner.downcast_ref()
+    }
+
+    /// Attempt to downcast to a concrete type, recovering the reader.
+    /// Returns the reader back if `R` is not equal to contained reader type.
+    ///
+    /// Only reachable while `StackAsyncRead` is not pinned.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let reader = stack_any::StackAsyncRead::<16>::new(futures::io::empty());
+    /// assert!(reader.downcast::<futures::io::Empty>().is_ok());
+    /// ```
+    pub fn downcast<R>(self) -> Result<R, Self>
+    where
+        R: AsyncRead + 'static,
+    {
+        let Self {
+            inner,
+            poll_read_fn,
+            poll_read_vectored_fn,
+        } = self;
+
+        inner.downcast().map_err(|inner| Self {
+            inner,
+            poll_read_fn,
+            poll_read_vectored_fn,
+        })
+    }
+}
+
+impl<const N: usize> AsyncRead for StackAsyncRead<N> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<std::io::Result<usize>> {
+        // SAFETY: `self` is not moved out of the pin, and the contained reader is structurally pinned.
+        let this = unsafe { self.get_unchecked_mut() };
+        unsafe { (this.poll_read_fn)(this.inner.as_ptr(), cx, buf) }
+    }
+
+    fn poll_read_vectored(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        bufs: &mut [IoSliceMut<'_>],
+    ) -> Poll<std::io::Result<usize>> {
+        // SAFETY: `self` is not moved out of the pin, and the contained reader is structurally pinned.
+        let this = unsafe { self.get_unchecked_mut() };
+        unsafe { (this.poll_read_vectored_fn)(this.inner.as_ptr(), cx, bufs) }
+    }
+}
+
+impl<const N: usize> core::fmt::Debug for StackAsyncRead<N> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "StackAsyncRead<{}>", N)
+    }
+}
+
+/// A type that owns a stack allocation of `N` size containing an async writer, and is an async writer itself.
+///
+/// `poll_write`, `poll_write_vectored`, `poll_flush` and `poll_close` are delegated to the contained writer
+/// through the functions captured at construction. As [`StackFuture`](crate::StackFuture), `StackAsyncWrite`
+/// is never `Unpin`, so the contained writer is never moved once it is polled.
+///
+/// # Examples
+///
+/// ```
+/// use futures::executor::block_on;
+/// use futures::io::{AsyncWriteExt, BufWriter, Cursor};
+///
+/// type Buffered = BufWriter<Cursor<Vec<u8>>>;
+///
+/// let mut writer = Box::pin(stack_any::StackAsyncWrite::<64>::new(BufWriter::new(Cursor::new(vec![]))));
+/// let written = |writer: &stack_any::StackAsyncWrite<64>| {
+///     writer.downcast_ref::<Buffered>().unwrap().get_ref().get_ref().clone()
+/// };
+///
+/// block_on(writer.write_all(b"hello")).unwrap();
+/// assert_eq!(written(&writer), b"");
+///
+/// block_on(writer.close()).unwrap();
+/// assert_eq!(written(&writer), b"hello");
+/// ```
+pub struct StackAsyncWrite<const N: usize> {
+    inner: Pinned<N>,
+    poll_write_fn: PollWrite,
+    poll_write_vectored_fn: PollWriteVectored,
+    poll_flush_fn: PollFlush,
+    poll_close_fn: PollFlush,
+}
+
+impl<const N: usize> StackAsyncWrite<N> {
+    /// Allocates N-size memory on the stack and then places `writer` into it.
+    ///
+    /// Whether `W` fits is checked at compile time, as in [`StackAny::new`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let writer = stack_any::StackAsyncWrite::<16>::new(futures::io::sink());
+    /// ```
+    ///
+    /// ```compile_fail
+    /// let writer = stack_any::StackAsyncWrite::<16>::new(futures::io::Cursor::new(vec![5u8]));
+    /// ```
+    pub fn new<W>(writer: W) -> Self
+    where
+        W: AsyncWrite + 'static,
+    {
+        const {
+            assert!(
+                StackAny::<N>::fits::<W>(),
+                "value does not fit in the stack"
+            )
+        };
+
+        match Self::try_new_checked(writer) {
+            Ok(stack) => stack,
+            Err(_) => unreachable!(),
+        }
+    }
+
+    /// Allocates N-size memory on the stack and then places `writer` into it.
+    /// Returns None if `W` size is larger than N or `W` alignment is larger than [`StackAny::ALIGN`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let writer = stack_any::StackAsyncWrite::<16>::try_new(futures::io::Cursor::new(vec![5u8]));
+    /// assert!(writer.is_none());
+    /// ```
+    pub fn try_new<W>(writer: W) -> Option<Self>
+    where
+        W: AsyncWrite + 'static,
+    {
+        Self::try_new_checked(writer).ok()
+    }
+
+    /// Allocates N-size memory on the stack and then places `writer` into it.
+    /// Returns [`CapacityError`] carrying `writer` back if `W` size is larger than N
+    /// or `W` alignment is larger than [`StackAny::ALIGN`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let writer = futures::io::Cursor::new(vec![5u8]);
+    ///
+    /// let err = stack_any::StackAsyncWrite::<16>::try_new_checked(writer).unwrap_err();
+    /// assert_eq!(err.into_value().into_inner(), [5]);
+    /// ```
+    pub fn try_new_checked<W>(writer: W) -> Result<Self, CapacityError<W>>
+    where
+        W: AsyncWrite + 'static,
+    {
+        Ok(Self {
+            inner: Pinned::try_new(writer)?,
+            poll_write_fn: |ptr, cx, buf| unsafe { pinned::<W>(ptr) }.poll_write(cx, buf),
+            poll_write_vectored_fn: |ptr, cx, bufs| {
+                unsafe { pinned::<W>(ptr) }.poll_write_vectored(cx, bufs)
+            },
+            poll_flush_fn: |ptr, cx| unsafe { pinned::<W>(ptr) }.poll_flush(cx),
+            poll_close_fn: |ptr, cx| unsafe { pinned::<W>(ptr) }.poll_close(cx),
+        })
+    }
+
+    /// Attempt to return reference to the inner writer as a concrete type.
+    /// Returns None if `W` is not equal to contained writer type.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let writer = stack_any::StackAsyncWrite::<16>::new(futures::io::sink());
+    /// assert!(writer.downcast_ref::<futures::io::Sink>().is_some());
+    /// ```
+    pub fn downcast_ref<W>(&self) -> Option<&W>
+    where
+        W: AsyncWrite + 'static,
+    {
+        self.inner.downcast_ref()
+    }
+
+    /// Attempt to downcast to a concrete type, recovering the writer.
+    /// Returns the writer back if `W` is not equal to contained writer type.
+    ///
+    /// Only reachable while `StackAsyncWrite` is not pinned.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let writer = stack_any::StackAsyncWrite::<16>::new(futures::io::sink());
+    /// assert!(writer.downcast::<futures::io::Sink>().is_ok());
+    /// ```
+    pub fn downcast<W>(self) -> Result<W, Self>
+    where
+        W: AsyncWrite + 'static,
+    {
+        let Self {
+            inner,
+            poll_write_fn,
+            poll_write_vectored_fn,
+            poll_flush_fn,
+            poll_close_fn,
+        } = self;
+
+        inner.downcast().map_err(|inner| Self {
+            inner,
+            poll_write_fn,
+            poll_write_vectored_fn,
+            poll_flush_fn,
+            poll_close_fn,
+        })
+    }
+}
+
+impl<const N: usize> AsyncWrite for StackAsyncWrite<N> {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<std::io::Result<usize>> {
+        // SAFETY: `self` is not moved out of the pin, and the contained writer is structurally pinned.
+        let this = unsafe { self.get_unchecked_mut() };
+        unsafe { (this.poll_write_fn)(this.inner.as_ptr(), cx, buf) }
+    }
+
+    fn poll_write_vectored(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        bufs: &[IoSlice<'_>],
+    ) -> Poll<std::io::Result<usize>> {
+        // SAFETY: `self` is not moved out of the pin, and the contained writer is structurally pinned.
+        let this = unsafe { self.get_unchecked_mut() };
+        unsafe { (this.poll_write_vectored_fn)(this.inner.as_ptr(), cx, bufs) }
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        // SAFETY: `self` is not moved out of the pin, and the contained writer is structurally pinned.
+        let this = unsafe { self.get_unchecked_mut() };
+        unsafe { (this.poll_flush_fn)(this.inner.as_ptr(), cx) }
+    }
+
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        // SAFETY: `self` is not moved out of the pin, and the contained writer is structurally pinned.
+        let this = unsafe { self.get_unchecked_mut() };
+        unsafe { (this.poll_close_fn)(this.inner.as_ptr(), cx) }
+    }
+}
+
+impl<const N: usize> core::fmt::Debug for StackAsyncWrite<N> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "StackAsyncWrite<{}>", N)
+    }
+}
+
+/// A type that owns a stack allocation of `N` size containing a duplex stream, which is both
+/// an async reader and an async writer, e.g. a TLS stream or a plain TCP stream chosen at runtime.
+///
+/// It delegates as [`StackAsyncRead`] and [`StackAsyncWrite`] do.
+///
+/// # Examples
+///
+/// ```
+/// use std::collections::VecDeque;
+/// use std::io::ErrorKind;
+/// use std::pin::Pin;
+/// use std::task::{Context, Poll};
+///
+/// use futures::executor::block_on;
+/// use futures::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+///
+/// #[derive(Default)]
+/// struct Pipe {
+///     buf: VecDeque<u8>,
+///     closed: bool,
+/// }
+///
+/// impl AsyncRead for Pipe {
+///     fn poll_read(mut self: Pin<&mut Self>, _: &mut Context<'_>, buf: &mut [u8]) -> Poll<std::io::Result<usize>> {
+///         Poll::Ready(std::io::Read::read(&mut self.buf, buf))
+///     }
+/// }
+///
+/// impl AsyncWrite for Pipe {
+///     fn poll_write(mut self: Pin<&mut Self>, _: &mut Context<'_>, buf: &[u8]) -> Poll<std::io::Result<usize>> {
+///         if self.closed {
+///             return Poll::Ready(Err(ErrorKind::BrokenPipe.into()));
+///         }
+///         self.buf.extend(buf);
+///         Poll::Ready(Ok(buf.len()))
+///     }
+///
+///     fn poll_flush(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+///         Poll::Ready(Ok(()))
+///     }
+///
+///     fn poll_close(mut self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+///         self.closed = true;
+///         Poll::Ready(Ok(()))
+///     }
+/// }
+///
+/// let mut pipe = Box::pin(stack_any::StackAsyncDuplex::<48>::new(Pipe::default()));
+///
+/// block_on(pipe.write_all(b"hello")).unwrap();
+/// block_on(pipe.close()).unwrap();
+/// assert_eq!(block_on(pipe.write_all(b"!")).unwrap_err().kind(), ErrorKind::BrokenPipe);
+///
+/// let mut text = String::new();
+/// block_on(pipe.read_to_string(&mut text)).unwrap();
+/// assert_eq!(text, "hello");
+/// ```
+pub struct StackAsyncDuplex<const N: usize> {
+    inner: Pinned<N>,
+    poll_read_fn: PollRead,
+    poll_read_vectored_fn: PollReadVectored,
+    poll_write_fn: PollWrite,
+    poll_write_vectored_fn: PollWriteVectored,
+    poll_flush_fn: PollFlush,
+    poll_close_fn: PollFlush,
+}
+
+impl<const N: usize> StackAsyncDuplex<N> {
+    /// Allocates N-size memory on the stack and then places `stream` into it.
+    ///
+    /// Whether `S` fits is checked at compile time, as in [`StackAny::new`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let stream = stack_any::StackAsyncDuplex::<32>::new(futures::io::Cursor::new(vec![5u8]));
+    /// ```
+    ///
+    /// ```compile_fail
+    /// let stream = stack_any::StackAsyncDuplex::<16>::new(futures::io::Cursor::new(vec![5u8]));
+    /// ```
+    pub fn new<S>(stream: S) -> Self
+    where
+        S: AsyncRead + AsyncWrite + 'static,
+    {
+        const {
+            assert!(
+                StackAny::<N>::fits::<S>(),
+                "value does not fit in the stack"
+            )
+        };
+
+        match Self::try_new_checked(stream) {
+            Ok(stack) => stack,
+            Err(_) => unreachable!(),
+        }
+    }
+
+    /// Allocates N-size memory on the stack and then places `stream` into it.
+    /// Returns None if `S` size is larger than N or `S` alignment is larger than [`StackAny::ALIGN`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let stream = stack_any::StackAsyncDuplex::<16>::try_new(futures::io::Cursor::new(vec![5u8]));
+    /// assert!(stream.is_none());
+    /// ```
+    pub fn try_new<S>(stream: S) -> Option<Self>
+    where
+        S: AsyncRead + AsyncWrite + 'static,
+    {
+        Self::try_new_checked(stream).ok()
+    }
+
+    /// Allocates N-size memory on the stack and then places `stream` into it.
+    /// Returns [`CapacityError`] carrying `stream` back if `S` size is larger than N
+    /// or `S` alignment is larger than [`StackAny::ALIGN`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let stream = futures::io::Cursor::new(vec![5u8]);
+    ///
+    /// let err = stack_any::StackAsyncDuplex::<16>::try_new_checked(stream).unwrap_err();
+    /// assert_eq!(err.into_value().into_inner(), [5]);
+    /// ```
+    pub fn try_new_checked<S>(stream: S) -> Result<Self, CapacityError<S>>
+    where
+        S: AsyncRead + AsyncWrite + 'static,
+    {
+        Ok(Self {
+            inner: Pinned::try_new(stream)?,
+            poll_read_fn: |ptr, cx, buf| unsafe { pinned::<S>(ptr) }.poll_read(cx, buf),
+            poll_read_vectored_fn: |ptr, cx, bufs| {
+                unsafe { pinned::<S>(ptr) }.poll_read_vectored(cx, bufs)
+            },
+            poll_write_fn: |ptr, cx, buf| unsafe { pinned::<S>(ptr) }.poll_write(cx, buf),
+            poll_write_vectored_fn: |ptr, cx, bufs| {
+                unsafe { pinned::<S>(ptr) }.poll_write_vectored(cx, bufs)
+            },
+            poll_flush_fn: |ptr, cx| unsafe { pinned::<S>(ptr) }.poll_flush(cx),
+            poll_close_fn: |ptr, cx| unsafe { pinned::<S>(ptr) }.poll_close(cx),
+        })
+    }
+
+    /// Attempt to return reference to the inner stream as a concrete type.
+    /// Returns None if `S` is not equal to contained stream type.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use futures::io::Cursor;
+    ///
+    /// let stream = stack_any::StackAsyncDuplex::<32>::new(Cursor::new(vec![5u8]));
+    /// assert_eq!(stream.downcast_ref::<Cursor<Vec<u8>>>().map(|stream| stream.get_ref().len()), Some(1));
+    /// ```
+    pub fn downcast_ref<S>(&self) -> Option<&S>
+    where
+        S: AsyncRead + AsyncWrite + 'static,
+    {
+        self.inner.downcast_ref()
+    }
+
+    /// Attempt to downcast to a concrete type, recovering the stream.
+    /// Returns the stream back if `S` is not equal to contained stream type.
+    ///
+    /// Only reachable while `StackAsyncDuplex` is not pinned.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use futures::io::Cursor;
+    ///
+    /// let stream = stack_any::StackAsyncDuplex::<32>::new(Cursor::new(vec![5u8]));
+    /// assert_eq!(stream.downcast::<Cursor<Vec<u8>>>().ok().map(Cursor::into_inner), Some(vec![5]));
+    /// ```
+    pub fn downcast<S>(self) -> Result<S, Self>
+    where
+        S: AsyncRead + AsyncWrite + 'static,
+    {
+        let Self {
+            inner,
+            poll_read_fn,
+            poll_read_vectored_fn,
+            poll_write_fn,
+            poll_write_vectored_fn,
+            poll_flush_fn,
+            poll_close_fn,
+        } = self;
+
+        inner.downcast().map_err(|inner| Self {
+            inner,
+            poll_read_fn,
+            poll_read_vectored_fn,
+            poll_write_fn,
+            poll_write_vectored_fn,
+            poll_flush_fn,
+            poll_close_fn,
+        })
+    }
+}
+
+impl<const N: usize> AsyncRead for StackAsyncDuplex<N> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<std::io::Result<usize>> {
+        // SAFETY: `self` is not moved out of the pin, and the contained stream is structurally pinned.
+        let this = unsafe { self.get_unchecked_mut() };
+        unsafe { (this.poll_read_fn)(this.inner.as_ptr(), cx, buf) }
+    }
+
+    fn poll_read_vectored(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        bufs: &mut [IoSliceMut<'_>],
+    ) -> Poll<std::io::Result<usize>> {
+        // SAFETY: `self` is not moved out of the pin, and the contained stream is structurally pinned.
+        let this = unsafe { self.get_unchecked_mut() };
+        unsafe { (this.poll_read_vectored_fn)(this.inner.as_ptr(), cx, bufs) }
+    }
+}
+
+impl<const N: usize> AsyncWrite for StackAsyncDuplex<N> {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<std::io::Result<usize>> {
+        // SAFETY: `self` is not moved out of the pin, and the contained stream is structurally pinned.
+        let this = unsafe { self.get_unchecked_mut() };
+        unsafe { (this.poll_write_fn)(this.inner.as_ptr(), cx, buf) }
+    }
+
+    fn poll_write_vectored(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        bufs: &[IoSlice<'_>],
+    ) -> Poll<std::io::Result<usize>> {
+        // SAFETY: `self` is not moved out of the pin, and the contained stream is structurally pinned.
+        let this = unsafe { self.get_unchecked_mut() };
+        unsafe { (this.poll_write_vectored_fn)(this.inner.as_ptr(), cx, bufs) }
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        // SAFETY: `self` is not moved out of the pin, and the contained stream is structurally pinned.
+        let this = unsafe { self.get_unchecked_mut() };
+        unsafe { (this.poll_flush_fn)(this.inner.as_ptr(), cx) }
+    }
+
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        // SAFETY: `self` is not moved out of the pin, and the contained stream is structurally pinned.
+        let this = unsafe { self.get_unchecked_mut() };
+        unsafe { (this.poll_close_fn)(this.inner.as_ptr(), cx) }
+    }
+}
+
+impl<const N: usize> core::fmt::Debug for StackAsyncDuplex<N> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "StackAsyncDuplex<{}>", N)
+    }
+}
@@ -0,0 +1,208 @@
+#[cfg(feature = "serde")]
+use crate::vtable::WithSerialize;
+use crate::vtable::{Capture, Plain, WithClone, WithDebug, WithDisplay, WithEq, WithHash, WithOrd};
+use crate::{CapacityError, StackAny};
+
+/// A builder that places a value into a [`StackAny`] together with the capabilities of its type,
+/// returned by [`StackAny::builder`].
+///
+/// A plain [`StackAny`] only knows how to drop its value. Each `with_*` method captures
+/// one more trait implementation of `T`, so that [`StackAny`] can implement the trait itself.
+/// The capabilities are tracked by `V`, and end up in the static [`VTable`](crate::VTable) of `T`.
+///
+/// # Examples
+///
+/// ```
+/// let strings = stack_any::StackAny::<24>::builder(vec![String::from("x")])
+///     .with_clone()
+///     .build();
+///
+/// let cloned = strings.clone();
+/// assert_eq!(cloned.downcast_ref::<Vec<String>>(), Some(&vec![String::from("x")]));
+/// ```
+pub struct StackAnyBuilder<T, const N: usize, V = Plain> {
+    value: T,
+    _marker: core::marker::PhantomData<V>,
+}
+
+impl<T, const N: usize> StackAnyBuilder<T, N>
+where
+    T: core::any::Any,
+{
+    pub(crate) fn new(value: T) -> Self {
+        Self {
+            value,
+            _marker: core::marker::PhantomData,
+        }
+    }
+}
+
+impl<T, const N: usize, V> StackAnyBuilder<T, N, V>
+where
+    T: core::any::Any,
+    V: Capture<T>,
+{
+    /// Captures one more capability, tracked by `U`.
+    fn capture<U>(self) -> StackAnyBuilder<T, N, U> {
+        StackAnyBuilder {
+            value: self.value,
+            _marker: core::marker::PhantomData,
+        }
+    }
+
+    /// Captures `Clone` of `T`, so that the stack can be cloned by [`StackAny::try_clone`] and `Clone`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let five = stack_any::StackAny::<4>::builder(5i32).with_clone().build();
+    /// assert!(five.is_cloneable());
+    /// ```
+    pub fn with_clone(self) -> StackAnyBuilder<T, N, WithClone<V>>
+    where
+        T: Clone,
+    {
+        self.capture()
+    }
+
+    /// Captures `PartialEq` of `T`, so that the stack can be compared by `PartialEq`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let five = stack_any::StackAny::<4>::builder(5i32).with_eq().build();
+    /// assert_eq!(five, stack_any::StackAny::<8>::builder(5i32).with_eq().build());
+    /// ```
+    pub fn with_eq(self) -> StackAnyBuilder<T, N, WithEq<V>>
+    where
+        T: PartialEq,
+    {
+        self.capture()
+    }
+
+    /// Captures `Ord` of `T`, so that the stack can be ordered by `Ord`.
+    ///
+    /// `PartialEq` of `T` is captured as well, so that the orderings agree with the equality.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let five = stack_any::StackAny::<4>::builder(5i32).with_ord().build();
+    /// assert!(five < stack_any::StackAny::<4>::builder(7i32).with_ord().build());
+    /// assert!(five == stack_any::StackAny::<4>::builder(5i32).with_ord().build());
+    /// ```
+    pub fn with_ord(self) -> StackAnyBuilder<T, N, WithEq<WithOrd<V>>>
+    where
+        T: Ord,
+    {
+        self.capture::<WithOrd<V>>().with_eq()
+    }
+
+    /// Captures `Hash` of `T`, so that the stack can be hashed by `Hash`.
+    ///
+    /// Together with [`StackAnyBuilder::with_eq`], the stack can be used as a key of a hash map.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let five = stack_any::StackAny::<4>::builder(5i32).with_eq().with_hash().build();
+    ///
+    /// let set = std::collections::HashSet::from([five]);
+    /// assert!(set.contains(&stack_any::StackAny::<4>::builder(5i32).with_eq().with_hash().build()));
+    /// ```
+    pub fn with_hash(self) -> StackAnyBuilder<T, N, WithHash<V>>
+    where
+        T: core::hash::Hash,
+    {
+        self.capture()
+    }
+
+    /// Captures `Debug` of `T`, so that `Debug` of the stack prints the contained value.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let five = stack_any::StackAny::<4>::builder(5i32).with_debug().build();
+    /// assert_eq!(format!("{:?}", five), "5");
+    /// ```
+    pub fn with_debug(self) -> StackAnyBuilder<T, N, WithDebug<V>>
+    where
+        T: core::fmt::Debug,
+    {
+        self.capture()
+    }
+
+    /// Captures `Display` of `T`, so that the stack implements `Display` by the contained value.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let five = stack_any::StackAny::<4>::builder(5i32).with_display().build();
+    /// assert_eq!(five.to_string(), "5");
+    /// ```
+    pub fn with_display(self) -> StackAnyBuilder<T, N, WithDisplay<V>>
+    where
+        T: core::fmt::Display,
+    {
+        self.capture()
+    }
+
+    /// Captures `Serialize` of `T`, so that the stack can be serialized together with the name of `T`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let five = stack_any::StackAny::<4>::builder(5i32).with_serialize().build();
+    /// assert_eq!(serde_json::to_string(&five).unwrap(), r#"{"type":"i32","value":5}"#);
+    /// ```
+    #[cfg(feature = "serde")]
+    pub fn with_serialize(self) -> StackAnyBuilder<T, N, WithSerialize<V>>
+    where
+        T: serde::Serialize,
+    {
+        self.capture()
+    }
+
+    /// Places the value with the captured capabilities into N-size memory.
+    ///
+    /// Whether `T` fits is checked at compile time, as in [`StackAny::new`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let five = stack_any::StackAny::<4>::builder(5i32).build();
+    /// assert_eq!(five.downcast_ref::<i32>(), Some(&5));
+    /// ```
+    ///
+    /// ```compile_fail
+    /// let five = stack_any::StackAny::<4>::builder(5i64).build();
+    /// ```
+    pub fn build(self) -> StackAny<N> {
+        const {
+            assert!(
+                StackAny::<N>::fits::<T>(),
+                "value does not fit in the stack"
+            )
+        };
+
+        match self.try_build() {
+            Ok(stack) => stack,
+            Err(_) => unreachable!(),
+        }
+    }
+
+    /// Places the value with the captured capabilities into N-size memory.
+    /// Returns [`CapacityError`] carrying the value back if `T` does not fit.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let err = stack_any::StackAny::<4>::builder(5i64).with_clone().try_build().unwrap_err();
+    /// assert_eq!(err.into_value(), 5);
+    /// ```
+    pub fn try_build(self) -> Result<StackAny<N>, CapacityError<T>> {
+        let mut stack = StackAny::try_new_checked(self.value)?;
+        stack.vtable = V::REF;
+        Ok(stack)
+    }
+}
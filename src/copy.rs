@@ -0,0 +1,220 @@
+use crate::{Buffer, CapacityError, RawParts, StackAny, VTable};
+
+/// A convertible type that owns a stack allocation of `N` size and is `Copy` itself.
+///
+/// Only `Copy` values can be placed into it, so it has no drop glue and can be
+/// passed around by value freely.
+///
+/// # Examples
+///
+/// ```
+/// let sample = stack_any::copy_stack_any!([f32; 3], [0.5, 1.0, 2.0]);
+///
+/// let samples = [sample; 64];
+/// assert!(samples.iter().all(|sample| sample.downcast_ref::<[f32; 3]>() == Some(&[0.5, 1.0, 2.0])));
+/// assert_eq!(sample.downcast::<[f32; 3]>().ok(), Some([0.5, 1.0, 2.0]));
+/// ```
+///
+/// ```compile_fail
+/// let stack = stack_any::CopyStackAny::<24>::try_new(String::from("x"));
+/// ```
+#[derive(Clone, Copy)]
+pub struct CopyStackAny<const N: usize> {
+    type_id: core::any::TypeId,
+    bytes: Buffer<N>,
+    vtable: &'static VTable,
+    _marker: core::marker::PhantomData<*mut ()>,
+}
+
+impl<const N: usize> CopyStackAny<N> {
+    /// Allocates N-size memory on the stack and then places `value` into it.
+    ///
+    /// Whether `T` fits is checked at compile time, as in [`StackAny::new`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let five = stack_any::CopyStackAny::<4>::new(5i32);
+    /// assert_eq!(five.downcast_ref::<i32>(), Some(&5));
+    /// ```
+    ///
+    /// ```compile_fail
+    /// let five = stack_any::CopyStackAny::<4>::new(5u64);
+    /// ```
+    pub fn new<T>(value: T) -> Self
+    where
+        T: core::any::Any + Copy,
+    {
+        Self::from_stack_any(StackAny::new(value))
+    }
+
+    /// Allocates N-size memory on the stack and then places `value` into it.
+    /// Returns None if `T` size is larger than N or `T` alignment is larger than [`StackAny::ALIGN`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let five = stack_any::CopyStackAny::<{ std::mem::size_of::<i32>() }>::try_new(5);
+    /// ```
+    pub fn try_new<T>(value: T) -> Option<Self>
+    where
+        T: core::any::Any + Copy,
+    {
+        StackAny::try_new(value).map(Self::from_stack_any)
+    }
+
+    /// Allocates N-size memory on the stack and then places `value` into it.
+    /// Returns [`CapacityError`] carrying `value` back if `T` size is larger than N
+    /// or `T` alignment is larger than [`StackAny::ALIGN`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let err = stack_any::CopyStackAny::<4>::try_new_checked(5i64).unwrap_err();
+    /// assert_eq!(err.into_value(), 5);
+    /// ```
+    pub fn try_new_checked<T>(value: T) -> Result<Self, CapacityError<T>>
+    where
+        T: core::any::Any + Copy,
+    {
+        StackAny::try_new_checked(value).map(Self::from_stack_any)
+    }
+
+    /// Takes over the value of the stack, which must be `Copy`.
+    fn from_stack_any(stack: StackAny<N>) -> Self {
+        let parts = stack.into_raw_parts();
+        Self {
+            type_id: parts.type_id,
+            bytes: Buffer(parts.bytes),
+            vtable: parts.vtable,
+            _marker: core::marker::PhantomData,
+        }
+    }
+
+    /// Returns true if the contained value type is `T`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let five = stack_any::copy_stack_any!(i32, 5);
+    /// assert!(five.is::<i32>());
+    /// assert!(!five.is::<i64>());
+    /// ```
+    pub fn is<T>(&self) -> bool
+    where
+        T: core::any::Any,
+    {
+        core::any::TypeId::of::<T>() == self.type_id
+    }
+
+    /// Attempt to return reference to the inner value as a concrete type.
+    /// Returns None if `T` is not equal to contained value type.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let five = stack_any::copy_stack_any!(i32, 5);
+    /// assert_eq!(five.downcast_ref::<i32>(), Some(&5));
+    /// assert_eq!(five.downcast_ref::<i64>(), None);
+    /// ```
+    pub fn downcast_ref<T>(&self) -> Option<&T>
+    where
+        T: core::any::Any,
+    {
+        if !self.is::<T>() {
+            return None;
+        }
+
+        let ptr = crate::value_ptr::<T>(self.bytes.0.as_ptr() as *mut _);
+        Some(unsafe { &*ptr })
+    }
+
+    /// Attempt to return mutable reference to the inner value as a concrete type.
+    /// Returns None if `T` is not equal to contained value type.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut five = stack_any::copy_stack_any!(i32, 5);
+    /// *five.downcast_mut::<i32>().unwrap() = 7;
+    /// assert_eq!(five.downcast_ref::<i32>(), Some(&7));
+    /// ```
+    pub fn downcast_mut<T>(&mut self) -> Option<&mut T>
+    where
+        T: core::any::Any,
+    {
+        if !self.is::<T>() {
+            return None;
+        }
+
+        let ptr = crate::value_ptr::<T>(self.bytes.0.as_mut_ptr());
+        Some(unsafe { &mut *ptr })
+    }
+
+    /// Attempt to downcast the stack to a concrete type.
+    /// Returns the stack back if `T` is not equal to contained value type.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let five = stack_any::copy_stack_any!(i32, 5);
+    /// assert_eq!(five.downcast::<i32>().ok(), Some(5));
+    /// assert!(five.downcast::<i64>().is_err());
+    /// ```
+    pub fn downcast<T>(self) -> Result<T, Self>
+    where
+        T: core::any::Any,
+    {
+        match self.downcast_ref::<T>() {
+            Some(value) => Ok(unsafe { core::ptr::read(value) }),
+            None => Err(self),
+        }
+    }
+
+    /// Converts into the plain [`StackAny`], forgetting that the contained value is `Copy`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let five = stack_any::copy_stack_any!(i32, 5);
+    /// let stack = five.into_stack_any();
+    /// assert_eq!(stack.downcast_ref::<i32>(), Some(&5));
+    /// assert_eq!(five.downcast_ref::<i32>(), Some(&5));
+    /// ```
+    pub fn into_stack_any(self) -> StackAny<N> {
+        let parts = RawParts {
+            type_id: self.type_id,
+            bytes: self.bytes.0,
+            vtable: self.vtable,
+        };
+        unsafe { StackAny::from_raw_parts(parts) }
+    }
+}
+
+impl<const N: usize> From<CopyStackAny<N>> for StackAny<N> {
+    fn from(value: CopyStackAny<N>) -> Self {
+        value.into_stack_any()
+    }
+}
+
+impl<const N: usize> core::fmt::Debug for CopyStackAny<N> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_tuple("CopyStackAny")
+            .field(&self.into_stack_any())
+            .finish()
+    }
+}
+
+/// Allocates memory on the stack and then places `Copy` value based on given type and value.
+///
+/// # Examples
+///
+/// ```
+/// let five = stack_any::copy_stack_any!(i32, 5);
+/// ```
+#[macro_export]
+macro_rules! copy_stack_any {
+    ($type:ty, $init:expr) => {
+        $crate::CopyStackAny::<{ ::core::mem::size_of::<$type>() }>::new::<$type>($init)
+    };
+}
@@ -0,0 +1,250 @@
+/// An error returned by [`StackAny::map`](crate::StackAny::map).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MapError {
+    /// The contained value type is not equal to the requested type.
+    Mismatch,
+    /// The mapped value does not fit in the stack.
+    Capacity,
+}
+
+impl core::fmt::Display for MapError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::Mismatch => write!(f, "contained value type is not equal to the requested type"),
+            Self::Capacity => write!(f, "mapped value does not fit in the stack"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for MapError {}
+
+/// An error returned by [`StackAny::swap_value_with`](crate::StackAny::swap_value_with).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SwapError {
+    /// The value type contained by this stack is not equal to the requested type.
+    SelfMismatch,
+    /// The value type contained by the other stack is not equal to the requested type.
+    OtherMismatch,
+}
+
+impl core::fmt::Display for SwapError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::SelfMismatch => {
+                write!(f, "contained value type is not equal to the requested type")
+            }
+            Self::OtherMismatch => write!(
+                f,
+                "other contained value type is not equal to the requested type"
+            ),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for SwapError {}
+
+/// An error returned by [`StackAny::try_new_checked`](crate::StackAny::try_new_checked)
+/// when the value does not fit in the stack. It carries the rejected value back.
+pub struct CapacityError<T> {
+    value: T,
+    capacity: usize,
+    align: usize,
+}
+
+impl<T> CapacityError<T> {
+    pub(crate) fn new(value: T, capacity: usize, align: usize) -> Self {
+        Self {
+            value,
+            capacity,
+            align,
+        }
+    }
+
+    /// Returns the rejected value.
+    pub fn into_value(self) -> T {
+        self.value
+    }
+
+    /// Returns the size of the rejected value type in bytes.
+    pub fn required_size(&self) -> usize {
+        core::mem::size_of::<T>()
+    }
+
+    /// Returns the alignment of the rejected value type in bytes.
+    pub fn required_align(&self) -> usize {
+        core::mem::align_of::<T>()
+    }
+
+    /// Returns the capacity of the stack in bytes.
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Returns the alignment of the stack in bytes.
+    pub fn align(&self) -> usize {
+        self.align
+    }
+}
+
+impl<T> core::fmt::Debug for CapacityError<T> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("CapacityError")
+            .field("type", &core::any::type_name::<T>())
+            .field("required_size", &self.required_size())
+            .field("required_align", &self.required_align())
+            .field("capacity", &self.capacity)
+            .field("align", &self.align)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<T> core::fmt::Display for CapacityError<T> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let name = core::any::type_name::<T>();
+
+        if self.capacity < self.required_size() {
+            write!(
+                f,
+                "value of type {} needs {} bytes but capacity is {}",
+                name,
+                self.required_size(),
+                self.capacity
+            )
+        } else {
+            write!(
+                f,
+                "value of type {} needs alignment {} but alignment is {}",
+                name,
+                self.required_align(),
+                self.align
+            )
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T> std::error::Error for CapacityError<T> {}
+
+/// An error returned by [`StackAny::get`](crate::StackAny::get) and
+/// [`StackAny::get_mut`](crate::StackAny::get_mut) when the contained value type is not equal to the requested type.
+///
+/// With the `type-name` feature, both type names are printed, otherwise their `TypeId`s.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DowncastError {
+    expected: core::any::TypeId,
+    found: core::any::TypeId,
+    #[cfg(feature = "type-name")]
+    expected_name: &'static str,
+    #[cfg(feature = "type-name")]
+    found_name: &'static str,
+}
+
+impl DowncastError {
+    pub(crate) fn new<T>(
+        found: core::any::TypeId,
+        #[cfg(feature = "type-name")] found_name: &'static str,
+    ) -> Self
+    where
+        T: core::any::Any,
+    {
+        Self {
+            expected: core::any::TypeId::of::<T>(),
+            found,
+            #[cfg(feature = "type-name")]
+            expected_name: core::any::type_name::<T>(),
+            #[cfg(feature = "type-name")]
+            found_name,
+        }
+    }
+
+    /// Returns the `TypeId` of the requested type.
+    pub fn expected(&self) -> core::any::TypeId {
+        self.expected
+    }
+
+    /// Returns the `TypeId` of the contained value type.
+    pub fn found(&self) -> core::any::TypeId {
+        self.found
+    }
+
+    /// Returns the name of the requested type.
+    #[cfg(feature = "type-name")]
+    pub fn expected_name(&self) -> &'static str {
+        self.expected_name
+    }
+
+    /// Returns the name of the contained value type.
+    #[cfg(feature = "type-name")]
+    pub fn found_name(&self) -> &'static str {
+        self.found_name
+    }
+}
+
+impl core::fmt::Display for DowncastError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        #[cfg(feature = "type-name")]
+        return write!(
+            f,
+            "expected {}, found {}",
+            self.expected_name, self.found_name
+        );
+        #[cfg(not(feature = "type-name"))]
+        return write!(f, "expected {:?}, found {:?}", self.expected, self.found);
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for DowncastError {}
+
+/// An error returned by [`StackAny::replace_with`](crate::StackAny::replace_with)
+/// when the contained value type is not equal to the requested type.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TypeMismatch;
+
+impl core::fmt::Display for TypeMismatch {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "contained value type is not equal to the requested type")
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for TypeMismatch {}
+
+/// An error returned by [`StackSlice::try_from_slice`](crate::StackSlice::try_from_slice) and
+/// [`StackSlice::try_from_iter`](crate::StackSlice::try_from_iter) when the elements do not fit in the stack.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SliceCapacityError {
+    len: usize,
+    max_len: usize,
+}
+
+impl SliceCapacityError {
+    pub(crate) fn new(len: usize, max_len: usize) -> Self {
+        Self { len, max_len }
+    }
+
+    /// Returns the number of the rejected elements.
+    pub fn requested_len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns the number of elements that would have fit in the stack.
+    pub fn max_len(&self) -> usize {
+        self.max_len
+    }
+}
+
+impl core::fmt::Display for SliceCapacityError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(
+            f,
+            "slice of {} elements does not fit in the stack, which fits {} elements",
+            self.len, self.max_len
+        )
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for SliceCapacityError {}
@@ -0,0 +1,309 @@
+use std::collections::HashMap;
+
+use crate::{CapacityError, DebugElement, StackAny};
+
+/// A token that identifies a value of `T` in [`StackExtensions`].
+///
+/// Keys are identified by their addresses, so declare each of them as a `static`, not as a `const`.
+/// Unlike `TypeId` keys, several keys of the same type are distinct from each other.
+///
+/// # Examples
+///
+/// ```
+/// static NAME: stack_any::Key<String> = stack_any::Key::new();
+/// ```
+pub struct Key<T> {
+    // A non-zero size makes the address of each static key unique.
+    _unique: u8,
+    _marker: core::marker::PhantomData<fn() -> T>,
+}
+
+impl<T> Key<T> {
+    /// Creates a key of `T`.
+    pub const fn new() -> Self {
+        Self {
+            _unique: 0,
+            _marker: core::marker::PhantomData,
+        }
+    }
+
+    /// Returns the identity of the key, which is typed so that keys of different types never alias.
+    fn id(&'static self) -> (usize, core::any::TypeId)
+    where
+        T: core::any::Any,
+    {
+        (self as *const Self as usize, core::any::TypeId::of::<T>())
+    }
+}
+
+impl<T> Default for Key<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> core::fmt::Debug for Key<T> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "Key<{}>({:p})", core::any::type_name::<T>(), self)
+    }
+}
+
+/// A map of values identified by typed [`Key`]s, each placed into a stack of `N` size.
+///
+/// Like `http::Extensions`, values are typed by their keys, so getting a value never fails on its type.
+///
+/// # Examples
+///
+/// ```
+/// use std::time::Duration;
+///
+/// use stack_any::{Key, StackExtensions};
+///
+/// static TIMEOUT: Key<Duration> = Key::new();
+/// static USER: Key<String> = Key::new();
+/// static HOST: Key<String> = Key::new();
+///
+/// let mut extensions = StackExtensions::<24>::new();
+/// extensions.insert(&TIMEOUT, Duration::from_secs(5)).unwrap();
+/// extensions.insert(&USER, String::from("alice")).unwrap();
+/// extensions.insert(&HOST, String::from("localhost")).unwrap();
+///
+/// assert_eq!(extensions.get(&TIMEOUT), Some(&Duration::from_secs(5)));
+/// assert_eq!(extensions.get(&USER).unwrap(), "alice");
+/// assert_eq!(extensions.get(&HOST).unwrap(), "localhost");
+///
+/// extensions.get_mut(&USER).unwrap().push_str("@example");
+/// assert_eq!(extensions.remove(&USER).unwrap(), "alice@example");
+/// assert_eq!(extensions.get(&HOST).unwrap(), "localhost");
+/// assert_eq!(extensions.len(), 2);
+/// ```
+pub struct StackExtensions<const N: usize> {
+    map: HashMap<(usize, core::any::TypeId), StackAny<N>>,
+}
+
+impl<const N: usize> StackExtensions<N> {
+    /// Creates an empty map.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let extensions = stack_any::StackExtensions::<8>::new();
+    /// assert!(extensions.is_empty());
+    /// ```
+    pub fn new() -> Self {
+        Self {
+            map: HashMap::new(),
+        }
+    }
+
+    /// Places `value` into a stack and then inserts it into the map, replacing the value of `key`.
+    /// Returns the replaced value if any.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`CapacityError`] carrying `value` back, leaving the map untouched,
+    /// if `T` size is larger than N or `T` alignment is larger than [`StackAny::ALIGN`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// static RETRIES: stack_any::Key<u32> = stack_any::Key::new();
+    /// static PAYLOAD: stack_any::Key<[u8; 16]> = stack_any::Key::new();
+    ///
+    /// let mut extensions = stack_any::StackExtensions::<8>::new();
+    /// assert_eq!(extensions.insert(&RETRIES, 3).unwrap(), None);
+    /// assert_eq!(extensions.insert(&RETRIES, 5).unwrap(), Some(3));
+    ///
+    /// let err = extensions.insert(&PAYLOAD, [0; 16]).unwrap_err();
+    /// assert_eq!(err.into_value(), [0; 16]);
+    /// assert_eq!(extensions.len(), 1);
+    /// ```
+    pub fn insert<T>(
+        &mut self,
+        key: &'static Key<T>,
+        value: T,
+    ) -> Result<Option<T>, CapacityError<T>>
+    where
+        T: core::any::Any,
+    {
+        let stack = StackAny::try_new_checked(value)?;
+
+        let previous = self.map.insert(key.id(), stack);
+        Ok(previous.map(|stack| match stack.downcast() {
+            Ok(value) => value,
+            Err(_) => unreachable!(),
+        }))
+    }
+
+    /// Returns reference to the value of `key`.
+    /// Returns None if the map contains no value of `key`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// static RETRIES: stack_any::Key<u32> = stack_any::Key::new();
+    /// static LIMIT: stack_any::Key<u32> = stack_any::Key::new();
+    ///
+    /// let mut extensions = stack_any::StackExtensions::<8>::new();
+    /// extensions.insert(&RETRIES, 3).unwrap();
+    ///
+    /// assert_eq!(extensions.get(&RETRIES), Some(&3));
+    /// assert_eq!(extensions.get(&LIMIT), None);
+    /// ```
+    pub fn get<T>(&self, key: &'static Key<T>) -> Option<&T>
+    where
+        T: core::any::Any,
+    {
+        let stack = self.map.get(&key.id())?;
+        Some(unsafe { stack.downcast_ref_unchecked() })
+    }
+
+    /// Returns mutable reference to the value of `key`.
+    /// Returns None if the map contains no value of `key`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// static RETRIES: stack_any::Key<u32> = stack_any::Key::new();
+    ///
+    /// let mut extensions = stack_any::StackExtensions::<8>::new();
+    /// extensions.insert(&RETRIES, 3).unwrap();
+    ///
+    /// *extensions.get_mut(&RETRIES).unwrap() += 1;
+    /// assert_eq!(extensions.get(&RETRIES), Some(&4));
+    /// ```
+    pub fn get_mut<T>(&mut self, key: &'static Key<T>) -> Option<&mut T>
+    where
+        T: core::any::Any,
+    {
+        let stack = self.map.get_mut(&key.id())?;
+        Some(unsafe { stack.downcast_mut_unchecked() })
+    }
+
+    /// Removes the value of `key` from the map and returns it.
+    /// Returns None if the map contains no value of `key`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// static RETRIES: stack_any::Key<u32> = stack_any::Key::new();
+    ///
+    /// let mut extensions = stack_any::StackExtensions::<8>::new();
+    /// extensions.insert(&RETRIES, 3).unwrap();
+    ///
+    /// assert_eq!(extensions.remove(&RETRIES), Some(3));
+    /// assert_eq!(extensions.remove(&RETRIES), None);
+    /// ```
+    pub fn remove<T>(&mut self, key: &'static Key<T>) -> Option<T>
+    where
+        T: core::any::Any,
+    {
+        let stack = self.map.remove(&key.id())?;
+        match stack.downcast() {
+            Ok(value) => Some(value),
+            Err(_) => unreachable!(),
+        }
+    }
+
+    /// Returns true if the map contains a value of `key`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// static RETRIES: stack_any::Key<u32> = stack_any::Key::new();
+    ///
+    /// let mut extensions = stack_any::StackExtensions::<8>::new();
+    /// assert!(!extensions.contains(&RETRIES));
+    ///
+    /// extensions.insert(&RETRIES, 3).unwrap();
+    /// assert!(extensions.contains(&RETRIES));
+    /// ```
+    pub fn contains<T>(&self, key: &'static Key<T>) -> bool
+    where
+        T: core::any::Any,
+    {
+        self.map.contains_key(&key.id())
+    }
+
+    /// Returns the number of values in the map.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// static RETRIES: stack_any::Key<u32> = stack_any::Key::new();
+    /// static LIMIT: stack_any::Key<u32> = stack_any::Key::new();
+    ///
+    /// let mut extensions = stack_any::StackExtensions::<8>::new();
+    /// extensions.insert(&RETRIES, 3).unwrap();
+    /// extensions.insert(&LIMIT, 3).unwrap();
+    /// assert_eq!(extensions.len(), 2);
+    /// ```
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    /// Returns true if the map contains no values.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let extensions = stack_any::StackExtensions::<8>::new();
+    /// assert!(extensions.is_empty());
+    /// ```
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    /// Drops every value, leaving the map empty.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::rc::Rc;
+    ///
+    /// static SHARED: stack_any::Key<Rc<()>> = stack_any::Key::new();
+    ///
+    /// let counter = Rc::new(());
+    ///
+    /// let mut extensions = stack_any::StackExtensions::<8>::new();
+    /// extensions.insert(&SHARED, counter.clone()).unwrap();
+    /// assert_eq!(Rc::strong_count(&counter), 2);
+    ///
+    /// extensions.clear();
+    /// assert!(extensions.is_empty());
+    /// assert_eq!(Rc::strong_count(&counter), 1);
+    /// ```
+    pub fn clear(&mut self) {
+        self.map.clear();
+    }
+}
+
+impl<const N: usize> Default for StackExtensions<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Formats the values as a set in arbitrary order, each of them as in `Debug` of [`StackAnyVec`](crate::StackAnyVec).
+///
+/// # Examples
+///
+/// ```
+/// use stack_any::{Key, StackExtensions};
+///
+/// static RETRIES: Key<u8> = Key::new();
+///
+/// let mut extensions = StackExtensions::<8>::new();
+/// extensions.insert(&RETRIES, 3).unwrap();
+/// #[cfg(feature = "type-name")]
+/// # assert_eq!(format!("{:?}", extensions), "{u8: <opaque 1 bytes>}");
+/// #[cfg(not(feature = "type-name"))]
+/// assert_eq!(format!("{:?}", extensions), "{<opaque 1 bytes>}");
+/// ```
+impl<const N: usize> core::fmt::Debug for StackExtensions<N> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_set()
+            .entries(self.map.values().map(DebugElement))
+            .finish()
+    }
+}
@@ -0,0 +1,422 @@
+//! A C compatible stack allocation and functions to handle it across a C ABI, enabled by the `ffi` feature.
+//!
+//! [`FfiStackAny`] is `#[repr(C)]` and identifies its value by a stable 64-bit hash instead of `TypeId`,
+//! which does not exist on the C side. Rust types are registered by implementing [`FfiType`],
+//! while C code passes the hash to [`stack_any_new`] directly.
+//!
+//! # Examples
+//!
+//! A value placed by Rust is handed to a C function through an `extern "C"` function pointer.
+//!
+//! ```
+//! use std::ffi::c_void;
+//! use std::sync::atomic::{AtomicUsize, Ordering};
+//!
+//! use stack_any::ffi::{self, FfiStackAny, FfiType};
+//!
+//! static DROPS: AtomicUsize = AtomicUsize::new(0);
+//!
+//! #[repr(C)]
+//! struct Point {
+//!     x: i32,
+//!     y: i32,
+//! }
+//!
+//! impl Drop for Point {
+//!     fn drop(&mut self) {
+//!         DROPS.fetch_add(1, Ordering::SeqCst);
+//!     }
+//! }
+//!
+//! unsafe impl FfiType for Point {
+//!     const TYPE_HASH: u64 = 0x5f3a_9c21_d4e8_0b17;
+//! }
+//!
+//! // Stands in for a function written in C.
+//! unsafe extern "C" fn consume(stack: *mut FfiStackAny) -> i32 {
+//!     if unsafe { ffi::stack_any_type_hash(stack) } != 0x5f3a_9c21_d4e8_0b17 {
+//!         return -1;
+//!     }
+//!
+//!     let point = unsafe { ffi::stack_any_get_ptr(stack) } as *const i32;
+//!     let sum = unsafe { *point + *point.add(1) };
+//!
+//!     unsafe { ffi::stack_any_drop(stack) };
+//!     sum
+//! }
+//!
+//! let callback: unsafe extern "C" fn(*mut FfiStackAny) -> i32 = consume;
+//!
+//! let mut stack = FfiStackAny::try_new(Point { x: 2, y: 3 }).unwrap();
+//! assert_eq!(unsafe { callback(&mut stack) }, 5);
+//! assert_eq!(DROPS.load(Ordering::SeqCst), 1);
+//!
+//! assert!(stack.is_empty());
+//! drop(stack);
+//! assert_eq!(DROPS.load(Ordering::SeqCst), 1);
+//! ```
+
+use core::ffi::c_void;
+
+use crate::{value_ptr, Buffer, StackAny};
+
+/// The capacity of [`FfiStackAny`] used by the C functions, in bytes.
+pub const CAPACITY: usize = 64;
+
+/// A function that drops the value placed at the pointer, callable from both sides of a C ABI.
+pub type DropFn = unsafe extern "C" fn(*mut c_void);
+
+/// A Rust type that can be placed into [`FfiStackAny`], identified by a stable hash.
+///
+/// # Safety
+///
+/// `TYPE_HASH` must not be used by any other type, whether placed by Rust or by C, and must not be zero.
+pub unsafe trait FfiType: core::any::Any {
+    /// The hash identifying the type on both sides of a C ABI.
+    const TYPE_HASH: u64;
+}
+
+/// A C compatible type that owns a stack allocation of `N` size.
+///
+/// The layout is the following C struct, where N is [`CAPACITY`] unless stated otherwise:
+///
+/// ```c
+/// typedef struct FfiStackAny {
+///     _Alignas(8) unsigned char bytes[N];
+///     size_t size;
+///     uint64_t type_hash;
+///     void (*drop_fn)(void *);
+/// } FfiStackAny;
+/// ```
+///
+/// An empty stack has zero `type_hash` and null `drop_fn`.
+///
+/// ```
+/// use std::mem::size_of;
+///
+/// let size = 64 + size_of::<usize>() + size_of::<u64>() + size_of::<stack_any::ffi::DropFn>();
+/// assert_eq!(size_of::<stack_any::ffi::FfiStackAny>(), size);
+/// ```
+#[repr(C)]
+pub struct FfiStackAny<const N: usize = CAPACITY> {
+    bytes: Buffer<N>,
+    size: usize,
+    type_hash: u64,
+    drop_fn: Option<DropFn>,
+}
+
+impl<const N: usize> FfiStackAny<N> {
+    /// Allocates N-size memory on the stack without placing any value into it.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let empty = stack_any::ffi::FfiStackAny::<8>::empty();
+    /// assert!(empty.is_empty());
+    /// assert_eq!(empty.type_hash(), 0);
+    /// ```
+    pub const fn empty() -> Self {
+        Self {
+            bytes: Buffer([core::mem::MaybeUninit::uninit(); N]),
+            size: 0,
+            type_hash: 0,
+            drop_fn: None,
+        }
+    }
+
+    /// Allocates N-size memory on the stack and then places `value` into it.
+    /// Returns None if `T` size is larger than N or `T` alignment is larger than [`StackAny::ALIGN`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// struct Meters(u64);
+    ///
+    /// unsafe impl stack_any::ffi::FfiType for Meters {
+    ///     const TYPE_HASH: u64 = 1;
+    /// }
+    ///
+    /// let five = stack_any::ffi::FfiStackAny::<8>::try_new(Meters(5)).unwrap();
+    /// assert_eq!(five.type_hash(), 1);
+    ///
+    /// assert!(stack_any::ffi::FfiStackAny::<4>::try_new(Meters(5)).is_none());
+    /// ```
+    pub fn try_new<T>(value: T) -> Option<Self>
+    where
+        T: FfiType,
+    {
+        if !StackAny::<N>::fits::<T>() {
+            return None;
+        }
+
+        let mut stack = Self::empty();
+
+        let ptr = value_ptr::<T>(stack.bytes.0.as_mut_ptr());
+        unsafe { core::ptr::write(ptr, value) };
+
+        stack.size = core::mem::size_of::<T>();
+        stack.type_hash = T::TYPE_HASH;
+        if core::mem::needs_drop::<T>() {
+            stack.drop_fn = Some(drop_thunk::<T>);
+        }
+        Some(stack)
+    }
+
+    /// Returns true if the stack contains nothing.
+    pub fn is_empty(&self) -> bool {
+        self.type_hash == 0
+    }
+
+    /// Returns the hash identifying the contained value type, or zero if the stack is empty.
+    pub fn type_hash(&self) -> u64 {
+        self.type_hash
+    }
+
+    /// Returns true if the contained value type is equal to `T`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// struct Meters(u32);
+    /// struct Seconds(u32);
+    ///
+    /// unsafe impl stack_any::ffi::FfiType for Meters {
+    ///     const TYPE_HASH: u64 = 1;
+    /// }
+    ///
+    /// unsafe impl stack_any::ffi::FfiType for Seconds {
+    ///     const TYPE_HASH: u64 = 2;
+    /// }
+    ///
+    /// let five = stack_any::ffi::FfiStackAny::<4>::try_new(Meters(5)).unwrap();
+    /// assert!(five.is::<Meters>());
+    /// assert!(!five.is::<Seconds>());
+    /// ```
+    pub fn is<T>(&self) -> bool
+    where
+        T: FfiType,
+    {
+        T::TYPE_HASH == self.type_hash
+    }
+
+    /// Attempt to return reference to the inner value as a concrete type.
+    /// Returns None if `T` is not equal to contained value type.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// #[derive(Debug, PartialEq)]
+    /// struct Meters(u32);
+    ///
+    /// unsafe impl stack_any::ffi::FfiType for Meters {
+    ///     const TYPE_HASH: u64 = 1;
+    /// }
+    ///
+    /// let five = stack_any::ffi::FfiStackAny::<4>::try_new(Meters(5)).unwrap();
+    /// assert_eq!(five.downcast_ref::<Meters>(), Some(&Meters(5)));
+    /// ```
+    pub fn downcast_ref<T>(&self) -> Option<&T>
+    where
+        T: FfiType,
+    {
+        if !self.is::<T>() {
+            return None;
+        }
+
+        let ptr = value_ptr::<T>(self.bytes.0.as_ptr() as *mut _);
+        Some(unsafe { &*ptr })
+    }
+
+    /// Attempt to return mutable reference to the inner value as a concrete type.
+    /// Returns None if `T` is not equal to contained value type.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// #[derive(Debug, PartialEq)]
+    /// struct Meters(u32);
+    ///
+    /// unsafe impl stack_any::ffi::FfiType for Meters {
+    ///     const TYPE_HASH: u64 = 1;
+    /// }
+    ///
+    /// let mut five = stack_any::ffi::FfiStackAny::<4>::try_new(Meters(5)).unwrap();
+    /// five.downcast_mut::<Meters>().unwrap().0 = 7;
+    /// assert_eq!(five.downcast_ref::<Meters>(), Some(&Meters(7)));
+    /// ```
+    pub fn downcast_mut<T>(&mut self) -> Option<&mut T>
+    where
+        T: FfiType,
+    {
+        if !self.is::<T>() {
+            return None;
+        }
+
+        let ptr = value_ptr::<T>(self.bytes.0.as_mut_ptr());
+        Some(unsafe { &mut *ptr })
+    }
+
+    /// Attempt to downcast the stack to a concrete type.
+    /// Returns the stack back if `T` is not equal to contained value type.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// struct Name(String);
+    ///
+    /// unsafe impl stack_any::ffi::FfiType for Name {
+    ///     const TYPE_HASH: u64 = 1;
+    /// }
+    ///
+    /// let name = stack_any::ffi::FfiStackAny::<24>::try_new(Name(String::from("x"))).unwrap();
+    /// assert_eq!(name.downcast::<Name>().ok().map(|name| name.0), Some(String::from("x")));
+    /// ```
+    pub fn downcast<T>(mut self) -> Result<T, Self>
+    where
+        T: FfiType,
+    {
+        if !self.is::<T>() {
+            return Err(self);
+        }
+
+        let ptr = value_ptr::<T>(self.bytes.0.as_mut_ptr());
+        let value = unsafe { core::ptr::read(ptr) };
+
+        self.set_empty();
+        Ok(value)
+    }
+
+    /// Drops the contained value, leaving the stack empty.
+    fn clear(&mut self) {
+        let drop_fn = self.drop_fn;
+        self.set_empty();
+
+        if let Some(drop_fn) = drop_fn {
+            unsafe { drop_fn(self.bytes.0.as_mut_ptr().cast()) };
+        }
+    }
+
+    /// Marks the stack as empty without dropping the contained value.
+    fn set_empty(&mut self) {
+        self.size = 0;
+        self.type_hash = 0;
+        self.drop_fn = None;
+    }
+}
+
+/// Drops the value of `T` placed at `ptr`.
+unsafe extern "C" fn drop_thunk<T>(ptr: *mut c_void) {
+    unsafe { core::ptr::drop_in_place(value_ptr::<T>(ptr.cast())) };
+}
+
+impl<const N: usize> core::fmt::Debug for FfiStackAny<N> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("FfiStackAny")
+            .field("capacity", &N)
+            .field("type_hash", &self.type_hash)
+            .field("size", &self.size)
+            .finish()
+    }
+}
+
+impl<const N: usize> Drop for FfiStackAny<N> {
+    fn drop(&mut self) {
+        self.clear();
+    }
+}
+
+/// Initializes `stack` by copying the `size` bytes of the value at `value` into it.
+/// Returns false without touching `stack` if the value is larger than [`CAPACITY`]
+/// or its alignment is larger than [`StackAny::ALIGN`].
+///
+/// The ownership of the value moves to the stack, and `drop_fn`, if any, is called on it when the stack is dropped.
+///
+/// # Safety
+///
+/// - `stack` must be valid for writes, and any value it contained is not dropped.
+/// - `value` must be valid for reads of `size` bytes and aligned to `align`.
+/// - `type_hash` must identify the value type and must not be zero.
+///
+/// # Examples
+///
+/// ```
+/// use std::ffi::c_void;
+/// use std::mem::MaybeUninit;
+///
+/// use stack_any::ffi::{self, FfiStackAny};
+///
+/// unsafe extern "C" fn release(ptr: *mut c_void) {
+///     unsafe { *(ptr as *mut u32) = 0 };
+/// }
+///
+/// let value = 5u32;
+/// let mut stack = MaybeUninit::<FfiStackAny>::uninit();
+///
+/// let placed = unsafe {
+///     ffi::stack_any_new(stack.as_mut_ptr(), &value as *const u32 as *const c_void, 4, 4, 7, Some(release))
+/// };
+/// assert!(placed);
+///
+/// let mut stack = unsafe { stack.assume_init() };
+/// assert_eq!(unsafe { ffi::stack_any_type_hash(&stack) }, 7);
+/// assert_eq!(unsafe { *(ffi::stack_any_get_ptr(&mut stack) as *const u32) }, 5);
+/// ```
+#[no_mangle]
+pub unsafe extern "C" fn stack_any_new(
+    stack: *mut FfiStackAny,
+    value: *const c_void,
+    size: usize,
+    align: usize,
+    type_hash: u64,
+    drop_fn: Option<DropFn>,
+) -> bool {
+    if size != 0 && (CAPACITY < size || StackAny::<CAPACITY>::ALIGN < align) {
+        return false;
+    }
+
+    let mut new = FfiStackAny::empty();
+    let dst = new.bytes.0.as_mut_ptr();
+    unsafe { core::ptr::copy_nonoverlapping(value.cast(), dst, size) };
+
+    new.size = size;
+    new.type_hash = type_hash;
+    new.drop_fn = drop_fn;
+
+    unsafe { core::ptr::write(stack, new) };
+    true
+}
+
+/// Drops the value contained by `stack`, leaving it empty. Does nothing if it is already empty.
+///
+/// # Safety
+///
+/// `stack` must point to an initialized [`FfiStackAny`].
+#[no_mangle]
+pub unsafe extern "C" fn stack_any_drop(stack: *mut FfiStackAny) {
+    unsafe { (*stack).clear() };
+}
+
+/// Returns the pointer to the value contained by `stack`, or null if it is empty.
+///
+/// # Safety
+///
+/// `stack` must point to an initialized [`FfiStackAny`].
+#[no_mangle]
+pub unsafe extern "C" fn stack_any_get_ptr(stack: *mut FfiStackAny) -> *mut c_void {
+    let stack = unsafe { &mut *stack };
+    if stack.is_empty() {
+        return core::ptr::null_mut();
+    }
+
+    stack.bytes.0.as_mut_ptr().cast()
+}
+
+/// Returns the hash identifying the value type contained by `stack`, or zero if it is empty.
+///
+/// # Safety
+///
+/// `stack` must point to an initialized [`FfiStackAny`].
+#[no_mangle]
+pub unsafe extern "C" fn stack_any_type_hash(stack: *const FfiStackAny) -> u64 {
+    unsafe { (*stack).type_hash }
+}
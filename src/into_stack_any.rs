@@ -0,0 +1,65 @@
+use crate::StackAny;
+
+/// An extension trait that places any value into a [`StackAny`].
+///
+/// # Examples
+///
+/// ```
+/// use stack_any::{IntoStackAny, StackAny};
+///
+/// let stacks: Vec<StackAny<32>> = ["alice", "bob"]
+///     .map(String::from)
+///     .into_iter()
+///     .map(IntoStackAny::into_stack_any_exact)
+///     .collect();
+/// assert_eq!(stacks[1].downcast_ref::<String>().unwrap(), "bob");
+///
+/// let values = [vec![0u8; 1], vec![0u8; 2]];
+/// let stacks: Result<Vec<_>, _> = values.into_iter().map(IntoStackAny::into_stack_any::<16>).collect();
+/// assert_eq!(stacks.err(), Some(vec![0]));
+/// ```
+pub trait IntoStackAny: core::any::Any + Sized {
+    /// Allocates N-size memory on the stack and then places the value into it.
+    /// Returns the value back if its size is larger than N or its alignment is larger than [`StackAny::ALIGN`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use stack_any::IntoStackAny;
+    ///
+    /// let five = 5u64.into_stack_any::<8>().unwrap();
+    /// assert_eq!(five.downcast_ref::<u64>(), Some(&5));
+    ///
+    /// let name = String::from("x");
+    /// assert_eq!(name.into_stack_any::<8>().err().unwrap(), "x");
+    /// ```
+    fn into_stack_any<const N: usize>(self) -> Result<StackAny<N>, Self> {
+        StackAny::try_new_or_return(self)
+    }
+
+    /// Allocates N-size memory on the stack and then places the value into it.
+    ///
+    /// The capacity is inferred from the context, such as a type annotation or a parameter type,
+    /// as it cannot be computed from `Self` in a const generic.
+    /// Whether the value fits is checked at compile time, as in [`StackAny::new`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use stack_any::{IntoStackAny, StackAny};
+    ///
+    /// let five: StackAny<8> = 5u64.into_stack_any_exact();
+    /// assert_eq!(five.downcast_ref::<u64>(), Some(&5));
+    /// ```
+    ///
+    /// ```compile_fail
+    /// use stack_any::{IntoStackAny, StackAny};
+    ///
+    /// let name: StackAny<8> = String::from("x").into_stack_any_exact();
+    /// ```
+    fn into_stack_any_exact<const N: usize>(self) -> StackAny<N> {
+        StackAny::new(self)
+    }
+}
+
+impl<T> IntoStackAny for T where T: core::any::Any {}
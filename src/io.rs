@@ -0,0 +1,496 @@
+use std::io::{IoSlice, IoSliceMut, Read, Write};
+
+use crate::{CapacityError, StackAny};
+
+/// A convertible type that owns a stack allocation of `N` size containing a writer, and is a writer itself.
+///
+/// `write`, `write_vectored` and `flush` are delegated to the contained writer through the functions
+/// captured at construction. The writer is dropped with `StackWrite`, e.g. closing a file.
+///
+/// # Examples
+///
+/// ```
+/// use std::io::Write;
+///
+/// use stack_any::StackWrite;
+///
+/// fn sink(capture: bool) -> StackWrite<24> {
+///     match capture {
+///         true => StackWrite::new(Vec::<u8>::new()),
+///         false => StackWrite::new(std::io::sink()),
+///     }
+/// }
+///
+/// let mut captured = sink(true);
+/// write!(captured, "x = {}", 5).unwrap();
+/// assert_eq!(captured.downcast_ref::<Vec<u8>>().unwrap(), b"x = 5");
+///
+/// let mut discarded = sink(false);
+/// write!(discarded, "x = {}", 5).unwrap();
+/// assert!(discarded.downcast_ref::<Vec<u8>>().is_none());
+/// ```
+///
+/// Writes are passed through as they are, so buffering is up to the contained writer.
+///
+/// ```
+/// use std::io::{BufWriter, Write};
+///
+/// let path = std::env::temp_dir().join(format!("stack-any-write-{}", std::process::id()));
+/// let file = std::fs::File::create(&path).unwrap();
+///
+/// let mut writer = stack_any::StackWrite::<32>::new(BufWriter::new(file));
+/// writer.write_all(b"hello").unwrap();
+/// assert_eq!(std::fs::read(&path).unwrap(), b"");
+///
+/// writer.flush().unwrap();
+/// assert_eq!(std::fs::read(&path).unwrap(), b"hello");
+///
+/// writer.write_all(b" world").unwrap();
+/// drop(writer);
+/// assert_eq!(std::fs::read(&path).unwrap(), b"hello world");
+/// # std::fs::remove_file(&path).unwrap();
+/// ```
+pub struct StackWrite<const N: usize> {
+    stack: StackAny<N>,
+    write_fn: fn(&mut StackAny<N>, &[u8]) -> std::io::Result<usize>,
+    write_vectored_fn: fn(&mut StackAny<N>, &[IoSlice<'_>]) -> std::io::Result<usize>,
+    flush_fn: fn(&mut StackAny<N>) -> std::io::Result<()>,
+}
+
+impl<const N: usize> StackWrite<N> {
+    /// Allocates N-size memory on the stack and then places `writer` into it.
+    ///
+    /// Whether `W` fits is checked at compile time, as in [`StackAny::new`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let writer = stack_any::StackWrite::<24>::new(Vec::<u8>::new());
+    /// ```
+    ///
+    /// ```compile_fail
+    /// let writer = stack_any::StackWrite::<8>::new(Vec::<u8>::new());
+    /// ```
+    pub fn new<W>(writer: W) -> Self
+    where
+        W: Write + 'static,
+    {
+        Self::from_stack_any::<W>(StackAny::new(writer))
+    }
+
+    /// Allocates N-size memory on the stack and then places `writer` into it.
+    /// Returns None if `W` size is larger than N or `W` alignment is larger than [`StackAny::ALIGN`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let writer = stack_any::StackWrite::<8>::try_new(Vec::<u8>::new());
+    /// assert!(writer.is_none());
+    /// ```
+    pub fn try_new<W>(writer: W) -> Option<Self>
+    where
+        W: Write + 'static,
+    {
+        StackAny::try_new(writer).map(Self::from_stack_any::<W>)
+    }
+
+    /// Allocates N-size memory on the stack and then places `writer` into it.
+    /// Returns [`CapacityError`] carrying `writer` back if `W` size is larger than N
+    /// or `W` alignment is larger than [`StackAny::ALIGN`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let err = stack_any::StackWrite::<8>::try_new_checked(vec![5u8]).unwrap_err();
+    /// assert_eq!(err.into_value(), [5]);
+    /// ```
+    pub fn try_new_checked<W>(writer: W) -> Result<Self, CapacityError<W>>
+    where
+        W: Write + 'static,
+    {
+        StackAny::try_new_checked(writer).map(Self::from_stack_any::<W>)
+    }
+
+    /// Captures the functions writing to `W` contained by `stack`.
+    fn from_stack_any<W>(stack: StackAny<N>) -> Self
+    where
+        W: Write + 'static,
+    {
+        Self {
+            stack,
+            write_fn: |stack, buf| unsafe { stack.downcast_mut_unchecked::<W>() }.write(buf),
+            write_vectored_fn: |stack, bufs| {
+                unsafe { stack.downcast_mut_unchecked::<W>() }.write_vectored(bufs)
+            },
+            flush_fn: |stack| unsafe { stack.downcast_mut_unchecked::<W>() }.flush(),
+        }
+    }
+
+    /// Returns true if the contained writer type is equal to `W`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let writer = stack_any::StackWrite::<24>::new(Vec::<u8>::new());
+    /// assert!(writer.is::<Vec<u8>>());
+    /// assert!(!writer.is::<std::io::Sink>());
+    /// ```
+    pub fn is<W>(&self) -> bool
+    where
+        W: Write + 'static,
+    {
+        self.stack.is::<W>()
+    }
+
+    /// Attempt to return reference to the inner writer as a concrete type.
+    /// Returns None if `W` is not equal to contained writer type.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let writer = stack_any::StackWrite::<24>::new(vec![5u8]);
+    /// assert_eq!(writer.downcast_ref::<Vec<u8>>(), Some(&vec![5]));
+    /// assert!(writer.downcast_ref::<std::io::Sink>().is_none());
+    /// ```
+    pub fn downcast_ref<W>(&self) -> Option<&W>
+    where
+        W: Write + 'static,
+    {
+        self.stack.downcast_ref()
+    }
+
+    /// Attempt to return mutable reference to the inner writer as a concrete type.
+    /// Returns None if `W` is not equal to contained writer type.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut writer = stack_any::StackWrite::<24>::new(vec![5u8]);
+    /// writer.downcast_mut::<Vec<u8>>().unwrap().clear();
+    /// assert_eq!(writer.downcast_ref::<Vec<u8>>(), Some(&vec![]));
+    /// ```
+    pub fn downcast_mut<W>(&mut self) -> Option<&mut W>
+    where
+        W: Write + 'static,
+    {
+        self.stack.downcast_mut()
+    }
+
+    /// Attempt to downcast to a concrete type, recovering the writer.
+    /// Returns the writer back if `W` is not equal to contained writer type.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::io::Write;
+    ///
+    /// let mut writer = stack_any::StackWrite::<24>::new(Vec::<u8>::new());
+    /// writer.write_all(b"x").unwrap();
+    ///
+    /// let writer = writer.downcast::<std::io::Sink>().unwrap_err();
+    /// assert_eq!(writer.downcast::<Vec<u8>>().ok(), Some(b"x".to_vec()));
+    /// ```
+    pub fn downcast<W>(self) -> Result<W, Self>
+    where
+        W: Write + 'static,
+    {
+        let Self {
+            stack,
+            write_fn,
+            write_vectored_fn,
+            flush_fn,
+        } = self;
+
+        stack.downcast().map_err(|stack| Self {
+            stack,
+            write_fn,
+            write_vectored_fn,
+            flush_fn,
+        })
+    }
+}
+
+impl<const N: usize> Write for StackWrite<N> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        (self.write_fn)(&mut self.stack, buf)
+    }
+
+    /// Writes through the `write_vectored` of the contained writer, e.g. a single `writev` call.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::io::{IoSlice, Write};
+    ///
+    /// let mut writer = stack_any::StackWrite::<24>::new(Vec::<u8>::new());
+    /// let written = writer.write_vectored(&[IoSlice::new(b"ab"), IoSlice::new(b"c")]).unwrap();
+    /// assert_eq!(written, 3);
+    /// assert_eq!(writer.downcast_ref::<Vec<u8>>().unwrap(), b"abc");
+    /// ```
+    fn write_vectored(&mut self, bufs: &[IoSlice<'_>]) -> std::io::Result<usize> {
+        (self.write_vectored_fn)(&mut self.stack, bufs)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        (self.flush_fn)(&mut self.stack)
+    }
+}
+
+impl<const N: usize> core::fmt::Debug for StackWrite<N> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_tuple("StackWrite").field(&self.stack).finish()
+    }
+}
+
+/// A convertible type that owns a stack allocation of `N` size containing a reader, and is a reader itself.
+///
+/// `read`, `read_vectored` and `read_to_end` are delegated to the contained reader through the functions
+/// captured at construction, so zero-length and interrupted reads behave exactly as the contained reader does.
+///
+/// # Examples
+///
+/// ```
+/// use std::io::{Cursor, Read};
+///
+/// use stack_any::StackRead;
+///
+/// fn source(chained: bool) -> StackRead<48> {
+///     match chained {
+///         true => StackRead::new(Cursor::new(&b"ab"[..]).chain(&b"cd"[..])),
+///         false => StackRead::new(Cursor::new(b"xyz".to_vec())),
+///     }
+/// }
+///
+/// let mut text = String::new();
+/// source(true).read_to_string(&mut text).unwrap();
+/// assert_eq!(text, "abcd");
+///
+/// let mut reader = source(false);
+/// let mut buf = [0; 2];
+/// reader.read_exact(&mut buf).unwrap();
+/// assert_eq!(&buf, b"xy");
+/// assert_eq!(reader.downcast_ref::<Cursor<Vec<u8>>>().unwrap().position(), 2);
+/// ```
+///
+/// ```
+/// use std::io::{ErrorKind, Read};
+///
+/// struct Flaky(bool);
+///
+/// impl Read for Flaky {
+///     fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+///         if std::mem::replace(&mut self.0, false) {
+///             return Err(ErrorKind::Interrupted.into());
+///         }
+///         buf[..1].copy_from_slice(b"x");
+///         Ok(1)
+///     }
+/// }
+///
+/// let mut reader = stack_any::StackRead::<8>::new(Flaky(true));
+/// assert_eq!(reader.read(&mut []).unwrap_err().kind(), ErrorKind::Interrupted);
+///
+/// let mut buf = [0; 2];
+/// reader.read_exact(&mut buf).unwrap();
+/// assert_eq!(&buf, b"xx");
+/// ```
+pub struct StackRead<const N: usize> {
+    stack: StackAny<N>,
+    read_fn: fn(&mut StackAny<N>, &mut [u8]) -> std::io::Result<usize>,
+    read_vectored_fn: fn(&mut StackAny<N>, &mut [IoSliceMut<'_>]) -> std::io::Result<usize>,
+    read_to_end_fn: fn(&mut StackAny<N>, &mut Vec<u8>) -> std::io::Result<usize>,
+}
+
+impl<const N: usize> StackRead<N> {
+    /// Allocates N-size memory on the stack and then places `reader` into it.
+    ///
+    /// Whether `R` fits is checked at compile time, as in [`StackAny::new`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let reader = stack_any::StackRead::<16>::new(&b"x"[..]);
+    /// ```
+    ///
+    /// ```compile_fail
+    /// let reader = stack_any::StackRead::<8>::new(&b"x"[..]);
+    /// ```
+    pub fn new<R>(reader: R) -> Self
+    where
+        R: Read + 'static,
+    {
+        Self::from_stack_any::<R>(StackAny::new(reader))
+    }
+
+    /// Allocates N-size memory on the stack and then places `reader` into it.
+    /// Returns None if `R` size is larger than N or `R` alignment is larger than [`StackAny::ALIGN`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let reader = stack_any::StackRead::<8>::try_new(&b"x"[..]);
+    /// assert!(reader.is_none());
+    /// ```
+    pub fn try_new<R>(reader: R) -> Option<Self>
+    where
+        R: Read + 'static,
+    {
+        StackAny::try_new(reader).map(Self::from_stack_any::<R>)
+    }
+
+    /// Allocates N-size memory on the stack and then places `reader` into it.
+    /// Returns [`CapacityError`] carrying `reader` back if `R` size is larger than N
+    /// or `R` alignment is larger than [`StackAny::ALIGN`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let err = stack_any::StackRead::<8>::try_new_checked(&b"x"[..]).unwrap_err();
+    /// assert_eq!(err.into_value(), b"x");
+    /// ```
+    pub fn try_new_checked<R>(reader: R) -> Result<Self, CapacityError<R>>
+    where
+        R: Read + 'static,
+    {
+        StackAny::try_new_checked(reader).map(Self::from_stack_any::<R>)
+    }
+
+    /// Captures the functions reading from `R` contained by `stack`.
+    fn from_stack_any<R>(stack: StackAny<N>) -> Self
+    where
+        R: Read + 'static,
+    {
+        Self {
+            stack,
+            read_fn: |stack, buf| unsafe { stack.downcast_mut_unchecked::<R>() }.read(buf),
+            read_vectored_fn: |stack, bufs| {
+                unsafe { stack.downcast_mut_unchecked::<R>() }.read_vectored(bufs)
+            },
+            read_to_end_fn: |stack, buf| {
+                unsafe { stack.downcast_mut_unchecked::<R>() }.read_to_end(buf)
+            },
+        }
+    }
+
+    /// Returns true if the contained reader type is equal to `R`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let reader = stack_any::StackRead::<16>::new(&b"x"[..]);
+    /// assert!(reader.is::<&[u8]>());
+    /// assert!(!reader.is::<std::io::Empty>());
+    /// ```
+    pub fn is<R>(&self) -> bool
+    where
+        R: Read + 'static,
+    {
+        self.stack.is::<R>()
+    }
+
+    /// Attempt to return reference to the inner reader as a concrete type.
+    /// Returns None if `R` is not equal to contained reader type.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let reader = stack_any::StackRead::<16>::new(&b"x"[..]);
+    /// assert_eq!(reader.downcast_ref::<&[u8]>(), Some(&&b"x"[..]));
+    /// assert!(reader.downcast_ref::<std::io::Empty>().is_none());
+    /// ```
+    pub fn downcast_ref<R>(&self) -> Option<&R>
+    where
+        R: Read + 'static,
+    {
+        self.stack.downcast_ref()
+    }
+
+    /// Attempt to return mutable reference to the inner reader as a concrete type.
+    /// Returns None if `R` is not equal to contained reader type.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::io::{Cursor, Read};
+    ///
+    /// let mut reader = stack_any::StackRead::<32>::new(Cursor::new(b"xy".to_vec()));
+    /// reader.downcast_mut::<Cursor<Vec<u8>>>().unwrap().set_position(1);
+    ///
+    /// let mut text = String::new();
+    /// reader.read_to_string(&mut text).unwrap();
+    /// assert_eq!(text, "y");
+    /// ```
+    pub fn downcast_mut<R>(&mut self) -> Option<&mut R>
+    where
+        R: Read + 'static,
+    {
+        self.stack.downcast_mut()
+    }
+
+    /// Attempt to downcast to a concrete type, recovering the reader.
+    /// Returns the reader back if `R` is not equal to contained reader type.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::io::Read;
+    ///
+    /// let mut reader = stack_any::StackRead::<16>::new(&b"xy"[..]);
+    /// reader.read_exact(&mut [0]).unwrap();
+    ///
+    /// let reader = reader.downcast::<std::io::Empty>().unwrap_err();
+    /// assert_eq!(reader.downcast::<&[u8]>().ok(), Some(&b"y"[..]));
+    /// ```
+    pub fn downcast<R>(self) -> Result<R, Self>
+    where
+        R: Read + 'static,
+    {
+        let Self {
+            stack,
+            read_fn,
+            read_vectored_fn,
+            read_to_end_fn,
+        } = self;
+
+        stack.downcast().map_err(|stack| Self {
+            stack,
+            read_fn,
+            read_vectored_fn,
+            read_to_end_fn,
+        })
+    }
+}
+
+impl<const N: usize> Read for StackRead<N> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        (self.read_fn)(&mut self.stack, buf)
+    }
+
+    /// Reads through the `read_vectored` of the contained reader, e.g. a single `readv` call.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::io::{IoSliceMut, Read};
+    ///
+    /// let mut reader = stack_any::StackRead::<16>::new(&b"abc"[..]);
+    ///
+    /// let (mut a, mut b) = ([0; 2], [0; 1]);
+    /// let read = reader.read_vectored(&mut [IoSliceMut::new(&mut a), IoSliceMut::new(&mut b)]).unwrap();
+    /// assert_eq!(read, 3);
+    /// assert_eq!((&a, &b), (b"ab", b"c"));
+    /// ```
+    fn read_vectored(&mut self, bufs: &mut [IoSliceMut<'_>]) -> std::io::Result<usize> {
+        (self.read_vectored_fn)(&mut self.stack, bufs)
+    }
+
+    fn read_to_end(&mut self, buf: &mut Vec<u8>) -> std::io::Result<usize> {
+        (self.read_to_end_fn)(&mut self.stack, buf)
+    }
+}
+
+impl<const N: usize> core::fmt::Debug for StackRead<N> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_tuple("StackRead").field(&self.stack).finish()
+    }
+}
@@ -16,137 +16,2987 @@
 //! assert_eq!(stacks[0].downcast_ref(), Some(&vec![5]));
 //! assert_eq!(stacks[1].downcast_ref(), Some(&vec!['x']));
 //! ```
+//!
+//! ## no_std
+//!
+//! Disable the default `std` feature to use this crate without the standard library.
+//! The macros only refer to `core`, so they expand the same way in both modes.
+//!
+//! ```
+//! let mut stack = stack_any::stack_any!(core::cell::Cell<u32>, core::cell::Cell::new(5));
+//! stack.downcast_mut::<core::cell::Cell<u32>>().unwrap().set(7);
+//!
+//! assert_eq!(stack.downcast::<core::cell::Cell<u32>>().map(|cell| cell.get()).ok(), Some(7));
+//! ```
 
 #![cfg_attr(not(feature = "std"), no_std)]
+#![cfg_attr(feature = "unsize", feature(unsize))]
 
-/// A convertible type that owns a stack allocation of `N` size.
+#[cfg(feature = "alloc")]
+extern crate alloc;
+
+mod aligned;
+#[cfg(feature = "arbitrary")]
+pub mod arbitrary;
+#[cfg(feature = "futures-io")]
+mod async_io;
+mod builder;
+mod copy;
+mod error;
+#[cfg(feature = "std")]
+mod extensions;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+mod into_stack_any;
+#[cfg(feature = "std")]
+mod io;
+mod pinned;
+#[cfg(feature = "serde")]
+mod registry;
+mod scoped;
+mod send;
+#[cfg(feature = "serde")]
+mod serialize;
+mod slice;
+#[cfg(feature = "alloc")]
+mod small;
+mod stack_any_bag;
+mod stack_any_column;
+mod stack_any_deque;
+#[cfg(feature = "std")]
+mod stack_any_map;
+mod stack_any_pool;
+mod stack_any_vec;
+mod stack_box;
+mod stack_error;
+mod stack_fn;
+mod stack_future;
+mod stack_hasher;
+mod stack_iter;
+#[cfg(feature = "serde")]
+mod stack_serialize;
+mod stack_slice;
+#[cfg(feature = "futures")]
+mod stack_stream;
+mod stack_tuple;
+mod sync;
+mod tagged;
+mod unchecked;
+mod vtable;
+#[cfg(feature = "zeroize")]
+mod zeroizing;
+
+pub use aligned::{Align, Alignment, StackAnyAligned};
+#[cfg(feature = "futures-io")]
+pub use async_io::{StackAsyncDuplex, StackAsyncRead, StackAsyncWrite};
+pub use builder::StackAnyBuilder;
+pub use copy::CopyStackAny;
+pub use error::{
+    CapacityError, DowncastError, MapError, SliceCapacityError, SwapError, TypeMismatch,
+};
+#[cfg(feature = "std")]
+pub use extensions::{Key, StackExtensions};
+pub use into_stack_any::IntoStackAny;
+#[cfg(feature = "std")]
+pub use io::{StackRead, StackWrite};
+pub use pinned::PinnedStackAny;
+#[cfg(feature = "serde")]
+pub use registry::{Registry, StackAnySeed};
+pub use scoped::{ScopedStackAny, ScopedType};
+pub use send::StackAnySend;
+pub use slice::{IterDowncast, IterDowncastMut, StackAnySlice};
+#[cfg(feature = "alloc")]
+pub use small::{SmallAny, StoredAny};
+pub use stack_any_bag::StackAnyBag;
+pub use stack_any_column::StackAnyColumn;
+pub use stack_any_deque::StackAnyDeque;
+#[cfg(feature = "std")]
+pub use stack_any_map::{Entry, OccupiedEntry, StackAnyMap, VacantEntry};
+pub use stack_any_pool::{PoolKey, StackAnyPool};
+pub use stack_any_vec::{Drain, StackAnyVec};
+pub use stack_box::StackBox;
+pub use stack_error::{Context, StackError};
+pub use stack_fn::{StackFn, StackFnMut, StackFnOnce};
+pub use stack_future::StackFuture;
+pub use stack_hasher::StackHasher;
+pub use stack_iter::StackIter;
+#[cfg(feature = "serde")]
+pub use stack_serialize::StackSerialize;
+pub use stack_slice::StackSlice;
+#[cfg(feature = "futures")]
+pub use stack_stream::StackStream;
+pub use stack_tuple::StackTuple;
+pub use sync::StackAnySync;
+pub use tagged::TaggedStackAny;
+pub use unchecked::UncheckedStackAny;
+pub use vtable::VTable;
+#[cfg(feature = "zeroize")]
+pub use zeroizing::ZeroizingStackAny;
+
+/// Byte storage whose alignment is raised so that common types can be placed into it.
+#[derive(Clone, Copy)]
+#[repr(C, align(8))]
+struct Buffer<const N: usize>([core::mem::MaybeUninit<u8>; N]);
+
+/// Returns a pointer to `T` placed at `ptr`.
+/// Zero-sized `T` does not occupy the storage, so a dangling pointer is returned instead.
+fn value_ptr<T>(ptr: *mut core::mem::MaybeUninit<u8>) -> *mut T {
+    if core::mem::size_of::<T>() == 0 {
+        return core::ptr::NonNull::dangling().as_ptr();
+    }
+
+    ptr as *mut T
+}
+
+/// Returns true if a value of `size` and `align` can be placed into `capacity`-size memory aligned to `max_align`.
+/// Zero-sized values do not occupy the storage, so they fit regardless of their alignment.
+const fn fits(size: usize, align: usize, capacity: usize, max_align: usize) -> bool {
+    size == 0 || (size <= capacity && align <= max_align)
+}
+
+/// Returns a pointer to the value described by `meta` placed at `ptr`.
+/// Zero-sized values do not occupy the storage, so `meta` itself is returned instead.
+fn any_ptr<Dyn>(meta: *mut Dyn, size: usize, ptr: *mut core::mem::MaybeUninit<u8>) -> *mut Dyn
+where
+    Dyn: ?Sized,
+{
+    let mut any = meta;
+    if size != 0 {
+        // The address is stored first in a pointer to an unsized type, followed by its metadata.
+        unsafe { *(&mut any as *mut *mut Dyn).cast::<*mut _>() = ptr };
+    }
+    any
+}
+
+/// Returns the maximum of `values`, or 0 if it is empty. Used by the macros to compute capacities.
+#[doc(hidden)]
+pub const fn max(values: &[usize]) -> usize {
+    let mut max = 0;
+    let mut i = 0;
+    while i < values.len() {
+        if values[i] > max {
+            max = values[i];
+        }
+        i += 1;
+    }
+    max
+}
+
+/// Returns the size of values of `layouts`, pairs of a size and an alignment, placed one after another
+/// at offsets aligned to each alignment. Used by the macros to compute capacities.
+#[doc(hidden)]
+pub const fn packed_size(layouts: &[(usize, usize)]) -> usize {
+    let mut end: usize = 0;
+    let mut i = 0;
+    while i < layouts.len() {
+        let (size, align) = layouts[i];
+        end = end.next_multiple_of(align) + size;
+        i += 1;
+    }
+    end
+}
+
+/// A marker type contained by an empty stack.
+/// It is private, so no value of other types is ever mistaken for it.
+struct Empty;
+
+/// A marker type contained by a poisoned stack, whose value was moved out to a closure that panicked.
+/// It is private for the same reason as [`Empty`].
+struct Poisoned;
+
+/// The parts that make up a [`StackAny`], returned by [`StackAny::into_raw_parts`].
 #[derive(Debug)]
+pub struct RawParts<const N: usize> {
+    /// The `TypeId` of the contained value.
+    pub type_id: core::any::TypeId,
+    /// The bytes of the contained value, placed at the beginning.
+    pub bytes: [core::mem::MaybeUninit<u8>; N],
+    /// The vtable of the contained value type.
+    pub vtable: &'static VTable,
+}
+
+/// A convertible type that owns a stack allocation of `N` size.
+///
+/// The allocation is aligned to [`StackAny::ALIGN`] bytes, so types with a stricter
+/// alignment requirement can not be placed into it.
+///
+/// Besides the allocation, it only holds the `TypeId` and a pointer to the [`VTable`] of the
+/// contained value type, however many capabilities are captured by [`StackAnyBuilder`].
+///
+/// ```
+/// use std::mem::size_of;
+///
+/// let size = size_of::<std::any::TypeId>() + 24 + size_of::<&stack_any::VTable>();
+/// assert_eq!(size_of::<stack_any::StackAny<24>>(), size);
+/// ```
+///
+/// Since any type can be placed into it, `StackAny` is neither `Send` nor `Sync`.
+///
+/// ```compile_fail
+/// fn assert_send<T: Send>() {}
+/// assert_send::<stack_any::StackAny<8>>();
+/// ```
+///
+/// ```compile_fail
+/// fn assert_sync<T: Sync>() {}
+/// assert_sync::<stack_any::StackAny<8>>();
+/// ```
 pub struct StackAny<const N: usize> {
     type_id: core::any::TypeId,
-    bytes: [core::mem::MaybeUninit<u8>; N],
-    drop_fn: fn(*mut core::mem::MaybeUninit<u8>) -> (),
+    bytes: Buffer<N>,
+    vtable: &'static VTable,
+    _marker: core::marker::PhantomData<*mut ()>,
 }
 
 impl<const N: usize> StackAny<N> {
+    /// The alignment of the allocation in bytes.
+    pub const ALIGN: usize = core::mem::align_of::<Buffer<N>>();
+
+    /// Allocates N-size memory on the stack and then places `value` into it.
+    ///
+    /// Whether `T` fits is checked at compile time, so this never fails at runtime.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let five = stack_any::StackAny::<8>::new(5u64);
+    /// assert_eq!(five.downcast_ref::<u64>(), Some(&5));
+    /// ```
+    ///
+    /// A value that does not fit is rejected by the compiler.
+    ///
+    /// ```compile_fail
+    /// let five = stack_any::StackAny::<4>::new::<u64>(5);
+    /// ```
+    pub fn new<T>(value: T) -> Self
+    where
+        T: core::any::Any,
+    {
+        const { assert!(Self::fits::<T>(), "value does not fit in the stack") };
+
+        match Self::try_new_or_return(value) {
+            Ok(stack) => stack,
+            Err(_) => unreachable!(),
+        }
+    }
+
     /// Allocates N-size memory on the stack and then places `value` into it.
-    /// Returns None if `T` size is larger than N.
+    /// Returns None if `T` size is larger than N or `T` alignment is larger than [`StackAny::ALIGN`].
+    /// Zero-sized types are always accepted.
+    ///
+    /// The rejected value is dropped, use [`StackAny::try_new_checked`] to find out why
+    /// and to get it back instead.
     ///
     /// # Examples
     ///
     /// ```
     /// let five = stack_any::StackAny::<{ std::mem::size_of::<i32>() }>::try_new(5);
     /// ```
+    ///
+    /// Over-aligned types are rejected.
+    ///
+    /// ```
+    /// #[repr(align(32))]
+    /// struct Aligned(u8);
+    ///
+    /// let aligned = stack_any::StackAny::<32>::try_new(Aligned(5));
+    /// assert!(aligned.is_none());
+    /// ```
+    ///
+    /// Zero-sized types are dropped as usual.
+    ///
+    /// ```
+    /// use std::sync::atomic::{AtomicBool, Ordering};
+    ///
+    /// static DROPPED: AtomicBool = AtomicBool::new(false);
+    ///
+    /// #[repr(align(32))]
+    /// struct Marker;
+    ///
+    /// impl Drop for Marker {
+    ///     fn drop(&mut self) {
+    ///         DROPPED.store(true, Ordering::SeqCst);
+    ///     }
+    /// }
+    ///
+    /// let marker = stack_any::StackAny::<0>::try_new(Marker).unwrap();
+    /// assert!(marker.downcast_ref::<Marker>().is_some());
+    /// assert!(!DROPPED.load(Ordering::SeqCst));
+    ///
+    /// drop(marker);
+    /// assert!(DROPPED.load(Ordering::SeqCst));
+    /// ```
     pub fn try_new<T>(value: T) -> Option<Self>
     where
         T: core::any::Any,
     {
-        let type_id = core::any::TypeId::of::<T>();
-        let size = core::mem::size_of::<T>();
+        Self::try_new_or_return(value).ok()
+    }
 
-        if N < size {
-            return None;
-        }
+    /// Allocates N-size memory on the stack and then places `value` into it.
+    /// Returns `value` back if `T` size is larger than N or `T` alignment is larger than [`StackAny::ALIGN`],
+    /// so that it can be placed elsewhere, e.g. on the heap.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let five = stack_any::StackAny::<4>::try_new_or_return(5i32);
+    /// assert!(five.is_ok());
+    ///
+    /// let five = stack_any::StackAny::<4>::try_new_or_return(5i64);
+    /// assert_eq!(five.unwrap_err(), 5i64);
+    /// ```
+    ///
+    /// The rejected value is not dropped.
+    ///
+    /// ```
+    /// use std::rc::Rc;
+    ///
+    /// let counter = Rc::new(());
+    /// let values = vec![counter.clone(); 4];
+    ///
+    /// let values = stack_any::StackAny::<4>::try_new_or_return(values).unwrap_err();
+    /// assert_eq!(Rc::strong_count(&counter), 5);
+    ///
+    /// let boxed: Box<dyn std::any::Any> = Box::new(values);
+    /// drop(boxed);
+    /// assert_eq!(Rc::strong_count(&counter), 1);
+    /// ```
+    pub fn try_new_or_return<T>(value: T) -> Result<Self, T>
+    where
+        T: core::any::Any,
+    {
+        Self::try_new_checked(value).map_err(CapacityError::into_value)
+    }
 
-        let mut bytes = [core::mem::MaybeUninit::uninit(); N];
+    /// Allocates N-size memory on the stack and then places `value` into it.
+    /// Returns [`CapacityError`] carrying `value` back if `T` size is larger than N
+    /// or `T` alignment is larger than [`StackAny::ALIGN`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let five = stack_any::StackAny::<4>::try_new_checked(5i32);
+    /// assert!(five.is_ok());
+    ///
+    /// let err = stack_any::StackAny::<32>::try_new_checked([0u64; 6]).unwrap_err();
+    /// assert_eq!(err.required_size(), 48);
+    /// assert_eq!(err.required_align(), 8);
+    /// assert_eq!(err.capacity(), 32);
+    /// assert_eq!(err.to_string(), "value of type [u64; 6] needs 48 bytes but capacity is 32");
+    /// assert_eq!(err.into_value(), [0; 6]);
+    /// ```
+    ///
+    /// ```
+    /// #[repr(align(32))]
+    /// struct Aligned(u8);
+    ///
+    /// let err = stack_any::StackAny::<32>::try_new_checked(Aligned(5)).unwrap_err();
+    /// assert_eq!(err.required_align(), 32);
+    /// assert!(err.to_string().ends_with("Aligned needs alignment 32 but alignment is 8"));
+    /// ```
+    pub fn try_new_checked<T>(value: T) -> Result<Self, CapacityError<T>>
+    where
+        T: core::any::Any,
+    {
+        if !Self::fits::<T>() {
+            return Err(CapacityError::new(value, N, Self::ALIGN));
+        }
 
-        let src = &value as *const _ as *const _;
-        let dst = bytes.as_mut_ptr();
-        unsafe { core::ptr::copy_nonoverlapping(src, dst, size) };
+        let mut stack = Self::empty();
 
-        let drop_fn = |ptr| unsafe { core::ptr::drop_in_place(ptr as *mut T) };
-        core::mem::forget(value);
+        let ptr = value_ptr::<T>(stack.bytes.0.as_mut_ptr());
+        unsafe { core::ptr::write(ptr, value) };
 
-        Some(Self {
-            type_id,
-            bytes,
-            drop_fn,
-        })
+        stack.set_metadata::<T>();
+        Ok(stack)
     }
 
-    /// Attempt to return reference to the inner value as a concrete type.
-    /// Returns None if `T` is not equal to contained value type.
+    /// Allocates N-size memory on the stack and then places the value computed from `f` directly into it.
+    /// Returns None without calling `f` if `T` size is larger than N or `T` alignment is larger than [`StackAny::ALIGN`].
+    ///
+    /// If `f` panics, nothing is leaked.
     ///
     /// # Examples
     ///
     /// ```
-    /// let five = stack_any::stack_any!(i32, 5);
-    /// assert_eq!(five.downcast_ref::<i32>(), Some(&5));
-    /// assert_eq!(five.downcast_ref::<i64>(), None);
+    /// struct Frame([u8; 2048]);
+    ///
+    /// let frame = stack_any::StackAny::<2048>::try_new_with(|| Frame([5; 2048])).unwrap();
+    /// assert_eq!(frame.downcast_ref::<Frame>().unwrap().0[0], 5);
+    ///
+    /// let frame = stack_any::StackAny::<1024>::try_new_with(|| Frame([5; 2048]));
+    /// assert!(frame.is_none());
+    ///
+    /// let result = std::panic::catch_unwind(|| {
+    ///     stack_any::StackAny::<2048>::try_new_with::<Frame>(|| panic!())
+    /// });
+    /// assert!(result.is_err());
     /// ```
-    pub fn downcast_ref<T>(&self) -> Option<&T>
+    pub fn try_new_with<T>(f: impl FnOnce() -> T) -> Option<Self>
     where
         T: core::any::Any,
     {
-        if core::any::TypeId::of::<T>() != self.type_id {
+        if !Self::fits::<T>() {
             return None;
         }
 
-        let ptr = self.bytes.as_ptr();
-        Some(unsafe { &*(ptr as *const T) })
+        let mut stack = Self::empty();
+
+        let ptr = value_ptr::<T>(stack.bytes.0.as_mut_ptr());
+        unsafe { core::ptr::write(ptr, f()) };
+
+        stack.set_metadata::<T>();
+        Some(stack)
     }
 
-    /// Attempt to return mutable reference to the inner value as a concrete type.
-    /// Returns None if `T` is not equal to contained value type.
+    /// Allocates N-size memory on the stack and then lets `init` initialize a value in place.
+    /// Returns None without calling `init` if `T` size is larger than N or `T` alignment is larger than [`StackAny::ALIGN`].
+    ///
+    /// The type id and the drop glue are set only after `init` returns,
+    /// so if `init` panics, the partially initialized value is never dropped.
+    ///
+    /// # Safety
+    ///
+    /// `init` must fully initialize the value when it returns.
     ///
     /// # Examples
     ///
     /// ```
-    /// let mut five = stack_any::stack_any!(i32, 5);
-    /// assert_eq!(five.downcast_mut::<i32>(), Some(&mut 5));
-    /// assert_eq!(five.downcast_mut::<i64>(), None);
+    /// use std::rc::Rc;
+    ///
+    /// struct Frame {
+    ///     bytes: [u8; 2048],
+    ///     counter: Rc<()>,
+    /// }
+    ///
+    /// let counter = Rc::new(());
+    ///
+    /// let frame = unsafe {
+    ///     stack_any::StackAny::<2064>::try_emplace(|frame: &mut std::mem::MaybeUninit<Frame>| {
+    ///         let ptr = frame.as_mut_ptr();
+    ///         std::ptr::addr_of_mut!((*ptr).bytes).write_bytes(5, 1);
+    ///         std::ptr::addr_of_mut!((*ptr).counter).write(counter.clone());
+    ///     })
+    /// }
+    /// .unwrap();
+    ///
+    /// assert_eq!(frame.downcast_ref::<Frame>().unwrap().bytes[0], 5);
+    /// assert_eq!(Rc::strong_count(&counter), 2);
+    ///
+    /// drop(frame);
+    /// assert_eq!(Rc::strong_count(&counter), 1);
     /// ```
-    pub fn downcast_mut<T>(&mut self) -> Option<&mut T>
+    pub unsafe fn try_emplace<T>(init: impl FnOnce(&mut core::mem::MaybeUninit<T>)) -> Option<Self>
     where
         T: core::any::Any,
     {
-        if core::any::TypeId::of::<T>() != self.type_id {
+        if !Self::fits::<T>() {
             return None;
         }
 
-        let ptr = self.bytes.as_mut_ptr();
-        Some(unsafe { &mut *(ptr as *mut T) })
+        let mut stack = Self::empty();
+
+        let ptr = value_ptr::<core::mem::MaybeUninit<T>>(stack.bytes.0.as_mut_ptr());
+        init(unsafe { &mut *ptr });
+
+        stack.set_metadata::<T>();
+        Some(stack)
     }
 
-    /// Attempt to downcast the stack to a concrete type.
-    /// Returns None if `T` is not equal to contained value type.
+    /// Allocates N-size memory on the stack without placing any value into it.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let empty = stack_any::StackAny::<8>::empty();
+    /// assert!(empty.is_empty());
+    /// assert_eq!(empty.downcast_ref::<i32>(), None);
+    ///
+    /// let stacks = [const { stack_any::StackAny::<64>::empty() }; 16];
+    /// assert!(stacks.iter().all(|stack| stack.is_empty()));
+    ///
+    /// let stacks: [stack_any::StackAny<64>; 16] = core::array::from_fn(|_| Default::default());
+    /// assert!(stacks.iter().all(|stack| stack.is_empty()));
+    /// ```
+    pub const fn empty() -> Self {
+        Self {
+            type_id: core::any::TypeId::of::<Empty>(),
+            bytes: Buffer([core::mem::MaybeUninit::uninit(); N]),
+            vtable: <vtable::Plain as vtable::Capture<Empty>>::REF,
+            _marker: core::marker::PhantomData,
+        }
+    }
+
+    /// Returns a builder that places `value` together with the capabilities of its type,
+    /// such as `Clone`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let five = stack_any::StackAny::<4>::builder(5i32).with_clone().build();
+    /// assert_eq!(five.clone().downcast_ref::<i32>(), Some(&5));
+    /// ```
+    pub fn builder<T>(value: T) -> StackAnyBuilder<T, N>
+    where
+        T: core::any::Any,
+    {
+        StackAnyBuilder::new(value)
+    }
+
+    /// Returns true if a value of `T` can be placed into N-size memory.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// assert!(stack_any::StackAny::<8>::fits::<u64>());
+    /// assert!(!stack_any::StackAny::<4>::fits::<u64>());
+    /// assert!(stack_any::StackAny::<0>::fits::<()>());
+    /// ```
+    pub const fn fits<T>() -> bool {
+        fits(
+            core::mem::size_of::<T>(),
+            core::mem::align_of::<T>(),
+            N,
+            Self::ALIGN,
+        )
+    }
+
+    /// Returns the size of the allocation in bytes, which is `N`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let five = stack_any::StackAny::<8>::try_new(5i32).unwrap();
+    /// assert_eq!(five.capacity(), 8);
+    /// ```
+    pub const fn capacity(&self) -> usize {
+        N
+    }
+
+    /// Returns the size of the contained value in bytes.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let five = stack_any::StackAny::<8>::try_new(5i32).unwrap();
+    /// assert_eq!(five.value_size(), 4);
+    /// ```
+    pub fn value_size(&self) -> usize {
+        self.vtable.size
+    }
+
+    /// Returns the `TypeId` of the contained value.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let five = stack_any::stack_any!(i32, 5);
+    /// assert_eq!(five.contained_type_id(), std::any::TypeId::of::<i32>());
+    /// ```
+    pub fn contained_type_id(&self) -> core::any::TypeId {
+        self.type_id
+    }
+
+    /// Returns the name of the contained value type.
     ///
     /// # Examples
     ///
     /// ```
     /// let five = stack_any::stack_any!(i32, 5);
-    /// assert_eq!(five.downcast::<i32>(), Some(5));
+    /// assert_eq!(five.type_name(), "i32");
     /// ```
-    pub fn downcast<T>(mut self) -> Option<T>
+    #[cfg(feature = "type-name")]
+    pub fn type_name(&self) -> &'static str {
+        self.vtable.type_name()
+    }
+
+    /// Returns true if the contained value type is equal to `T`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let five = stack_any::stack_any!(i32, 5);
+    /// assert!(five.is::<i32>());
+    /// assert!(!five.is::<i64>());
+    /// ```
+    pub fn is<T>(&self) -> bool
     where
         T: core::any::Any,
     {
-        if core::any::TypeId::of::<T>() != self.type_id {
-            return None;
-        }
+        core::any::TypeId::of::<T>() == self.type_id
+    }
 
-        self.drop_fn = |_| {};
+    /// Returns true if the stack contains nothing, e.g. after its value was taken
+    /// or the stack was [poisoned](StackAny::is_poisoned).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut five = stack_any::stack_any!(i32, 5);
+    /// assert!(!five.is_empty());
+    ///
+    /// five.take::<i32>();
+    /// assert!(five.is_empty());
+    /// ```
+    pub fn is_empty(&self) -> bool {
+        self.is::<Empty>() || self.is_poisoned()
+    }
 
-        let ptr = self.bytes.as_ptr();
-        Some(unsafe { core::ptr::read(ptr as *const T) })
+    /// Returns true if a closure given to [`StackAny::replace_with`] or [`StackAny::map`] panicked
+    /// while the value was moved out to it.
+    ///
+    /// A poisoned stack contains nothing: downcasts fail without touching the bytes,
+    /// and dropping it runs no drop glue. [`StackAny::clear`] or placing a new value resets it.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::sync::atomic::{AtomicUsize, Ordering};
+    ///
+    /// static DROPS: AtomicUsize = AtomicUsize::new(0);
+    ///
+    /// struct Counted;
+    ///
+    /// impl Drop for Counted {
+    ///     fn drop(&mut self) {
+    ///         DROPS.fetch_add(1, Ordering::SeqCst);
+    ///     }
+    /// }
+    ///
+    /// let mut slot = stack_any::stack_any!(Counted, Counted);
+    /// assert!(!slot.is_poisoned());
+    ///
+    /// let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+    ///     slot.map(|_: Counted| -> Counted { panic!() })
+    /// }));
+    /// assert!(result.is_err());
+    /// assert!(slot.is_poisoned());
+    /// assert!(slot.downcast_ref::<Counted>().is_none());
+    /// assert!(slot.take::<Counted>().is_none());
+    /// assert_eq!(DROPS.load(Ordering::SeqCst), 1);
+    ///
+    /// slot.clear();
+    /// assert!(!slot.is_poisoned());
+    /// assert!(slot.is_empty());
+    ///
+    /// drop(slot);
+    /// assert_eq!(DROPS.load(Ordering::SeqCst), 1);
+    /// ```
+    pub fn is_poisoned(&self) -> bool {
+        self.is::<Poisoned>()
     }
-}
 
-impl<const N: usize> Drop for StackAny<N> {
-    fn drop(&mut self) {
-        (self.drop_fn)(self.bytes.as_mut_ptr());
+    /// Drops the contained value, leaving the stack empty.
+    /// Does nothing if the stack is already empty.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut five = stack_any::stack_any!(i32, 5);
+    ///
+    /// five.clear();
+    /// assert!(five.is_empty());
+    /// assert_eq!(five.downcast_ref::<i32>(), None);
+    ///
+    /// five.clear();
+    /// assert!(five.is_empty());
+    /// ```
+    ///
+    /// With the `zeroize` feature, the bytes of the dropped value are overwritten with zeros.
+    ///
+    /// ```
+    /// # #[cfg(feature = "zeroize")]
+    /// # {
+    /// let mut secret = stack_any::stack_any!(String, String::from("hunter2"));
+    /// secret.clear();
+    ///
+    /// let parts = secret.into_raw_parts();
+    /// assert!(parts.bytes.iter().all(|byte| unsafe { byte.assume_init() } == 0));
+    ///
+    /// let mut secret = stack_any::stack_any!([u8; 8], *b"hunter2!");
+    /// assert_eq!(secret.take::<[u8; 8]>(), Some(*b"hunter2!"));
+    ///
+    /// let parts = secret.into_raw_parts();
+    /// assert!(parts.bytes.iter().all(|byte| unsafe { byte.assume_init() } == 0));
+    /// # }
+    /// ```
+    pub fn clear(&mut self) {
+        let vtable = self.vtable;
+        self.set_empty();
+
+        if vtable.needs_drop {
+            let ptr = any_ptr(vtable.meta, vtable.size, self.bytes.0.as_mut_ptr());
+            unsafe { core::ptr::drop_in_place(ptr) };
+        }
+        self.scrub(vtable.size);
     }
-}
 
-/// Allocates memory on the stack and then places value based on given type and value.
-///
-/// # Examples
-///
-/// ```
-/// let five = stack_any::stack_any!(i32, 5);
-/// ```
-#[macro_export]
-macro_rules! stack_any {
-    ($type:ty, $init:expr) => {
-        $crate::StackAny::<{ std::mem::size_of::<$type>() }>::try_new::<$type>($init).unwrap()
+    /// Forgets the contained value without dropping it, leaving the stack empty.
+    /// Does nothing if the stack is already empty.
+    ///
+    /// This leaks the resources owned by the value, e.g. after handing them over to another owner.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::sync::atomic::{AtomicUsize, Ordering};
+    ///
+    /// static DROPS: AtomicUsize = AtomicUsize::new(0);
+    ///
+    /// struct Handle;
+    ///
+    /// impl Drop for Handle {
+    ///     fn drop(&mut self) {
+    ///         DROPS.fetch_add(1, Ordering::SeqCst);
+    ///     }
+    /// }
+    ///
+    /// let mut stack = stack_any::stack_any!(Handle, Handle);
+    ///
+    /// stack.forget_inner();
+    /// assert!(stack.is_empty());
+    ///
+    /// drop(stack);
+    /// assert_eq!(DROPS.load(Ordering::SeqCst), 0);
+    /// ```
+    pub fn forget_inner(&mut self) {
+        self.set_empty();
+    }
+
+    /// Marks the stack as containing a value of `T` without touching its bytes.
+    fn set_metadata<T>(&mut self)
+    where
+        T: core::any::Any,
+    {
+        self.type_id = core::any::TypeId::of::<T>();
+        self.vtable = <vtable::Plain as vtable::Capture<T>>::REF;
+    }
+
+    /// Marks the stack as empty without dropping the contained value.
+    fn set_empty(&mut self) {
+        self.set_metadata::<Empty>();
+    }
+
+    /// Marks the stack as poisoned without dropping the contained value.
+    fn set_poisoned(&mut self) {
+        self.set_metadata::<Poisoned>();
+    }
+
+    /// Moves the contained value of `T` out, leaving the stack empty.
+    /// The contained value type must be `T`.
+    unsafe fn move_out<T>(&mut self) -> T
+    where
+        T: core::any::Any,
+    {
+        self.set_empty();
+
+        let ptr = value_ptr::<T>(self.bytes.0.as_mut_ptr());
+        let value = unsafe { core::ptr::read(ptr) };
+
+        self.scrub(core::mem::size_of::<T>());
+        value
+    }
+
+    /// Overwrites the first `size` bytes, which a value was dropped or moved out of, with zeros
+    /// if the `zeroize` feature is enabled.
+    #[allow(unused_variables)]
+    fn scrub(&mut self, size: usize) {
+        #[cfg(feature = "zeroize")]
+        zeroize::Zeroize::zeroize(&mut self.bytes.0[..size]);
+    }
+
+    /// Returns true if the stack can be cloned, i.e. it is empty or `Clone` of the contained value
+    /// type was captured by [`StackAnyBuilder::with_clone`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// assert!(stack_any::StackAny::<8>::empty().is_cloneable());
+    /// assert!(stack_any::StackAny::<4>::builder(5i32).with_clone().build().is_cloneable());
+    /// assert!(!stack_any::stack_any!(i32, 5).is_cloneable());
+    /// ```
+    pub fn is_cloneable(&self) -> bool {
+        self.is_empty() || self.vtable.clone_fn.is_some()
+    }
+
+    /// Clones the stack together with the contained value.
+    /// Returns None if the stack is not [cloneable](StackAny::is_cloneable).
+    ///
+    /// If `Clone` of the contained value type panics, nothing is left to be dropped but the original.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let strings = stack_any::StackAny::<24>::builder(vec![String::from("x")])
+    ///     .with_clone()
+    ///     .build();
+    ///
+    /// let mut cloned = strings.try_clone().unwrap();
+    /// cloned.downcast_mut::<Vec<String>>().unwrap().push(String::from("y"));
+    /// assert_eq!(strings.downcast_ref::<Vec<String>>().map(Vec::len), Some(1));
+    /// assert_eq!(cloned.downcast_ref::<Vec<String>>().map(Vec::len), Some(2));
+    ///
+    /// let strings = stack_any::stack_any!(Vec<String>, vec![String::from("x")]);
+    /// assert!(strings.try_clone().is_none());
+    /// ```
+    ///
+    /// ```
+    /// use std::rc::Rc;
+    ///
+    /// struct Faulty(Rc<()>);
+    ///
+    /// impl Clone for Faulty {
+    ///     fn clone(&self) -> Self {
+    ///         panic!()
+    ///     }
+    /// }
+    ///
+    /// let counter = Rc::new(());
+    /// let stack = stack_any::StackAny::<8>::builder(Faulty(counter.clone())).with_clone().build();
+    ///
+    /// let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| stack.try_clone()));
+    /// assert!(result.is_err());
+    /// assert_eq!(Rc::strong_count(&counter), 2);
+    ///
+    /// drop(stack);
+    /// assert_eq!(Rc::strong_count(&counter), 1);
+    /// ```
+    pub fn try_clone(&self) -> Option<Self> {
+        let mut stack = Self::empty();
+
+        if !self.is_empty() {
+            let clone_fn = self.vtable.clone_fn?;
+            unsafe { clone_fn(self.bytes.0.as_ptr(), stack.bytes.0.as_mut_ptr()) };
+
+            stack.type_id = self.type_id;
+            stack.vtable = self.vtable;
+        }
+
+        Some(stack)
+    }
+
+    /// Attempt to return reference to the inner value as a concrete type.
+    /// Returns None if `T` is not equal to contained value type.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let five = stack_any::stack_any!(i32, 5);
+    /// assert_eq!(five.downcast_ref::<i32>(), Some(&5));
+    /// assert_eq!(five.downcast_ref::<i64>(), None);
+    /// ```
+    ///
+    /// The returned reference is properly aligned.
+    ///
+    /// ```
+    /// let five = stack_any::stack_any!(u64, 5);
+    /// let ptr = five.downcast_ref::<u64>().unwrap() as *const u64;
+    /// assert_eq!(ptr as usize % std::mem::align_of::<u64>(), 0);
+    /// ```
+    pub fn downcast_ref<T>(&self) -> Option<&T>
+    where
+        T: core::any::Any,
+    {
+        if !self.is::<T>() {
+            return None;
+        }
+
+        let ptr = value_ptr::<T>(self.bytes.0.as_ptr() as *mut _);
+        Some(unsafe { &*ptr })
+    }
+
+    /// Attempt to return mutable reference to the inner value as a concrete type.
+    /// Returns None if `T` is not equal to contained value type.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut five = stack_any::stack_any!(i32, 5);
+    /// assert_eq!(five.downcast_mut::<i32>(), Some(&mut 5));
+    /// assert_eq!(five.downcast_mut::<i64>(), None);
+    /// ```
+    ///
+    /// The returned reference is properly aligned.
+    ///
+    /// ```
+    /// let mut five = stack_any::stack_any!(u64, 5);
+    /// let ptr = five.downcast_mut::<u64>().unwrap() as *mut u64;
+    /// assert_eq!(ptr as usize % std::mem::align_of::<u64>(), 0);
+    /// ```
+    pub fn downcast_mut<T>(&mut self) -> Option<&mut T>
+    where
+        T: core::any::Any,
+    {
+        if !self.is::<T>() {
+            return None;
+        }
+
+        let ptr = value_ptr::<T>(self.bytes.0.as_mut_ptr());
+        Some(unsafe { &mut *ptr })
+    }
+
+    /// Attempt to return reference to the inner value as a concrete type.
+    /// Returns [`DowncastError`] describing both types if `T` is not equal to contained value type.
+    ///
+    /// Prefer this over [`StackAny::downcast_ref`] when the failure needs to be reported.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let string = stack_any::stack_any!(String, String::from("x"));
+    /// assert_eq!(string.get::<String>().map(String::as_str), Ok("x"));
+    ///
+    /// let err = string.get::<i64>().unwrap_err();
+    /// assert_eq!(err.expected(), std::any::TypeId::of::<i64>());
+    /// assert_eq!(err.found(), std::any::TypeId::of::<String>());
+    /// # #[cfg(feature = "type-name")]
+    /// # assert_eq!(err.to_string(), "expected i64, found alloc::string::String");
+    /// ```
+    pub fn get<T>(&self) -> Result<&T, DowncastError>
+    where
+        T: core::any::Any,
+    {
+        if !self.is::<T>() {
+            return Err(self.downcast_error::<T>());
+        }
+
+        Ok(unsafe { self.downcast_ref_unchecked() })
+    }
+
+    /// Attempt to return mutable reference to the inner value as a concrete type.
+    /// Returns [`DowncastError`] describing both types if `T` is not equal to contained value type.
+    ///
+    /// Prefer this over [`StackAny::downcast_mut`] when the failure needs to be reported.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut five = stack_any::stack_any!(i32, 5);
+    ///
+    /// *five.get_mut::<i32>().unwrap() = 7;
+    /// assert_eq!(five.get::<i32>(), Ok(&7));
+    ///
+    /// let err = five.get_mut::<u8>().unwrap_err();
+    /// assert_eq!(err.expected(), std::any::TypeId::of::<u8>());
+    /// # #[cfg(feature = "type-name")]
+    /// # assert_eq!(err.to_string(), "expected u8, found i32");
+    /// ```
+    pub fn get_mut<T>(&mut self) -> Result<&mut T, DowncastError>
+    where
+        T: core::any::Any,
+    {
+        if !self.is::<T>() {
+            return Err(self.downcast_error::<T>());
+        }
+
+        Ok(unsafe { self.downcast_mut_unchecked() })
+    }
+
+    /// Describes a failed downcast of the contained value to `T`.
+    fn downcast_error<T>(&self) -> DowncastError
+    where
+        T: core::any::Any,
+    {
+        DowncastError::new::<T>(
+            self.type_id,
+            #[cfg(feature = "type-name")]
+            self.vtable.type_name(),
+        )
+    }
+
+    /// Attempt to downcast the stack to a concrete type.
+    /// Returns the stack back if `T` is not equal to contained value type,
+    /// so that other types can be tried.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let five = stack_any::stack_any!(i32, 5);
+    /// assert_eq!(five.downcast::<i32>().ok(), Some(5));
+    /// ```
+    ///
+    /// ```
+    /// let x = stack_any::stack_any!(char, 'x');
+    ///
+    /// let x = x.downcast::<i32>().unwrap_err();
+    /// assert_eq!(x.downcast::<char>().ok(), Some('x'));
+    /// ```
+    ///
+    /// Values with alignment up to [`StackAny::ALIGN`] are read back intact.
+    ///
+    /// ```
+    /// #[derive(Debug, PartialEq)]
+    /// #[repr(align(8))]
+    /// struct Aligned(u8);
+    ///
+    /// let aligned = stack_any::stack_any!(Aligned, Aligned(5));
+    /// assert_eq!(aligned.downcast::<Aligned>().ok(), Some(Aligned(5)));
+    /// ```
+    pub fn downcast<T>(mut self) -> Result<T, Self>
+    where
+        T: core::any::Any,
+    {
+        if !self.is::<T>() {
+            return Err(self);
+        }
+
+        Ok(unsafe { self.move_out() })
+    }
+
+    /// Returns reference to the inner value as `dyn Any`, whose `type_id` is the contained value type.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let five = stack_any::stack_any!(i32, 5);
+    ///
+    /// let any: &dyn std::any::Any = five.as_any();
+    /// assert_eq!(any.downcast_ref::<i32>(), five.downcast_ref::<i32>());
+    /// assert_eq!(any.downcast_ref::<i64>(), five.downcast_ref::<i64>());
+    ///
+    /// let strings = stack_any::stack_any!(Vec<String>, vec![String::from("x")]);
+    /// let any = strings.as_any();
+    /// assert_eq!(any.type_id(), std::any::TypeId::of::<Vec<String>>());
+    /// assert_eq!(any.downcast_ref::<Vec<String>>(), strings.downcast_ref::<Vec<String>>());
+    /// ```
+    pub fn as_any(&self) -> &dyn core::any::Any {
+        let ptr = self.bytes.0.as_ptr() as *mut _;
+        unsafe { &*any_ptr(self.vtable.meta, self.vtable.size, ptr) }
+    }
+
+    /// Returns mutable reference to the inner value as `dyn Any`, whose `type_id` is the contained value type.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut five = stack_any::stack_any!(i32, 5);
+    ///
+    /// *five.as_any_mut().downcast_mut::<i32>().unwrap() = 7;
+    /// assert_eq!(five.downcast_ref::<i32>(), Some(&7));
+    /// ```
+    pub fn as_any_mut(&mut self) -> &mut dyn core::any::Any {
+        let ptr = self.bytes.0.as_mut_ptr();
+        unsafe { &mut *any_ptr(self.vtable.meta, self.vtable.size, ptr) }
+    }
+
+    /// Attempt to clone the inner value as a concrete type, leaving the stack untouched.
+    /// Returns None if `T` is not equal to contained value type.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let strings = stack_any::stack_any!(Vec<String>, vec![String::from("x")]);
+    ///
+    /// assert_eq!(strings.downcast_clone::<Vec<String>>(), Some(vec![String::from("x")]));
+    /// assert_eq!(strings.downcast_clone::<String>(), None);
+    /// assert_eq!(strings.downcast_ref::<Vec<String>>(), Some(&vec![String::from("x")]));
+    /// ```
+    pub fn downcast_clone<T>(&self) -> Option<T>
+    where
+        T: core::any::Any + Clone,
+    {
+        self.downcast_ref::<T>().cloned()
+    }
+
+    /// Attempt to copy the inner value as a concrete type, leaving the stack untouched.
+    /// Returns None if `T` is not equal to contained value type.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let bytes = stack_any::stack_any!([u8; 32], [5; 32]);
+    ///
+    /// assert_eq!(bytes.downcast_copy::<[u8; 32]>(), Some([5; 32]));
+    /// assert_eq!(bytes.downcast_copy::<[u8; 16]>(), None);
+    /// ```
+    pub fn downcast_copy<T>(&self) -> Option<T>
+    where
+        T: core::any::Any + Copy,
+    {
+        self.downcast_ref::<T>().copied()
+    }
+
+    /// Calls `f` with reference to the inner value as a concrete type, returning its result.
+    /// Returns None without calling `f` if `T` is not equal to contained value type.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let five = stack_any::stack_any!(i32, 5);
+    /// assert_eq!(five.with_ref(|value: &i32| value + 1), Some(6));
+    /// assert_eq!(five.with_ref(|value: &i64| value + 1), None);
+    /// ```
+    pub fn with_ref<T, R>(&self, f: impl FnOnce(&T) -> R) -> Option<R>
+    where
+        T: core::any::Any,
+    {
+        self.downcast_ref().map(f)
+    }
+
+    /// Calls `f` with mutable reference to the inner value as a concrete type, returning its result.
+    /// Returns None without calling `f` if `T` is not equal to contained value type.
+    ///
+    /// If `f` panics, the inner value stays in place with whatever changes `f` made before panicking.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// struct Counter(u32);
+    ///
+    /// impl Counter {
+    ///     fn bump(&mut self) -> u32 {
+    ///         self.0 += 1;
+    ///         self.0
+    ///     }
+    /// }
+    ///
+    /// let mut stacks = [
+    ///     stack_any::StackAny::<8>::try_new(Counter(0)).unwrap(),
+    ///     stack_any::StackAny::<8>::try_new('x').unwrap(),
+    ///     stack_any::StackAny::<8>::try_new(Counter(5)).unwrap(),
+    /// ];
+    ///
+    /// let counts = stacks
+    ///     .iter_mut()
+    ///     .filter_map(|stack| stack.with_mut::<Counter, _>(|counter| counter.bump()))
+    ///     .collect::<Vec<_>>();
+    /// assert_eq!(counts, vec![1, 6]);
+    /// ```
+    ///
+    /// ```
+    /// let mut stack = stack_any::stack_any!(Vec<i32>, vec![5]);
+    ///
+    /// let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+    ///     stack.with_mut(|value: &mut Vec<i32>| {
+    ///         value.push(7);
+    ///         panic!();
+    ///     });
+    /// }));
+    /// assert!(result.is_err());
+    /// assert_eq!(stack.downcast_ref::<Vec<i32>>(), Some(&vec![5, 7]));
+    /// ```
+    pub fn with_mut<T, R>(&mut self, f: impl FnOnce(&mut T) -> R) -> Option<R>
+    where
+        T: core::any::Any,
+    {
+        self.downcast_mut().map(f)
+    }
+
+    /// Returns reference to the inner value as a concrete type without checking its type.
+    ///
+    /// # Safety
+    ///
+    /// The contained value type must be equal to `T`.
+    /// Debug builds assert this.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let five = stack_any::stack_any!(i32, 5);
+    /// assert_eq!(unsafe { five.downcast_ref_unchecked::<i32>() }, &5);
+    /// ```
+    pub unsafe fn downcast_ref_unchecked<T>(&self) -> &T
+    where
+        T: core::any::Any,
+    {
+        debug_assert!(self.is::<T>());
+
+        let ptr = value_ptr::<T>(self.bytes.0.as_ptr() as *mut _);
+        unsafe { &*ptr }
+    }
+
+    /// Returns mutable reference to the inner value as a concrete type without checking its type.
+    ///
+    /// # Safety
+    ///
+    /// The contained value type must be equal to `T`.
+    /// Debug builds assert this.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut five = stack_any::stack_any!(i32, 5);
+    /// assert_eq!(unsafe { five.downcast_mut_unchecked::<i32>() }, &mut 5);
+    /// ```
+    pub unsafe fn downcast_mut_unchecked<T>(&mut self) -> &mut T
+    where
+        T: core::any::Any,
+    {
+        debug_assert!(self.is::<T>());
+
+        let ptr = value_ptr::<T>(self.bytes.0.as_mut_ptr());
+        unsafe { &mut *ptr }
+    }
+
+    /// Downcasts the stack to a concrete type without checking its type.
+    ///
+    /// # Safety
+    ///
+    /// The contained value type must be equal to `T`.
+    /// Debug builds assert this.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let five = stack_any::stack_any!(i32, 5);
+    /// assert_eq!(unsafe { five.downcast_unchecked::<i32>() }, 5);
+    /// ```
+    pub unsafe fn downcast_unchecked<T>(mut self) -> T
+    where
+        T: core::any::Any,
+    {
+        debug_assert!(self.is::<T>());
+
+        unsafe { self.move_out() }
+    }
+
+    /// Places `value` into the stack and then drops the previously contained value.
+    /// Returns `value` back without touching the contained value if `U` does not fit.
+    ///
+    /// As the old value is dropped last, a panic while dropping it leaves the stack holding the new value.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut stack = stack_any::StackAny::<8>::try_new(5i32).unwrap();
+    ///
+    /// assert_eq!(stack.insert('x'), Ok(()));
+    /// assert_eq!(stack.downcast_ref::<char>(), Some(&'x'));
+    ///
+    /// assert_eq!(stack.insert([0u8; 16]), Err([0u8; 16]));
+    /// assert_eq!(stack.downcast_ref::<char>(), Some(&'x'));
+    /// ```
+    ///
+    /// The old value is dropped exactly once.
+    ///
+    /// ```
+    /// use std::rc::Rc;
+    ///
+    /// let counter = Rc::new(());
+    /// let mut stack = stack_any::StackAny::<8>::try_new(counter.clone()).unwrap();
+    /// assert_eq!(Rc::strong_count(&counter), 2);
+    ///
+    /// stack.insert(counter.clone()).unwrap();
+    /// assert_eq!(Rc::strong_count(&counter), 2);
+    ///
+    /// stack.insert(5i32).unwrap();
+    /// assert_eq!(Rc::strong_count(&counter), 1);
+    /// ```
+    pub fn insert<U>(&mut self, value: U) -> Result<(), U>
+    where
+        U: core::any::Any,
+    {
+        if !Self::fits::<U>() {
+            return Err(value);
+        }
+
+        self.replace_any(value).map(drop)
+    }
+
+    /// Returns mutable reference to the inner value as a concrete type, placing `value` first
+    /// if `T` is not equal to contained value type.
+    /// Returns None without touching the contained value if `T` does not fit.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut stack = stack_any::StackAny::<8>::empty();
+    ///
+    /// *stack.get_or_insert(5i32).unwrap() += 1;
+    /// *stack.get_or_insert(5i32).unwrap() += 1;
+    /// assert_eq!(stack.downcast_ref::<i32>(), Some(&7));
+    ///
+    /// assert_eq!(stack.get_or_insert([0u8; 16]), None);
+    /// ```
+    pub fn get_or_insert<T>(&mut self, value: T) -> Option<&mut T>
+    where
+        T: core::any::Any,
+    {
+        self.get_or_insert_with(|| value)
+    }
+
+    /// Returns mutable reference to the inner value as a concrete type, placing the value
+    /// computed from `f` first if `T` is not equal to contained value type.
+    /// Returns None without touching the contained value if `T` does not fit.
+    ///
+    /// `f` is called only when the value is placed. If `f` panics, the contained value is left untouched.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut stack = stack_any::stack_any!(i32, 5);
+    ///
+    /// let value = stack.get_or_insert_with(|| unreachable!());
+    /// assert_eq!(value, Some(&mut 5));
+    ///
+    /// let value = stack.get_or_insert_with(|| 'x');
+    /// assert_eq!(value, Some(&mut 'x'));
+    ///
+    /// let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+    ///     stack.get_or_insert_with::<u32>(|| panic!());
+    /// }));
+    /// assert!(result.is_err());
+    /// assert_eq!(stack.downcast_ref::<char>(), Some(&'x'));
+    /// ```
+    pub fn get_or_insert_with<T>(&mut self, f: impl FnOnce() -> T) -> Option<&mut T>
+    where
+        T: core::any::Any,
+    {
+        self.downcast_mut_or_insert_with(f)
+    }
+
+    /// Returns reference to the inner value as a concrete type, replacing the contained value
+    /// with the value computed from `f` first if `T` is not equal to contained value type.
+    /// Returns None without touching the contained value if `T` does not fit.
+    ///
+    /// When `T` is equal to contained value type, this costs a single `TypeId` comparison and `f` is not called.
+    /// Otherwise the old value is dropped once the new value is computed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut memo = stack_any::StackAny::<64>::empty();
+    ///
+    /// let value = memo.downcast_ref_or_insert_with(|| String::from("x"));
+    /// assert_eq!(value.map(String::as_str), Some("x"));
+    ///
+    /// let value = memo.downcast_ref_or_insert_with::<String>(|| unreachable!());
+    /// assert_eq!(value.map(String::as_str), Some("x"));
+    ///
+    /// assert_eq!(memo.downcast_ref_or_insert_with(|| [0u8; 128]), None);
+    /// assert_eq!(memo.downcast_ref::<String>().map(String::as_str), Some("x"));
+    /// ```
+    ///
+    /// ```
+    /// use std::rc::Rc;
+    ///
+    /// let counter = Rc::new(());
+    /// let mut memo = stack_any::StackAny::<8>::try_new(counter.clone()).unwrap();
+    ///
+    /// memo.downcast_ref_or_insert_with(|| 5i32);
+    /// assert_eq!(Rc::strong_count(&counter), 1);
+    /// assert_eq!(memo.downcast_ref::<i32>(), Some(&5));
+    /// ```
+    pub fn downcast_ref_or_insert_with<T>(&mut self, f: impl FnOnce() -> T) -> Option<&T>
+    where
+        T: core::any::Any,
+    {
+        self.downcast_mut_or_insert_with(f).map(|value| &*value)
+    }
+
+    /// Returns mutable reference to the inner value as a concrete type, replacing the contained value
+    /// with the value computed from `f` first if `T` is not equal to contained value type.
+    /// Returns None without touching the contained value if `T` does not fit.
+    ///
+    /// When `T` is equal to contained value type, this costs a single `TypeId` comparison and `f` is not called.
+    /// Otherwise the old value is dropped once the new value is computed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut memo = stack_any::stack_any!(i32, 5);
+    ///
+    /// *memo.downcast_mut_or_insert_with::<i32>(|| unreachable!()).unwrap() += 1;
+    /// assert_eq!(memo.downcast_ref::<i32>(), Some(&6));
+    ///
+    /// *memo.downcast_mut_or_insert_with(|| 'x').unwrap() = 'y';
+    /// assert_eq!(memo.downcast_ref::<char>(), Some(&'y'));
+    /// ```
+    pub fn downcast_mut_or_insert_with<T>(&mut self, f: impl FnOnce() -> T) -> Option<&mut T>
+    where
+        T: core::any::Any,
+    {
+        if !self.is::<T>() {
+            if !Self::fits::<T>() {
+                return None;
+            }
+
+            self.insert(f()).ok()?;
+        }
+
+        Some(unsafe { self.downcast_mut_unchecked() })
+    }
+
+    /// Replaces the inner value of a concrete type with the value computed from it by `f`.
+    /// Returns [`TypeMismatch`] without calling `f` if `T` is not equal to contained value type.
+    ///
+    /// The value is moved out while `f` runs. If `f` panics, the stack is left [poisoned](StackAny::is_poisoned)
+    /// and the moved out value is dropped once during unwinding.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// #[derive(Debug, PartialEq)]
+    /// struct Counter {
+    ///     n: u32,
+    ///     step: u32,
+    /// }
+    ///
+    /// let mut slot = stack_any::stack_any!(Counter, Counter { n: 0, step: 2 });
+    ///
+    /// assert_eq!(slot.replace_with::<Counter>(|c| Counter { n: c.n + c.step, ..c }), Ok(()));
+    /// assert_eq!(slot.downcast_ref(), Some(&Counter { n: 2, step: 2 }));
+    ///
+    /// assert_eq!(slot.replace_with::<i32>(|_| unreachable!()), Err(stack_any::TypeMismatch));
+    /// ```
+    ///
+    /// ```
+    /// use std::sync::atomic::{AtomicUsize, Ordering};
+    ///
+    /// static DROPS: AtomicUsize = AtomicUsize::new(0);
+    ///
+    /// struct Counted;
+    ///
+    /// impl Drop for Counted {
+    ///     fn drop(&mut self) {
+    ///         DROPS.fetch_add(1, Ordering::SeqCst);
+    ///     }
+    /// }
+    ///
+    /// let mut slot = stack_any::stack_any!(Counted, Counted);
+    ///
+    /// let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+    ///     slot.replace_with::<Counted>(|_| panic!())
+    /// }));
+    /// assert!(result.is_err());
+    /// assert!(slot.is_empty());
+    /// assert!(slot.is_poisoned());
+    /// assert_eq!(DROPS.load(Ordering::SeqCst), 1);
+    ///
+    /// drop(slot);
+    /// assert_eq!(DROPS.load(Ordering::SeqCst), 1);
+    /// ```
+    pub fn replace_with<T>(&mut self, f: impl FnOnce(T) -> T) -> Result<(), TypeMismatch>
+    where
+        T: core::any::Any,
+    {
+        let vtable = self.vtable;
+        let value = self.take::<T>().ok_or(TypeMismatch)?;
+
+        self.set_poisoned();
+        let value = f(value);
+
+        let ptr = value_ptr::<T>(self.bytes.0.as_mut_ptr());
+        unsafe { core::ptr::write(ptr, value) };
+
+        self.set_metadata::<T>();
+        self.vtable = vtable;
+        Ok(())
+    }
+
+    /// Transforms the inner value of a concrete type into a value of another type in the same memory.
+    /// Returns an error without touching the contained value if `T` is not equal to contained
+    /// value type or `U` does not fit.
+    ///
+    /// If `f` panics, the stack is left [poisoned](StackAny::is_poisoned) and the moved out value
+    /// is dropped during unwinding.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut stack = stack_any::StackAny::<24>::try_new(5i32).unwrap();
+    ///
+    /// assert_eq!(stack.map(|value: i32| vec![value]), Ok(()));
+    /// assert_eq!(stack.downcast_ref::<Vec<i32>>(), Some(&vec![5]));
+    ///
+    /// assert_eq!(stack.map(|value: i32| value), Err(stack_any::MapError::Mismatch));
+    /// assert_eq!(stack.map(|value: Vec<i32>| (value, 7u64)), Err(stack_any::MapError::Capacity));
+    /// assert_eq!(stack.downcast_ref::<Vec<i32>>(), Some(&vec![5]));
+    /// ```
+    ///
+    /// ```
+    /// use std::rc::Rc;
+    ///
+    /// let counter = Rc::new(());
+    /// let mut stack = stack_any::StackAny::<8>::try_new(counter.clone()).unwrap();
+    ///
+    /// let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+    ///     stack.map(|_: Rc<()>| -> i32 { panic!() })
+    /// }));
+    /// assert!(result.is_err());
+    /// assert!(stack.is_empty());
+    /// assert!(stack.is_poisoned());
+    ///
+    /// drop(stack);
+    /// assert_eq!(Rc::strong_count(&counter), 1);
+    /// ```
+    pub fn map<T, U>(&mut self, f: impl FnOnce(T) -> U) -> Result<(), MapError>
+    where
+        T: core::any::Any,
+        U: core::any::Any,
+    {
+        if !self.is::<T>() {
+            return Err(MapError::Mismatch);
+        }
+
+        if !Self::fits::<U>() {
+            return Err(MapError::Capacity);
+        }
+
+        let value = self.take::<T>().unwrap();
+
+        self.set_poisoned();
+        let value = f(value);
+
+        let ptr = value_ptr::<U>(self.bytes.0.as_mut_ptr());
+        unsafe { core::ptr::write(ptr, value) };
+
+        self.set_metadata::<U>();
+        Ok(())
+    }
+
+    /// Transforms the stack containing a value of a concrete type into a stack containing a value of another type.
+    /// Returns the stack back if `T` is not equal to contained value type or `U` does not fit.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let stack = stack_any::StackAny::<24>::try_new(5i32).unwrap();
+    ///
+    /// let stack = stack.map_into(|value: i32| vec![value]).unwrap();
+    /// assert_eq!(stack.downcast_ref::<Vec<i32>>(), Some(&vec![5]));
+    ///
+    /// let stack = stack.map_into(|value: i32| value).unwrap_err();
+    /// assert_eq!(stack.downcast_ref::<Vec<i32>>(), Some(&vec![5]));
+    /// ```
+    pub fn map_into<T, U>(mut self, f: impl FnOnce(T) -> U) -> Result<Self, Self>
+    where
+        T: core::any::Any,
+        U: core::any::Any,
+    {
+        match self.map(f) {
+            Ok(()) => Ok(self),
+            Err(_) => Err(self),
+        }
+    }
+
+    /// Swaps the contained values of two stacks, regardless of their types.
+    /// The drop glue follows the values, so each value is still dropped exactly once.
+    ///
+    /// Elements of a slice are swapped in the same way by [`slice::swap`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut strings = stack_any::StackAny::<24>::try_new(vec![String::from("x")]).unwrap();
+    /// let mut five = stack_any::StackAny::<24>::try_new(5i32).unwrap();
+    ///
+    /// strings.swap(&mut five);
+    /// assert_eq!(strings.downcast_ref::<i32>(), Some(&5));
+    /// assert_eq!(five.downcast_ref::<Vec<String>>(), Some(&vec![String::from("x")]));
+    ///
+    /// let mut stacks = [strings, five, stack_any::StackAny::<24>::empty()];
+    /// stacks.swap(1, 2);
+    /// assert!(stacks[1].is_empty());
+    /// assert_eq!(stacks[2].downcast_ref::<Vec<String>>(), Some(&vec![String::from("x")]));
+    /// ```
+    ///
+    /// ```
+    /// use std::rc::Rc;
+    ///
+    /// let counter = Rc::new(());
+    /// let mut stack = stack_any::StackAny::<8>::try_new(counter.clone()).unwrap();
+    /// let mut empty = stack_any::StackAny::<8>::empty();
+    ///
+    /// stack.swap(&mut empty);
+    /// drop(stack);
+    /// assert_eq!(Rc::strong_count(&counter), 2);
+    ///
+    /// drop(empty);
+    /// assert_eq!(Rc::strong_count(&counter), 1);
+    /// ```
+    pub fn swap(&mut self, other: &mut Self) {
+        core::mem::swap(self, other);
+    }
+
+    /// Swaps the inner values as a concrete type with another stack of any capacity.
+    /// Returns an error without touching either value if `T` is not equal to either contained value type.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::path::PathBuf;
+    ///
+    /// let mut component = stack_any::StackAny::<64>::try_new(PathBuf::from("a")).unwrap();
+    /// let mut scratch = stack_any::StackAny::<128>::try_new(PathBuf::from("b")).unwrap();
+    ///
+    /// assert_eq!(component.swap_value_with::<PathBuf, 128>(&mut scratch), Ok(()));
+    /// assert_eq!(component.downcast_ref::<PathBuf>(), Some(&PathBuf::from("b")));
+    /// assert_eq!(scratch.downcast_ref::<PathBuf>(), Some(&PathBuf::from("a")));
+    ///
+    /// let mut empty = stack_any::StackAny::<128>::empty();
+    /// let result = component.swap_value_with::<PathBuf, 128>(&mut empty);
+    /// assert_eq!(result, Err(stack_any::SwapError::OtherMismatch));
+    ///
+    /// let result = component.swap_value_with::<String, 128>(&mut scratch);
+    /// assert_eq!(result, Err(stack_any::SwapError::SelfMismatch));
+    /// ```
+    pub fn swap_value_with<T, const M: usize>(
+        &mut self,
+        other: &mut StackAny<M>,
+    ) -> Result<(), SwapError>
+    where
+        T: core::any::Any,
+    {
+        let value = self.downcast_mut::<T>().ok_or(SwapError::SelfMismatch)?;
+        let other = other.downcast_mut::<T>().ok_or(SwapError::OtherMismatch)?;
+
+        core::mem::swap(value, other);
+        Ok(())
+    }
+
+    /// Attempt to move the inner value out as a concrete type, leaving the stack empty.
+    /// Returns None without touching the contained value if `T` is not equal to contained value type.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut stacks = [
+    ///     stack_any::stack_any!(Vec<i32>, vec![5]),
+    ///     stack_any::stack_any!(Vec<char>, vec!['x']),
+    /// ];
+    ///
+    /// assert_eq!(stacks[0].take::<Vec<char>>(), None);
+    /// assert_eq!(stacks[0].take::<Vec<i32>>(), Some(vec![5]));
+    ///
+    /// assert!(stacks[0].is_empty());
+    /// assert_eq!(stacks[0].take::<Vec<i32>>(), None);
+    /// assert_eq!(stacks[0].downcast_ref::<Vec<i32>>(), None);
+    /// ```
+    ///
+    /// Dropping the empty stack does not drop the taken value again.
+    ///
+    /// ```
+    /// use std::rc::Rc;
+    ///
+    /// let counter = Rc::new(());
+    /// let mut stack = stack_any::StackAny::<8>::try_new(counter.clone()).unwrap();
+    ///
+    /// let taken = stack.take::<Rc<()>>().unwrap();
+    /// drop(stack);
+    /// assert_eq!(Rc::strong_count(&counter), 2);
+    ///
+    /// drop(taken);
+    /// assert_eq!(Rc::strong_count(&counter), 1);
+    /// ```
+    pub fn take<T>(&mut self) -> Option<T>
+    where
+        T: core::any::Any,
+    {
+        if !self.is::<T>() {
+            return None;
+        }
+
+        Some(unsafe { self.move_out() })
+    }
+
+    /// Attempt to move the inner value out as a concrete type if `pred` returns true for it,
+    /// leaving the stack empty.
+    /// Returns None without touching the contained value if `T` is not equal to contained value type
+    /// or `pred` returns false.
+    ///
+    /// If `pred` panics, the inner value stays in place.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// struct Timer {
+    ///     deadline: u32,
+    /// }
+    ///
+    /// let mut stacks = [
+    ///     stack_any::StackAny::<8>::try_new(Timer { deadline: 5 }).unwrap(),
+    ///     stack_any::StackAny::<8>::try_new('x').unwrap(),
+    ///     stack_any::StackAny::<8>::try_new(Timer { deadline: 15 }).unwrap(),
+    /// ];
+    ///
+    /// let expired = stacks
+    ///     .iter_mut()
+    ///     .filter_map(|stack| stack.take_if(|timer: &Timer| timer.deadline < 10))
+    ///     .count();
+    /// assert_eq!(expired, 1);
+    ///
+    /// assert!(stacks[0].is_empty());
+    /// assert!(stacks[1].is::<char>());
+    /// assert!(stacks[2].is::<Timer>());
+    /// ```
+    ///
+    /// ```
+    /// let mut stack = stack_any::stack_any!(i32, 5);
+    ///
+    /// let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+    ///     stack.take_if(|_: &i32| panic!());
+    /// }));
+    /// assert!(result.is_err());
+    /// assert_eq!(stack.downcast_ref::<i32>(), Some(&5));
+    /// ```
+    pub fn take_if<T>(&mut self, pred: impl FnOnce(&T) -> bool) -> Option<T>
+    where
+        T: core::any::Any,
+    {
+        if !pred(self.downcast_ref::<T>()?) {
+            return None;
+        }
+
+        self.take()
+    }
+
+    /// Replaces the contained value with `value`, returning the old value.
+    /// Returns `value` back without touching the contained value if `T` is not equal to contained value type.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut stack = stack_any::stack_any!(Vec<i32>, vec![5]);
+    ///
+    /// assert_eq!(stack.replace(vec![7]), Ok(vec![5]));
+    /// assert_eq!(stack.downcast_ref::<Vec<i32>>(), Some(&vec![7]));
+    ///
+    /// assert_eq!(stack.replace(vec!['x']), Err(vec!['x']));
+    /// assert_eq!(stack.downcast_ref::<Vec<i32>>(), Some(&vec![7]));
+    /// ```
+    pub fn replace<T>(&mut self, value: T) -> Result<T, T>
+    where
+        T: core::any::Any,
+    {
+        match self.downcast_mut() {
+            Some(inner) => Ok(core::mem::replace(inner, value)),
+            None => Err(value),
+        }
+    }
+
+    /// Replaces the contained value with `value` of any type, returning the old value as another stack.
+    /// Returns `value` back without touching the contained value if `U` does not fit.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut stack = stack_any::StackAny::<8>::try_new(5i32).unwrap();
+    ///
+    /// let old = stack.replace_any('x').unwrap();
+    /// assert_eq!(old.downcast_ref::<i32>(), Some(&5));
+    /// assert_eq!(stack.downcast_ref::<char>(), Some(&'x'));
+    ///
+    /// assert!(stack.replace_any([0u8; 16]).is_err());
+    /// ```
+    pub fn replace_any<U>(&mut self, value: U) -> Result<Self, U>
+    where
+        U: core::any::Any,
+    {
+        if !Self::fits::<U>() {
+            return Err(value);
+        }
+
+        let stack = Self::try_new(value).unwrap();
+        Ok(core::mem::replace(self, stack))
+    }
+
+    /// Allocates N-size memory on the stack and then moves the value pointed to by `ptr` into it.
+    /// Returns None without reading `ptr` if `T` size is larger than N or `T` alignment is larger than [`StackAny::ALIGN`].
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must be valid for reads, properly aligned and point to an initialized value of `T`.
+    /// On success, the ownership of the value moves to the stack,
+    /// so the pointee must not be used or dropped afterwards.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut value = std::mem::ManuallyDrop::new(vec![5]);
+    ///
+    /// let stack = unsafe { stack_any::StackAny::<24>::from_raw_ptr(&mut *value as *mut Vec<i32>) };
+    /// assert_eq!(stack.unwrap().downcast_ref::<Vec<i32>>(), Some(&vec![5]));
+    /// ```
+    pub unsafe fn from_raw_ptr<T>(ptr: *mut T) -> Option<Self>
+    where
+        T: core::any::Any,
+    {
+        Self::try_new_with(|| unsafe { core::ptr::read(ptr) })
+    }
+
+    /// Attempt to move the inner value as a concrete type out to `dst`.
+    /// Returns the stack back without writing `dst` if `T` is not equal to contained value type.
+    ///
+    /// # Safety
+    ///
+    /// `dst` must be valid for writes and properly aligned.
+    /// The previous value at `dst` is overwritten without being dropped.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let five = stack_any::stack_any!(i32, 5);
+    ///
+    /// let mut value = std::mem::MaybeUninit::<i32>::uninit();
+    /// unsafe { five.write_to_ptr(value.as_mut_ptr()) }.unwrap();
+    /// assert_eq!(unsafe { value.assume_init() }, 5);
+    /// ```
+    pub unsafe fn write_to_ptr<T>(self, dst: *mut T) -> Result<(), Self>
+    where
+        T: core::any::Any,
+    {
+        let value = self.downcast::<T>()?;
+        unsafe { core::ptr::write(dst, value) };
+        Ok(())
+    }
+
+    /// Moves the contained value into a stack of larger capacity `M` without dropping it.
+    ///
+    /// Whether `M` is not less than `N` is checked at compile time.
+    /// `From` can not express this conversion, since it would overlap with `From<T> for T` when `M` equals `N`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let five = stack_any::StackAny::<4>::try_new(5i32).unwrap();
+    ///
+    /// let five = five.grow::<16>();
+    /// assert_eq!(five.capacity(), 16);
+    /// assert_eq!(five.downcast_ref::<i32>(), Some(&5));
+    /// ```
+    ///
+    /// ```
+    /// use std::rc::Rc;
+    ///
+    /// let counter = Rc::new(());
+    /// let stack = stack_any::StackAny::<8>::try_new(counter.clone()).unwrap();
+    ///
+    /// let stack = stack.grow::<64>();
+    /// assert_eq!(Rc::strong_count(&counter), 2);
+    ///
+    /// drop(stack);
+    /// assert_eq!(Rc::strong_count(&counter), 1);
+    /// ```
+    ///
+    /// ```compile_fail
+    /// let five = stack_any::StackAny::<16>::try_new(5i32).unwrap();
+    /// let five = five.grow::<4>();
+    /// ```
+    pub fn grow<const M: usize>(self) -> StackAny<M> {
+        const { assert!(N <= M, "capacity can not shrink") };
+
+        self.into_capacity()
+    }
+
+    /// Attempt to move the contained value into a stack of smaller capacity `M` without dropping it.
+    /// Returns the stack back if the contained value does not fit in `M`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let five = stack_any::StackAny::<256>::try_new(5i32).unwrap();
+    ///
+    /// let five = five.try_shrink::<4>().unwrap();
+    /// assert_eq!(five.capacity(), 4);
+    /// assert_eq!(five.downcast_ref::<i32>(), Some(&5));
+    ///
+    /// let empty = stack_any::StackAny::<256>::empty();
+    /// assert!(empty.try_shrink::<0>().unwrap().is_empty());
+    /// ```
+    ///
+    /// ```
+    /// use std::rc::Rc;
+    ///
+    /// let counter = Rc::new(());
+    /// let stack = stack_any::StackAny::<256>::try_new(vec![counter.clone()]).unwrap();
+    ///
+    /// let stack = stack.try_shrink::<8>().unwrap_err();
+    /// assert_eq!(stack.downcast_ref::<Vec<Rc<()>>>().map(Vec::len), Some(1));
+    /// assert_eq!(Rc::strong_count(&counter), 2);
+    ///
+    /// drop(stack);
+    /// assert_eq!(Rc::strong_count(&counter), 1);
+    /// ```
+    pub fn try_shrink<const M: usize>(self) -> Result<StackAny<M>, Self> {
+        if M < self.vtable.size {
+            return Err(self);
+        }
+
+        Ok(self.into_capacity())
+    }
+
+    /// Moves the contained value into a stack of capacity `M` without dropping it.
+    /// The contained value must fit in `M`.
+    fn into_capacity<const M: usize>(self) -> StackAny<M> {
+        let parts = self.into_raw_parts();
+        let size = parts.vtable.size;
+
+        let mut bytes = [core::mem::MaybeUninit::uninit(); M];
+        bytes[..size].copy_from_slice(&parts.bytes[..size]);
+
+        let parts = RawParts {
+            type_id: parts.type_id,
+            bytes,
+            vtable: parts.vtable,
+        };
+        unsafe { StackAny::from_raw_parts(parts) }
+    }
+
+    /// Moves the contained value onto the heap, keeping its concrete type.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let string = stack_any::stack_any!(String, String::from("x"));
+    ///
+    /// let boxed = string.into_box_any();
+    /// assert_eq!(boxed.downcast::<String>().ok(), Some(Box::new(String::from("x"))));
+    /// ```
+    ///
+    /// ```
+    /// use std::rc::Rc;
+    ///
+    /// let counter = Rc::new(());
+    /// let stack = stack_any::StackAny::<8>::try_new(counter.clone()).unwrap();
+    ///
+    /// let boxed = stack.into_box_any();
+    /// assert_eq!(Rc::strong_count(&counter), 2);
+    ///
+    /// drop(boxed);
+    /// assert_eq!(Rc::strong_count(&counter), 1);
+    /// ```
+    #[cfg(feature = "alloc")]
+    pub fn into_box_any(self) -> alloc::boxed::Box<dyn core::any::Any> {
+        let this = core::mem::ManuallyDrop::new(self);
+
+        let layout = core::alloc::Layout::for_value(this.as_any());
+        if layout.size() == 0 {
+            return unsafe { alloc::boxed::Box::from_raw(this.vtable.meta) };
+        }
+
+        let dst = unsafe { alloc::alloc::alloc(layout) };
+        if dst.is_null() {
+            alloc::alloc::handle_alloc_error(layout);
+        }
+
+        let src = this.bytes.0.as_ptr();
+        unsafe { core::ptr::copy_nonoverlapping(src, dst.cast(), layout.size()) };
+        unsafe { alloc::boxed::Box::from_raw(any_ptr(this.vtable.meta, layout.size(), dst.cast())) }
+    }
+
+    /// Allocates N-size memory on the stack and then places `value` into it.
+    /// Places `value` onto the heap instead if `T` size is larger than N or `T` alignment is larger than [`StackAny::ALIGN`],
+    /// telling which one happened by the variant of [`StoredAny`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use stack_any::{StackAny, StoredAny};
+    ///
+    /// assert!(matches!(StackAny::<8>::try_new_or_boxed(5i32), StoredAny::Inline(_)));
+    /// assert!(matches!(StackAny::<8>::try_new_or_boxed([5u64; 16]), StoredAny::Boxed(_)));
+    /// ```
+    ///
+    /// The value is dropped exactly once in either variant.
+    ///
+    /// ```
+    /// use std::rc::Rc;
+    ///
+    /// let counter = Rc::new(());
+    /// let inline = stack_any::StackAny::<8>::try_new_or_boxed(counter.clone());
+    /// let boxed = stack_any::StackAny::<8>::try_new_or_boxed(vec![counter.clone(); 2]);
+    /// assert_eq!(Rc::strong_count(&counter), 4);
+    ///
+    /// drop(inline);
+    /// assert_eq!(Rc::strong_count(&counter), 3);
+    ///
+    /// drop(boxed);
+    /// assert_eq!(Rc::strong_count(&counter), 1);
+    /// ```
+    #[cfg(feature = "alloc")]
+    pub fn try_new_or_boxed<T>(value: T) -> StoredAny<N>
+    where
+        T: core::any::Any,
+    {
+        match Self::try_new_or_return(value) {
+            Ok(stack) => StoredAny::Inline(stack),
+            Err(value) => StoredAny::Boxed(alloc::boxed::Box::new(value)),
+        }
+    }
+
+    /// Decomposes the stack into its raw parts without dropping the contained value.
+    ///
+    /// The vtable keeps the capabilities captured by [`StackAnyBuilder`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let five = stack_any::stack_any!(i32, 5);
+    ///
+    /// let parts = five.into_raw_parts();
+    /// assert_eq!(parts.type_id, std::any::TypeId::of::<i32>());
+    /// assert_eq!(parts.vtable.size(), 4);
+    ///
+    /// let five = unsafe { stack_any::StackAny::from_raw_parts(parts) };
+    /// assert_eq!(five.downcast_ref::<i32>(), Some(&5));
+    /// ```
+    pub fn into_raw_parts(self) -> RawParts<N> {
+        let this = core::mem::ManuallyDrop::new(self);
+
+        RawParts {
+            type_id: this.type_id,
+            bytes: this.bytes.0,
+            vtable: this.vtable,
+        }
+    }
+
+    /// Returns the bytes of the contained value without dropping it.
+    ///
+    /// This leaks the resources owned by the value unless the caller reinterprets the bytes as the value.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::rc::Rc;
+    ///
+    /// let counter = Rc::new(());
+    /// let stack = stack_any::StackAny::<8>::try_new(counter.clone()).unwrap();
+    ///
+    /// let bytes = stack.into_inner_bytes();
+    /// assert_eq!(Rc::strong_count(&counter), 2);
+    ///
+    /// let value = unsafe { std::ptr::read_unaligned(bytes.as_ptr() as *const Rc<()>) };
+    /// drop(value);
+    /// assert_eq!(Rc::strong_count(&counter), 1);
+    /// ```
+    pub fn into_inner_bytes(self) -> [core::mem::MaybeUninit<u8>; N] {
+        self.into_raw_parts().bytes
+    }
+
+    /// Composes the stack from its raw parts.
+    ///
+    /// # Safety
+    ///
+    /// The parts must describe a single value, as the ones returned by [`StackAny::into_raw_parts`] do:
+    ///
+    /// - `bytes` must begin with a valid value of the type identified by `type_id`,
+    ///   whose size is at most N and whose alignment is at most [`StackAny::ALIGN`].
+    /// - `vtable` must describe that type.
+    /// - The value must not be used or dropped elsewhere afterwards.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::rc::Rc;
+    ///
+    /// let counter = Rc::new(());
+    /// let stack = stack_any::StackAny::<8>::try_new(counter.clone()).unwrap();
+    ///
+    /// let parts = stack.into_raw_parts();
+    /// assert_eq!(Rc::strong_count(&counter), 2);
+    ///
+    /// let stack = unsafe { stack_any::StackAny::from_raw_parts(parts) };
+    /// drop(stack);
+    /// assert_eq!(Rc::strong_count(&counter), 1);
+    /// ```
+    pub unsafe fn from_raw_parts(parts: RawParts<N>) -> Self {
+        Self {
+            type_id: parts.type_id,
+            bytes: Buffer(parts.bytes),
+            vtable: parts.vtable,
+            _marker: core::marker::PhantomData,
+        }
+    }
+}
+
+/// Formats the contained value by `Debug` captured by [`StackAnyBuilder::with_debug`],
+/// forwarding the formatting flags such as `{:#?}`.
+///
+/// A value without the capability is not touched, and its metadata is formatted instead.
+/// With the `type-name` feature, the name of the contained value type is printed,
+/// otherwise its `TypeId`.
+///
+/// # Examples
+///
+/// ```
+/// #[derive(Debug)]
+/// struct Point {
+///     x: i32,
+///     y: i32,
+/// }
+///
+/// let point = stack_any::StackAny::<8>::builder(Point { x: 1, y: 2 }).with_debug().build();
+/// assert_eq!(format!("{:?}", point), format!("{:?}", Point { x: 1, y: 2 }));
+/// assert_eq!(format!("{:#?}", point), format!("{:#?}", Point { x: 1, y: 2 }));
+///
+/// let values = stack_any::StackAny::<24>::builder(vec![1.5f32, 2.0]).with_debug().build();
+/// assert_eq!(format!("{:?}", values), format!("{:?}", vec![1.5f32, 2.0]));
+/// assert_eq!(format!("{:.2?}", values), "[1.50, 2.00]");
+/// ```
+///
+/// ```
+/// let stack = stack_any::stack_any!(Vec<i32>, vec![5]);
+/// let debug = format!("{:?}", stack);
+///
+/// assert!(debug.starts_with("StackAny { capacity: 24, type"));
+/// assert!(debug.ends_with("size: 24 }"));
+/// # #[cfg(feature = "type-name")]
+/// # assert_eq!(debug, "StackAny { capacity: 24, type: \"alloc::vec::Vec<i32>\", size: 24 }");
+/// ```
+impl<const N: usize> core::fmt::Debug for StackAny<N> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        if let Some(debug_fn) = self.vtable.debug_fn {
+            return unsafe { debug_fn(self.bytes.0.as_ptr(), f) };
+        }
+
+        let mut debug = f.debug_struct("StackAny");
+        debug.field("capacity", &N);
+        #[cfg(feature = "type-name")]
+        debug.field("type", &self.vtable.type_name());
+        #[cfg(not(feature = "type-name"))]
+        debug.field("type_id", &self.type_id);
+        debug.field("size", &self.vtable.size).finish()
+    }
+}
+
+/// Formats a stack as an element of a collection, that is the name of the contained value type
+/// with the `type-name` feature, followed by the value formatted by `Debug` captured by
+/// [`StackAnyBuilder::with_debug`], or `<opaque N bytes>` without the capability.
+pub(crate) struct DebugElement<'a, const N: usize>(pub(crate) &'a StackAny<N>);
+
+impl<const N: usize> core::fmt::Debug for DebugElement<'_, N> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let stack = self.0;
+        if stack.is_empty() {
+            return write!(f, "<empty>");
+        }
+
+        #[cfg(feature = "type-name")]
+        write!(f, "{}: ", stack.vtable.type_name())?;
+
+        match stack.vtable.debug_fn {
+            Some(debug_fn) => unsafe { debug_fn(stack.bytes.0.as_ptr(), f) },
+            None => write!(f, "<opaque {} bytes>", stack.vtable.size),
+        }
+    }
+}
+
+/// Formats the contained value by `Display` captured by [`StackAnyBuilder::with_display`],
+/// forwarding the formatting flags such as width and precision.
+///
+/// A value without the capability is not touched, and its type is formatted in angle brackets instead.
+/// With the `type-name` feature, the name of the contained value type is printed,
+/// otherwise its `TypeId`.
+///
+/// # Examples
+///
+/// ```
+/// let status = [
+///     stack_any::StackAny::<24>::builder(5i32).with_display().build(),
+///     stack_any::StackAny::<24>::builder(1.5f64).with_display().build(),
+///     stack_any::StackAny::<24>::builder(String::from("ok")).with_display().build(),
+/// ];
+///
+/// let table: Vec<String> = status.iter().map(|value| format!("[{:>4.2}]", value)).collect();
+/// assert_eq!(table, ["[   5]", "[1.50]", "[  ok]"]);
+/// ```
+///
+/// ```
+/// let five = stack_any::stack_any!(i32, 5);
+/// assert!(five.to_string().starts_with('<'));
+/// # #[cfg(feature = "type-name")]
+/// # assert_eq!(five.to_string(), "<i32>");
+/// ```
+impl<const N: usize> core::fmt::Display for StackAny<N> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        if let Some(display_fn) = self.vtable.display_fn {
+            return unsafe { display_fn(self.bytes.0.as_ptr(), f) };
+        }
+
+        #[cfg(feature = "type-name")]
+        return write!(f, "<{}>", self.vtable.type_name());
+        #[cfg(not(feature = "type-name"))]
+        return write!(f, "<{:?}>", self.type_id);
+    }
+}
+
+/// Clones the stack together with the contained value.
+///
+/// Only stacks built with [`StackAnyBuilder::with_clone`] can be cloned, see [`StackAny::try_clone`]
+/// for the non-panicking version.
+///
+/// # Panics
+///
+/// Panics if the stack is not [cloneable](StackAny::is_cloneable).
+///
+/// # Examples
+///
+/// ```
+/// let five = stack_any::StackAny::<4>::builder(5i32).with_clone().build();
+/// let stacks = vec![five; 4];
+/// assert!(stacks.iter().all(|stack| stack.downcast_ref::<i32>() == Some(&5)));
+/// ```
+///
+/// ```should_panic
+/// let five = stack_any::stack_any!(i32, 5);
+/// let _ = five.clone();
+/// ```
+impl<const N: usize> Clone for StackAny<N> {
+    fn clone(&self) -> Self {
+        match self.try_clone() {
+            Some(stack) => stack,
+            None => panic!("contained value is not cloneable"),
+        }
+    }
+}
+
+/// Compares the contained values, which are equal only if their types are equal
+/// and they compare equal.
+///
+/// Values are compared by `PartialEq` captured by [`StackAnyBuilder::with_eq`], if both stacks captured it
+/// and either both or neither captured `Hash` by [`StackAnyBuilder::with_hash`], so that equal stacks hash equally.
+/// Otherwise a stack is equal only to itself, while empty stacks are equal.
+///
+/// # Examples
+///
+/// ```
+/// let string = |value: &str| {
+///     stack_any::StackAny::<24>::builder(String::from(value)).with_eq().build()
+/// };
+///
+/// assert_eq!(string("x"), string("x"));
+/// assert_ne!(string("x"), string("y"));
+/// assert_ne!(string("x"), stack_any::StackAny::<24>::builder(5i32).with_eq().build());
+///
+/// let mut settings = vec![string("x"), string("x"), string("y")];
+/// settings.dedup();
+/// assert_eq!(settings.len(), 2);
+/// ```
+///
+/// ```
+/// let five = stack_any::stack_any!(i32, 5);
+/// assert_eq!(five, five);
+/// assert_ne!(five, stack_any::stack_any!(i32, 5));
+/// assert_ne!(five, stack_any::StackAny::<4>::builder(5i32).with_eq().build());
+///
+/// let hashed = stack_any::StackAny::<4>::builder(5i32).with_eq().with_hash().build();
+/// assert_ne!(hashed, stack_any::StackAny::<4>::builder(5i32).with_eq().build());
+/// assert_eq!(hashed, stack_any::StackAny::<4>::builder(5i32).with_eq().with_hash().build());
+///
+/// assert_eq!(stack_any::StackAny::<4>::empty(), stack_any::StackAny::<8>::empty());
+/// ```
+impl<const N: usize, const M: usize> PartialEq<StackAny<M>> for StackAny<N> {
+    fn eq(&self, other: &StackAny<M>) -> bool {
+        if self.type_id != other.type_id {
+            return false;
+        }
+
+        if self.is_empty() {
+            return true;
+        }
+
+        match (self.vtable.eq_fn, other.vtable.eq_fn) {
+            (Some(eq_fn), Some(_))
+                if self.vtable.hash_fn.is_some() == other.vtable.hash_fn.is_some() =>
+            unsafe { eq_fn(self.bytes.0.as_ptr(), other.bytes.0.as_ptr()) },
+            _ => core::ptr::addr_eq(self, other),
+        }
+    }
+}
+
+/// Stacks are compared as described in `PartialEq`, which is reflexive as long as
+/// the `PartialEq` captured by [`StackAnyBuilder::with_eq`] is.
+impl<const N: usize> Eq for StackAny<N> {}
+
+/// Orders the stacks by the `TypeId`s of the contained value types first,
+/// and then by the contained values of the same type.
+///
+/// The values are ordered by `Ord` captured by [`StackAnyBuilder::with_ord`], if both stacks captured it,
+/// the ones without `Hash` captured by [`StackAnyBuilder::with_hash`] before the ones with it, as in `PartialEq`.
+/// Otherwise values of the same type are not ordered unless they are equal, and None is returned.
+/// The order between types is stable within a build, but not across builds.
+///
+/// # Examples
+///
+/// ```
+/// use std::cmp::Ordering;
+///
+/// let plain = stack_any::stack_any!(i32, 5);
+/// let ordered = stack_any::StackAny::<4>::builder(1i32).with_ord().build();
+///
+/// assert_eq!(plain.partial_cmp(&ordered), None);
+/// assert_eq!(plain.partial_cmp(&stack_any::stack_any!(i32, 7)), None);
+/// assert_eq!(plain.partial_cmp(&plain), Some(Ordering::Equal));
+/// assert_eq!(ordered.partial_cmp(&stack_any::StackAny::<4>::builder(2i32).with_ord().build()), Some(Ordering::Less));
+/// assert!(plain.partial_cmp(&stack_any::stack_any!(u32, 1)).is_some());
+/// ```
+#[allow(clippy::non_canonical_partial_ord_impl)]
+impl<const N: usize> PartialOrd for StackAny<N> {
+    fn partial_cmp(&self, other: &Self) -> Option<core::cmp::Ordering> {
+        match self.type_id.cmp(&other.type_id) {
+            core::cmp::Ordering::Equal => {}
+            ordering => return Some(ordering),
+        }
+
+        if self.is_empty() {
+            return Some(core::cmp::Ordering::Equal);
+        }
+
+        match (self.vtable.cmp_fn, other.vtable.cmp_fn) {
+            (Some(cmp_fn), Some(_)) => {
+                let lhs = self.vtable.hash_fn.is_some();
+                let rhs = other.vtable.hash_fn.is_some();
+                Some(
+                    lhs.cmp(&rhs).then_with(|| unsafe {
+                        cmp_fn(self.bytes.0.as_ptr(), other.bytes.0.as_ptr())
+                    }),
+                )
+            }
+            _ => (self == other).then_some(core::cmp::Ordering::Equal),
+        }
+    }
+}
+
+/// Orders the stacks as described in `PartialOrd`, so that slices of stacks can be sorted
+/// as long as the values of the same type are built with [`StackAnyBuilder::with_ord`].
+///
+/// # Panics
+///
+/// Panics if the stacks are not ordered, see [`PartialOrd::partial_cmp`] for the non-panicking version.
+///
+/// # Examples
+///
+/// ```
+/// fn sample<T: Ord + 'static>(value: T) -> stack_any::StackAny<32> {
+///     stack_any::StackAny::builder(value).with_ord().build()
+/// }
+///
+/// let mut samples = Vec::new();
+/// for i in 0..64u32 {
+///     samples.push(sample(i % 7));
+///     samples.push(sample(i as i64 % 5 - 2));
+///     samples.push(sample(format!("{}", i % 11)));
+/// }
+///
+/// // Shuffle deterministically with a linear congruential generator.
+/// let mut seed = 12345u64;
+/// for i in (1..samples.len()).rev() {
+///     seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+///     samples.swap(i, (seed >> 33) as usize % (i + 1));
+/// }
+///
+/// let describe = |samples: &[stack_any::StackAny<32>]| {
+///     let mut values: Vec<_> = samples
+///         .iter()
+///         .map(|s| match (s.downcast_ref::<u32>(), s.downcast_ref::<i64>()) {
+///             (Some(value), _) => format!("u32 {}", value),
+///             (_, Some(value)) => format!("i64 {}", value),
+///             _ => format!("string {}", s.downcast_ref::<String>().unwrap()),
+///         })
+///         .collect();
+///     values.sort();
+///     values
+/// };
+/// let before = describe(&samples);
+///
+/// samples.sort();
+///
+/// assert!(samples.windows(2).all(|w| w[0] <= w[1]));
+/// assert_eq!(describe(&samples), before);
+/// assert_eq!(samples.len(), 192);
+/// ```
+///
+/// ```
+/// use std::cmp::Ordering;
+///
+/// let plain = stack_any::stack_any!(i32, 5);
+/// assert_eq!(plain.cmp(&plain), Ordering::Equal);
+/// assert_eq!(stack_any::StackAny::<4>::empty().cmp(&stack_any::StackAny::empty()), Ordering::Equal);
+/// ```
+///
+/// ```should_panic
+/// let plain = stack_any::stack_any!(i32, 5);
+/// let _ = plain.cmp(&stack_any::stack_any!(i32, 7));
+/// ```
+impl<const N: usize> Ord for StackAny<N> {
+    fn cmp(&self, other: &Self) -> core::cmp::Ordering {
+        match self.partial_cmp(other) {
+            Some(ordering) => ordering,
+            None => panic!("contained values are not ordered"),
+        }
+    }
+}
+
+/// Hashes the `TypeId` of the contained value type, followed by the contained value.
+///
+/// The value is hashed by `Hash` captured by [`StackAnyBuilder::with_hash`],
+/// while only the `TypeId` is hashed for a value without the capability.
+/// A stack with the capability is equal only to stacks with it, so equal stacks hash equally.
+///
+/// # Examples
+///
+/// ```
+/// use std::collections::HashMap;
+///
+/// fn key<T: Eq + std::hash::Hash + 'static>(value: T) -> stack_any::StackAny<24> {
+///     stack_any::StackAny::builder(value).with_eq().with_hash().build()
+/// }
+///
+/// let mut cache = HashMap::new();
+/// cache.insert(key(5i32), "i32");
+/// cache.insert(key(5i64), "i64");
+/// cache.insert(key(String::from("x")), "string");
+/// cache.insert(key(()), "unit");
+///
+/// assert_eq!(cache.len(), 4);
+/// assert_eq!(cache.get(&key(5i32)), Some(&"i32"));
+/// assert_eq!(cache.get(&key(5i64)), Some(&"i64"));
+/// assert_eq!(cache.get(&key(String::from("x"))), Some(&"string"));
+/// assert_eq!(cache.get(&key(())), Some(&"unit"));
+/// assert_eq!(cache.get(&key(6i32)), None);
+/// assert_eq!(cache.get(&key(String::from("y"))), None);
+/// ```
+impl<const N: usize> core::hash::Hash for StackAny<N> {
+    fn hash<H: core::hash::Hasher>(&self, state: &mut H) {
+        self.type_id.hash(state);
+
+        if let Some(hash_fn) = self.vtable.hash_fn {
+            unsafe { hash_fn(self.bytes.0.as_ptr(), state) };
+        }
+    }
+}
+
+impl<const N: usize> Default for StackAny<N> {
+    fn default() -> Self {
+        Self::empty()
+    }
+}
+
+/// Moves the boxed value onto the stack, freeing the heap allocation.
+/// Returns the box back if the value size is larger than N or its alignment is larger than [`StackAny::ALIGN`].
+///
+/// The concrete type is not known, so with the `type-name` feature the stack reports `dyn Any` as its type name.
+///
+/// The vtable of the type is only known at runtime, so it is allocated on the first conversion of each type
+/// and leaked for the rest of the program, and looked up by a linear search over the types converted so far.
+/// Prefer [`StackAny::try_new`] where the type is known at compile time.
+///
+/// # Examples
+///
+/// ```
+/// use std::any::Any;
+///
+/// let boxed: Box<dyn Any> = Box::new(5i32);
+/// let five = stack_any::StackAny::<8>::try_from(boxed).unwrap();
+/// assert_eq!(five.downcast_ref::<i32>(), Some(&5));
+///
+/// let boxed: Box<dyn Any> = Box::new([0u8; 16]);
+/// let boxed = stack_any::StackAny::<8>::try_from(boxed).unwrap_err();
+/// assert_eq!(boxed.downcast_ref::<[u8; 16]>(), Some(&[0; 16]));
+///
+/// let boxed: Box<dyn Any> = Box::new(());
+/// let unit = stack_any::StackAny::<0>::try_from(boxed).unwrap();
+/// assert!(unit.is::<()>());
+/// ```
+///
+/// ```
+/// use std::rc::Rc;
+///
+/// let counter = Rc::new(());
+/// let boxed: Box<dyn std::any::Any> = Box::new(counter.clone());
+///
+/// let stack = stack_any::StackAny::<8>::try_from(boxed).unwrap();
+/// assert_eq!(Rc::strong_count(&counter), 2);
+///
+/// let boxed = stack.into_box_any();
+/// assert_eq!(Rc::strong_count(&counter), 2);
+///
+/// let stack = stack_any::StackAny::<8>::try_from(boxed).unwrap();
+/// drop(stack);
+/// assert_eq!(Rc::strong_count(&counter), 1);
+/// ```
+///
+/// ```
+/// use std::any::Any;
+///
+/// #[derive(Clone, Copy)]
+/// struct Interned(u16);
+///
+/// let vtable = || {
+///     let boxed: Box<dyn Any> = Box::new(Interned(5));
+///     let parts = stack_any::StackAny::<8>::try_from(boxed).ok().unwrap().into_raw_parts();
+///     parts.vtable as *const stack_any::VTable as usize
+/// };
+///
+/// let handles: Vec<_> = (0..4).map(|_| std::thread::spawn(vtable)).collect();
+/// let vtables: Vec<usize> = handles.into_iter().map(|handle| handle.join().unwrap()).collect();
+///
+/// assert!(vtables.iter().all(|&other| other == vtable()));
+/// ```
+#[cfg(feature = "alloc")]
+impl<const N: usize> TryFrom<alloc::boxed::Box<dyn core::any::Any>> for StackAny<N> {
+    type Error = alloc::boxed::Box<dyn core::any::Any>;
+
+    fn try_from(value: alloc::boxed::Box<dyn core::any::Any>) -> Result<Self, Self::Error> {
+        let layout = core::alloc::Layout::for_value(&*value);
+        if layout.size() != 0 && (N < layout.size() || Self::ALIGN < layout.align()) {
+            return Err(value);
+        }
+
+        let mut stack = Self::empty();
+        let type_id = (*value).type_id();
+        let vtable = VTable::of_boxed(&*value);
+
+        let ptr = alloc::boxed::Box::into_raw(value);
+        if layout.size() != 0 {
+            let dst = stack.bytes.0.as_mut_ptr();
+            unsafe { core::ptr::copy_nonoverlapping(ptr.cast(), dst, layout.size()) };
+            unsafe { alloc::alloc::dealloc(ptr.cast(), layout) };
+        }
+        stack.type_id = type_id;
+        stack.vtable = vtable;
+
+        Ok(stack)
+    }
+}
+
+impl<const N: usize> Drop for StackAny<N> {
+    fn drop(&mut self) {
+        if self.vtable.needs_drop {
+            let ptr = any_ptr(
+                self.vtable.meta,
+                self.vtable.size,
+                self.bytes.0.as_mut_ptr(),
+            );
+            unsafe { core::ptr::drop_in_place(ptr) };
+        }
+        self.scrub(self.vtable.size);
+    }
+}
+
+/// Expands to the largest size of given types, or 0 if none is given, as a const expression.
+///
+/// # Examples
+///
+/// ```
+/// use stack_any::{max_size_of, StackAny};
+///
+/// struct Position([f32; 3]);
+/// struct Name(&'static str);
+///
+/// type Slot = StackAny<{ max_size_of!(u8, Position, Name, String, (u8, u64)) }>;
+///
+/// assert_eq!(max_size_of!(u8, Position, Name, String, (u8, u64)), 24);
+/// assert_eq!(max_size_of!(u8, u16), 2);
+/// assert_eq!(max_size_of!(), 0);
+///
+/// let slots: [Slot; 5] = [
+///     StackAny::new(1u8),
+///     StackAny::new(Position([0.0; 3])),
+///     StackAny::new(Name("alice")),
+///     StackAny::new(String::from("bob")),
+///     StackAny::new((1u8, 2u64)),
+/// ];
+/// assert_eq!(slots[3].downcast_ref::<String>().unwrap(), "bob");
+///
+/// const N: usize = max_size_of!([u8; 3], u32,);
+/// assert_eq!(N, 4);
+/// ```
+#[macro_export]
+macro_rules! max_size_of {
+    ($($type:ty),* $(,)?) => {
+        $crate::max(&[$(::core::mem::size_of::<$type>()),*])
+    };
+}
+
+/// Expands to the largest alignment of given types, or 0 if none is given, as a const expression.
+///
+/// # Examples
+///
+/// ```
+/// use stack_any::{max_align_of, StackAny};
+///
+/// assert_eq!(max_align_of!(u8, [u16; 3], (u8, u64)), 8);
+/// assert_eq!(max_align_of!(u8, [u8; 16]), 1);
+/// assert_eq!(max_align_of!(), 0);
+///
+/// const { assert!(max_align_of!(u8, u32, f64) <= StackAny::<8>::ALIGN) };
+/// ```
+#[macro_export]
+macro_rules! max_align_of {
+    ($($type:ty),* $(,)?) => {
+        $crate::max(&[$(::core::mem::align_of::<$type>()),*])
+    };
+}
+
+/// Allocates memory on the stack and then places value based on given type and value.
+///
+/// With a type, the capacity is the size of the type. With only a value, the type is inferred
+/// from the value, and the capacity from the context, such as a type annotation or a parameter type,
+/// as a capacity cannot be computed from an inferred type in a const generic. Either form may be
+/// preceded by a capacity and `;` to override it. In every form, whether the value fits is checked
+/// at compile time, as in [`StackAny::new`].
+///
+/// # Examples
+///
+/// ```
+/// let five = stack_any::stack_any!(i32, 5);
+/// let unit = stack_any::stack_any!((), ());
+/// ```
+///
+/// ```
+/// use stack_any::{stack_any, StackAny};
+///
+/// let numbers: StackAny<24> = stack_any!(vec![1i32, 2, 3]);
+/// assert_eq!(numbers.downcast_ref::<Vec<i32>>(), Some(&vec![1, 2, 3]));
+///
+/// let pair: StackAny<8> = stack_any!((1u8, 'x'));
+/// assert_eq!(pair.downcast_ref::<(u8, char)>(), Some(&(1, 'x')));
+///
+/// let offset = 1;
+/// let increment: StackAny<8> = stack_any!(move |x: i32| x + offset);
+/// assert!(increment.downcast_ref::<Vec<i32>>().is_none());
+///
+/// fn queue(stacks: &mut Vec<StackAny<24>>, stack: StackAny<24>) {
+///     stacks.push(stack);
+/// }
+///
+/// let mut stacks = Vec::new();
+/// queue(&mut stacks, stack_any!([1u32, 2]));
+/// queue(&mut stacks, stack_any!(String::from("x")));
+/// assert_eq!(stacks[0].downcast_ref::<[u32; 2]>(), Some(&[1, 2]));
+///
+/// let add: StackAny<0> = stack_any!(|a: i32, b: i32| a + b);
+/// assert!(add.downcast_ref::<u8>().is_none());
+/// ```
+///
+/// ```
+/// use std::collections::HashMap;
+///
+/// let map = stack_any::stack_any!(HashMap<u8, Vec<u8>>, HashMap::new());
+/// assert!(map.downcast_ref::<HashMap<u8, Vec<u8>>>().unwrap().is_empty());
+///
+/// let add = stack_any::stack_any!(fn(i32, i32) -> i32, |a, b| a + b);
+/// assert_eq!(add.downcast_ref::<fn(i32, i32) -> i32>().unwrap()(1, 2), 3);
+///
+/// let name = stack_any::stack_any!(<String as ToOwned>::Owned, String::from("x"));
+/// assert_eq!(name.downcast_ref::<String>().unwrap(), "x");
+/// ```
+///
+/// ```
+/// use stack_any::stack_any;
+///
+/// const N: usize = stack_any::max_size_of!(u8, String, [u16; 4]);
+///
+/// let values = [
+///     stack_any!(N; u8, 0),
+///     stack_any!(N; String, String::from("x")),
+///     stack_any!(N; [u16; 4], [1, 2, 3, 4]),
+///     stack_any!(N; 1.5f64),
+///     stack_any!(N; |a: i32, b: i32| a + b),
+/// ];
+/// assert_eq!(values[0].capacity(), 24);
+/// assert_eq!(values[1].downcast_ref::<String>().unwrap(), "x");
+/// assert_eq!(values[3].downcast_ref::<f64>(), Some(&1.5));
+///
+/// let small = stack_any!(2 * 4; u8, 0);
+/// assert_eq!(small.capacity(), 8);
+///
+/// let shifted = stack_any!(1 << 5; u8, 0);
+/// assert_eq!(shifted.capacity(), 32);
+///
+/// let shifted = stack_any!(64 >> 1; Vec<Vec<u8>>, vec![]);
+/// assert_eq!(shifted.capacity(), 32);
+///
+/// let chosen = stack_any!(if 1 < 2 { 16 } else { 8 }; (u8, u16), (1, 2));
+/// assert_eq!(chosen.capacity(), 16);
+///
+/// let bytes = stack_any!(::core::mem::size_of::<u64>() << 1; 5u64);
+/// assert_eq!(bytes.capacity(), 16);
+/// ```
+///
+/// ```compile_fail
+/// let numbers: stack_any::StackAny<8> = stack_any::stack_any!(vec![1i32, 2, 3]);
+/// ```
+///
+/// ```compile_fail
+/// let name = stack_any::stack_any!(8; String, String::from("x"));
+/// ```
+///
+/// ```compile_fail
+/// let numbers = stack_any::stack_any!(8; vec![1i32, 2, 3]);
+/// ```
+///
+/// A generic type is checked once it is resolved, which is still at compile time.
+///
+/// ```compile_fail
+/// fn erase<T: core::any::Any>(value: T) -> stack_any::StackAny<8> {
+///     stack_any::stack_any!(8; T, value)
+/// }
+///
+/// let bytes = erase([0u8; 16]);
+/// ```
+#[macro_export]
+macro_rules! stack_any {
+    // Neither a type nor an expression can be parsed without failing on the other, so the input
+    // is split at the first comma outside angle brackets, counting their depth by `@`.
+    // The first bracket holds the capacity if it is given. A `;` never appears in a type or an expression
+    // outside a group, so the capacity ends there regardless of the depth counted for its shifts.
+    (@split [$($cap:tt)*] [$($type:tt)+] [] , $($init:tt)+) => {{
+        let value: $($type)+ = $($init)+;
+        $crate::StackAny::<{ $crate::stack_any!(@capacity [$($cap)*] $($type)+) }>::new::<$($type)+>(value)
+    }};
+    (@split [] [$($cap:tt)+] [$($depth:tt)*] ; $($input:tt)+) => {
+        $crate::stack_any!(@with [$($cap)+] $($input)+)
+    };
+    (@split [$($cap:tt)*] [$($head:tt)*] [$($depth:tt)*] < $($rest:tt)*) => {
+        $crate::stack_any!(@split [$($cap)*] [$($head)* <] [$($depth)* @] $($rest)*)
+    };
+    (@split [$($cap:tt)*] [$($head:tt)*] [$($depth:tt)*] << $($rest:tt)*) => {
+        $crate::stack_any!(@split [$($cap)*] [$($head)* <<] [$($depth)* @ @] $($rest)*)
+    };
+    (@split [$($cap:tt)*] [$($head:tt)*] [@ $($depth:tt)*] > $($rest:tt)*) => {
+        $crate::stack_any!(@split [$($cap)*] [$($head)* >] [$($depth)*] $($rest)*)
+    };
+    (@split [$($cap:tt)*] [$($head:tt)*] [@ @ $($depth:tt)*] >> $($rest:tt)*) => {
+        $crate::stack_any!(@split [$($cap)*] [$($head)* >>] [$($depth)*] $($rest)*)
+    };
+    (@split [$($cap:tt)*] [$($head:tt)*] [$($depth:tt)*] $next:tt $($rest:tt)*) => {
+        $crate::stack_any!(@split [$($cap)*] [$($head)* $next] [$($depth)*] $($rest)*)
+    };
+    (@split [] [$($init:tt)+] [$($depth:tt)*]) => {{
+        let value = $($init)+;
+        $crate::StackAny::new(value)
+    }};
+    (@split [$($cap:tt)+] [$($init:tt)+] [$($depth:tt)*]) => {{
+        let value = $($init)+;
+        $crate::StackAny::<{ $($cap)+ }>::new(value)
+    }};
+    (@capacity [] $($type:tt)+) => {
+        ::core::mem::size_of::<$($type)+>()
+    };
+    (@capacity [$($cap:tt)+] $($type:tt)+) => {
+        $($cap)+
+    };
+    // The parameters of a closure may contain commas.
+    (@with [$($cap:tt)+] | $($init:tt)*) => {
+        $crate::StackAny::<{ $($cap)+ }>::new(| $($init)*)
+    };
+    (@with [$($cap:tt)+] || $($init:tt)*) => {
+        $crate::StackAny::<{ $($cap)+ }>::new(|| $($init)*)
+    };
+    (@with [$($cap:tt)+] move $($init:tt)*) => {
+        $crate::StackAny::<{ $($cap)+ }>::new(move $($init)*)
+    };
+    (@with [$($cap:tt)+] $($input:tt)+) => {
+        $crate::stack_any!(@split [$($cap)+] [] [] $($input)+)
+    };
+    (| $($init:tt)*) => {
+        $crate::StackAny::new(| $($init)*)
+    };
+    (|| $($init:tt)*) => {
+        $crate::StackAny::new(|| $($init)*)
+    };
+    (move $($init:tt)*) => {
+        $crate::StackAny::new(move $($init)*)
+    };
+    ($($input:tt)+) => {
+        $crate::stack_any!(@split [] [] [] $($input)+)
+    };
+}
+
+/// Allocates memory on the stack for each given value, and then places them into an array.
+///
+/// The capacity is shared by every element and is the largest size of given types, so every value
+/// fits by construction. Whether the alignment of every type is supported is checked at compile time.
+///
+/// # Examples
+///
+/// ```
+/// let mut stacks = stack_any::stack_any_array![
+///     u8 => 5,
+///     Vec<String> => vec![],
+///     (u16, char) => (1, 'x'),
+/// ];
+///
+/// stacks[1].downcast_mut::<Vec<String>>().unwrap().push(String::from("x"));
+///
+/// assert_eq!(stacks.len(), 3);
+/// assert_eq!(stacks[0].capacity(), 24);
+/// assert_eq!(stacks[0].downcast_ref::<u8>(), Some(&5));
+/// assert_eq!(stacks[1].downcast_ref::<Vec<String>>().unwrap(), &["x"]);
+/// assert_eq!(stacks[2].downcast_ref::<(u16, char)>(), Some(&(1, 'x')));
+/// ```
+///
+/// ```
+/// let stacks = stack_any::stack_any_array![u32 => 1, u32 => 2, u32 => 3];
+/// assert_eq!(stacks[2].capacity(), 4);
+/// assert_eq!(stacks.map(|stack| *stack.downcast_ref::<u32>().unwrap()), [1, 2, 3]);
+///
+/// let stacks = stack_any::stack_any_array![[u8; 3] => [1, 2, 3],];
+/// assert_eq!(stacks[0].downcast_ref::<[u8; 3]>(), Some(&[1, 2, 3]));
+///
+/// let stacks: [stack_any::StackAny<0>; 0] = stack_any::stack_any_array![];
+/// assert!(stacks.is_empty());
+/// ```
+///
+/// ```
+/// const N: usize = 100;
+///
+/// let stacks = stack_any::stack_any_array![u64 => N as u64, [u8; N] => [0; N], u8 => 1];
+/// assert_eq!(stacks[0].downcast_ref::<u64>(), Some(&100));
+/// assert_eq!(stacks[1].capacity(), 100);
+/// ```
+///
+/// ```compile_fail
+/// #[repr(align(16))]
+/// struct Aligned(u8);
+///
+/// let stacks = stack_any::stack_any_array![u8 => 5, Aligned => Aligned(7)];
+/// ```
+#[macro_export]
+macro_rules! stack_any_array {
+    ($($type:ty => $init:expr),* $(,)?) => {{
+        // No item is declared, as the name of an item would shadow the same name in given expressions.
+        const {
+            ::core::assert!(
+                $crate::max_align_of!($($type),*)
+                    <= $crate::StackAny::<{ $crate::max_size_of!($($type),*) }>::ALIGN,
+                "value does not fit in the stack"
+            )
+        };
+
+        let array: [
+            $crate::StackAny<{ $crate::max_size_of!($($type),*) }>;
+            <[&str]>::len(&[$(::core::stringify!($type)),*])
+        ] = [$($crate::StackAny::new::<$type>($init)),*];
+        array
+    }};
+}
+
+/// Dispatches on the contained value type of a stack with match-like arms.
+///
+/// Each arm binds the value as `ref` for a reference, `ref mut` for a mutable reference,
+/// or by value for the value itself, which consumes the stack. The last arm is `_`,
+/// or an identifier binding the stack when no other arm matches.
+/// The contained type is compared once per arm, and the stack is moved only by the arm that matches,
+/// so no value is lost to an arm that does not.
+///
+/// Pass a reference to the stack for `ref` arms, a mutable reference for `ref mut` arms,
+/// and the stack itself for arms by value.
+///
+/// # Examples
+///
+/// ```
+/// use stack_any::{downcast_match, StackAny};
+///
+/// struct MouseEvent { x: i32, y: i32 }
+/// struct KeyEvent(char);
+///
+/// fn describe(event: &StackAny<8>) -> String {
+///     downcast_match!(event, {
+///         ref m: MouseEvent => format!("mouse at ({}, {})", m.x, m.y),
+///         ref k: KeyEvent => format!("key {}", k.0),
+///         _ => String::from("unknown"),
+///     })
+/// }
+///
+/// assert_eq!(describe(&StackAny::new(MouseEvent { x: 1, y: 2 })), "mouse at (1, 2)");
+/// assert_eq!(describe(&StackAny::new(KeyEvent('x'))), "key x");
+/// assert_eq!(describe(&StackAny::new(5u8)), "unknown");
+/// ```
+///
+/// ```
+/// let mut counter = stack_any::stack_any!(u32, 5);
+///
+/// stack_any::downcast_match!(&mut counter, {
+///     ref mut n: u8 => *n += 1,
+///     ref mut n: u32 => *n += 10,
+///     _ => unreachable!(),
+/// });
+/// assert_eq!(counter.downcast_ref::<u32>(), Some(&15));
+/// ```
+///
+/// ```
+/// use std::rc::Rc;
+///
+/// let counter = Rc::new(());
+/// let stack = stack_any::stack_any!(Rc<()>, counter.clone());
+///
+/// let taken = stack_any::downcast_match!(stack, {
+///     n: u8 => Err(n),
+///     name: String => Err(name.len() as u8),
+///     rc: Rc<()> => Ok(rc),
+///     _ => unreachable!(),
+/// });
+/// assert_eq!(Rc::strong_count(&counter), 2);
+///
+/// drop(taken);
+/// assert_eq!(Rc::strong_count(&counter), 1);
+///
+/// let stack = stack_any::stack_any!(String, String::from("x"));
+///
+/// let stack = stack_any::downcast_match!(stack, {
+///     n: u8 => panic!("{n}"),
+///     other => other,
+/// });
+/// assert_eq!(stack.downcast_ref::<String>().unwrap(), "x");
+/// ```
+///
+/// ```compile_fail
+/// let five = stack_any::stack_any!(i32, 5);
+///
+/// stack_any::downcast_match!(&five, {
+///     ref mut n: i32 => *n += 1,
+///     _ => {}
+/// });
+/// ```
+///
+/// ```compile_fail
+/// let five = stack_any::stack_any!(i32, 5);
+///
+/// stack_any::downcast_match!(five, {
+///     n: i32 => n,
+/// });
+/// ```
+#[macro_export]
+macro_rules! downcast_match {
+    (@arms $stack:ident $type_id:ident ref mut $bind:ident : $type:ty => $body:expr $(, $($rest:tt)*)?) => {
+        if $type_id == ::core::any::TypeId::of::<$type>() {
+            // SAFETY: the contained value type was just compared to `$type`.
+            let $bind = unsafe { $stack.downcast_mut_unchecked::<$type>() };
+            $body
+        } else {
+            $crate::downcast_match!(@arms $stack $type_id $($($rest)*)?)
+        }
+    };
+    (@arms $stack:ident $type_id:ident ref $bind:ident : $type:ty => $body:expr $(, $($rest:tt)*)?) => {
+        if $type_id == ::core::any::TypeId::of::<$type>() {
+            // SAFETY: the contained value type was just compared to `$type`.
+            let $bind = unsafe { $stack.downcast_ref_unchecked::<$type>() };
+            $body
+        } else {
+            $crate::downcast_match!(@arms $stack $type_id $($($rest)*)?)
+        }
+    };
+    (@arms $stack:ident $type_id:ident $bind:ident : $type:ty => $body:expr $(, $($rest:tt)*)?) => {
+        if $type_id == ::core::any::TypeId::of::<$type>() {
+            // SAFETY: the contained value type was just compared to `$type`.
+            let $bind = unsafe { $stack.downcast_unchecked::<$type>() };
+            $body
+        } else {
+            $crate::downcast_match!(@arms $stack $type_id $($($rest)*)?)
+        }
+    };
+    (@arms $stack:ident $type_id:ident _ => $body:expr $(,)?) => {
+        $body
+    };
+    (@arms $stack:ident $type_id:ident $other:ident => $body:expr $(,)?) => {{
+        let $other = $stack;
+        $body
+    }};
+    (@arms $stack:ident $type_id:ident) => {
+        ::core::compile_error!("the last arm must be `_` or an identifier binding the stack")
+    };
+    ($value:expr, { $($arms:tt)+ }) => {
+        match $value {
+            #[allow(unused_mut)]
+            mut stack => {
+                let type_id = stack.contained_type_id();
+                $crate::downcast_match!(@arms stack type_id $($arms)+)
+            }
+        }
     };
 }
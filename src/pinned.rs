@@ -0,0 +1,246 @@
+use crate::{value_ptr, CapacityError, StackAny};
+
+/// A convertible type that owns a stack allocation of `N` size, whose contained value can be pinned.
+///
+/// The contained value is structurally pinned: once `PinnedStackAny` is pinned, e.g. by [`core::pin::pin!`],
+/// the value is never moved again and is dropped in place.
+/// `PinnedStackAny` is never `Unpin`, so the methods taking `&mut self` or `self`, which may move the value,
+/// are only reachable before it is pinned. After that, only [`PinnedStackAny::downcast_pin_mut`] gives
+/// mutable access, as `Pin<&mut T>`.
+///
+/// # Examples
+///
+/// A self-referential future, which must not move once it is polled, is stored and polled in place.
+///
+/// ```
+/// use std::future::Future;
+/// use std::marker::PhantomPinned;
+/// use std::pin::{pin, Pin};
+/// use std::task::{Context, Poll, Waker};
+///
+/// struct SelfRef {
+///     value: u32,
+///     ptr: *const u32,
+///     _pinned: PhantomPinned,
+/// }
+///
+/// impl Future for SelfRef {
+///     type Output = u32;
+///
+///     fn poll(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<u32> {
+///         let this = unsafe { self.get_unchecked_mut() };
+///         if this.ptr.is_null() {
+///             this.ptr = &this.value;
+///             return Poll::Pending;
+///         }
+///         Poll::Ready(unsafe { *this.ptr } + 1)
+///     }
+/// }
+///
+/// let future = SelfRef { value: 5, ptr: std::ptr::null(), _pinned: PhantomPinned };
+/// let mut stack = pin!(stack_any::PinnedStackAny::<16>::try_new(future).unwrap());
+///
+/// let mut cx = Context::from_waker(Waker::noop());
+/// let mut poll = || stack.as_mut().downcast_pin_mut::<SelfRef>().unwrap().poll(&mut cx);
+/// assert_eq!(poll(), Poll::Pending);
+/// assert_eq!(poll(), Poll::Ready(6));
+/// ```
+///
+/// A pinned `PinnedStackAny` can not be moved out of its pin.
+///
+/// ```compile_fail
+/// let mut stack = std::pin::pin!(stack_any::PinnedStackAny::<4>::try_new(5i32).unwrap());
+/// let stack: &mut stack_any::PinnedStackAny<4> = std::pin::Pin::get_mut(stack.as_mut());
+/// ```
+pub struct PinnedStackAny<const N: usize> {
+    stack: StackAny<N>,
+    _pinned: core::marker::PhantomPinned,
+}
+
+impl<const N: usize> PinnedStackAny<N> {
+    /// Allocates N-size memory on the stack and then places `value` into it.
+    /// Returns None if `T` size is larger than N or `T` alignment is larger than [`StackAny::ALIGN`].
+    ///
+    /// The value is not pinned until `PinnedStackAny` itself is.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let five = stack_any::PinnedStackAny::<{ std::mem::size_of::<i32>() }>::try_new(5);
+    /// ```
+    pub fn try_new<T>(value: T) -> Option<Self>
+    where
+        T: core::any::Any,
+    {
+        StackAny::try_new(value).map(Self::from)
+    }
+
+    /// Allocates N-size memory on the stack and then places `value` into it.
+    /// Returns [`CapacityError`] carrying `value` back if `T` size is larger than N
+    /// or `T` alignment is larger than [`StackAny::ALIGN`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let err = stack_any::PinnedStackAny::<4>::try_new_checked(5i64).unwrap_err();
+    /// assert_eq!(err.into_value(), 5);
+    /// ```
+    pub fn try_new_checked<T>(value: T) -> Result<Self, CapacityError<T>>
+    where
+        T: core::any::Any,
+    {
+        StackAny::try_new_checked(value).map(Self::from)
+    }
+
+    /// Returns true if the contained value type is equal to `T`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let five = stack_any::PinnedStackAny::<4>::try_new(5i32).unwrap();
+    /// assert!(five.is::<i32>());
+    /// assert!(!five.is::<i64>());
+    /// ```
+    pub fn is<T>(&self) -> bool
+    where
+        T: core::any::Any,
+    {
+        self.stack.is::<T>()
+    }
+
+    /// Attempt to return reference to the inner value as a concrete type.
+    /// Returns None if `T` is not equal to contained value type.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let five = std::pin::pin!(stack_any::PinnedStackAny::<4>::try_new(5i32).unwrap());
+    /// assert_eq!(five.downcast_ref::<i32>(), Some(&5));
+    /// assert_eq!(five.downcast_ref::<i64>(), None);
+    /// ```
+    pub fn downcast_ref<T>(&self) -> Option<&T>
+    where
+        T: core::any::Any,
+    {
+        self.stack.downcast_ref()
+    }
+
+    /// Attempt to return pinned reference to the inner value as a concrete type.
+    /// Returns None if `T` is not equal to contained value type.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let five = std::pin::pin!(stack_any::PinnedStackAny::<4>::try_new(5i32).unwrap());
+    /// assert_eq!(five.as_ref().downcast_pin_ref::<i32>().as_deref(), Some(&5));
+    /// ```
+    pub fn downcast_pin_ref<T>(self: core::pin::Pin<&Self>) -> Option<core::pin::Pin<&T>>
+    where
+        T: core::any::Any,
+    {
+        let value = self.get_ref().stack.downcast_ref()?;
+        // SAFETY: the contained value is structurally pinned.
+        Some(unsafe { core::pin::Pin::new_unchecked(value) })
+    }
+
+    /// Attempt to return pinned mutable reference to the inner value as a concrete type.
+    /// Returns None if `T` is not equal to contained value type.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut five = std::pin::pin!(stack_any::PinnedStackAny::<4>::try_new(5i32).unwrap());
+    ///
+    /// *five.as_mut().downcast_pin_mut::<i32>().unwrap() = 7;
+    /// assert_eq!(five.downcast_ref::<i32>(), Some(&7));
+    /// assert!(five.as_mut().downcast_pin_mut::<i64>().is_none());
+    /// ```
+    pub fn downcast_pin_mut<T>(self: core::pin::Pin<&mut Self>) -> Option<core::pin::Pin<&mut T>>
+    where
+        T: core::any::Any,
+    {
+        // SAFETY: `self` is not moved out of the pin, and the contained value is structurally pinned.
+        let this = unsafe { self.get_unchecked_mut() };
+        if !this.stack.is::<T>() {
+            return None;
+        }
+
+        // The buffer is not borrowed as a whole, which would invalidate references into a self-referential value.
+        let ptr = value_ptr::<T>(core::ptr::addr_of_mut!(this.stack.bytes).cast());
+        Some(unsafe { core::pin::Pin::new_unchecked(&mut *ptr) })
+    }
+
+    /// Attempt to return mutable reference to the inner value as a concrete type.
+    /// Returns None if `T` is not equal to contained value type.
+    ///
+    /// Only reachable before `PinnedStackAny` is pinned.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut five = stack_any::PinnedStackAny::<4>::try_new(5i32).unwrap();
+    /// assert_eq!(five.downcast_mut::<i32>(), Some(&mut 5));
+    /// ```
+    pub fn downcast_mut<T>(&mut self) -> Option<&mut T>
+    where
+        T: core::any::Any,
+    {
+        self.stack.downcast_mut()
+    }
+
+    /// Attempt to downcast to a concrete type.
+    /// Returns the value back if `T` is not equal to contained value type.
+    ///
+    /// Only reachable before `PinnedStackAny` is pinned.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let five = stack_any::PinnedStackAny::<4>::try_new(5i32).unwrap();
+    /// assert_eq!(five.downcast::<i32>().ok(), Some(5));
+    /// ```
+    pub fn downcast<T>(self) -> Result<T, Self>
+    where
+        T: core::any::Any,
+    {
+        self.stack.downcast().map_err(Self::from)
+    }
+
+    /// Converts into the plain [`StackAny`].
+    ///
+    /// Only reachable before `PinnedStackAny` is pinned.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let five = stack_any::PinnedStackAny::<4>::try_new(5i32).unwrap().into_stack_any();
+    /// assert_eq!(five.downcast_ref::<i32>(), Some(&5));
+    /// ```
+    pub fn into_stack_any(self) -> StackAny<N> {
+        self.stack
+    }
+}
+
+/// Takes over the value of the stack, so that it can be pinned.
+///
+/// # Examples
+///
+/// ```
+/// let five = stack_any::PinnedStackAny::from(stack_any::stack_any!(i32, 5));
+/// let five = std::pin::pin!(five);
+/// assert_eq!(five.downcast_ref::<i32>(), Some(&5));
+/// ```
+impl<const N: usize> From<StackAny<N>> for PinnedStackAny<N> {
+    fn from(value: StackAny<N>) -> Self {
+        Self {
+            stack: value,
+            _pinned: core::marker::PhantomPinned,
+        }
+    }
+}
+
+impl<const N: usize> core::fmt::Debug for PinnedStackAny<N> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_tuple("PinnedStackAny").field(&self.stack).finish()
+    }
+}
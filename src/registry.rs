@@ -0,0 +1,542 @@
+use crate::vtable::{Capture, Plain, WithSerialize};
+use crate::{value_ptr, StackAny, StackAnyVec, VTable};
+
+/// A function that deserializes a value and places it at the pointer.
+type DeserializeFn = for<'de> unsafe fn(
+    &mut dyn erased_serde::Deserializer<'de>,
+    *mut core::mem::MaybeUninit<u8>,
+) -> Result<(), erased_serde::Error>;
+
+/// The vtable and the deserialization of a registered type.
+#[derive(Clone, Copy)]
+struct Entry {
+    type_id: core::any::TypeId,
+    vtable: &'static VTable,
+    deserialize_fn: DeserializeFn,
+}
+
+/// A registry of types that can be deserialized into a [`StackAny`], keyed by their names.
+///
+/// A name must be the one written by `Serialize` of [`StackAny`], which is `core::any::type_name` of the type.
+///
+/// # Examples
+///
+/// ```
+/// use serde::de::DeserializeSeed;
+///
+/// let mut registry = stack_any::Registry::new();
+/// registry.register::<i32>("i32");
+/// registry.register::<String>("alloc::string::String");
+///
+/// let stack = stack_any::StackAny::<24>::builder(String::from("x")).with_serialize().build();
+/// let json = serde_json::to_string(&stack).unwrap();
+///
+/// let mut deserializer = serde_json::Deserializer::from_str(&json);
+/// let stack = registry.seed::<24>().deserialize(&mut deserializer).unwrap();
+/// assert_eq!(stack.downcast_ref::<String>().map(String::as_str), Some("x"));
+/// ```
+#[derive(Default)]
+pub struct Registry {
+    entries: alloc::collections::BTreeMap<&'static str, Entry>,
+}
+
+impl Registry {
+    /// Creates an empty registry.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let registry = stack_any::Registry::new();
+    /// assert!(!registry.contains("i32"));
+    /// ```
+    pub const fn new() -> Self {
+        Self {
+            entries: alloc::collections::BTreeMap::new(),
+        }
+    }
+
+    /// Returns the process-global registry.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// stack_any::Registry::global().write().unwrap().register::<u8>("u8");
+    ///
+    /// let mut deserializer = serde_json::Deserializer::from_str(r#"{"type":"u8","value":5}"#);
+    /// let registry = stack_any::Registry::global().read().unwrap();
+    /// let stack = stack_any::StackAny::<1>::deserialize_with(&registry, &mut deserializer).unwrap();
+    /// assert_eq!(stack.downcast_ref::<u8>(), Some(&5));
+    /// ```
+    #[cfg(feature = "std")]
+    pub fn global() -> &'static std::sync::RwLock<Registry> {
+        static GLOBAL: std::sync::RwLock<Registry> = std::sync::RwLock::new(Registry::new());
+        &GLOBAL
+    }
+
+    /// Registers `T` under `name`, replacing the type registered under the same name.
+    ///
+    /// A deserialized value captures `Serialize` of `T`, so that it can be serialized again.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut registry = stack_any::Registry::new();
+    /// registry.register::<i32>("i32");
+    /// assert!(registry.contains("i32"));
+    /// ```
+    pub fn register<T>(&mut self, name: &'static str)
+    where
+        T: core::any::Any + serde::Serialize + serde::de::DeserializeOwned,
+    {
+        let entry = Entry {
+            type_id: core::any::TypeId::of::<T>(),
+            vtable: <WithSerialize<Plain> as Capture<T>>::REF,
+            deserialize_fn: |deserializer, ptr| {
+                let value = erased_serde::deserialize::<T>(deserializer)?;
+                unsafe { core::ptr::write(value_ptr::<T>(ptr), value) };
+                Ok(())
+            },
+        };
+        self.entries.insert(name, entry);
+    }
+
+    /// Returns true if a type is registered under `name`.
+    pub fn contains(&self, name: &str) -> bool {
+        self.entries.contains_key(name)
+    }
+
+    /// Returns a `DeserializeSeed` that deserializes a [`StackAny`] of N-size by this registry.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use serde::de::DeserializeSeed;
+    ///
+    /// let mut registry = stack_any::Registry::new();
+    /// registry.register::<i32>("i32");
+    ///
+    /// let mut deserializer = serde_json::Deserializer::from_str(r#"{"type":"i32","value":5}"#);
+    /// let five = registry.seed::<4>().deserialize(&mut deserializer).unwrap();
+    /// assert_eq!(five.downcast_ref::<i32>(), Some(&5));
+    /// ```
+    pub fn seed<const N: usize>(&self) -> StackAnySeed<'_, N> {
+        StackAnySeed { registry: self }
+    }
+}
+
+impl<const N: usize> StackAny<N> {
+    /// Deserializes a stack in the format written by `Serialize` of [`StackAny`],
+    /// constructing the value of the type registered in `registry` under the written name.
+    ///
+    /// Fails if no type is registered under the name, or if the type does not fit.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// #[derive(Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+    /// struct Point {
+    ///     x: i32,
+    ///     y: i32,
+    /// }
+    ///
+    /// let mut registry = stack_any::Registry::new();
+    /// registry.register::<u64>("u64");
+    /// registry.register::<Vec<String>>("alloc::vec::Vec<alloc::string::String>");
+    /// registry.register::<Point>(std::any::type_name::<Point>());
+    /// registry.register::<String>("alloc::string::String");
+    ///
+    /// let stacks = vec![
+    ///     stack_any::StackAny::<24>::builder(5u64).with_serialize().build(),
+    ///     stack_any::StackAny::<24>::builder(vec![String::from("x")]).with_serialize().build(),
+    ///     stack_any::StackAny::<24>::builder(Point { x: 1, y: 2 }).with_serialize().build(),
+    ///     stack_any::StackAny::<24>::builder(String::from("y")).with_serialize().build(),
+    ///     stack_any::StackAny::<24>::empty(),
+    /// ];
+    /// let json = serde_json::to_string(&stacks).unwrap();
+    ///
+    /// let mut deserializer = serde_json::Deserializer::from_str(&json);
+    /// let values: Vec<serde_json::Value> = serde::Deserialize::deserialize(&mut deserializer).unwrap();
+    /// let stacks = values
+    ///     .into_iter()
+    ///     .map(|value| stack_any::StackAny::<24>::deserialize_with(&registry, value).unwrap())
+    ///     .collect::<Vec<_>>();
+    ///
+    /// assert_eq!(stacks[0].downcast_ref::<u64>(), Some(&5));
+    /// assert_eq!(stacks[1].downcast_ref::<Vec<String>>(), Some(&vec![String::from("x")]));
+    /// assert_eq!(stacks[2].downcast_ref::<Point>(), Some(&Point { x: 1, y: 2 }));
+    /// assert_eq!(stacks[3].downcast_ref::<String>().map(String::as_str), Some("y"));
+    /// assert!(stacks[4].is_empty());
+    ///
+    /// assert_eq!(serde_json::to_string(&stacks).unwrap(), json);
+    /// ```
+    ///
+    /// ```
+    /// let mut registry = stack_any::Registry::new();
+    /// registry.register::<u64>("u64");
+    ///
+    /// let mut deserializer = serde_json::Deserializer::from_str(r#"{"type":"i8","value":5}"#);
+    /// let err = stack_any::StackAny::<8>::deserialize_with(&registry, &mut deserializer).unwrap_err();
+    /// assert!(err.to_string().starts_with("unknown type `i8`"));
+    ///
+    /// let mut deserializer = serde_json::Deserializer::from_str(r#"{"type":"u64","value":5}"#);
+    /// let err = stack_any::StackAny::<4>::deserialize_with(&registry, &mut deserializer).unwrap_err();
+    /// assert!(err.to_string().starts_with("type `u64` does not fit in the stack"));
+    /// ```
+    pub fn deserialize_with<'de, D>(registry: &Registry, deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        serde::de::DeserializeSeed::deserialize(registry.seed(), deserializer)
+    }
+}
+
+impl<const CAP: usize, const N: usize> StackAnyVec<CAP, N> {
+    /// Deserializes a vector in the format written by `Serialize` of [`StackAnyVec`],
+    /// constructing each element as [`StackAny::deserialize_with`] does.
+    ///
+    /// Fails if an element fails, reporting its index, or if there are more than `CAP` elements.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bincode::Options;
+    /// use stack_any::{Registry, StackAny, StackAnyVec};
+    ///
+    /// let mut registry = Registry::new();
+    /// registry.register::<u64>("u64");
+    /// registry.register::<String>("alloc::string::String");
+    ///
+    /// let mut values = StackAnyVec::<4, 24>::new();
+    /// values.push(StackAny::builder(5u64).with_serialize().build()).unwrap();
+    /// values.push(StackAny::builder(String::from("x")).with_serialize().build()).unwrap();
+    /// values.push(StackAny::empty()).unwrap();
+    ///
+    /// let json = serde_json::to_string(&values).unwrap();
+    /// let mut deserializer = serde_json::Deserializer::from_str(&json);
+    /// let decoded = StackAnyVec::<4, 24>::deserialize_with(&registry, &mut deserializer).unwrap();
+    /// assert_eq!(serde_json::to_string(&decoded).unwrap(), json);
+    ///
+    /// let bytes = bincode::serialize(&values).unwrap();
+    /// let mut deserializer = bincode::Deserializer::from_slice(&bytes, bincode::options().with_fixint_encoding());
+    /// let decoded = StackAnyVec::<4, 24>::deserialize_with(&registry, &mut deserializer).unwrap();
+    /// assert_eq!(decoded.len(), 3);
+    /// assert_eq!(decoded.get(0).unwrap().downcast_ref::<u64>(), Some(&5));
+    /// assert_eq!(decoded.get(1).unwrap().downcast_ref::<String>().unwrap(), "x");
+    /// assert!(decoded.get(2).unwrap().is_empty());
+    /// ```
+    ///
+    /// ```
+    /// use stack_any::{Registry, StackAnyVec};
+    ///
+    /// let mut registry = Registry::new();
+    /// registry.register::<u8>("u8");
+    /// registry.register::<u64>("u64");
+    ///
+    /// let mut deserializer = serde_json::Deserializer::from_str("[]");
+    /// let empty = StackAnyVec::<4, 1>::deserialize_with(&registry, &mut deserializer).unwrap();
+    /// assert!(empty.is_empty());
+    ///
+    /// let json = r#"[{"type":"u8","value":1},{"type":"i8","value":2}]"#;
+    /// let mut deserializer = serde_json::Deserializer::from_str(json);
+    /// let err = StackAnyVec::<4, 1>::deserialize_with(&registry, &mut deserializer).err().unwrap();
+    /// assert!(err.to_string().starts_with("element 1: unknown type `i8`"));
+    ///
+    /// let json = r#"[{"type":"u64","value":1}]"#;
+    /// let mut deserializer = serde_json::Deserializer::from_str(json);
+    /// let err = StackAnyVec::<4, 1>::deserialize_with(&registry, &mut deserializer).err().unwrap();
+    /// assert!(err.to_string().starts_with("element 0: type `u64` does not fit in the stack"));
+    ///
+    /// let json = r#"[{"type":"u8","value":1},{"type":"u8","value":2}]"#;
+    /// let mut deserializer = serde_json::Deserializer::from_str(json);
+    /// assert!(StackAnyVec::<1, 1>::deserialize_with(&registry, &mut deserializer).is_err());
+    /// ```
+    pub fn deserialize_with<'de, D>(registry: &Registry, deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        deserializer.deserialize_seq(VecVisitor::<CAP, N> { registry })
+    }
+}
+
+/// A `Visitor` that deserializes a [`StackAnyVec`] by a [`Registry`].
+struct VecVisitor<'a, const CAP: usize, const N: usize> {
+    registry: &'a Registry,
+}
+
+impl<'de, const CAP: usize, const N: usize> serde::de::Visitor<'de> for VecVisitor<'_, CAP, N> {
+    type Value = StackAnyVec<CAP, N>;
+
+    fn expecting(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "a sequence of at most {} stacks", CAP)
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+    where
+        A: serde::de::SeqAccess<'de>,
+    {
+        use serde::de::Error;
+
+        let mut vec = StackAnyVec::new();
+        for index in 0.. {
+            let element = seq
+                .next_element_seed(self.registry.seed::<N>())
+                .map_err(|err| A::Error::custom(format_args!("element {}: {}", index, err)))?;
+            let Some(stack) = element else {
+                break;
+            };
+
+            if vec.push(stack).is_err() {
+                return Err(A::Error::invalid_length(index + 1, &self));
+            }
+        }
+        Ok(vec)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<const N: usize> crate::StackAnyMap<N> {
+    /// Deserializes a map in the format written by `Serialize` of [`StackAnyMap`](crate::StackAnyMap),
+    /// constructing each value of the type registered in `registry` under its key.
+    ///
+    /// Fails if no type is registered under a key, or if a value fails, reporting its key.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bincode::Options;
+    /// use stack_any::{Registry, StackAny, StackAnyMap};
+    ///
+    /// let mut registry = Registry::new();
+    /// registry.register::<u64>("u64");
+    /// registry.register::<String>("alloc::string::String");
+    ///
+    /// let mut map = StackAnyMap::<24>::new();
+    /// map.insert_stack(StackAny::builder(5u64).with_serialize().build()).unwrap();
+    /// map.insert_stack(StackAny::builder(String::from("x")).with_serialize().build()).unwrap();
+    ///
+    /// let json = serde_json::to_string(&map).unwrap();
+    /// let mut deserializer = serde_json::Deserializer::from_str(&json);
+    /// let decoded = StackAnyMap::<24>::deserialize_with(&registry, &mut deserializer).unwrap();
+    /// assert_eq!(decoded.get::<u64>(), Some(&5));
+    /// assert_eq!(decoded.get::<String>().unwrap(), "x");
+    ///
+    /// let bytes = bincode::serialize(&map).unwrap();
+    /// let mut deserializer = bincode::Deserializer::from_slice(&bytes, bincode::options().with_fixint_encoding());
+    /// let decoded = StackAnyMap::<24>::deserialize_with(&registry, &mut deserializer).unwrap();
+    /// assert_eq!(decoded.len(), 2);
+    /// assert_eq!(decoded.get::<u64>(), Some(&5));
+    /// ```
+    ///
+    /// ```
+    /// use stack_any::{Registry, StackAnyMap};
+    ///
+    /// let mut registry = Registry::new();
+    /// registry.register::<u8>("u8");
+    /// registry.register::<u64>("u64");
+    ///
+    /// let mut deserializer = serde_json::Deserializer::from_str("{}");
+    /// let empty = StackAnyMap::<1>::deserialize_with(&registry, &mut deserializer).unwrap();
+    /// assert!(empty.is_empty());
+    ///
+    /// let mut deserializer = serde_json::Deserializer::from_str(r#"{"u8":1}"#);
+    /// let single = StackAnyMap::<1>::deserialize_with(&registry, &mut deserializer).unwrap();
+    /// assert_eq!(single.get::<u8>(), Some(&1));
+    ///
+    /// let mut deserializer = serde_json::Deserializer::from_str(r#"{"i8":1}"#);
+    /// let err = StackAnyMap::<1>::deserialize_with(&registry, &mut deserializer).err().unwrap();
+    /// assert!(err.to_string().starts_with("unknown type `i8`"));
+    ///
+    /// let mut deserializer = serde_json::Deserializer::from_str(r#"{"u64":1}"#);
+    /// let err = StackAnyMap::<1>::deserialize_with(&registry, &mut deserializer).err().unwrap();
+    /// assert!(err.to_string().starts_with("type `u64` does not fit in the stack"));
+    ///
+    /// let mut deserializer = serde_json::Deserializer::from_str(r#"{"u8":"one"}"#);
+    /// let err = StackAnyMap::<1>::deserialize_with(&registry, &mut deserializer).err().unwrap();
+    /// assert!(err.to_string().starts_with("key `u8`: "));
+    /// ```
+    pub fn deserialize_with<'de, D>(registry: &Registry, deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        deserializer.deserialize_map(MapVisitor::<N> { registry })
+    }
+}
+
+/// A `Visitor` that deserializes a [`StackAnyMap`](crate::StackAnyMap) by a [`Registry`].
+#[cfg(feature = "std")]
+struct MapVisitor<'a, const N: usize> {
+    registry: &'a Registry,
+}
+
+#[cfg(feature = "std")]
+impl<'de, const N: usize> serde::de::Visitor<'de> for MapVisitor<'_, N> {
+    type Value = crate::StackAnyMap<N>;
+
+    fn expecting(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "a map from type names to values")
+    }
+
+    fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+    where
+        A: serde::de::MapAccess<'de>,
+    {
+        use serde::de::Error;
+
+        let seed = self.registry.seed::<N>();
+
+        let mut stacks = crate::StackAnyMap::new();
+        while let Some(name) = map.next_key::<alloc::string::String>()? {
+            let entry = seed.entry(&name)?;
+
+            let mut stack = StackAny::empty();
+            map.next_value_seed(ValueSeed {
+                entry,
+                stack: &mut stack,
+            })
+            .map_err(|err| A::Error::custom(format_args!("key `{}`: {}", name, err)))?;
+
+            if stacks.insert_stack(stack).is_err() {
+                unreachable!();
+            }
+        }
+        Ok(stacks)
+    }
+}
+
+/// A `DeserializeSeed` that deserializes a [`StackAny`] of N-size by a [`Registry`],
+/// returned by [`Registry::seed`].
+pub struct StackAnySeed<'a, const N: usize> {
+    registry: &'a Registry,
+}
+
+impl<'de, const N: usize> serde::de::DeserializeSeed<'de> for StackAnySeed<'_, N> {
+    type Value = StackAny<N>;
+
+    fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        deserializer.deserialize_option(self)
+    }
+}
+
+impl<'de, const N: usize> serde::de::Visitor<'de> for StackAnySeed<'_, N> {
+    type Value = StackAny<N>;
+
+    fn expecting(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "a stack of a type name and a value, or none")
+    }
+
+    fn visit_none<E>(self) -> Result<Self::Value, E>
+    where
+        E: serde::de::Error,
+    {
+        Ok(StackAny::empty())
+    }
+
+    fn visit_unit<E>(self) -> Result<Self::Value, E>
+    where
+        E: serde::de::Error,
+    {
+        Ok(StackAny::empty())
+    }
+
+    fn visit_some<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        deserializer.deserialize_struct("StackAny", &["type", "value"], self)
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+    where
+        A: serde::de::SeqAccess<'de>,
+    {
+        use serde::de::Error;
+
+        let name: alloc::string::String = seq
+            .next_element()?
+            .ok_or_else(|| A::Error::invalid_length(0, &self))?;
+        let entry = self.entry(&name)?;
+
+        let mut stack = StackAny::empty();
+        seq.next_element_seed(ValueSeed {
+            entry,
+            stack: &mut stack,
+        })?
+        .ok_or_else(|| A::Error::invalid_length(1, &self))?;
+        Ok(stack)
+    }
+
+    fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+    where
+        A: serde::de::MapAccess<'de>,
+    {
+        use serde::de::Error;
+
+        match map.next_key::<alloc::string::String>()?.as_deref() {
+            Some("type") => {}
+            _ => return Err(A::Error::missing_field("type")),
+        }
+        let name: alloc::string::String = map.next_value()?;
+        let entry = self.entry(&name)?;
+
+        match map.next_key::<alloc::string::String>()?.as_deref() {
+            Some("value") => {}
+            _ => return Err(A::Error::missing_field("value")),
+        }
+        let mut stack = StackAny::empty();
+        map.next_value_seed(ValueSeed {
+            entry,
+            stack: &mut stack,
+        })?;
+        Ok(stack)
+    }
+}
+
+impl<const N: usize> StackAnySeed<'_, N> {
+    /// Returns the entry registered under `name` if its type fits.
+    fn entry<E>(&self, name: &str) -> Result<Entry, E>
+    where
+        E: serde::de::Error,
+    {
+        let Some(entry) = self.registry.entries.get(name) else {
+            return Err(E::custom(format_args!("unknown type `{}`", name)));
+        };
+
+        let vtable = entry.vtable;
+        if vtable.size != 0 && (N < vtable.size || StackAny::<N>::ALIGN < vtable.align) {
+            return Err(E::custom(format_args!(
+                "type `{}` does not fit in the stack",
+                name
+            )));
+        }
+
+        Ok(*entry)
+    }
+}
+
+/// A `DeserializeSeed` that places the deserialized value of the registered type into the stack.
+struct ValueSeed<'a, const N: usize> {
+    entry: Entry,
+    stack: &'a mut StackAny<N>,
+}
+
+impl<'de, const N: usize> serde::de::DeserializeSeed<'de> for ValueSeed<'_, N> {
+    type Value = ();
+
+    fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        use serde::de::Error;
+
+        let mut deserializer = <dyn erased_serde::Deserializer>::erase(deserializer);
+        let ptr = self.stack.bytes.0.as_mut_ptr();
+        unsafe { (self.entry.deserialize_fn)(&mut deserializer, ptr) }.map_err(D::Error::custom)?;
+
+        self.stack.type_id = self.entry.type_id;
+        self.stack.vtable = self.entry.vtable;
+        Ok(())
+    }
+}
@@ -0,0 +1,298 @@
+use crate::{value_ptr, Buffer, CapacityError, StackAny};
+
+/// A type that can be placed into a [`ScopedStackAny<'a, N>`](ScopedStackAny) although it borrows for `'a`.
+///
+/// `core::any::Any` requires `'static`, so the `TypeId` of the type itself does not exist.
+/// Instead, `Static` stands in as the witness of the type: it is the same type with `'a` replaced by `'static`.
+///
+/// # Safety
+///
+/// Within a given `'a`, `Static` must identify `Self` uniquely,
+/// i.e. no two types implementing `ScopedType<'a>` may share the same `Static`.
+/// This holds when every lifetime of `Self` is `'a` and `Static` is `Self` with each of them replaced by `'static`.
+///
+/// # Examples
+///
+/// ```
+/// struct View<'a> {
+///     name: &'a str,
+/// }
+///
+/// unsafe impl<'a> stack_any::ScopedType<'a> for View<'a> {
+///     type Static = View<'static>;
+/// }
+///
+/// let name = String::from("x");
+/// let view = stack_any::ScopedStackAny::<16>::try_new(View { name: &name }).unwrap();
+/// assert_eq!(view.downcast_ref::<View>().map(|view| view.name), Some("x"));
+/// ```
+pub unsafe trait ScopedType<'a>: 'a {
+    /// The type with `'a` replaced by `'static`.
+    type Static: 'static;
+}
+
+unsafe impl<'a, T> ScopedType<'a> for &'a T
+where
+    T: ?Sized + 'static,
+{
+    type Static = &'static T;
+}
+
+unsafe impl<'a, T> ScopedType<'a> for &'a mut T
+where
+    T: ?Sized + 'static,
+{
+    type Static = &'static mut T;
+}
+
+/// A type that owns a stack allocation of `N` size for values borrowing for `'a`.
+///
+/// Values are identified by [`ScopedType::Static`] instead of `TypeId`, and downcasts only accept types
+/// whose borrows are exactly `'a`. `'a` is invariant, so it can neither be shortened to hide a value
+/// nor be extended to downcast it to a longer-living type.
+///
+/// # Examples
+///
+/// ```
+/// let text = String::from("hello");
+/// let lengths: Vec<usize> = text.split('l').map(str::len).collect();
+///
+/// let stacks = [
+///     stack_any::ScopedStackAny::<16>::try_new(&text[..]).unwrap(),
+///     stack_any::ScopedStackAny::<16>::try_new(&lengths[..]).unwrap(),
+/// ];
+///
+/// assert_eq!(stacks[0].downcast_ref::<&str>(), Some(&"hello"));
+/// assert_eq!(stacks[1].downcast_ref::<&[usize]>(), Some(&&[2, 0, 1][..]));
+/// assert_eq!(stacks[1].downcast_ref::<&str>(), None);
+/// ```
+///
+/// The stack can not outlive the borrowed value.
+///
+/// ```compile_fail
+/// let stack = {
+///     let text = String::from("hello");
+///     stack_any::ScopedStackAny::<16>::try_new(&text[..]).unwrap()
+/// };
+/// ```
+///
+/// The value can not be downcast to a type borrowing for longer, which would let it escape.
+///
+/// ```compile_fail
+/// fn escape(text: &str) -> &'static str {
+///     let stack = stack_any::ScopedStackAny::<16>::try_new(text).unwrap();
+///     stack.downcast::<&'static str>().ok().unwrap()
+/// }
+/// ```
+pub struct ScopedStackAny<'a, const N: usize> {
+    type_id: core::any::TypeId,
+    bytes: Buffer<N>,
+    size: usize,
+    drop_fn: Option<unsafe fn(*mut core::mem::MaybeUninit<u8>)>,
+    _marker: core::marker::PhantomData<*mut &'a ()>,
+}
+
+impl<'a, const N: usize> ScopedStackAny<'a, N> {
+    /// Allocates N-size memory on the stack and then places `value` into it.
+    /// Returns None if `T` size is larger than N or `T` alignment is larger than [`StackAny::ALIGN`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let five = 5;
+    /// let five = stack_any::ScopedStackAny::<8>::try_new(&five);
+    /// assert!(five.is_some());
+    /// ```
+    pub fn try_new<T>(value: T) -> Option<Self>
+    where
+        T: ScopedType<'a>,
+    {
+        Self::try_new_checked(value).ok()
+    }
+
+    /// Allocates N-size memory on the stack and then places `value` into it.
+    /// Returns [`CapacityError`] carrying `value` back if `T` size is larger than N
+    /// or `T` alignment is larger than [`StackAny::ALIGN`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let text = "x";
+    /// let err = stack_any::ScopedStackAny::<8>::try_new_checked(text).unwrap_err();
+    /// assert_eq!(err.into_value(), "x");
+    /// ```
+    pub fn try_new_checked<T>(value: T) -> Result<Self, CapacityError<T>>
+    where
+        T: ScopedType<'a>,
+    {
+        if !StackAny::<N>::fits::<T>() {
+            return Err(CapacityError::new(value, N, StackAny::<N>::ALIGN));
+        }
+
+        let mut bytes = Buffer([core::mem::MaybeUninit::uninit(); N]);
+
+        let ptr = value_ptr::<T>(bytes.0.as_mut_ptr());
+        unsafe { core::ptr::write(ptr, value) };
+
+        let drop_fn: Option<unsafe fn(_)> = match core::mem::needs_drop::<T>() {
+            true => Some(|ptr| unsafe { core::ptr::drop_in_place(value_ptr::<T>(ptr)) }),
+            false => None,
+        };
+
+        Ok(Self {
+            type_id: core::any::TypeId::of::<T::Static>(),
+            bytes,
+            size: core::mem::size_of::<T>(),
+            drop_fn,
+            _marker: core::marker::PhantomData,
+        })
+    }
+
+    /// Returns true if the contained value type is equal to `T`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let five = 5;
+    /// let five = stack_any::ScopedStackAny::<8>::try_new(&five).unwrap();
+    /// assert!(five.is::<&i32>());
+    /// assert!(!five.is::<&mut i32>());
+    /// ```
+    pub fn is<T>(&self) -> bool
+    where
+        T: ScopedType<'a>,
+    {
+        core::any::TypeId::of::<T::Static>() == self.type_id
+    }
+
+    /// Attempt to return reference to the inner value as a concrete type.
+    /// Returns None if `T` is not equal to contained value type.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let five = 5;
+    /// let five = stack_any::ScopedStackAny::<8>::try_new(&five).unwrap();
+    /// assert_eq!(five.downcast_ref::<&i32>(), Some(&&5));
+    /// assert_eq!(five.downcast_ref::<&i64>(), None);
+    /// ```
+    pub fn downcast_ref<T>(&self) -> Option<&T>
+    where
+        T: ScopedType<'a>,
+    {
+        if !self.is::<T>() {
+            return None;
+        }
+
+        let ptr = value_ptr::<T>(self.bytes.0.as_ptr() as *mut _);
+        Some(unsafe { &*ptr })
+    }
+
+    /// Attempt to return mutable reference to the inner value as a concrete type.
+    /// Returns None if `T` is not equal to contained value type.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut counts = vec![5];
+    ///
+    /// let mut stack = stack_any::ScopedStackAny::<8>::try_new(&mut counts).unwrap();
+    /// stack.downcast_mut::<&mut Vec<i32>>().unwrap().push(7);
+    /// drop(stack);
+    ///
+    /// assert_eq!(counts, [5, 7]);
+    /// ```
+    pub fn downcast_mut<T>(&mut self) -> Option<&mut T>
+    where
+        T: ScopedType<'a>,
+    {
+        if !self.is::<T>() {
+            return None;
+        }
+
+        let ptr = value_ptr::<T>(self.bytes.0.as_mut_ptr());
+        Some(unsafe { &mut *ptr })
+    }
+
+    /// Attempt to downcast the stack to a concrete type.
+    /// Returns the stack back if `T` is not equal to contained value type.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let text = String::from("x");
+    ///
+    /// let stack = stack_any::ScopedStackAny::<16>::try_new(&text[..]).unwrap();
+    /// let stack = stack.downcast::<&[u8]>().unwrap_err();
+    /// assert_eq!(stack.downcast::<&str>().ok(), Some("x"));
+    /// ```
+    pub fn downcast<T>(self) -> Result<T, Self>
+    where
+        T: ScopedType<'a>,
+    {
+        if !self.is::<T>() {
+            return Err(self);
+        }
+
+        let mut this = core::mem::ManuallyDrop::new(self);
+
+        let ptr = value_ptr::<T>(this.bytes.0.as_mut_ptr());
+        let value = unsafe { core::ptr::read(ptr) };
+
+        this.scrub();
+        Ok(value)
+    }
+
+    /// Overwrites the bytes of the value, which was dropped or moved out, with zeros
+    /// if the `zeroize` feature is enabled.
+    fn scrub(&mut self) {
+        #[cfg(feature = "zeroize")]
+        zeroize::Zeroize::zeroize(&mut self.bytes.0[..self.size]);
+    }
+}
+
+impl<const N: usize> core::fmt::Debug for ScopedStackAny<'_, N> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("ScopedStackAny")
+            .field("capacity", &N)
+            .field("type_id", &self.type_id)
+            .field("size", &self.size)
+            .finish()
+    }
+}
+
+/// Drops the contained value.
+///
+/// # Examples
+///
+/// ```
+/// use std::cell::Cell;
+///
+/// struct Guard<'a>(&'a Cell<u32>);
+///
+/// impl Drop for Guard<'_> {
+///     fn drop(&mut self) {
+///         self.0.set(self.0.get() + 1);
+///     }
+/// }
+///
+/// unsafe impl<'a> stack_any::ScopedType<'a> for Guard<'a> {
+///     type Static = Guard<'static>;
+/// }
+///
+/// let drops = Cell::new(0);
+///
+/// let stack = stack_any::ScopedStackAny::<8>::try_new(Guard(&drops)).unwrap();
+/// assert_eq!(drops.get(), 0);
+///
+/// drop(stack);
+/// assert_eq!(drops.get(), 1);
+/// ```
+impl<const N: usize> Drop for ScopedStackAny<'_, N> {
+    fn drop(&mut self) {
+        if let Some(drop_fn) = self.drop_fn {
+            unsafe { drop_fn(self.bytes.0.as_mut_ptr()) };
+        }
+        self.scrub();
+    }
+}
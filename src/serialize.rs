@@ -0,0 +1,160 @@
+use crate::{StackAny, StackAnyVec};
+
+/// Serializes the contained value as a struct of its type name and the value,
+/// such as `{"type": "i32", "value": 5}`.
+///
+/// The value is serialized by `Serialize` captured by [`StackAnyBuilder::with_serialize`](crate::StackAnyBuilder::with_serialize),
+/// while an empty stack is serialized as none.
+///
+/// # Errors
+///
+/// Returns an error of the serializer if the stack contains a value without the capability.
+///
+/// # Examples
+///
+/// ```
+/// let state = vec![
+///     stack_any::StackAny::<32>::builder(5i32).with_serialize().build(),
+///     stack_any::StackAny::<32>::builder(vec!['x', 'y']).with_serialize().build(),
+///     stack_any::StackAny::<32>::builder((String::from("x"), 2u32))
+///         .with_serialize()
+///         .build(),
+///     stack_any::StackAny::<32>::empty(),
+/// ];
+///
+/// assert_eq!(
+///     serde_json::to_string(&state).unwrap(),
+///     concat!(
+///         r#"[{"type":"i32","value":5},"#,
+///         r#"{"type":"alloc::vec::Vec<char>","value":["x","y"]},"#,
+///         r#"{"type":"(alloc::string::String, u32)","value":["x",2]},"#,
+///         r#"null]"#,
+///     ),
+/// );
+/// ```
+///
+/// ```
+/// let five = stack_any::stack_any!(i32, 5);
+///
+/// let err = serde_json::to_string(&five).unwrap_err();
+/// assert_eq!(err.to_string(), "contained value is not serializable");
+/// ```
+impl<const N: usize> serde::Serialize for StackAny<N> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        use serde::ser::Error;
+
+        if self.is_empty() {
+            return serializer.serialize_none();
+        }
+
+        let Some((name, value)) = self.serialize_parts() else {
+            return Err(S::Error::custom("contained value is not serializable"));
+        };
+
+        // Marked as some, so that formats which are not self-describing tell it from none.
+        serializer.serialize_some(&Tagged { name, value })
+    }
+}
+
+/// Serializes the elements as a sequence of tagged values, as `Serialize` of [`StackAny`] does.
+///
+/// # Errors
+///
+/// Returns an error of the serializer if an element contains a value without the capability.
+///
+/// # Examples
+///
+/// ```
+/// use stack_any::{StackAny, StackAnyVec};
+///
+/// let mut values = StackAnyVec::<2, 8>::new();
+/// values.push(StackAny::builder(5i32).with_serialize().build()).unwrap();
+/// values.push(StackAny::builder('x').with_serialize().build()).unwrap();
+///
+/// assert_eq!(
+///     serde_json::to_string(&values).unwrap(),
+///     r#"[{"type":"i32","value":5},{"type":"char","value":"x"}]"#,
+/// );
+///
+/// let mut plain = StackAnyVec::<2, 8>::new();
+/// plain.push_value(5u8).unwrap();
+/// assert!(serde_json::to_string(&plain).is_err());
+/// ```
+impl<const CAP: usize, const N: usize> serde::Serialize for StackAnyVec<CAP, N> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.collect_seq(self.as_slice())
+    }
+}
+
+/// Serializes the values as a map from their type names to themselves.
+///
+/// # Errors
+///
+/// Returns an error of the serializer if the map contains a value without the capability.
+///
+/// # Examples
+///
+/// ```
+/// let mut map = stack_any::StackAnyMap::<8>::new();
+/// map.insert_stack(stack_any::StackAny::builder(5i32).with_serialize().build()).unwrap();
+///
+/// assert_eq!(serde_json::to_string(&map).unwrap(), r#"{"i32":5}"#);
+///
+/// map.insert(5u8).unwrap();
+/// assert!(serde_json::to_string(&map).is_err());
+/// ```
+#[cfg(feature = "std")]
+impl<const N: usize> serde::Serialize for crate::StackAnyMap<N> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        use serde::ser::{Error, SerializeMap};
+
+        let mut state = serializer.serialize_map(Some(self.len()))?;
+        for stack in self.stacks() {
+            let Some((name, value)) = stack.serialize_parts() else {
+                return Err(S::Error::custom("contained value is not serializable"));
+            };
+            state.serialize_entry(name, value)?;
+        }
+        state.end()
+    }
+}
+
+impl<const N: usize> StackAny<N> {
+    /// Returns the type name and the contained value to be serialized,
+    /// or None if the stack contains a value without the capability.
+    pub(crate) fn serialize_parts(&self) -> Option<(&'static str, &dyn erased_serde::Serialize)> {
+        let serialize_fn = self.vtable.serialize_fn?;
+
+        let (name, value) = unsafe { serialize_fn(self.bytes.0.as_ptr()) };
+        Some((name, unsafe { &*value }))
+    }
+}
+
+/// A contained value serialized as a struct of its type name and the value.
+struct Tagged<'a> {
+    name: &'static str,
+    value: &'a dyn erased_serde::Serialize,
+}
+
+impl serde::Serialize for Tagged<'_> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        use serde::ser::SerializeStruct;
+
+        let mut state = serializer.serialize_struct("StackAny", 2)?;
+        state.serialize_field("type", self.name)?;
+        state.serialize_field("value", self.value)?;
+        state.end()
+    }
+}
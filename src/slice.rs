@@ -0,0 +1,191 @@
+use crate::StackAny;
+
+/// Provides typed iteration over slices of [`StackAny`], such as the elements of a [`StackAnyVec`](crate::StackAnyVec).
+///
+/// # Examples
+///
+/// ```
+/// use stack_any::{StackAny, StackAnySlice};
+///
+/// let mut stacks = [
+///     StackAny::<24>::new(1i32),
+///     StackAny::<24>::new('x'),
+///     StackAny::<24>::new(String::from("y")),
+///     StackAny::<24>::new(2i32),
+/// ];
+///
+/// let numbers: Vec<_> = stacks.iter_downcast::<i32>().collect();
+/// assert_eq!(numbers, [&1, &2]);
+///
+/// stacks.iter_downcast_mut::<String>().for_each(|text| text.push('z'));
+/// assert_eq!(stacks[2].downcast_ref::<String>().unwrap(), "yz");
+/// ```
+pub trait StackAnySlice<const N: usize> {
+    /// Returns an iterator over the contained values of `T`, skipping the values of other types.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use stack_any::{StackAny, StackAnySlice};
+    ///
+    /// let stacks = [
+    ///     StackAny::<4>::new(1i32),
+    ///     StackAny::<4>::new('x'),
+    ///     StackAny::<4>::new(2u16),
+    ///     StackAny::<4>::new(3i32),
+    ///     StackAny::<4>::new('y'),
+    ///     StackAny::<4>::new(4u16),
+    /// ];
+    ///
+    /// assert_eq!(stacks.iter_downcast::<i32>().collect::<Vec<_>>(), [&1, &3]);
+    /// assert_eq!(stacks.iter_downcast::<char>().rev().collect::<Vec<_>>(), [&'y', &'x']);
+    /// assert_eq!(stacks.iter_downcast::<u16>().size_hint(), (0, Some(6)));
+    /// assert_eq!(stacks.iter_downcast::<u8>().next(), None);
+    /// ```
+    fn iter_downcast<T>(&self) -> IterDowncast<'_, T, N>
+    where
+        T: core::any::Any;
+
+    /// Returns an iterator over mutable references to the contained values of `T`,
+    /// skipping the values of other types.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use stack_any::{StackAny, StackAnySlice};
+    ///
+    /// let mut stacks = [
+    ///     StackAny::<4>::new(1i32),
+    ///     StackAny::<4>::new('x'),
+    ///     StackAny::<4>::new(2i32),
+    /// ];
+    ///
+    /// let mut numbers = stacks.iter_downcast_mut::<i32>();
+    /// *numbers.next_back().unwrap() *= 10;
+    /// *numbers.next().unwrap() *= 100;
+    /// assert!(numbers.next().is_none());
+    ///
+    /// assert_eq!(stacks[0].downcast_ref::<i32>(), Some(&100));
+    /// assert_eq!(stacks[2].downcast_ref::<i32>(), Some(&20));
+    /// ```
+    fn iter_downcast_mut<T>(&mut self) -> IterDowncastMut<'_, T, N>
+    where
+        T: core::any::Any;
+}
+
+impl<const N: usize> StackAnySlice<N> for [StackAny<N>] {
+    fn iter_downcast<T>(&self) -> IterDowncast<'_, T, N>
+    where
+        T: core::any::Any,
+    {
+        IterDowncast {
+            iter: self.iter(),
+            _marker: core::marker::PhantomData,
+        }
+    }
+
+    fn iter_downcast_mut<T>(&mut self) -> IterDowncastMut<'_, T, N>
+    where
+        T: core::any::Any,
+    {
+        IterDowncastMut {
+            iter: self.iter_mut(),
+            _marker: core::marker::PhantomData,
+        }
+    }
+}
+
+/// An iterator over the contained values of `T`, returned by [`StackAnySlice::iter_downcast`].
+pub struct IterDowncast<'a, T, const N: usize> {
+    iter: core::slice::Iter<'a, StackAny<N>>,
+    _marker: core::marker::PhantomData<fn() -> T>,
+}
+
+impl<'a, T, const N: usize> Iterator for IterDowncast<'a, T, N>
+where
+    T: core::any::Any,
+{
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<&'a T> {
+        self.iter.find_map(StackAny::downcast_ref)
+    }
+
+    /// Returns the bounds from none of the remaining stacks to all of them containing `T`.
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, Some(self.iter.len()))
+    }
+}
+
+impl<'a, T, const N: usize> DoubleEndedIterator for IterDowncast<'a, T, N>
+where
+    T: core::any::Any,
+{
+    fn next_back(&mut self) -> Option<&'a T> {
+        self.iter.by_ref().rev().find_map(StackAny::downcast_ref)
+    }
+}
+
+impl<T, const N: usize> core::iter::FusedIterator for IterDowncast<'_, T, N> where T: core::any::Any {}
+
+impl<T, const N: usize> Clone for IterDowncast<'_, T, N> {
+    fn clone(&self) -> Self {
+        Self {
+            iter: self.iter.clone(),
+            _marker: core::marker::PhantomData,
+        }
+    }
+}
+
+impl<T, const N: usize> core::fmt::Debug for IterDowncast<'_, T, N> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("IterDowncast")
+            .field("remaining", &self.iter.len())
+            .finish()
+    }
+}
+
+/// An iterator over mutable references to the contained values of `T`,
+/// returned by [`StackAnySlice::iter_downcast_mut`].
+pub struct IterDowncastMut<'a, T, const N: usize> {
+    iter: core::slice::IterMut<'a, StackAny<N>>,
+    _marker: core::marker::PhantomData<fn() -> T>,
+}
+
+impl<'a, T, const N: usize> Iterator for IterDowncastMut<'a, T, N>
+where
+    T: core::any::Any,
+{
+    type Item = &'a mut T;
+
+    fn next(&mut self) -> Option<&'a mut T> {
+        self.iter.find_map(StackAny::downcast_mut)
+    }
+
+    /// Returns the bounds from none of the remaining stacks to all of them containing `T`.
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, Some(self.iter.len()))
+    }
+}
+
+impl<'a, T, const N: usize> DoubleEndedIterator for IterDowncastMut<'a, T, N>
+where
+    T: core::any::Any,
+{
+    fn next_back(&mut self) -> Option<&'a mut T> {
+        self.iter.by_ref().rev().find_map(StackAny::downcast_mut)
+    }
+}
+
+impl<T, const N: usize> core::iter::FusedIterator for IterDowncastMut<'_, T, N> where
+    T: core::any::Any
+{
+}
+
+impl<T, const N: usize> core::fmt::Debug for IterDowncastMut<'_, T, N> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("IterDowncastMut")
+            .field("remaining", &self.iter.len())
+            .finish()
+    }
+}
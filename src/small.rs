@@ -0,0 +1,479 @@
+use alloc::boxed::Box;
+
+use crate::StackAny;
+
+/// A value placed either on the stack or on the heap, returned by [`StackAny::try_new_or_boxed`].
+///
+/// Unlike [`SmallAny`], which one of the two is exposed, so that the caller can tell heap-backed values apart.
+/// The downcast methods dispatch to whichever is present.
+///
+/// # Examples
+///
+/// ```
+/// use stack_any::{StackAny, StoredAny};
+///
+/// let values = [
+///     StackAny::<8>::try_new_or_boxed(5i32),
+///     StackAny::<8>::try_new_or_boxed([5u64; 16]),
+///     StackAny::<8>::try_new_or_boxed('x'),
+/// ];
+///
+/// let boxed = values.iter().filter(|value| matches!(value, StoredAny::Boxed(_))).count();
+/// assert_eq!(boxed, 1);
+///
+/// assert_eq!(values[0].downcast_ref::<i32>(), Some(&5));
+/// assert_eq!(values[1].downcast_ref::<[u64; 16]>(), Some(&[5; 16]));
+/// ```
+pub enum StoredAny<const N: usize> {
+    /// The value is placed on the stack.
+    Inline(StackAny<N>),
+    /// The value did not fit, so it is placed on the heap.
+    Boxed(Box<dyn core::any::Any>),
+}
+
+impl<const N: usize> StoredAny<N> {
+    /// Returns true if the value is placed on the stack rather than on the heap.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// assert!(stack_any::StackAny::<4>::try_new_or_boxed(5i32).is_inline());
+    /// assert!(!stack_any::StackAny::<4>::try_new_or_boxed(5i64).is_inline());
+    /// ```
+    pub fn is_inline(&self) -> bool {
+        matches!(self, Self::Inline(_))
+    }
+
+    /// Returns true if the contained value type is equal to `T`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let five = stack_any::StackAny::<2>::try_new_or_boxed(5i32);
+    /// assert!(five.is::<i32>());
+    /// assert!(!five.is::<i64>());
+    /// ```
+    pub fn is<T>(&self) -> bool
+    where
+        T: core::any::Any,
+    {
+        match self {
+            Self::Inline(stack) => stack.is::<T>(),
+            Self::Boxed(boxed) => boxed.is::<T>(),
+        }
+    }
+
+    /// Attempt to return reference to the inner value as a concrete type.
+    /// Returns None if `T` is not equal to contained value type.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let five = stack_any::StackAny::<2>::try_new_or_boxed(5i32);
+    /// assert_eq!(five.downcast_ref::<i32>(), Some(&5));
+    /// assert_eq!(five.downcast_ref::<i64>(), None);
+    /// ```
+    pub fn downcast_ref<T>(&self) -> Option<&T>
+    where
+        T: core::any::Any,
+    {
+        match self {
+            Self::Inline(stack) => stack.downcast_ref(),
+            Self::Boxed(boxed) => boxed.downcast_ref(),
+        }
+    }
+
+    /// Attempt to return mutable reference to the inner value as a concrete type.
+    /// Returns None if `T` is not equal to contained value type.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut five = stack_any::StackAny::<2>::try_new_or_boxed(5i32);
+    /// assert_eq!(five.downcast_mut::<i32>(), Some(&mut 5));
+    /// assert_eq!(five.downcast_mut::<i64>(), None);
+    /// ```
+    pub fn downcast_mut<T>(&mut self) -> Option<&mut T>
+    where
+        T: core::any::Any,
+    {
+        match self {
+            Self::Inline(stack) => stack.downcast_mut(),
+            Self::Boxed(boxed) => boxed.downcast_mut(),
+        }
+    }
+
+    /// Attempt to downcast to a concrete type.
+    /// Returns the value back if `T` is not equal to contained value type.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let five = stack_any::StackAny::<8>::try_new_or_boxed(5i32);
+    /// assert_eq!(five.downcast::<i32>().ok(), Some(5));
+    ///
+    /// let bytes = stack_any::StackAny::<8>::try_new_or_boxed([5u8; 64]);
+    /// let bytes = bytes.downcast::<i32>().unwrap_err();
+    /// assert_eq!(bytes.downcast::<[u8; 64]>().ok(), Some([5; 64]));
+    /// ```
+    pub fn downcast<T>(self) -> Result<T, Self>
+    where
+        T: core::any::Any,
+    {
+        match self {
+            Self::Inline(stack) => stack.downcast().map_err(Self::Inline),
+            Self::Boxed(boxed) => boxed.downcast().map(|boxed| *boxed).map_err(Self::Boxed),
+        }
+    }
+
+    /// Returns reference to the inner value as `dyn Any`, whose `type_id` is the contained value type.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let bytes = stack_any::StackAny::<8>::try_new_or_boxed([5u8; 64]);
+    /// assert_eq!(bytes.as_any().type_id(), std::any::TypeId::of::<[u8; 64]>());
+    /// ```
+    pub fn as_any(&self) -> &dyn core::any::Any {
+        match self {
+            Self::Inline(stack) => stack.as_any(),
+            Self::Boxed(boxed) => &**boxed,
+        }
+    }
+
+    /// Returns mutable reference to the inner value as `dyn Any`, whose `type_id` is the contained value type.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut five = stack_any::StackAny::<2>::try_new_or_boxed(5i32);
+    /// *five.as_any_mut().downcast_mut::<i32>().unwrap() = 7;
+    /// assert_eq!(five.downcast_ref::<i32>(), Some(&7));
+    /// ```
+    pub fn as_any_mut(&mut self) -> &mut dyn core::any::Any {
+        match self {
+            Self::Inline(stack) => stack.as_any_mut(),
+            Self::Boxed(boxed) => &mut **boxed,
+        }
+    }
+
+    /// Moves the contained value onto the heap, keeping its concrete type.
+    /// A boxed value is returned as is, without allocating again.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let five = stack_any::StackAny::<8>::try_new_or_boxed(5i32);
+    /// assert_eq!(five.into_box_any().downcast::<i32>().ok(), Some(Box::new(5)));
+    /// ```
+    pub fn into_box_any(self) -> Box<dyn core::any::Any> {
+        match self {
+            Self::Inline(stack) => stack.into_box_any(),
+            Self::Boxed(boxed) => boxed,
+        }
+    }
+}
+
+impl<const N: usize> core::fmt::Debug for StoredAny<N> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::Inline(stack) => f.debug_tuple("Inline").field(stack).finish(),
+            Self::Boxed(boxed) => f.debug_tuple("Boxed").field(boxed).finish(),
+        }
+    }
+}
+
+/// A convertible type that owns a stack allocation of `N` size, spilling values that do not fit onto the heap.
+///
+/// A value whose size is at most N and whose alignment is at most [`StackAny::ALIGN`] is placed inline
+/// as in [`StackAny`], otherwise it is boxed. Either way, it is accessed through the same methods.
+///
+/// # Examples
+///
+/// ```
+/// let values = [
+///     stack_any::SmallAny::<8>::new(5i32),
+///     stack_any::SmallAny::<8>::new([5u64; 16]),
+/// ];
+///
+/// assert!(values[0].is_inline());
+/// assert!(!values[1].is_inline());
+///
+/// assert_eq!(values[0].downcast_ref::<i32>(), Some(&5));
+/// assert_eq!(values[1].downcast_ref::<[u64; 16]>(), Some(&[5; 16]));
+/// ```
+///
+/// Since any type can be placed into it, `SmallAny` is neither `Send` nor `Sync`.
+///
+/// ```compile_fail
+/// fn assert_send<T: Send>() {}
+/// assert_send::<stack_any::SmallAny<8>>();
+/// ```
+pub struct SmallAny<const N: usize>(StoredAny<N>);
+
+impl<const N: usize> SmallAny<N> {
+    /// Places `value` into N-size memory on the stack, or onto the heap if `T` size is larger than N
+    /// or `T` alignment is larger than [`StackAny::ALIGN`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let five = stack_any::SmallAny::<4>::new(5i32);
+    /// assert!(five.is_inline());
+    ///
+    /// let five = stack_any::SmallAny::<4>::new(5i64);
+    /// assert!(!five.is_inline());
+    /// ```
+    ///
+    /// The value is dropped exactly once in either place.
+    ///
+    /// ```
+    /// use std::rc::Rc;
+    ///
+    /// let counter = Rc::new(());
+    /// let inline = stack_any::SmallAny::<8>::new(counter.clone());
+    /// let boxed = stack_any::SmallAny::<8>::new([counter.clone(), counter.clone()]);
+    /// assert_eq!(Rc::strong_count(&counter), 4);
+    ///
+    /// drop(inline);
+    /// assert_eq!(Rc::strong_count(&counter), 3);
+    ///
+    /// drop(boxed);
+    /// assert_eq!(Rc::strong_count(&counter), 1);
+    /// ```
+    pub fn new<T>(value: T) -> Self
+    where
+        T: core::any::Any,
+    {
+        Self(StackAny::try_new_or_boxed(value))
+    }
+
+    /// Returns true if the contained value is placed on the stack rather than on the heap.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// assert!(stack_any::SmallAny::<24>::new(String::from("x")).is_inline());
+    /// assert!(!stack_any::SmallAny::<8>::new(String::from("x")).is_inline());
+    /// ```
+    pub fn is_inline(&self) -> bool {
+        self.0.is_inline()
+    }
+
+    /// Returns true if the contained value type is equal to `T`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let strings = stack_any::SmallAny::<8>::new(vec![String::from("x")]);
+    /// assert!(strings.is::<Vec<String>>());
+    /// assert!(!strings.is::<String>());
+    /// ```
+    pub fn is<T>(&self) -> bool
+    where
+        T: core::any::Any,
+    {
+        self.0.is::<T>()
+    }
+
+    /// Attempt to return reference to the inner value as a concrete type.
+    /// Returns None if `T` is not equal to contained value type.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let five = stack_any::SmallAny::<2>::new(5i32);
+    /// assert_eq!(five.downcast_ref::<i32>(), Some(&5));
+    /// assert_eq!(five.downcast_ref::<i64>(), None);
+    /// ```
+    pub fn downcast_ref<T>(&self) -> Option<&T>
+    where
+        T: core::any::Any,
+    {
+        self.0.downcast_ref()
+    }
+
+    /// Attempt to return mutable reference to the inner value as a concrete type.
+    /// Returns None if `T` is not equal to contained value type.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut five = stack_any::SmallAny::<2>::new(5i32);
+    /// assert_eq!(five.downcast_mut::<i32>(), Some(&mut 5));
+    /// assert_eq!(five.downcast_mut::<i64>(), None);
+    /// ```
+    pub fn downcast_mut<T>(&mut self) -> Option<&mut T>
+    where
+        T: core::any::Any,
+    {
+        self.0.downcast_mut()
+    }
+
+    /// Attempt to downcast to a concrete type.
+    /// Returns the value back if `T` is not equal to contained value type.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let five = stack_any::SmallAny::<8>::new(5i32);
+    /// assert_eq!(five.downcast::<i32>().ok(), Some(5));
+    ///
+    /// let bytes = stack_any::SmallAny::<8>::new([5u8; 64]);
+    /// let bytes = bytes.downcast::<i32>().unwrap_err();
+    /// assert_eq!(bytes.downcast::<[u8; 64]>().ok(), Some([5; 64]));
+    /// ```
+    pub fn downcast<T>(self) -> Result<T, Self>
+    where
+        T: core::any::Any,
+    {
+        self.0.downcast().map_err(Self)
+    }
+
+    /// Returns reference to the inner value as `dyn Any`, whose `type_id` is the contained value type.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let bytes = stack_any::SmallAny::<8>::new([5u8; 64]);
+    /// assert_eq!(bytes.as_any().type_id(), std::any::TypeId::of::<[u8; 64]>());
+    /// ```
+    pub fn as_any(&self) -> &dyn core::any::Any {
+        self.0.as_any()
+    }
+
+    /// Returns mutable reference to the inner value as `dyn Any`, whose `type_id` is the contained value type.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut five = stack_any::SmallAny::<2>::new(5i32);
+    /// *five.as_any_mut().downcast_mut::<i32>().unwrap() = 7;
+    /// assert_eq!(five.downcast_ref::<i32>(), Some(&7));
+    /// ```
+    pub fn as_any_mut(&mut self) -> &mut dyn core::any::Any {
+        self.0.as_any_mut()
+    }
+
+    /// Drops the contained value and then places `value` into the same memory,
+    /// or onto the heap if `U` does not fit.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::rc::Rc;
+    ///
+    /// let counter = Rc::new(());
+    /// let mut value = stack_any::SmallAny::<8>::new(counter.clone());
+    /// assert!(value.is_inline());
+    ///
+    /// value.insert(vec![counter.clone(); 2]);
+    /// assert!(!value.is_inline());
+    /// assert_eq!(Rc::strong_count(&counter), 3);
+    ///
+    /// value.insert(5i32);
+    /// assert!(value.is_inline());
+    /// assert_eq!(Rc::strong_count(&counter), 1);
+    /// ```
+    pub fn insert<U>(&mut self, value: U)
+    where
+        U: core::any::Any,
+    {
+        *self = Self::new(value);
+    }
+
+    /// Moves the contained value onto the heap, keeping its concrete type.
+    /// A boxed value is returned as is, without allocating again.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let five = stack_any::SmallAny::<8>::new(5i32);
+    /// assert_eq!(five.into_box_any().downcast::<i32>().ok(), Some(Box::new(5)));
+    /// ```
+    pub fn into_box_any(self) -> Box<dyn core::any::Any> {
+        self.0.into_box_any()
+    }
+
+    /// Converts into a [`StackAny`] if the contained value is placed inline.
+    /// Returns the value back otherwise.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let five = stack_any::SmallAny::<8>::new(5i32).into_stack_any().unwrap();
+    /// assert_eq!(five.downcast_ref::<i32>(), Some(&5));
+    ///
+    /// let bytes = stack_any::SmallAny::<8>::new([5u8; 64]);
+    /// assert!(bytes.into_stack_any().is_err());
+    /// ```
+    pub fn into_stack_any(self) -> Result<StackAny<N>, Self> {
+        match self.0 {
+            StoredAny::Inline(stack) => Ok(stack),
+            stored => Err(Self(stored)),
+        }
+    }
+}
+
+impl<const N: usize> From<StackAny<N>> for SmallAny<N> {
+    fn from(value: StackAny<N>) -> Self {
+        Self(StoredAny::Inline(value))
+    }
+}
+
+/// Hides whether the value is placed on the stack or on the heap.
+impl<const N: usize> From<StoredAny<N>> for SmallAny<N> {
+    fn from(value: StoredAny<N>) -> Self {
+        Self(value)
+    }
+}
+
+/// Exposes whether the value is placed on the stack or on the heap.
+///
+/// # Examples
+///
+/// ```
+/// let bytes = stack_any::SmallAny::<8>::new([5u8; 64]);
+/// assert!(matches!(bytes.into(), stack_any::StoredAny::<8>::Boxed(_)));
+/// ```
+impl<const N: usize> From<SmallAny<N>> for StoredAny<N> {
+    fn from(value: SmallAny<N>) -> Self {
+        value.0
+    }
+}
+
+/// Moves the boxed value onto the stack if it fits, keeping the box otherwise.
+///
+/// # Examples
+///
+/// ```
+/// use std::rc::Rc;
+///
+/// let counter = Rc::new(());
+/// let boxed: Box<dyn std::any::Any> = Box::new(counter.clone());
+///
+/// let value = stack_any::SmallAny::<8>::from(boxed);
+/// assert!(value.is_inline());
+/// assert_eq!(Rc::strong_count(&counter), 2);
+///
+/// let value = stack_any::SmallAny::<0>::from(value.into_box_any());
+/// assert!(!value.is_inline());
+/// assert_eq!(Rc::strong_count(&counter), 2);
+///
+/// drop(value);
+/// assert_eq!(Rc::strong_count(&counter), 1);
+/// ```
+impl<const N: usize> From<Box<dyn core::any::Any>> for SmallAny<N> {
+    fn from(value: Box<dyn core::any::Any>) -> Self {
+        match StackAny::try_from(value) {
+            Ok(stack) => Self(StoredAny::Inline(stack)),
+            Err(boxed) => Self(StoredAny::Boxed(boxed)),
+        }
+    }
+}
+
+impl<const N: usize> core::fmt::Debug for SmallAny<N> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_tuple("SmallAny").field(&self.0).finish()
+    }
+}
@@ -0,0 +1,287 @@
+use crate::{DebugElement, StackAny};
+
+/// A property bag of up to `CAP` values keyed by `&'static str`, each placed into a stack of `N` size.
+///
+/// It searches the keys linearly without hashing nor allocation, so it works without `std` and `alloc`.
+/// Keys are kept in the order of insertion.
+///
+/// # Examples
+///
+/// ```
+/// let mut config = stack_any::StackAnyBag::<4, 8>::new();
+/// config.set("baud", 115_200u32).unwrap();
+/// config.set("parity", false).unwrap();
+/// config.set("timeout", 0.5f32).unwrap();
+///
+/// assert_eq!(config.get::<u32>("baud"), Some(&115_200));
+/// assert_eq!(config.get::<bool>("parity"), Some(&false));
+/// assert_eq!(config.get::<u64>("baud"), None);
+/// assert_eq!(config.get::<u32>("stop"), None);
+///
+/// config.set("baud", 9_600u32).unwrap();
+/// assert!(config.remove("parity").is_some());
+/// assert!(config.keys().eq(["baud", "timeout"]));
+/// ```
+pub struct StackAnyBag<const CAP: usize, const N: usize> {
+    keys: [&'static str; CAP],
+    values: [StackAny<N>; CAP],
+    len: usize,
+}
+
+impl<const CAP: usize, const N: usize> StackAnyBag<CAP, N> {
+    /// Creates an empty bag.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// const CONFIG: stack_any::StackAnyBag<4, 8> = stack_any::StackAnyBag::new();
+    /// assert!(CONFIG.is_empty());
+    /// ```
+    pub const fn new() -> Self {
+        Self {
+            keys: [""; CAP],
+            values: [const { StackAny::empty() }; CAP],
+            len: 0,
+        }
+    }
+
+    /// Returns the number of values.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut config = stack_any::StackAnyBag::<4, 8>::new();
+    /// config.set("baud", 115_200u32).unwrap();
+    /// config.set("baud", 9_600u32).unwrap();
+    /// assert_eq!(config.len(), 1);
+    /// ```
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns true if the bag contains no values.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let config = stack_any::StackAnyBag::<4, 8>::new();
+    /// assert!(config.is_empty());
+    /// ```
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the index of `key`.
+    fn position(&self, key: &str) -> Option<usize> {
+        self.keys[..self.len].iter().position(|k| *k == key)
+    }
+
+    /// Places `value` into a stack and then sets it to `key`, dropping the previous value of `key` if any,
+    /// whatever its type is.
+    /// Returns `value` back if `key` is new and the bag is full.
+    ///
+    /// Whether `T` fits is checked at compile time, as in [`StackAny::new`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::sync::atomic::{AtomicUsize, Ordering};
+    ///
+    /// static DROPS: AtomicUsize = AtomicUsize::new(0);
+    ///
+    /// struct Handle;
+    ///
+    /// impl Drop for Handle {
+    ///     fn drop(&mut self) {
+    ///         DROPS.fetch_add(1, Ordering::SeqCst);
+    ///     }
+    /// }
+    ///
+    /// let mut config = stack_any::StackAnyBag::<1, 8>::new();
+    /// config.set("device", Handle).unwrap_or_else(|_| unreachable!());
+    ///
+    /// config.set("device", 5u8).unwrap();
+    /// assert_eq!(DROPS.load(Ordering::SeqCst), 1);
+    /// assert_eq!(config.get::<u8>("device"), Some(&5));
+    ///
+    /// assert_eq!(config.set("other", 7u8), Err(7));
+    /// drop(config);
+    /// assert_eq!(DROPS.load(Ordering::SeqCst), 1);
+    /// ```
+    ///
+    /// ```compile_fail
+    /// let mut config = stack_any::StackAnyBag::<1, 8>::new();
+    /// config.set("device", [0u8; 16]).unwrap();
+    /// ```
+    pub fn set<T>(&mut self, key: &'static str, value: T) -> Result<(), T>
+    where
+        T: core::any::Any,
+    {
+        const {
+            assert!(
+                StackAny::<N>::fits::<T>(),
+                "value does not fit in the stack"
+            )
+        };
+
+        let index = match self.position(key) {
+            Some(index) => index,
+            None if self.len < CAP => {
+                self.keys[self.len] = key;
+                self.len += 1;
+                self.len - 1
+            }
+            None => return Err(value),
+        };
+
+        self.values[index] = StackAny::new(value);
+        Ok(())
+    }
+
+    /// Returns reference to the value of `key` as a concrete type.
+    /// Returns None if the bag contains no value of `key`, or if `T` is not equal to its type.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut config = stack_any::StackAnyBag::<4, 8>::new();
+    /// config.set("baud", 115_200u32).unwrap();
+    ///
+    /// assert_eq!(config.get::<u32>("baud"), Some(&115_200));
+    /// assert_eq!(config.get::<i32>("baud"), None);
+    /// ```
+    pub fn get<T>(&self, key: &str) -> Option<&T>
+    where
+        T: core::any::Any,
+    {
+        self.values[self.position(key)?].downcast_ref()
+    }
+
+    /// Returns mutable reference to the value of `key` as a concrete type.
+    /// Returns None if the bag contains no value of `key`, or if `T` is not equal to its type.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut config = stack_any::StackAnyBag::<4, 8>::new();
+    /// config.set("retries", 3u8).unwrap();
+    ///
+    /// *config.get_mut::<u8>("retries").unwrap() += 1;
+    /// assert_eq!(config.get::<u8>("retries"), Some(&4));
+    /// assert_eq!(config.get_mut::<u16>("retries"), None);
+    /// ```
+    pub fn get_mut<T>(&mut self, key: &str) -> Option<&mut T>
+    where
+        T: core::any::Any,
+    {
+        let index = self.position(key)?;
+        self.values[index].downcast_mut()
+    }
+
+    /// Returns true if the bag contains a value of `key`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut config = stack_any::StackAnyBag::<4, 8>::new();
+    /// config.set("baud", 115_200u32).unwrap();
+    ///
+    /// assert!(config.contains_key("baud"));
+    /// assert!(!config.contains_key("parity"));
+    /// ```
+    pub fn contains_key(&self, key: &str) -> bool {
+        self.position(key).is_some()
+    }
+
+    /// Removes the value of `key` from the bag and returns it.
+    /// Returns None if the bag contains no value of `key`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut config = stack_any::StackAnyBag::<4, 8>::new();
+    /// config.set("baud", 115_200u32).unwrap();
+    ///
+    /// let baud = config.remove("baud").unwrap();
+    /// assert_eq!(baud.downcast_ref::<u32>(), Some(&115_200));
+    /// assert!(config.remove("baud").is_none());
+    /// ```
+    pub fn remove(&mut self, key: &str) -> Option<StackAny<N>> {
+        let index = self.position(key)?;
+
+        // Shifts the following slots, so that the order of the keys is preserved.
+        self.keys[index..self.len].rotate_left(1);
+        self.values[index..self.len].rotate_left(1);
+        self.len -= 1;
+
+        Some(core::mem::take(&mut self.values[self.len]))
+    }
+
+    /// Returns an iterator over the keys in the order of insertion.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut config = stack_any::StackAnyBag::<4, 8>::new();
+    /// config.set("baud", 115_200u32).unwrap();
+    /// config.set("parity", false).unwrap();
+    ///
+    /// assert!(config.keys().eq(["baud", "parity"]));
+    /// ```
+    pub fn keys(&self) -> impl DoubleEndedIterator<Item = &'static str> + ExactSizeIterator + '_ {
+        self.keys[..self.len].iter().copied()
+    }
+
+    /// Drops every value, leaving the bag empty.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut config = stack_any::StackAnyBag::<4, 8>::new();
+    /// config.set("baud", 115_200u32).unwrap();
+    ///
+    /// config.clear();
+    /// assert!(config.is_empty());
+    /// ```
+    pub fn clear(&mut self) {
+        let len = core::mem::replace(&mut self.len, 0);
+
+        // Values left behind by a panicking destructor are dropped with the bag.
+        self.values[..len].iter_mut().for_each(StackAny::clear);
+    }
+}
+
+impl<const CAP: usize, const N: usize> Default for StackAnyBag<CAP, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Formats the bag as a map from the keys to the values in the order of insertion,
+/// each value as in `Debug` of [`StackAnyVec`](crate::StackAnyVec).
+///
+/// # Examples
+///
+/// ```
+/// let mut config = stack_any::StackAnyBag::<4, 8>::new();
+/// config.set("baud", 115_200u32).unwrap();
+/// config.set("parity", false).unwrap();
+///
+/// #[cfg(feature = "type-name")]
+/// assert_eq!(
+///     format!("{:?}", config),
+///     r#"{"baud": u32: <opaque 4 bytes>, "parity": bool: <opaque 1 bytes>}"#
+/// );
+/// #[cfg(not(feature = "type-name"))]
+/// assert_eq!(
+///     format!("{:?}", config),
+///     r#"{"baud": <opaque 4 bytes>, "parity": <opaque 1 bytes>}"#
+/// );
+/// ```
+impl<const CAP: usize, const N: usize> core::fmt::Debug for StackAnyBag<CAP, N> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let keys = &self.keys[..self.len];
+        let values = self.values[..self.len].iter().map(DebugElement);
+        f.debug_map().entries(keys.iter().zip(values)).finish()
+    }
+}
@@ -0,0 +1,445 @@
+use crate::vtable::{Capture, Plain};
+use crate::{value_ptr, Buffer, StackAny, VTable};
+
+/// A column of values of a single type, decided at runtime, stored contiguously in a stack allocation of `BYTES` size.
+///
+/// Unlike [`StackAnyVec`](crate::StackAnyVec) holding values of different types in stacks of their own,
+/// the values are packed without any metadata between them, which is the densest layout for iterating
+/// values of one type. Code not knowing the type can still move values in and out of the column,
+/// and every operation on a type other than the type of the column fails.
+///
+/// # Examples
+///
+/// ```
+/// use stack_any::StackAnyColumn;
+///
+/// #[derive(Debug, PartialEq)]
+/// struct Position(f32, f32);
+/// #[derive(Debug, PartialEq)]
+/// struct Velocity(f32, f32);
+///
+/// let mut positions = StackAnyColumn::<64>::new_for::<Position>();
+/// let mut velocities = StackAnyColumn::<64>::new_for::<Velocity>();
+/// for i in 0..4 {
+///     positions.push(Position(i as f32, 0.0)).unwrap();
+///     velocities.push(Velocity(1.0, 0.5)).unwrap();
+/// }
+///
+/// let velocities = velocities.as_slice::<Velocity>().unwrap();
+/// for (position, velocity) in positions.as_mut_slice::<Position>().unwrap().iter_mut().zip(velocities) {
+///     position.0 += velocity.0;
+///     position.1 += velocity.1;
+/// }
+///
+/// assert_eq!(positions.get::<Position>(3), Some(&Position(4.0, 0.5)));
+/// assert_eq!(positions.get::<Velocity>(3), None);
+/// assert!(positions.push(Velocity(0.0, 0.0)).is_err());
+/// ```
+pub struct StackAnyColumn<const BYTES: usize> {
+    bytes: Buffer<BYTES>,
+    type_id: core::any::TypeId,
+    vtable: &'static VTable,
+    drop_fn: Option<unsafe fn(*mut core::mem::MaybeUninit<u8>, usize)>,
+    len: usize,
+    _marker: core::marker::PhantomData<*mut ()>,
+}
+
+impl<const BYTES: usize> StackAnyColumn<BYTES> {
+    /// Creates an empty column of `T`.
+    ///
+    /// Whether a value of `T` fits is checked at compile time, as in [`StackAny::new`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let column = stack_any::StackAnyColumn::<16>::new_for::<u32>();
+    /// assert!(column.is::<u32>());
+    /// assert_eq!(column.capacity(), 4);
+    /// ```
+    ///
+    /// ```compile_fail
+    /// let column = stack_any::StackAnyColumn::<4>::new_for::<u64>();
+    /// ```
+    pub fn new_for<T>() -> Self
+    where
+        T: core::any::Any,
+    {
+        const {
+            assert!(
+                StackAny::<BYTES>::fits::<T>(),
+                "value does not fit in the stack"
+            )
+        };
+
+        let drop_fn: Option<unsafe fn(_, _)> = match core::mem::needs_drop::<T>() {
+            true => Some(|ptr, len| unsafe {
+                let slice = core::ptr::slice_from_raw_parts_mut(value_ptr::<T>(ptr), len);
+                core::ptr::drop_in_place(slice)
+            }),
+            false => None,
+        };
+
+        Self {
+            bytes: Buffer([core::mem::MaybeUninit::uninit(); BYTES]),
+            type_id: core::any::TypeId::of::<T>(),
+            vtable: <Plain as Capture<T>>::REF,
+            drop_fn,
+            len: 0,
+            _marker: core::marker::PhantomData,
+        }
+    }
+
+    /// Returns true if the type of the column is `T`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let column = stack_any::StackAnyColumn::<16>::new_for::<u32>();
+    /// assert!(column.is::<u32>());
+    /// assert!(!column.is::<i32>());
+    /// ```
+    pub fn is<T>(&self) -> bool
+    where
+        T: core::any::Any,
+    {
+        self.type_id == core::any::TypeId::of::<T>()
+    }
+
+    /// Returns the number of values the column can hold, or `usize::MAX` if its type is zero-sized.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let column = stack_any::StackAnyColumn::<16>::new_for::<[u8; 3]>();
+    /// assert_eq!(column.capacity(), 5);
+    /// ```
+    pub fn capacity(&self) -> usize {
+        BYTES.checked_div(self.vtable.size).unwrap_or(usize::MAX)
+    }
+
+    /// Returns the number of values.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut column = stack_any::StackAnyColumn::<16>::new_for::<u32>();
+    /// column.push(5u32).unwrap();
+    /// assert_eq!(column.len(), 1);
+    /// ```
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns true if the column contains no values.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let column = stack_any::StackAnyColumn::<16>::new_for::<u32>();
+    /// assert!(column.is_empty());
+    /// ```
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Appends `value` to the back of the column.
+    /// Returns `value` back if `T` is not equal to the type of the column, or if the column is full.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut column = stack_any::StackAnyColumn::<8>::new_for::<u32>();
+    /// column.push(1u32).unwrap();
+    /// column.push(2u32).unwrap();
+    ///
+    /// // The bytes of the column are exhausted.
+    /// assert_eq!(column.push(3u32), Err(3));
+    /// assert_eq!(column.push(4i32), Err(4));
+    /// assert_eq!(column.as_slice::<u32>(), Some(&[1, 2][..]));
+    /// ```
+    pub fn push<T>(&mut self, value: T) -> Result<(), T>
+    where
+        T: core::any::Any,
+    {
+        if !self.is::<T>() || self.len == self.capacity() {
+            return Err(value);
+        }
+
+        let ptr = value_ptr::<T>(self.bytes.0.as_mut_ptr());
+        unsafe { core::ptr::write(ptr.wrapping_add(self.len), value) };
+        self.len += 1;
+        Ok(())
+    }
+
+    /// Moves the contained value of `stack` to the back of the column, leaving nothing in `stack`.
+    /// Returns `stack` back if the contained value type is not equal to the type of the column,
+    /// or if the column is full.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::rc::Rc;
+    ///
+    /// let counter = Rc::new(());
+    ///
+    /// let mut column = stack_any::StackAnyColumn::<8>::new_for::<Rc<()>>();
+    /// column.push_erased(stack_any::StackAny::<8>::new(counter.clone())).unwrap();
+    /// assert_eq!(Rc::strong_count(&counter), 2);
+    ///
+    /// let five = column.push_erased(stack_any::StackAny::<8>::new(5i32)).unwrap_err();
+    /// assert_eq!(five.downcast_ref::<i32>(), Some(&5));
+    ///
+    /// let full = column.push_erased(stack_any::StackAny::<8>::new(counter.clone())).unwrap_err();
+    /// drop(full);
+    ///
+    /// drop(column);
+    /// assert_eq!(Rc::strong_count(&counter), 1);
+    /// ```
+    pub fn push_erased<const N: usize>(
+        &mut self,
+        mut stack: StackAny<N>,
+    ) -> Result<(), StackAny<N>> {
+        if stack.type_id != self.type_id || self.len == self.capacity() {
+            return Err(stack);
+        }
+
+        let size = self.vtable.size;
+        let ptr = self.bytes.0.as_mut_ptr().wrapping_add(self.len * size);
+        unsafe { core::ptr::copy_nonoverlapping(stack.bytes.0.as_ptr(), ptr, size) };
+        self.len += 1;
+
+        // The value is moved, so the stack must not drop it.
+        stack.set_empty();
+        stack.scrub(size);
+        Ok(())
+    }
+
+    /// Returns reference to the value at `index` as a concrete type.
+    /// Returns None if `index` is out of bounds, or if `T` is not equal to the type of the column.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut column = stack_any::StackAnyColumn::<16>::new_for::<u32>();
+    /// column.push(5u32).unwrap();
+    ///
+    /// assert_eq!(column.get::<u32>(0), Some(&5));
+    /// assert_eq!(column.get::<u32>(1), None);
+    /// assert_eq!(column.get::<i32>(0), None);
+    /// ```
+    pub fn get<T>(&self, index: usize) -> Option<&T>
+    where
+        T: core::any::Any,
+    {
+        self.as_slice()?.get(index)
+    }
+
+    /// Returns mutable reference to the value at `index` as a concrete type.
+    /// Returns None if `index` is out of bounds, or if `T` is not equal to the type of the column.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut column = stack_any::StackAnyColumn::<16>::new_for::<u32>();
+    /// column.push(5u32).unwrap();
+    ///
+    /// *column.get_mut::<u32>(0).unwrap() += 1;
+    /// assert_eq!(column.get::<u32>(0), Some(&6));
+    /// assert_eq!(column.get_mut::<i32>(0), None);
+    /// ```
+    pub fn get_mut<T>(&mut self, index: usize) -> Option<&mut T>
+    where
+        T: core::any::Any,
+    {
+        self.as_mut_slice()?.get_mut(index)
+    }
+
+    /// Attempt to return the values as a slice of a concrete type.
+    /// Returns None if `T` is not equal to the type of the column.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut column = stack_any::StackAnyColumn::<16>::new_for::<u32>();
+    /// column.push(1u32).unwrap();
+    /// column.push(2u32).unwrap();
+    ///
+    /// assert_eq!(column.as_slice::<u32>(), Some(&[1, 2][..]));
+    /// assert_eq!(column.as_slice::<i32>(), None);
+    /// ```
+    pub fn as_slice<T>(&self) -> Option<&[T]>
+    where
+        T: core::any::Any,
+    {
+        if !self.is::<T>() {
+            return None;
+        }
+
+        let ptr = value_ptr::<T>(self.bytes.0.as_ptr() as *mut _);
+        Some(unsafe { core::slice::from_raw_parts(ptr, self.len) })
+    }
+
+    /// Attempt to return the values as a mutable slice of a concrete type.
+    /// Returns None if `T` is not equal to the type of the column.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut column = stack_any::StackAnyColumn::<16>::new_for::<u32>();
+    /// column.push(2u32).unwrap();
+    /// column.push(1u32).unwrap();
+    ///
+    /// column.as_mut_slice::<u32>().unwrap().sort();
+    /// assert_eq!(column.as_slice::<u32>(), Some(&[1, 2][..]));
+    /// assert!(column.as_mut_slice::<i32>().is_none());
+    /// ```
+    pub fn as_mut_slice<T>(&mut self) -> Option<&mut [T]>
+    where
+        T: core::any::Any,
+    {
+        if !self.is::<T>() {
+            return None;
+        }
+
+        let ptr = value_ptr::<T>(self.bytes.0.as_mut_ptr());
+        Some(unsafe { core::slice::from_raw_parts_mut(ptr, self.len) })
+    }
+
+    /// Drops the value at `index`, replacing it by the last value.
+    ///
+    /// This does not preserve the order of the values, but is O(1).
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is out of bounds.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut column = stack_any::StackAnyColumn::<32>::new_for::<u32>();
+    /// for i in 0..4 {
+    ///     column.push(i as u32).unwrap();
+    /// }
+    ///
+    /// column.swap_remove(1);
+    /// assert_eq!(column.as_slice::<u32>(), Some(&[0, 3, 2][..]));
+    ///
+    /// column.swap_remove(2);
+    /// assert_eq!(column.as_slice::<u32>(), Some(&[0, 3][..]));
+    /// ```
+    ///
+    /// ```
+    /// use std::rc::Rc;
+    ///
+    /// let counters = [Rc::new(()), Rc::new(()), Rc::new(())];
+    ///
+    /// let mut column = stack_any::StackAnyColumn::<24>::new_for::<Rc<()>>();
+    /// for counter in &counters {
+    ///     column.push(counter.clone()).unwrap();
+    /// }
+    ///
+    /// column.swap_remove(1);
+    /// assert_eq!(Rc::strong_count(&counters[1]), 1);
+    /// assert!(Rc::ptr_eq(column.get::<Rc<()>>(1).unwrap(), &counters[2]));
+    /// assert_eq!(Rc::strong_count(&counters[0]), 2);
+    /// assert_eq!(Rc::strong_count(&counters[2]), 2);
+    /// ```
+    ///
+    /// ```should_panic
+    /// let mut column = stack_any::StackAnyColumn::<16>::new_for::<u32>();
+    /// column.swap_remove(0);
+    /// ```
+    pub fn swap_remove(&mut self, index: usize) {
+        let len = self.len;
+        if index >= len {
+            panic!("swap_remove index (is {index}) should be < len (is {len})");
+        }
+
+        let size = self.vtable.size;
+        let ptr = self.bytes.0.as_mut_ptr();
+        let last = ptr.wrapping_add((len - 1) * size);
+        if index != len - 1 {
+            unsafe { core::ptr::swap_nonoverlapping(ptr.wrapping_add(index * size), last, size) };
+        }
+
+        // The length is decreased first, so that the value is not dropped again if its destructor panics.
+        self.len -= 1;
+        if let Some(drop_fn) = self.drop_fn {
+            unsafe { drop_fn(last, 1) };
+        }
+    }
+
+    /// Drops every value, leaving the column empty.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::rc::Rc;
+    ///
+    /// let counter = Rc::new(());
+    ///
+    /// let mut column = stack_any::StackAnyColumn::<16>::new_for::<Rc<()>>();
+    /// column.push(counter.clone()).unwrap();
+    ///
+    /// column.clear();
+    /// assert!(column.is_empty());
+    /// assert_eq!(Rc::strong_count(&counter), 1);
+    /// ```
+    pub fn clear(&mut self) {
+        let len = core::mem::replace(&mut self.len, 0);
+        if let Some(drop_fn) = self.drop_fn {
+            unsafe { drop_fn(self.bytes.0.as_mut_ptr(), len) };
+        }
+    }
+}
+
+/// Formats the metadata of the elements, that is the name of their type with the `type-name` feature,
+/// or its `TypeId` without it, followed by their number.
+///
+/// # Examples
+///
+/// ```
+/// let mut column = stack_any::StackAnyColumn::<16>::new_for::<u32>();
+/// column.push(1u32).unwrap();
+/// column.push(2u32).unwrap();
+///
+/// let debug = format!("{:?}", column);
+/// assert!(debug.starts_with("StackAnyColumn { bytes: 16, type"));
+/// assert!(debug.ends_with("len: 2 }"));
+/// # #[cfg(feature = "type-name")]
+/// # assert_eq!(debug, "StackAnyColumn { bytes: 16, type: \"u32\", len: 2 }");
+/// ```
+impl<const BYTES: usize> core::fmt::Debug for StackAnyColumn<BYTES> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let mut debug = f.debug_struct("StackAnyColumn");
+        debug.field("bytes", &BYTES);
+        #[cfg(feature = "type-name")]
+        debug.field("type", &self.vtable.type_name());
+        #[cfg(not(feature = "type-name"))]
+        debug.field("type_id", &self.type_id);
+        debug.field("len", &self.len).finish()
+    }
+}
+
+/// Drops every value.
+///
+/// # Examples
+///
+/// ```
+/// use std::rc::Rc;
+///
+/// let counter = Rc::new(());
+///
+/// let mut column = stack_any::StackAnyColumn::<24>::new_for::<Rc<()>>();
+/// while column.push(counter.clone()).is_ok() {}
+/// assert_eq!(column.len(), 3);
+/// assert_eq!(Rc::strong_count(&counter), 4);
+///
+/// drop(column);
+/// assert_eq!(Rc::strong_count(&counter), 1);
+/// ```
+impl<const BYTES: usize> Drop for StackAnyColumn<BYTES> {
+    fn drop(&mut self) {
+        self.clear();
+    }
+}
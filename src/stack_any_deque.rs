@@ -0,0 +1,491 @@
+use crate::{DebugElement, StackAny};
+
+/// A double-ended queue of up to `CAP` stacks of `N` size, as a ring buffer placed inline.
+///
+/// It works without `std` and `alloc`, so values of different types can be queued where
+/// a hand-written enum of them would be used otherwise.
+///
+/// # Examples
+///
+/// ```
+/// use stack_any::{StackAny, StackAnyDeque};
+///
+/// struct ButtonPress(u8);
+/// struct AdcSample(u16);
+/// struct UartByte(u8);
+///
+/// let mut events = StackAnyDeque::<4, 2>::new();
+/// events.push_back_value(ButtonPress(1)).unwrap_or_else(|_| unreachable!());
+/// events.push_back_value(AdcSample(512)).unwrap_or_else(|_| unreachable!());
+/// events.push_back_value(UartByte(b'x')).unwrap_or_else(|_| unreachable!());
+///
+/// let mut log = Vec::new();
+/// while let Some(event) = events.pop_front() {
+///     if let Some(ButtonPress(button)) = event.downcast_ref() {
+///         log.push(format!("button {button}"));
+///     } else if let Some(AdcSample(sample)) = event.downcast_ref() {
+///         log.push(format!("sample {sample}"));
+///     } else if let Some(UartByte(byte)) = event.downcast_ref() {
+///         log.push(format!("byte {}", *byte as char));
+///     }
+/// }
+/// assert_eq!(log, ["button 1", "sample 512", "byte x"]);
+/// ```
+///
+/// ```
+/// use std::rc::Rc;
+///
+/// let counter = Rc::new(());
+///
+/// // Pushing and popping moves the ring across its boundary many times.
+/// let mut events = stack_any::StackAnyDeque::<3, 16>::new();
+/// let mut popped = Vec::new();
+/// for i in 0..10 {
+///     if events.is_full() {
+///         let (j, _) = events.pop_front().unwrap().downcast::<(i32, Rc<()>)>().unwrap();
+///         popped.push(j);
+///     }
+///     events.push_back_value((i, counter.clone())).unwrap();
+/// }
+/// assert_eq!(popped, [0, 1, 2, 3, 4, 5, 6]);
+/// assert_eq!(events.len(), 3);
+/// assert_eq!(Rc::strong_count(&counter), 4);
+///
+/// events.push_front_value((-1, counter.clone())).unwrap_err();
+/// let back = events.pop_back().unwrap();
+/// assert_eq!(back.downcast_ref::<(i32, Rc<()>)>().unwrap().0, 9);
+/// drop(back);
+///
+/// drop(events);
+/// assert_eq!(Rc::strong_count(&counter), 1);
+/// ```
+pub struct StackAnyDeque<const CAP: usize, const N: usize> {
+    items: [StackAny<N>; CAP],
+    head: usize,
+    len: usize,
+}
+
+impl<const CAP: usize, const N: usize> StackAnyDeque<CAP, N> {
+    /// Creates an empty deque.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// const EVENTS: stack_any::StackAnyDeque<4, 8> = stack_any::StackAnyDeque::new();
+    /// assert!(EVENTS.is_empty());
+    /// ```
+    pub const fn new() -> Self {
+        Self {
+            items: [const { StackAny::empty() }; CAP],
+            head: 0,
+            len: 0,
+        }
+    }
+
+    /// Returns the number of stacks the deque can hold, which is `CAP`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let events = stack_any::StackAnyDeque::<4, 8>::new();
+    /// assert_eq!(events.capacity(), 4);
+    /// ```
+    pub const fn capacity(&self) -> usize {
+        CAP
+    }
+
+    /// Returns the number of stacks.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut events = stack_any::StackAnyDeque::<4, 8>::new();
+    /// events.push_back_value(1u8).unwrap();
+    /// events.push_front_value(2u8).unwrap();
+    /// assert_eq!(events.len(), 2);
+    /// ```
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns true if the deque contains no stacks.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let events = stack_any::StackAnyDeque::<4, 8>::new();
+    /// assert!(events.is_empty());
+    /// ```
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns true if the deque holds `CAP` stacks.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut events = stack_any::StackAnyDeque::<1, 8>::new();
+    /// events.push_back_value(1u8).unwrap();
+    /// assert!(events.is_full());
+    /// ```
+    pub fn is_full(&self) -> bool {
+        self.len == CAP
+    }
+
+    /// Returns the index in `items` of the stack at `index` from the front.
+    fn slot(&self, index: usize) -> usize {
+        (self.head + index) % CAP
+    }
+
+    /// Appends `stack` to the back of the deque.
+    /// Returns `stack` back if the deque is full.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut events = stack_any::StackAnyDeque::<1, 8>::new();
+    /// events.push_back(stack_any::StackAny::new(1u8)).unwrap();
+    ///
+    /// let rejected = events.push_back(stack_any::StackAny::new(2u8)).unwrap_err();
+    /// assert_eq!(rejected.downcast_ref::<u8>(), Some(&2));
+    /// ```
+    pub fn push_back(&mut self, stack: StackAny<N>) -> Result<(), StackAny<N>> {
+        if self.is_full() {
+            return Err(stack);
+        }
+
+        let slot = self.slot(self.len);
+        self.items[slot] = stack;
+        self.len += 1;
+        Ok(())
+    }
+
+    /// Prepends `stack` to the front of the deque.
+    /// Returns `stack` back if the deque is full.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut events = stack_any::StackAnyDeque::<2, 8>::new();
+    /// events.push_front(stack_any::StackAny::new(1u8)).unwrap();
+    /// events.push_front(stack_any::StackAny::new(2u8)).unwrap();
+    /// assert_eq!(events.front().unwrap().downcast_ref::<u8>(), Some(&2));
+    ///
+    /// assert!(events.push_front(stack_any::StackAny::new(3u8)).is_err());
+    /// ```
+    pub fn push_front(&mut self, stack: StackAny<N>) -> Result<(), StackAny<N>> {
+        if self.is_full() {
+            return Err(stack);
+        }
+
+        self.head = self.slot(CAP - 1);
+        self.items[self.head] = stack;
+        self.len += 1;
+        Ok(())
+    }
+
+    /// Places `value` into a stack and then appends it to the back of the deque.
+    /// Returns `value` back if the deque is full.
+    ///
+    /// Whether `T` fits is checked at compile time, as in [`StackAny::new`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut events = stack_any::StackAnyDeque::<1, 8>::new();
+    /// events.push_back_value(1u8).unwrap();
+    /// assert_eq!(events.push_back_value(2u8), Err(2));
+    /// ```
+    ///
+    /// ```compile_fail
+    /// let mut events = stack_any::StackAnyDeque::<1, 8>::new();
+    /// events.push_back_value([0u8; 16]).unwrap();
+    /// ```
+    pub fn push_back_value<T>(&mut self, value: T) -> Result<(), T>
+    where
+        T: core::any::Any,
+    {
+        if self.is_full() {
+            return Err(value);
+        }
+
+        match self.push_back(StackAny::new(value)) {
+            Ok(()) => Ok(()),
+            Err(_) => unreachable!(),
+        }
+    }
+
+    /// Places `value` into a stack and then prepends it to the front of the deque.
+    /// Returns `value` back if the deque is full.
+    ///
+    /// Whether `T` fits is checked at compile time, as in [`StackAny::new`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut events = stack_any::StackAnyDeque::<1, 8>::new();
+    /// events.push_front_value(1u8).unwrap();
+    /// assert_eq!(events.push_front_value(2u8), Err(2));
+    /// ```
+    pub fn push_front_value<T>(&mut self, value: T) -> Result<(), T>
+    where
+        T: core::any::Any,
+    {
+        if self.is_full() {
+            return Err(value);
+        }
+
+        match self.push_front(StackAny::new(value)) {
+            Ok(()) => Ok(()),
+            Err(_) => unreachable!(),
+        }
+    }
+
+    /// Appends `stack` to the back of the deque, removing the front stack and returning it if the deque is full.
+    ///
+    /// This keeps the latest `CAP` stacks, as a buffer of telemetry.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut samples = stack_any::StackAnyDeque::<2, 8>::new();
+    /// assert!(samples.force_push_back(stack_any::StackAny::new(1u16)).is_none());
+    /// assert!(samples.force_push_back(stack_any::StackAny::new(2u16)).is_none());
+    ///
+    /// let oldest = samples.force_push_back(stack_any::StackAny::new(3u16)).unwrap();
+    /// assert_eq!(oldest.downcast_ref::<u16>(), Some(&1));
+    ///
+    /// let samples: Vec<u16> = samples.iter().map(|stack| *stack.downcast_ref::<u16>().unwrap()).collect();
+    /// assert_eq!(samples, [2, 3]);
+    /// ```
+    ///
+    /// ```
+    /// let mut samples = stack_any::StackAnyDeque::<0, 8>::new();
+    /// let rejected = samples.force_push_back(stack_any::StackAny::new(1u16)).unwrap();
+    /// assert_eq!(rejected.downcast_ref::<u16>(), Some(&1));
+    /// ```
+    pub fn force_push_back(&mut self, stack: StackAny<N>) -> Option<StackAny<N>> {
+        if CAP == 0 {
+            return Some(stack);
+        }
+
+        let Err(stack) = self.push_back(stack) else {
+            return None;
+        };
+
+        // The back of a full deque is the slot of the front stack.
+        let oldest = core::mem::replace(&mut self.items[self.head], stack);
+        self.head = self.slot(1);
+        Some(oldest)
+    }
+
+    /// Removes the front stack and returns it.
+    /// Returns None if the deque is empty.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut events = stack_any::StackAnyDeque::<4, 8>::new();
+    /// events.push_back_value(1u8).unwrap();
+    /// events.push_back_value(2u8).unwrap();
+    ///
+    /// assert_eq!(events.pop_front().unwrap().downcast_ref::<u8>(), Some(&1));
+    /// assert_eq!(events.pop_front().unwrap().downcast_ref::<u8>(), Some(&2));
+    /// assert!(events.pop_front().is_none());
+    /// ```
+    pub fn pop_front(&mut self) -> Option<StackAny<N>> {
+        if self.is_empty() {
+            return None;
+        }
+
+        let head = self.head;
+        self.head = self.slot(1);
+        self.len -= 1;
+        Some(core::mem::take(&mut self.items[head]))
+    }
+
+    /// Removes the back stack and returns it.
+    /// Returns None if the deque is empty.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut events = stack_any::StackAnyDeque::<4, 8>::new();
+    /// events.push_back_value(1u8).unwrap();
+    /// events.push_back_value(2u8).unwrap();
+    ///
+    /// assert_eq!(events.pop_back().unwrap().downcast_ref::<u8>(), Some(&2));
+    /// assert_eq!(events.pop_back().unwrap().downcast_ref::<u8>(), Some(&1));
+    /// assert!(events.pop_back().is_none());
+    /// ```
+    pub fn pop_back(&mut self) -> Option<StackAny<N>> {
+        if self.is_empty() {
+            return None;
+        }
+
+        self.len -= 1;
+        let slot = self.slot(self.len);
+        Some(core::mem::take(&mut self.items[slot]))
+    }
+
+    /// Returns reference to the stack at `index` from the front.
+    /// Returns None if `index` is out of bounds.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut events = stack_any::StackAnyDeque::<4, 8>::new();
+    /// events.push_back_value(1u8).unwrap();
+    /// events.push_front_value(2u8).unwrap();
+    ///
+    /// assert_eq!(events.get(0).unwrap().downcast_ref::<u8>(), Some(&2));
+    /// assert_eq!(events.get(1).unwrap().downcast_ref::<u8>(), Some(&1));
+    /// assert!(events.get(2).is_none());
+    /// ```
+    pub fn get(&self, index: usize) -> Option<&StackAny<N>> {
+        if index >= self.len {
+            return None;
+        }
+
+        Some(&self.items[self.slot(index)])
+    }
+
+    /// Returns mutable reference to the stack at `index` from the front.
+    /// Returns None if `index` is out of bounds.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut events = stack_any::StackAnyDeque::<4, 8>::new();
+    /// events.push_back_value(1u8).unwrap();
+    ///
+    /// *events.get_mut(0).unwrap().downcast_mut::<u8>().unwrap() += 1;
+    /// assert_eq!(events.get(0).unwrap().downcast_ref::<u8>(), Some(&2));
+    /// ```
+    pub fn get_mut(&mut self, index: usize) -> Option<&mut StackAny<N>> {
+        if index >= self.len {
+            return None;
+        }
+
+        let slot = self.slot(index);
+        Some(&mut self.items[slot])
+    }
+
+    /// Returns reference to the front stack.
+    /// Returns None if the deque is empty.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut events = stack_any::StackAnyDeque::<4, 8>::new();
+    /// assert!(events.front().is_none());
+    ///
+    /// events.push_back_value(1u8).unwrap();
+    /// events.push_back_value(2u8).unwrap();
+    /// assert_eq!(events.front().unwrap().downcast_ref::<u8>(), Some(&1));
+    /// ```
+    pub fn front(&self) -> Option<&StackAny<N>> {
+        self.get(0)
+    }
+
+    /// Returns mutable reference to the front stack.
+    /// Returns None if the deque is empty.
+    pub fn front_mut(&mut self) -> Option<&mut StackAny<N>> {
+        self.get_mut(0)
+    }
+
+    /// Returns reference to the back stack.
+    /// Returns None if the deque is empty.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut events = stack_any::StackAnyDeque::<4, 8>::new();
+    /// assert!(events.back().is_none());
+    ///
+    /// events.push_back_value(1u8).unwrap();
+    /// events.push_back_value(2u8).unwrap();
+    /// assert_eq!(events.back().unwrap().downcast_ref::<u8>(), Some(&2));
+    /// ```
+    pub fn back(&self) -> Option<&StackAny<N>> {
+        self.get(self.len.checked_sub(1)?)
+    }
+
+    /// Returns mutable reference to the back stack.
+    /// Returns None if the deque is empty.
+    pub fn back_mut(&mut self) -> Option<&mut StackAny<N>> {
+        self.get_mut(self.len.checked_sub(1)?)
+    }
+
+    /// Returns an iterator over the stacks from the front to the back.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut events = stack_any::StackAnyDeque::<4, 8>::new();
+    /// events.push_back_value(1u8).unwrap();
+    /// events.push_back_value(2u8).unwrap();
+    /// events.push_front_value(3u8).unwrap();
+    ///
+    /// let events: Vec<u8> = events.iter().map(|stack| *stack.downcast_ref::<u8>().unwrap()).collect();
+    /// assert_eq!(events, [3, 1, 2]);
+    /// ```
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = &StackAny<N>> + ExactSizeIterator + '_ {
+        (0..self.len).map(|index| &self.items[self.slot(index)])
+    }
+
+    /// Drops every stack, leaving the deque empty.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::rc::Rc;
+    ///
+    /// let counter = Rc::new(());
+    ///
+    /// let mut events = stack_any::StackAnyDeque::<4, 8>::new();
+    /// events.push_back_value(counter.clone()).unwrap();
+    ///
+    /// events.clear();
+    /// assert!(events.is_empty());
+    /// assert_eq!(Rc::strong_count(&counter), 1);
+    /// ```
+    pub fn clear(&mut self) {
+        let len = core::mem::replace(&mut self.len, 0);
+        let head = core::mem::replace(&mut self.head, 0);
+
+        // Stacks left behind by a panicking destructor are dropped with the deque.
+        for index in 0..len {
+            self.items[(head + index) % CAP].clear();
+        }
+    }
+}
+
+impl<const CAP: usize, const N: usize> Default for StackAnyDeque<CAP, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Formats the stacks as a list from the front to the back, each of them as in `Debug` of
+/// [`StackAnyVec`](crate::StackAnyVec).
+///
+/// # Examples
+///
+/// ```
+/// let mut events = stack_any::StackAnyDeque::<4, 8>::new();
+/// events.push_back_value(1u8).unwrap();
+///
+/// #[cfg(feature = "type-name")]
+/// assert_eq!(format!("{:?}", events), "[u8: <opaque 1 bytes>]");
+/// #[cfg(not(feature = "type-name"))]
+/// assert_eq!(format!("{:?}", events), "[<opaque 1 bytes>]");
+/// ```
+impl<const CAP: usize, const N: usize> core::fmt::Debug for StackAnyDeque<CAP, N> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_list()
+            .entries(self.iter().map(DebugElement))
+            .finish()
+    }
+}
@@ -0,0 +1,623 @@
+use std::collections::{hash_map, HashMap};
+
+use crate::{CapacityError, DebugElement, StackAny};
+
+/// A map holding at most one value of each type, each placed into a stack of `N` size.
+///
+/// Values are keyed by their `TypeId`, like the anymap pattern, but without boxing each of them.
+///
+/// # Examples
+///
+/// ```
+/// use std::rc::Rc;
+///
+/// struct RequestId(u64);
+/// struct User(String);
+///
+/// let counter = Rc::new(());
+///
+/// let mut extensions = stack_any::StackAnyMap::<24>::new();
+/// extensions.insert(RequestId(5)).unwrap();
+/// extensions.insert(User(String::from("alice"))).unwrap();
+/// extensions.insert(counter.clone()).unwrap();
+/// extensions.insert(5i32).unwrap();
+/// assert_eq!(extensions.len(), 4);
+///
+/// let previous = extensions.insert(User(String::from("bob"))).unwrap();
+/// assert_eq!(previous.unwrap().0, "alice");
+/// assert_eq!(extensions.get::<User>().unwrap().0, "bob");
+///
+/// assert_eq!(extensions.remove::<i32>(), Some(5));
+/// assert!(!extensions.contains::<i32>());
+///
+/// extensions.get_mut::<RequestId>().unwrap().0 += 1;
+/// assert_eq!(extensions.get::<RequestId>().unwrap().0, 6);
+/// assert_eq!(extensions.len(), 3);
+///
+/// assert_eq!(Rc::strong_count(&counter), 2);
+/// drop(extensions);
+/// assert_eq!(Rc::strong_count(&counter), 1);
+/// ```
+pub struct StackAnyMap<const N: usize> {
+    map: HashMap<core::any::TypeId, StackAny<N>>,
+}
+
+impl<const N: usize> StackAnyMap<N> {
+    /// Creates an empty map.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let map = stack_any::StackAnyMap::<8>::new();
+    /// assert!(map.is_empty());
+    /// ```
+    pub fn new() -> Self {
+        Self {
+            map: HashMap::new(),
+        }
+    }
+
+    /// Places `value` into a stack and then inserts it into the map, replacing the value of the same type.
+    /// Returns the replaced value if any.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`CapacityError`] carrying `value` back, leaving the map untouched,
+    /// if `T` size is larger than N or `T` alignment is larger than [`StackAny::ALIGN`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut map = stack_any::StackAnyMap::<8>::new();
+    /// assert_eq!(map.insert(5i32).unwrap(), None);
+    /// assert_eq!(map.insert(7i32).unwrap(), Some(5));
+    ///
+    /// let err = map.insert([1u8; 16]).unwrap_err();
+    /// assert_eq!(err.into_value(), [1; 16]);
+    /// assert_eq!(map.len(), 1);
+    /// ```
+    pub fn insert<T>(&mut self, value: T) -> Result<Option<T>, CapacityError<T>>
+    where
+        T: core::any::Any,
+    {
+        let stack = StackAny::try_new_checked(value)?;
+
+        let previous = self.map.insert(core::any::TypeId::of::<T>(), stack);
+        Ok(previous.map(|stack| match stack.downcast() {
+            Ok(value) => value,
+            Err(_) => unreachable!(),
+        }))
+    }
+
+    /// Inserts `stack` into the map, replacing the value of the same type.
+    /// Returns the replaced value in a stack if any.
+    ///
+    /// It keeps the capabilities captured by [`StackAnyBuilder`](crate::StackAnyBuilder),
+    /// unlike [`StackAnyMap::insert`].
+    ///
+    /// # Errors
+    ///
+    /// Returns `stack` back if it is empty, since no type keys it.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use stack_any::StackAny;
+    ///
+    /// let mut map = stack_any::StackAnyMap::<8>::new();
+    /// assert!(map.insert_stack(StackAny::builder(5i32).with_clone().build()).unwrap().is_none());
+    ///
+    /// let previous = map.insert_stack(StackAny::new(7i32)).unwrap();
+    /// assert_eq!(previous.unwrap().downcast_ref::<i32>(), Some(&5));
+    /// assert_eq!(map.get::<i32>(), Some(&7));
+    ///
+    /// assert!(map.insert_stack(StackAny::empty()).is_err());
+    /// assert_eq!(map.len(), 1);
+    /// ```
+    pub fn insert_stack(&mut self, stack: StackAny<N>) -> Result<Option<StackAny<N>>, StackAny<N>> {
+        if stack.is_empty() {
+            return Err(stack);
+        }
+
+        Ok(self.map.insert(stack.contained_type_id(), stack))
+    }
+
+    /// Returns reference to the value of `T`.
+    /// Returns None if the map contains no value of `T`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut map = stack_any::StackAnyMap::<8>::new();
+    /// map.insert(5i32).unwrap();
+    ///
+    /// assert_eq!(map.get::<i32>(), Some(&5));
+    /// assert_eq!(map.get::<u32>(), None);
+    /// ```
+    pub fn get<T>(&self) -> Option<&T>
+    where
+        T: core::any::Any,
+    {
+        let stack = self.map.get(&core::any::TypeId::of::<T>())?;
+        Some(unsafe { stack.downcast_ref_unchecked() })
+    }
+
+    /// Returns mutable reference to the value of `T`.
+    /// Returns None if the map contains no value of `T`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut map = stack_any::StackAnyMap::<8>::new();
+    /// map.insert(5i32).unwrap();
+    ///
+    /// *map.get_mut::<i32>().unwrap() += 1;
+    /// assert_eq!(map.get::<i32>(), Some(&6));
+    /// assert_eq!(map.get_mut::<u32>(), None);
+    /// ```
+    pub fn get_mut<T>(&mut self) -> Option<&mut T>
+    where
+        T: core::any::Any,
+    {
+        let stack = self.map.get_mut(&core::any::TypeId::of::<T>())?;
+        Some(unsafe { stack.downcast_mut_unchecked() })
+    }
+
+    /// Removes the value of `T` from the map and returns it.
+    /// Returns None if the map contains no value of `T`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut map = stack_any::StackAnyMap::<8>::new();
+    /// map.insert(5i32).unwrap();
+    ///
+    /// assert_eq!(map.remove::<i32>(), Some(5));
+    /// assert_eq!(map.remove::<i32>(), None);
+    /// ```
+    pub fn remove<T>(&mut self) -> Option<T>
+    where
+        T: core::any::Any,
+    {
+        let stack = self.map.remove(&core::any::TypeId::of::<T>())?;
+        match stack.downcast() {
+            Ok(value) => Some(value),
+            Err(_) => unreachable!(),
+        }
+    }
+
+    /// Returns the entry of `T` for in-place manipulation, like `HashMap::entry`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// #[derive(Default)]
+    /// struct Metrics {
+    ///     requests: u32,
+    /// }
+    ///
+    /// let mut map = stack_any::StackAnyMap::<8>::new();
+    /// for _ in 0..3 {
+    ///     map.entry::<Metrics>().or_default().requests += 1;
+    /// }
+    /// assert_eq!(map.get::<Metrics>().unwrap().requests, 3);
+    /// ```
+    pub fn entry<T>(&mut self) -> Entry<'_, T, N>
+    where
+        T: core::any::Any,
+    {
+        match self.map.entry(core::any::TypeId::of::<T>()) {
+            hash_map::Entry::Occupied(entry) => Entry::Occupied(OccupiedEntry {
+                entry,
+                _marker: core::marker::PhantomData,
+            }),
+            hash_map::Entry::Vacant(entry) => Entry::Vacant(VacantEntry {
+                entry,
+                _marker: core::marker::PhantomData,
+            }),
+        }
+    }
+
+    /// Returns true if the map contains a value of `T`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut map = stack_any::StackAnyMap::<8>::new();
+    /// map.insert(5i32).unwrap();
+    ///
+    /// assert!(map.contains::<i32>());
+    /// assert!(!map.contains::<u32>());
+    /// ```
+    pub fn contains<T>(&self) -> bool
+    where
+        T: core::any::Any,
+    {
+        self.map.contains_key(&core::any::TypeId::of::<T>())
+    }
+
+    /// Returns the number of values in the map.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut map = stack_any::StackAnyMap::<8>::new();
+    /// map.insert(5i32).unwrap();
+    /// map.insert('x').unwrap();
+    /// map.insert(7i32).unwrap();
+    /// assert_eq!(map.len(), 2);
+    /// ```
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    /// Returns true if the map contains no values.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut map = stack_any::StackAnyMap::<8>::new();
+    /// assert!(map.is_empty());
+    ///
+    /// map.insert(5i32).unwrap();
+    /// assert!(!map.is_empty());
+    /// ```
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    /// Returns an iterator over the stacks containing the values, in arbitrary order.
+    #[cfg(feature = "serde")]
+    pub(crate) fn stacks(&self) -> impl Iterator<Item = &StackAny<N>> {
+        self.map.values()
+    }
+
+    /// Drops every value, leaving the map empty.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::rc::Rc;
+    ///
+    /// let counter = Rc::new(());
+    ///
+    /// let mut map = stack_any::StackAnyMap::<8>::new();
+    /// map.insert(counter.clone()).unwrap();
+    ///
+    /// map.clear();
+    /// assert!(map.is_empty());
+    /// assert_eq!(Rc::strong_count(&counter), 1);
+    /// ```
+    pub fn clear(&mut self) {
+        self.map.clear();
+    }
+}
+
+impl<const N: usize> Default for StackAnyMap<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Formats the values as a set in arbitrary order, each of them as in `Debug` of [`StackAnyVec`](crate::StackAnyVec).
+///
+/// # Examples
+///
+/// ```
+/// let mut map = stack_any::StackAnyMap::<8>::new();
+/// assert_eq!(format!("{:?}", map), "{}");
+///
+/// map.insert(5i32).unwrap();
+/// #[cfg(feature = "type-name")]
+/// # assert_eq!(format!("{:?}", map), "{i32: <opaque 4 bytes>}");
+/// #[cfg(not(feature = "type-name"))]
+/// assert_eq!(format!("{:?}", map), "{<opaque 4 bytes>}");
+/// ```
+impl<const N: usize> core::fmt::Debug for StackAnyMap<N> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_set()
+            .entries(self.map.values().map(DebugElement))
+            .finish()
+    }
+}
+
+/// A view into the value of `T` in [`StackAnyMap`], returned by [`StackAnyMap::entry`].
+///
+/// Inserting a value which does not fit fails to compile, as in [`StackAny::new`],
+/// while the `try` variants reject it at runtime by [`CapacityError`] instead of panicking.
+pub enum Entry<'a, T, const N: usize> {
+    /// The map contains a value of `T`.
+    Occupied(OccupiedEntry<'a, T, N>),
+    /// The map contains no value of `T`.
+    Vacant(VacantEntry<'a, T, N>),
+}
+
+impl<'a, T, const N: usize> Entry<'a, T, N>
+where
+    T: core::any::Any,
+{
+    /// Returns mutable reference to the value, inserting `value` first if the entry is vacant.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut map = stack_any::StackAnyMap::<8>::new();
+    ///
+    /// *map.entry().or_insert(5i32) += 1;
+    /// *map.entry().or_insert(5i32) += 1;
+    /// assert_eq!(map.get::<i32>(), Some(&7));
+    /// ```
+    ///
+    /// ```compile_fail
+    /// let mut map = stack_any::StackAnyMap::<8>::new();
+    /// map.entry().or_insert([0u8; 16]);
+    /// ```
+    pub fn or_insert(self, value: T) -> &'a mut T {
+        match self {
+            Self::Occupied(entry) => entry.into_mut(),
+            Self::Vacant(entry) => entry.insert(value),
+        }
+    }
+
+    /// Returns mutable reference to the value, inserting the result of `f` first if the entry is vacant.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut map = stack_any::StackAnyMap::<24>::new();
+    ///
+    /// map.entry().or_insert_with(|| String::from("x")).push('y');
+    /// map.entry().or_insert_with(|| -> String { unreachable!() }).push('z');
+    /// assert_eq!(map.get::<String>().unwrap(), "xyz");
+    /// ```
+    pub fn or_insert_with(self, f: impl FnOnce() -> T) -> &'a mut T {
+        match self {
+            Self::Occupied(entry) => entry.into_mut(),
+            Self::Vacant(entry) => entry.insert(f()),
+        }
+    }
+
+    /// Returns mutable reference to the value, inserting the default value first if the entry is vacant.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut map = stack_any::StackAnyMap::<24>::new();
+    ///
+    /// map.entry::<Vec<i32>>().or_default().push(5);
+    /// map.entry::<Vec<i32>>().or_default().push(7);
+    /// assert_eq!(map.get::<Vec<i32>>(), Some(&vec![5, 7]));
+    /// ```
+    pub fn or_default(self) -> &'a mut T
+    where
+        T: Default,
+    {
+        self.or_insert_with(T::default)
+    }
+
+    /// Returns mutable reference to the value, inserting `value` first if the entry is vacant.
+    /// Returns [`CapacityError`] carrying `value` back if the entry is vacant and `value` does not fit.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut map = stack_any::StackAnyMap::<8>::new();
+    ///
+    /// assert_eq!(map.entry().or_try_insert(5i32).ok(), Some(&mut 5));
+    ///
+    /// let err = map.entry().or_try_insert([0u8; 16]).unwrap_err();
+    /// assert_eq!(err.into_value(), [0; 16]);
+    /// assert!(!map.contains::<[u8; 16]>());
+    /// ```
+    pub fn or_try_insert(self, value: T) -> Result<&'a mut T, CapacityError<T>> {
+        match self {
+            Self::Occupied(entry) => Ok(entry.into_mut()),
+            Self::Vacant(entry) => entry.try_insert(value),
+        }
+    }
+
+    /// Returns mutable reference to the value, inserting the result of `f` first if the entry is vacant.
+    /// Returns [`CapacityError`] carrying the result back if the entry is vacant and it does not fit.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut map = stack_any::StackAnyMap::<8>::new();
+    ///
+    /// let err = map.entry().or_try_insert_with(|| [0u8; 16]).unwrap_err();
+    /// assert_eq!(err.required_size(), 16);
+    /// ```
+    pub fn or_try_insert_with(self, f: impl FnOnce() -> T) -> Result<&'a mut T, CapacityError<T>> {
+        match self {
+            Self::Occupied(entry) => Ok(entry.into_mut()),
+            Self::Vacant(entry) => entry.try_insert(f()),
+        }
+    }
+
+    /// Calls `f` with the value if the entry is occupied, and then returns the entry.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut map = stack_any::StackAnyMap::<8>::new();
+    ///
+    /// map.entry::<i32>().and_modify(|x| *x += 1).or_insert(5);
+    /// map.entry::<i32>().and_modify(|x| *x += 1).or_insert(5);
+    /// assert_eq!(map.get::<i32>(), Some(&6));
+    /// ```
+    pub fn and_modify(self, f: impl FnOnce(&mut T)) -> Self {
+        match self {
+            Self::Occupied(mut entry) => {
+                f(entry.get_mut());
+                Self::Occupied(entry)
+            }
+            Self::Vacant(entry) => Self::Vacant(entry),
+        }
+    }
+}
+
+/// A view into the value of `T` contained by [`StackAnyMap`], which is a part of [`Entry`].
+pub struct OccupiedEntry<'a, T, const N: usize> {
+    entry: hash_map::OccupiedEntry<'a, core::any::TypeId, StackAny<N>>,
+    _marker: core::marker::PhantomData<&'a mut T>,
+}
+
+impl<'a, T, const N: usize> OccupiedEntry<'a, T, N>
+where
+    T: core::any::Any,
+{
+    /// Returns reference to the value.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use stack_any::Entry;
+    ///
+    /// let mut map = stack_any::StackAnyMap::<8>::new();
+    /// map.insert(5i32).unwrap();
+    ///
+    /// if let Entry::Occupied(entry) = map.entry::<i32>() {
+    ///     assert_eq!(entry.get(), &5);
+    /// }
+    /// ```
+    pub fn get(&self) -> &T {
+        unsafe { self.entry.get().downcast_ref_unchecked() }
+    }
+
+    /// Returns mutable reference to the value.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use stack_any::Entry;
+    ///
+    /// let mut map = stack_any::StackAnyMap::<8>::new();
+    /// map.insert(5i32).unwrap();
+    ///
+    /// if let Entry::Occupied(mut entry) = map.entry::<i32>() {
+    ///     *entry.get_mut() += 1;
+    /// }
+    /// assert_eq!(map.get::<i32>(), Some(&6));
+    /// ```
+    pub fn get_mut(&mut self) -> &mut T {
+        unsafe { self.entry.get_mut().downcast_mut_unchecked() }
+    }
+
+    /// Returns mutable reference to the value, which lives as long as the map is borrowed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use stack_any::Entry;
+    ///
+    /// let mut map = stack_any::StackAnyMap::<8>::new();
+    /// map.insert(5i32).unwrap();
+    ///
+    /// let value = match map.entry::<i32>() {
+    ///     Entry::Occupied(entry) => entry.into_mut(),
+    ///     Entry::Vacant(_) => unreachable!(),
+    /// };
+    /// *value += 1;
+    /// assert_eq!(map.get::<i32>(), Some(&6));
+    /// ```
+    pub fn into_mut(self) -> &'a mut T {
+        unsafe { self.entry.into_mut().downcast_mut_unchecked() }
+    }
+
+    /// Replaces the value by `value`, and returns the replaced one.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use stack_any::Entry;
+    ///
+    /// let mut map = stack_any::StackAnyMap::<8>::new();
+    /// map.insert(5i32).unwrap();
+    ///
+    /// if let Entry::Occupied(mut entry) = map.entry::<i32>() {
+    ///     assert_eq!(entry.insert(7), 5);
+    /// }
+    /// assert_eq!(map.get::<i32>(), Some(&7));
+    /// ```
+    pub fn insert(&mut self, value: T) -> T {
+        core::mem::replace(self.get_mut(), value)
+    }
+
+    /// Removes the value from the map and returns it.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use stack_any::Entry;
+    ///
+    /// let mut map = stack_any::StackAnyMap::<8>::new();
+    /// map.insert(5i32).unwrap();
+    ///
+    /// if let Entry::Occupied(entry) = map.entry::<i32>() {
+    ///     assert_eq!(entry.remove(), 5);
+    /// }
+    /// assert!(map.is_empty());
+    /// ```
+    pub fn remove(self) -> T {
+        match self.entry.remove().downcast() {
+            Ok(value) => value,
+            Err(_) => unreachable!(),
+        }
+    }
+}
+
+/// A view into the missing value of `T` in [`StackAnyMap`], which is a part of [`Entry`].
+pub struct VacantEntry<'a, T, const N: usize> {
+    entry: hash_map::VacantEntry<'a, core::any::TypeId, StackAny<N>>,
+    _marker: core::marker::PhantomData<&'a mut T>,
+}
+
+impl<'a, T, const N: usize> VacantEntry<'a, T, N>
+where
+    T: core::any::Any,
+{
+    /// Inserts `value` into the map, and returns mutable reference to it.
+    ///
+    /// Whether `T` fits is checked at compile time, as in [`StackAny::new`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use stack_any::Entry;
+    ///
+    /// let mut map = stack_any::StackAnyMap::<8>::new();
+    ///
+    /// if let Entry::Vacant(entry) = map.entry::<i32>() {
+    ///     *entry.insert(5) += 1;
+    /// }
+    /// assert_eq!(map.get::<i32>(), Some(&6));
+    /// ```
+    pub fn insert(self, value: T) -> &'a mut T {
+        unsafe {
+            self.entry
+                .insert(StackAny::new(value))
+                .downcast_mut_unchecked()
+        }
+    }
+
+    /// Inserts `value` into the map, and returns mutable reference to it.
+    /// Returns [`CapacityError`] carrying `value` back if `T` size is larger than N
+    /// or `T` alignment is larger than [`StackAny::ALIGN`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use stack_any::Entry;
+    ///
+    /// let mut map = stack_any::StackAnyMap::<8>::new();
+    ///
+    /// if let Entry::Vacant(entry) = map.entry::<[u8; 16]>() {
+    ///     assert!(entry.try_insert([0; 16]).is_err());
+    /// }
+    /// assert!(map.is_empty());
+    /// ```
+    pub fn try_insert(self, value: T) -> Result<&'a mut T, CapacityError<T>> {
+        let stack = StackAny::try_new_checked(value)?;
+        Ok(unsafe { self.entry.insert(stack).downcast_mut_unchecked() })
+    }
+}
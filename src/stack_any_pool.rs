@@ -0,0 +1,402 @@
+use crate::{DebugElement, StackAny};
+
+/// A handle of a value in a [`StackAnyPool`], made of the index of its slot and the generation of the slot.
+///
+/// Removing a value moves its slot to the next generation, so the key of the removed value
+/// never refers to a value inserted into the slot later.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub struct PoolKey {
+    index: usize,
+    generation: u32,
+}
+
+impl PoolKey {
+    /// Returns the index of the slot.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut pool = stack_any::StackAnyPool::<4, 8>::new();
+    /// let key = pool.insert(5i32).unwrap();
+    /// assert_eq!(key.index(), 0);
+    /// ```
+    pub fn index(&self) -> usize {
+        self.index
+    }
+}
+
+/// A pool of up to `CAP` slots, each placing a value into a stack of `N` size, with stable generational keys.
+///
+/// It is the slotmap pattern without allocation, so it works without `std` and `alloc`.
+/// A slot freed by [`StackAnyPool::remove`] is reused by a later insertion, and the keys of its
+/// previous values are rejected rather than referring to the new one.
+///
+/// # Examples
+///
+/// ```
+/// struct Position(f32, f32);
+/// struct Name(&'static str);
+///
+/// let mut entities = stack_any::StackAnyPool::<4, 16>::new();
+/// let player = entities.insert(Position(0.0, 1.0)).unwrap_or_else(|_| unreachable!());
+/// let label = entities.insert(Name("player")).unwrap_or_else(|_| unreachable!());
+///
+/// entities.get_mut::<Position>(player).unwrap().0 += 1.0;
+/// assert_eq!(entities.get::<Position>(player).unwrap().0, 1.0);
+/// assert_eq!(entities.get::<Name>(label).unwrap().0, "player");
+/// assert!(entities.get::<Name>(player).is_none());
+///
+/// assert!(entities.remove(player).is_some());
+/// assert!(entities.get::<Position>(player).is_none());
+/// assert_eq!(entities.len(), 1);
+/// ```
+pub struct StackAnyPool<const CAP: usize, const N: usize> {
+    slots: [StackAny<N>; CAP],
+    /// The generation of each slot, odd if the slot is occupied.
+    generations: [u32; CAP],
+    len: usize,
+}
+
+impl<const CAP: usize, const N: usize> StackAnyPool<CAP, N> {
+    /// Creates an empty pool.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// const ENTITIES: stack_any::StackAnyPool<4, 8> = stack_any::StackAnyPool::new();
+    /// assert!(ENTITIES.is_empty());
+    /// ```
+    pub const fn new() -> Self {
+        Self {
+            slots: [const { StackAny::empty() }; CAP],
+            generations: [0; CAP],
+            len: 0,
+        }
+    }
+
+    /// Returns the number of slots.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let pool = stack_any::StackAnyPool::<4, 8>::new();
+    /// assert_eq!(pool.capacity(), 4);
+    /// ```
+    pub const fn capacity(&self) -> usize {
+        CAP
+    }
+
+    /// Returns the number of values.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut pool = stack_any::StackAnyPool::<4, 8>::new();
+    /// let key = pool.insert(5i32).unwrap();
+    /// pool.insert(7i32).unwrap();
+    /// pool.remove(key);
+    /// assert_eq!(pool.len(), 1);
+    /// ```
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns true if the pool contains no values.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let pool = stack_any::StackAnyPool::<4, 8>::new();
+    /// assert!(pool.is_empty());
+    /// ```
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns true if every slot is occupied.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut pool = stack_any::StackAnyPool::<1, 8>::new();
+    /// pool.insert(5i32).unwrap();
+    /// assert!(pool.is_full());
+    /// ```
+    pub fn is_full(&self) -> bool {
+        self.len == CAP
+    }
+
+    /// Returns the index of the slot of `key` if it is occupied by the value of `key`.
+    fn position(&self, key: PoolKey) -> Option<usize> {
+        let generation = *self.generations.get(key.index)?;
+        (generation == key.generation && generation & 1 == 1).then_some(key.index)
+    }
+
+    /// Places `value` into a vacant slot and returns its key.
+    /// Returns `value` back if the pool is full.
+    ///
+    /// Whether `T` fits is checked at compile time, as in [`StackAny::new`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut pool = stack_any::StackAnyPool::<1, 8>::new();
+    /// let key = pool.insert(5i32).unwrap();
+    /// assert_eq!(pool.get::<i32>(key), Some(&5));
+    ///
+    /// assert_eq!(pool.insert(7i32), Err(7));
+    /// ```
+    ///
+    /// ```compile_fail
+    /// let mut pool = stack_any::StackAnyPool::<1, 8>::new();
+    /// pool.insert([0u8; 16]).unwrap();
+    /// ```
+    pub fn insert<T>(&mut self, value: T) -> Result<PoolKey, T>
+    where
+        T: core::any::Any,
+    {
+        const {
+            assert!(
+                StackAny::<N>::fits::<T>(),
+                "value does not fit in the stack"
+            )
+        };
+
+        let Some(index) = self.generations.iter().position(|g| g & 1 == 0) else {
+            return Err(value);
+        };
+
+        self.slots[index] = StackAny::new(value);
+        self.generations[index] = self.generations[index].wrapping_add(1);
+        self.len += 1;
+
+        Ok(PoolKey {
+            index,
+            generation: self.generations[index],
+        })
+    }
+
+    /// Returns reference to the value of `key` as a concrete type.
+    /// Returns None if the value of `key` was removed, or if `T` is not equal to its type.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut pool = stack_any::StackAnyPool::<4, 8>::new();
+    /// let key = pool.insert(5i32).unwrap();
+    ///
+    /// assert_eq!(pool.get::<i32>(key), Some(&5));
+    /// assert_eq!(pool.get::<u32>(key), None);
+    /// ```
+    pub fn get<T>(&self, key: PoolKey) -> Option<&T>
+    where
+        T: core::any::Any,
+    {
+        self.slots[self.position(key)?].downcast_ref()
+    }
+
+    /// Returns mutable reference to the value of `key` as a concrete type.
+    /// Returns None if the value of `key` was removed, or if `T` is not equal to its type.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut pool = stack_any::StackAnyPool::<4, 8>::new();
+    /// let key = pool.insert(5i32).unwrap();
+    ///
+    /// *pool.get_mut::<i32>(key).unwrap() += 1;
+    /// assert_eq!(pool.get::<i32>(key), Some(&6));
+    /// assert_eq!(pool.get_mut::<u32>(key), None);
+    /// ```
+    pub fn get_mut<T>(&mut self, key: PoolKey) -> Option<&mut T>
+    where
+        T: core::any::Any,
+    {
+        let index = self.position(key)?;
+        self.slots[index].downcast_mut()
+    }
+
+    /// Returns true if the pool contains the value of `key`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut pool = stack_any::StackAnyPool::<4, 8>::new();
+    /// let key = pool.insert(5i32).unwrap();
+    /// assert!(pool.contains_key(key));
+    ///
+    /// pool.remove(key);
+    /// assert!(!pool.contains_key(key));
+    /// ```
+    pub fn contains_key(&self, key: PoolKey) -> bool {
+        self.position(key).is_some()
+    }
+
+    /// Removes the value of `key` from the pool and returns it, freeing its slot for reuse.
+    /// Returns None if the value of `key` was already removed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut pool = stack_any::StackAnyPool::<1, 8>::new();
+    /// let stale = pool.insert(5i32).unwrap();
+    ///
+    /// let five = pool.remove(stale).unwrap();
+    /// assert_eq!(five.downcast_ref::<i32>(), Some(&5));
+    /// assert!(pool.remove(stale).is_none());
+    ///
+    /// // The slot is reused by a value of another type, which the stale key does not refer to.
+    /// let key = pool.insert(7u8).unwrap();
+    /// assert_eq!(key.index(), stale.index());
+    /// assert_ne!(key, stale);
+    ///
+    /// assert_eq!(pool.get::<i32>(stale), None);
+    /// assert_eq!(pool.get::<u8>(stale), None);
+    /// assert_eq!(pool.get_mut::<u8>(stale), None);
+    /// assert!(!pool.contains_key(stale));
+    /// assert!(pool.remove(stale).is_none());
+    /// assert_eq!(pool.get::<u8>(key), Some(&7));
+    /// ```
+    pub fn remove(&mut self, key: PoolKey) -> Option<StackAny<N>> {
+        let index = self.position(key)?;
+        Some(self.vacate(index))
+    }
+
+    /// Moves the value out of the occupied slot at `index`, moving the slot to the next generation.
+    fn vacate(&mut self, index: usize) -> StackAny<N> {
+        self.generations[index] = self.generations[index].wrapping_add(1);
+        self.len -= 1;
+        core::mem::take(&mut self.slots[index])
+    }
+
+    /// Returns an iterator over the keys and the values of the occupied slots in the order of the slots.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut pool = stack_any::StackAnyPool::<4, 8>::new();
+    /// let five = pool.insert(5i32).unwrap();
+    /// let removed = pool.insert(6i32).unwrap();
+    /// let seven = pool.insert(7u8).unwrap();
+    /// pool.remove(removed);
+    ///
+    /// let keys: Vec<_> = pool.iter().map(|(key, _)| key).collect();
+    /// assert_eq!(keys, [five, seven]);
+    ///
+    /// let sum: i32 = pool.iter().filter_map(|(_, stack)| stack.downcast_ref::<i32>()).sum();
+    /// assert_eq!(sum, 5);
+    /// ```
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = (PoolKey, &StackAny<N>)> + '_ {
+        let occupied = self.generations.iter().enumerate();
+        occupied
+            .zip(&self.slots)
+            .filter(|((_, generation), _)| *generation & 1 == 1)
+            .map(|((index, &generation), stack)| (PoolKey { index, generation }, stack))
+    }
+
+    /// Returns an iterator over the keys and the mutable values of the occupied slots in the order of the slots.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut pool = stack_any::StackAnyPool::<4, 8>::new();
+    /// let five = pool.insert(5i32).unwrap();
+    /// pool.insert(7u8).unwrap();
+    ///
+    /// for (_, stack) in pool.iter_mut() {
+    ///     if let Some(value) = stack.downcast_mut::<i32>() {
+    ///         *value *= 2;
+    ///     }
+    /// }
+    /// assert_eq!(pool.get::<i32>(five), Some(&10));
+    /// ```
+    pub fn iter_mut(
+        &mut self,
+    ) -> impl DoubleEndedIterator<Item = (PoolKey, &mut StackAny<N>)> + '_ {
+        let occupied = self.generations.iter().enumerate();
+        occupied
+            .zip(&mut self.slots)
+            .filter(|((_, generation), _)| *generation & 1 == 1)
+            .map(|((index, &generation), stack)| (PoolKey { index, generation }, stack))
+    }
+
+    /// Retains only the values for which `f` returns true, freeing the slots of the others.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut pool = stack_any::StackAnyPool::<4, 8>::new();
+    /// let five = pool.insert(5i32).unwrap();
+    /// let seven = pool.insert(7u8).unwrap();
+    /// let nine = pool.insert(9i32).unwrap();
+    ///
+    /// pool.retain(|key, stack| key != seven && stack.downcast_ref::<i32>() != Some(&9));
+    /// assert_eq!(pool.len(), 1);
+    /// assert!(pool.contains_key(five));
+    /// assert!(!pool.contains_key(seven));
+    /// assert!(!pool.contains_key(nine));
+    /// ```
+    pub fn retain(&mut self, mut f: impl FnMut(PoolKey, &mut StackAny<N>) -> bool) {
+        for index in 0..CAP {
+            let generation = self.generations[index];
+            if generation & 1 == 0 {
+                continue;
+            }
+
+            let key = PoolKey { index, generation };
+            if !f(key, &mut self.slots[index]) {
+                drop(self.vacate(index));
+            }
+        }
+    }
+
+    /// Drops every value, freeing every slot and invalidating every key.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut pool = stack_any::StackAnyPool::<4, 8>::new();
+    /// let key = pool.insert(5i32).unwrap();
+    ///
+    /// pool.clear();
+    /// assert!(pool.is_empty());
+    /// assert!(!pool.contains_key(key));
+    /// ```
+    pub fn clear(&mut self) {
+        self.retain(|_, _| false);
+    }
+}
+
+impl<const CAP: usize, const N: usize> Default for StackAnyPool<CAP, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Formats the pool as a map from the keys to the values in the order of the slots,
+/// each value as in `Debug` of [`StackAnyVec`](crate::StackAnyVec).
+///
+/// # Examples
+///
+/// ```
+/// let mut pool = stack_any::StackAnyPool::<4, 8>::new();
+/// pool.insert(5i32).unwrap();
+///
+/// #[cfg(feature = "type-name")]
+/// assert_eq!(
+///     format!("{:?}", pool),
+///     "{PoolKey { index: 0, generation: 1 }: i32: <opaque 4 bytes>}"
+/// );
+/// #[cfg(not(feature = "type-name"))]
+/// assert_eq!(
+///     format!("{:?}", pool),
+///     "{PoolKey { index: 0, generation: 1 }: <opaque 4 bytes>}"
+/// );
+/// ```
+impl<const CAP: usize, const N: usize> core::fmt::Debug for StackAnyPool<CAP, N> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_map()
+            .entries(self.iter().map(|(key, stack)| (key, DebugElement(stack))))
+            .finish()
+    }
+}
@@ -0,0 +1,1164 @@
+use crate::{DebugElement, IterDowncast, IterDowncastMut, StackAny, StackAnySlice};
+
+/// A vector of up to `CAP` stacks of `N` size, which is placed on the stack as a whole.
+///
+/// Each element is a [`StackAny`], so values of different types are mixed in it.
+/// Dropping the vector drops every element.
+///
+/// # Examples
+///
+/// ```
+/// let mut values = stack_any::StackAnyVec::<4, 24>::new();
+///
+/// values.push_value(vec![1, 2]).unwrap();
+/// values.push_value(String::from("x")).unwrap();
+/// values.push_value(5u8).unwrap();
+/// assert_eq!(values.len(), 3);
+///
+/// values.get_mut(0).unwrap().downcast_mut::<Vec<i32>>().unwrap().push(3);
+/// assert_eq!(values.get(0).unwrap().downcast_ref::<Vec<i32>>(), Some(&vec![1, 2, 3]));
+/// assert_eq!(values.get(1).unwrap().downcast_ref::<String>().map(String::as_str), Some("x"));
+///
+/// assert_eq!(values.pop().unwrap().downcast::<u8>().ok(), Some(5));
+/// assert_eq!(values.len(), 2);
+/// ```
+///
+/// ```
+/// use std::rc::Rc;
+///
+/// let counter = Rc::new(());
+///
+/// let mut values = stack_any::StackAnyVec::<4, 8>::new();
+/// values.push_value(counter.clone()).unwrap();
+/// values.push_value(5u64).unwrap();
+/// values.push_value(counter.clone()).unwrap();
+/// assert_eq!(Rc::strong_count(&counter), 3);
+///
+/// drop(values);
+/// assert_eq!(Rc::strong_count(&counter), 1);
+/// ```
+pub struct StackAnyVec<const CAP: usize, const N: usize> {
+    items: [StackAny<N>; CAP],
+    len: usize,
+}
+
+impl<const CAP: usize, const N: usize> StackAnyVec<CAP, N> {
+    /// Creates an empty vector.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// const VALUES: stack_any::StackAnyVec<4, 8> = stack_any::StackAnyVec::new();
+    /// assert!(VALUES.is_empty());
+    /// ```
+    pub const fn new() -> Self {
+        Self {
+            items: [const { StackAny::empty() }; CAP],
+            len: 0,
+        }
+    }
+
+    /// Returns the maximum number of elements, which is `CAP`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let values = stack_any::StackAnyVec::<4, 8>::new();
+    /// assert_eq!(values.capacity(), 4);
+    /// ```
+    pub const fn capacity(&self) -> usize {
+        CAP
+    }
+
+    /// Returns the number of elements.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut values = stack_any::StackAnyVec::<4, 8>::new();
+    /// values.push_value(5i32).unwrap();
+    /// assert_eq!(values.len(), 1);
+    /// ```
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns true if the vector contains no elements.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut values = stack_any::StackAnyVec::<4, 8>::new();
+    /// assert!(values.is_empty());
+    ///
+    /// values.push_value(5i32).unwrap();
+    /// assert!(!values.is_empty());
+    /// ```
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns true if the vector contains `CAP` elements.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut values = stack_any::StackAnyVec::<1, 8>::new();
+    /// assert!(!values.is_full());
+    ///
+    /// values.push_value(5i32).unwrap();
+    /// assert!(values.is_full());
+    /// ```
+    pub fn is_full(&self) -> bool {
+        self.len == CAP
+    }
+
+    /// Appends `stack` to the back of the vector.
+    /// Returns `stack` back if the vector is full.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut values = stack_any::StackAnyVec::<1, 8>::new();
+    /// values.push(stack_any::StackAny::new(5i32)).unwrap();
+    ///
+    /// let rejected = values.push(stack_any::StackAny::new(6i32)).unwrap_err();
+    /// assert_eq!(rejected.downcast_ref::<i32>(), Some(&6));
+    /// assert_eq!(values.len(), 1);
+    /// ```
+    pub fn push(&mut self, stack: StackAny<N>) -> Result<(), StackAny<N>> {
+        if self.is_full() {
+            return Err(stack);
+        }
+
+        self.items[self.len] = stack;
+        self.len += 1;
+        Ok(())
+    }
+
+    /// Appends `stack` to the back of the vector, mirroring `Vec::push_within_capacity`.
+    /// Returns `stack` back if the vector is full.
+    ///
+    /// It is equal to [`StackAnyVec::push`], since the capacity of the vector never grows.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut values = stack_any::StackAnyVec::<1, 8>::new();
+    /// values.push_within_capacity(stack_any::StackAny::new(5i32)).unwrap();
+    ///
+    /// let rejected = values.push_within_capacity(stack_any::StackAny::new(6i32)).unwrap_err();
+    /// assert_eq!(rejected.downcast_ref::<i32>(), Some(&6));
+    /// ```
+    pub fn push_within_capacity(&mut self, stack: StackAny<N>) -> Result<(), StackAny<N>> {
+        self.push(stack)
+    }
+
+    /// Appends every stack of `iter` to the back of the vector until it is full.
+    /// Returns the first stack which did not fit together with the rest of `iter`, which is not consumed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use stack_any::{StackAny, StackAnyVec};
+    ///
+    /// let mut values = StackAnyVec::<3, 8>::new();
+    /// values.try_extend([StackAny::new(1i32), StackAny::new(2i32)]).unwrap();
+    /// values.try_extend(std::iter::empty()).unwrap();
+    ///
+    /// let (rejected, mut rest) = values.try_extend((3..6).map(StackAny::new)).unwrap_err();
+    /// assert_eq!(values.len(), 3);
+    /// assert_eq!(rejected.downcast_ref::<i32>(), Some(&4));
+    /// assert_eq!(rest.next().unwrap().downcast_ref::<i32>(), Some(&5));
+    /// ```
+    ///
+    /// Exactly filling the vector is not an error, nor is an empty `iter` on a full vector.
+    ///
+    /// ```
+    /// use stack_any::{StackAny, StackAnyVec};
+    ///
+    /// let mut values = StackAnyVec::<2, 8>::new();
+    /// values.try_extend([StackAny::new(1i32), StackAny::new(2i32)]).unwrap();
+    /// values.try_extend(std::iter::empty()).unwrap();
+    /// assert!(values.is_full());
+    /// ```
+    ///
+    /// Every stack is owned by exactly one of the vector, the rejected one and the rest.
+    ///
+    /// ```
+    /// use std::rc::Rc;
+    ///
+    /// use stack_any::{StackAny, StackAnyVec};
+    ///
+    /// let counter = Rc::new(());
+    ///
+    /// let mut values = StackAnyVec::<2, 8>::new();
+    /// let stacks = [(); 4].map(|_| StackAny::new(counter.clone()));
+    /// let (rejected, rest) = values.try_extend(stacks).unwrap_err();
+    /// assert_eq!(Rc::strong_count(&counter), 5);
+    ///
+    /// drop((rejected, rest));
+    /// assert_eq!(Rc::strong_count(&counter), 3);
+    ///
+    /// drop(values);
+    /// assert_eq!(Rc::strong_count(&counter), 1);
+    /// ```
+    pub fn try_extend<I>(&mut self, iter: I) -> Result<(), (StackAny<N>, I::IntoIter)>
+    where
+        I: IntoIterator<Item = StackAny<N>>,
+    {
+        let mut iter = iter.into_iter();
+        for stack in iter.by_ref() {
+            if let Err(stack) = self.push(stack) {
+                return Err((stack, iter));
+            }
+        }
+        Ok(())
+    }
+
+    /// Places `value` into a stack and then appends it to the back of the vector.
+    /// Returns `value` back if the vector is full.
+    ///
+    /// Whether `T` fits is checked at compile time, as in [`StackAny::new`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut values = stack_any::StackAnyVec::<1, 24>::new();
+    /// values.push_value(String::from("x")).unwrap();
+    ///
+    /// let rejected = values.push_value(String::from("y")).unwrap_err();
+    /// assert_eq!(rejected, "y");
+    /// ```
+    ///
+    /// ```compile_fail
+    /// let mut values = stack_any::StackAnyVec::<1, 8>::new();
+    /// values.push_value(String::from("x")).unwrap();
+    /// ```
+    pub fn push_value<T>(&mut self, value: T) -> Result<(), T>
+    where
+        T: core::any::Any,
+    {
+        if self.is_full() {
+            return Err(value);
+        }
+
+        match self.push(StackAny::new(value)) {
+            Ok(()) => Ok(()),
+            Err(_) => unreachable!(),
+        }
+    }
+
+    /// Removes the last element and returns it.
+    /// Returns None if the vector is empty.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut values = stack_any::StackAnyVec::<4, 8>::new();
+    /// values.push_value(5i32).unwrap();
+    ///
+    /// assert_eq!(values.pop().unwrap().downcast_ref::<i32>(), Some(&5));
+    /// assert!(values.pop().is_none());
+    /// ```
+    pub fn pop(&mut self) -> Option<StackAny<N>> {
+        if self.is_empty() {
+            return None;
+        }
+
+        self.len -= 1;
+        Some(core::mem::take(&mut self.items[self.len]))
+    }
+
+    /// Removes the element at `index` and returns it, replacing it by the last element.
+    ///
+    /// This does not preserve the order of the elements, but is O(1).
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is out of bounds.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut values = stack_any::StackAnyVec::<4, 8>::new();
+    /// values.push_value(1i32).unwrap();
+    /// values.push_value('x').unwrap();
+    /// values.push_value(2u64).unwrap();
+    ///
+    /// assert_eq!(values.swap_remove(0).downcast_ref::<i32>(), Some(&1));
+    /// assert!(values.get(0).unwrap().is::<u64>());
+    ///
+    /// assert_eq!(values.swap_remove(1).downcast_ref::<char>(), Some(&'x'));
+    /// assert_eq!(values.len(), 1);
+    /// ```
+    ///
+    /// ```should_panic
+    /// let mut values = stack_any::StackAnyVec::<4, 8>::new();
+    /// values.swap_remove(0);
+    /// ```
+    pub fn swap_remove(&mut self, index: usize) -> StackAny<N> {
+        let len = self.len;
+        if index >= len {
+            panic!("swap_remove index (is {index}) should be < len (is {len})");
+        }
+
+        self.items.swap(index, len - 1);
+        self.len -= 1;
+        core::mem::take(&mut self.items[len - 1])
+    }
+
+    /// Removes the elements in `range` and returns an iterator over them.
+    ///
+    /// The elements not yielded by the iterator are dropped with it, and then the elements
+    /// after `range` are moved forward, preserving their order. If the iterator is leaked instead,
+    /// the vector keeps only the elements before `range`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the start of `range` is greater than the end or if the end is out of bounds.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut values = stack_any::StackAnyVec::<5, 8>::new();
+    /// for i in 0..5 {
+    ///     values.push_value(i).unwrap();
+    /// }
+    ///
+    /// let drained: Vec<i32> = values.drain(1..3).map(|stack| stack.downcast().ok().unwrap()).collect();
+    /// assert_eq!(drained, [1, 2]);
+    ///
+    /// let remaining: Vec<i32> = values.iter_downcast::<i32>().copied().collect();
+    /// assert_eq!(remaining, [0, 3, 4]);
+    /// ```
+    ///
+    /// Dropping the iterator halfway drops the rest of `range`.
+    ///
+    /// ```
+    /// use std::rc::Rc;
+    ///
+    /// let counter = Rc::new(());
+    ///
+    /// let mut values = stack_any::StackAnyVec::<5, 8>::new();
+    /// values.push_value(0u64).unwrap();
+    /// values.push_value(counter.clone()).unwrap();
+    /// values.push_value(counter.clone()).unwrap();
+    /// values.push_value(counter.clone()).unwrap();
+    /// values.push_value(4u64).unwrap();
+    ///
+    /// let mut drain = values.drain(1..4);
+    /// let first = drain.next().unwrap();
+    /// assert_eq!(drain.len(), 2);
+    /// drop(drain);
+    /// assert_eq!(Rc::strong_count(&counter), 2);
+    ///
+    /// drop(first);
+    /// assert_eq!(Rc::strong_count(&counter), 1);
+    ///
+    /// let remaining: Vec<u64> = values.iter_downcast::<u64>().copied().collect();
+    /// assert_eq!(remaining, [0, 4]);
+    /// assert_eq!(values.len(), 2);
+    /// ```
+    ///
+    /// A panicking destructor of the rest of `range` still leaves the vector valid.
+    ///
+    /// ```
+    /// struct Bomb;
+    ///
+    /// impl Drop for Bomb {
+    ///     fn drop(&mut self) {
+    ///         panic!("boom");
+    ///     }
+    /// }
+    ///
+    /// let mut values = stack_any::StackAnyVec::<4, 8>::new();
+    /// values.push_value(0i32).unwrap();
+    /// values.push_value(Bomb).unwrap_or_else(|_| unreachable!());
+    /// values.push_value(2i32).unwrap();
+    ///
+    /// let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+    ///     values.drain(..2);
+    /// }));
+    /// assert!(result.is_err());
+    ///
+    /// let remaining: Vec<i32> = values.iter_downcast::<i32>().copied().collect();
+    /// assert_eq!(remaining, [2]);
+    /// assert_eq!(values.len(), 1);
+    /// ```
+    ///
+    /// ```should_panic
+    /// let mut values = stack_any::StackAnyVec::<4, 8>::new();
+    /// values.drain(..1);
+    /// ```
+    pub fn drain(&mut self, range: impl core::ops::RangeBounds<usize>) -> Drain<'_, CAP, N> {
+        use core::ops::Bound;
+
+        let len = self.len;
+        let start = match range.start_bound() {
+            Bound::Included(&start) => start,
+            Bound::Excluded(&start) => start.checked_add(1).expect("range start overflows"),
+            Bound::Unbounded => 0,
+        };
+        let end = match range.end_bound() {
+            Bound::Included(&end) => end.checked_add(1).expect("range end overflows"),
+            Bound::Excluded(&end) => end,
+            Bound::Unbounded => len,
+        };
+        if start > end {
+            panic!("drain range start (is {start}) should be <= end (is {end})");
+        }
+        if end > len {
+            panic!("drain range end (is {end}) should be <= len (is {len})");
+        }
+
+        // The elements from `start` are out of the vector until the iterator is dropped.
+        self.len = start;
+
+        Drain {
+            vec: self,
+            start,
+            end,
+            front: start,
+            back: end,
+            len,
+        }
+    }
+
+    /// Returns reference to the element at `index`.
+    /// Returns None if `index` is out of bounds.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut values = stack_any::StackAnyVec::<4, 8>::new();
+    /// values.push_value(5i32).unwrap();
+    ///
+    /// assert_eq!(values.get(0).unwrap().downcast_ref::<i32>(), Some(&5));
+    /// assert!(values.get(1).is_none());
+    /// ```
+    pub fn get(&self, index: usize) -> Option<&StackAny<N>> {
+        self.as_slice().get(index)
+    }
+
+    /// Returns mutable reference to the element at `index`.
+    /// Returns None if `index` is out of bounds.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut values = stack_any::StackAnyVec::<4, 8>::new();
+    /// values.push_value(5i32).unwrap();
+    ///
+    /// *values.get_mut(0).unwrap().downcast_mut::<i32>().unwrap() += 1;
+    /// assert_eq!(values.get(0).unwrap().downcast_ref::<i32>(), Some(&6));
+    /// assert!(values.get_mut(1).is_none());
+    /// ```
+    pub fn get_mut(&mut self, index: usize) -> Option<&mut StackAny<N>> {
+        self.as_mut_slice().get_mut(index)
+    }
+
+    /// Returns mutable references to the elements at `indices` at once.
+    /// Returns None if any of `indices` is out of bounds, or if any two of them are equal.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut values = stack_any::StackAnyVec::<4, 8>::new();
+    /// values.push_value(1i32).unwrap();
+    /// values.push_value(2i32).unwrap();
+    /// values.push_value(3i32).unwrap();
+    ///
+    /// let [first, last] = values.get_disjoint_mut([0, 2]).unwrap();
+    /// core::mem::swap(first, last);
+    /// assert_eq!(values.get(0).unwrap().downcast_ref::<i32>(), Some(&3));
+    /// assert_eq!(values.get(2).unwrap().downcast_ref::<i32>(), Some(&1));
+    ///
+    /// assert!(values.get_disjoint_mut([1, 1]).is_none());
+    /// assert!(values.get_disjoint_mut([0, 3]).is_none());
+    /// ```
+    pub fn get_disjoint_mut<const K: usize>(
+        &mut self,
+        indices: [usize; K],
+    ) -> Option<[&mut StackAny<N>; K]> {
+        self.as_mut_slice().get_disjoint_mut(indices).ok()
+    }
+
+    /// Returns mutable references to the elements at `i` and `j` at once as concrete types.
+    /// Returns None if `i` or `j` is out of bounds, if they are equal, or if `A` or `B` is not equal to
+    /// the contained value type of each element.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// struct Physics {
+    ///     velocity: f32,
+    /// }
+    ///
+    /// struct Transform {
+    ///     position: f32,
+    /// }
+    ///
+    /// let mut values = stack_any::StackAnyVec::<4, 8>::new();
+    /// values.push_value(Physics { velocity: 2.0 }).unwrap_or_else(|_| unreachable!());
+    /// values.push_value(Transform { position: 1.0 }).unwrap_or_else(|_| unreachable!());
+    ///
+    /// let (physics, transform) = values.get2_mut::<Physics, Transform>(0, 1).unwrap();
+    /// transform.position += physics.velocity;
+    /// physics.velocity = 0.0;
+    ///
+    /// assert_eq!(values.get(0).unwrap().downcast_ref::<Physics>().unwrap().velocity, 0.0);
+    /// assert_eq!(values.get(1).unwrap().downcast_ref::<Transform>().unwrap().position, 3.0);
+    ///
+    /// assert!(values.get2_mut::<Physics, Physics>(0, 0).is_none());
+    /// assert!(values.get2_mut::<Physics, Transform>(1, 0).is_none());
+    /// assert!(values.get2_mut::<Physics, Transform>(0, 2).is_none());
+    /// ```
+    pub fn get2_mut<A, B>(&mut self, i: usize, j: usize) -> Option<(&mut A, &mut B)>
+    where
+        A: core::any::Any,
+        B: core::any::Any,
+    {
+        let [a, b] = self.get_disjoint_mut([i, j])?;
+        Some((a.downcast_mut()?, b.downcast_mut()?))
+    }
+
+    /// Returns the elements as a slice.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut values = stack_any::StackAnyVec::<4, 8>::new();
+    /// values.push_value(5i32).unwrap();
+    /// values.push_value('x').unwrap();
+    ///
+    /// let chars = values.as_slice().iter().filter(|stack| stack.is::<char>()).count();
+    /// assert_eq!(chars, 1);
+    /// ```
+    pub fn as_slice(&self) -> &[StackAny<N>] {
+        &self.items[..self.len]
+    }
+
+    /// Returns the elements as a mutable slice.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut values = stack_any::StackAnyVec::<4, 8>::new();
+    /// values.push_value(5i32).unwrap();
+    /// values.push_value('x').unwrap();
+    ///
+    /// values.as_mut_slice().swap(0, 1);
+    /// assert!(values.get(0).unwrap().is::<char>());
+    /// ```
+    pub fn as_mut_slice(&mut self) -> &mut [StackAny<N>] {
+        &mut self.items[..self.len]
+    }
+
+    /// Returns an iterator over the contained values of `T`, skipping the elements of other types.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut values = stack_any::StackAnyVec::<4, 24>::new();
+    /// values.push_value(1i32).unwrap();
+    /// values.push_value(String::from("x")).unwrap();
+    /// values.push_value('y').unwrap();
+    /// values.push_value(2i32).unwrap();
+    ///
+    /// assert_eq!(values.iter_downcast::<i32>().collect::<Vec<_>>(), [&1, &2]);
+    /// assert_eq!(values.iter_downcast::<i32>().rev().collect::<Vec<_>>(), [&2, &1]);
+    /// assert_eq!(values.iter_downcast::<char>().collect::<Vec<_>>(), [&'y']);
+    /// ```
+    pub fn iter_downcast<T>(&self) -> IterDowncast<'_, T, N>
+    where
+        T: core::any::Any,
+    {
+        self.as_slice().iter_downcast()
+    }
+
+    /// Returns an iterator over mutable references to the contained values of `T`,
+    /// skipping the elements of other types.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut values = stack_any::StackAnyVec::<4, 24>::new();
+    /// values.push_value(1i32).unwrap();
+    /// values.push_value(String::from("x")).unwrap();
+    /// values.push_value(2i32).unwrap();
+    ///
+    /// values.iter_downcast_mut::<i32>().for_each(|x| *x *= 10);
+    /// assert_eq!(values.iter_downcast::<i32>().collect::<Vec<_>>(), [&10, &20]);
+    /// assert_eq!(values.iter_downcast::<String>().collect::<Vec<_>>(), ["x"]);
+    /// ```
+    pub fn iter_downcast_mut<T>(&mut self) -> IterDowncastMut<'_, T, N>
+    where
+        T: core::any::Any,
+    {
+        self.as_mut_slice().iter_downcast_mut()
+    }
+
+    /// Retains only the elements for which `f` returns true, dropping the others in place.
+    ///
+    /// The order of the retained elements is preserved. If `f` panics, the elements not visited yet
+    /// are retained, and every element is either present or dropped, never both.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut values = stack_any::StackAnyVec::<4, 8>::new();
+    /// values.push_value(1i32).unwrap();
+    /// values.push_value('x').unwrap();
+    /// values.push_value(2i32).unwrap();
+    /// values.push_value(3u64).unwrap();
+    ///
+    /// values.retain(|stack| !stack.is::<i32>());
+    /// assert_eq!(values.len(), 2);
+    /// assert!(values.get(0).unwrap().is::<char>());
+    /// assert!(values.get(1).unwrap().is::<u64>());
+    /// ```
+    ///
+    /// ```
+    /// use std::rc::Rc;
+    ///
+    /// let counter = Rc::new(());
+    ///
+    /// let mut values = stack_any::StackAnyVec::<5, 16>::new();
+    /// for i in 0..5 {
+    ///     values.push_value((i, counter.clone())).unwrap_or_else(|_| unreachable!());
+    /// }
+    ///
+    /// let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+    ///     values.retain(|stack| match stack.downcast_ref::<(i32, Rc<()>)>().unwrap().0 {
+    ///         3 => panic!(),
+    ///         i => i % 2 == 0,
+    ///     });
+    /// }));
+    /// assert!(result.is_err());
+    ///
+    /// // 1 is dropped, 0 and 2 are retained, 3 and 4 are not visited
+    /// let order: Vec<i32> = values.iter_downcast::<(i32, Rc<()>)>().map(|(i, _)| *i).collect();
+    /// assert_eq!(order, [0, 2, 3, 4]);
+    /// assert_eq!(Rc::strong_count(&counter), 5);
+    ///
+    /// drop(values);
+    /// assert_eq!(Rc::strong_count(&counter), 1);
+    /// ```
+    pub fn retain(&mut self, mut f: impl FnMut(&mut StackAny<N>) -> bool) {
+        let len = core::mem::replace(&mut self.len, 0);
+
+        let mut guard = RetainGuard {
+            vec: self,
+            len,
+            processed: 0,
+            retained: 0,
+        };
+
+        // The elements between the retained and the processed ones are always empty.
+        while guard.processed < guard.len {
+            let index = guard.processed;
+            if f(&mut guard.vec.items[index]) {
+                guard.vec.items.swap(guard.retained, index);
+                guard.retained += 1;
+                guard.processed += 1;
+            } else {
+                // The element becomes empty before its destructor runs, even if it panics.
+                guard.processed += 1;
+                guard.vec.items[index].clear();
+            }
+        }
+    }
+
+    /// Retains only the elements for which `f` returns true given the contained value of `T`,
+    /// dropping the others in place. Elements of other types are always retained.
+    ///
+    /// It is as panic safe as [`StackAnyVec::retain`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::rc::Rc;
+    ///
+    /// struct Timer {
+    ///     remaining: u32,
+    ///     _resource: Rc<()>,
+    /// }
+    ///
+    /// let resource = Rc::new(());
+    /// let timer = |remaining| Timer { remaining, _resource: resource.clone() };
+    ///
+    /// let mut values = stack_any::StackAnyVec::<5, 16>::new();
+    /// values.push_value(timer(0)).unwrap_or_else(|_| unreachable!());
+    /// values.push_value('x').unwrap();
+    /// values.push_value(timer(3)).unwrap_or_else(|_| unreachable!());
+    /// values.push_value(timer(0)).unwrap_or_else(|_| unreachable!());
+    /// values.push_value(5i32).unwrap();
+    /// assert_eq!(Rc::strong_count(&resource), 4);
+    ///
+    /// values.retain_downcast::<Timer>(|timer| timer.remaining > 0);
+    /// assert_eq!(values.len(), 3);
+    /// assert_eq!(Rc::strong_count(&resource), 2);
+    ///
+    /// assert!(values.get(0).unwrap().is::<char>());
+    /// assert_eq!(values.get(1).unwrap().downcast_ref::<Timer>().unwrap().remaining, 3);
+    /// assert!(values.get(2).unwrap().is::<i32>());
+    /// ```
+    pub fn retain_downcast<T>(&mut self, mut f: impl FnMut(&mut T) -> bool)
+    where
+        T: core::any::Any,
+    {
+        self.retain(|stack| stack.downcast_mut::<T>().is_none_or(&mut f));
+    }
+
+    /// Sorts the elements by the `Ord` of [`StackAny`], which orders them by the contained value types first,
+    /// and then by the `Ord` captured by [`StackAnyBuilder::with_ord`](crate::StackAnyBuilder::with_ord).
+    ///
+    /// As [`StackAnyVec::sort_by`], the sort is in place by swapping elements and is not stable.
+    ///
+    /// # Panics
+    ///
+    /// Panics if two elements are not ordered, e.g. values of the same type without the capability,
+    /// leaving a permutation of the elements.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// fn sample<T: Ord + 'static>(value: T) -> stack_any::StackAny<8> {
+    ///     stack_any::StackAny::builder(value).with_ord().build()
+    /// }
+    ///
+    /// let mut values = stack_any::StackAnyVec::<6, 8>::new();
+    /// for stack in [sample(3u32), sample(-1i64), sample(1u32), sample(2i64), sample(2u32), sample(-5i64)] {
+    ///     values.push(stack).unwrap();
+    /// }
+    ///
+    /// values.sort();
+    /// assert!(values.as_slice().windows(2).all(|w| w[0] <= w[1]));
+    ///
+    /// let unsigned: Vec<u32> = values.iter_downcast::<u32>().copied().collect();
+    /// let signed: Vec<i64> = values.iter_downcast::<i64>().copied().collect();
+    /// assert_eq!(unsigned, [1, 2, 3]);
+    /// assert_eq!(signed, [-5, -1, 2]);
+    ///
+    /// // The values of one type are next to each other.
+    /// let first = values.get(0).unwrap().is::<u32>();
+    /// assert!(values.as_slice()[..3].iter().all(|stack| stack.is::<u32>() == first));
+    /// ```
+    ///
+    /// ```should_panic
+    /// let mut values = stack_any::stack_any_vec![u32 => 2, u32 => 1];
+    /// values.sort();
+    /// ```
+    pub fn sort(&mut self) {
+        self.sort_by(Ord::cmp);
+    }
+
+    /// Sorts the elements by `compare`.
+    ///
+    /// Elements are never moved out of the vector while sorting, only swapped in place (heapsort),
+    /// so no destructor runs, and a panicking `compare` leaves a permutation of the elements.
+    /// The sort is not stable, i.e. equal elements may be reordered.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::sync::atomic::{AtomicUsize, Ordering};
+    ///
+    /// static DROPS: AtomicUsize = AtomicUsize::new(0);
+    ///
+    /// struct Tracked(u32);
+    ///
+    /// impl Drop for Tracked {
+    ///     fn drop(&mut self) {
+    ///         DROPS.fetch_add(1, Ordering::SeqCst);
+    ///     }
+    /// }
+    ///
+    /// let key = |stack: &stack_any::StackAny<8>| match stack.downcast_ref::<Tracked>() {
+    ///     Some(tracked) => tracked.0,
+    ///     None => *stack.downcast_ref::<u32>().unwrap(),
+    /// };
+    ///
+    /// let mut values = stack_any::StackAnyVec::<8, 8>::new();
+    /// for i in [5, 3, 7, 1, 6, 2, 8, 4] {
+    ///     match i % 2 {
+    ///         0 => values.push_value(Tracked(i)).unwrap_or_else(|_| unreachable!()),
+    ///         _ => values.push_value(i).unwrap(),
+    ///     }
+    /// }
+    ///
+    /// values.sort_by(|a, b| key(a).cmp(&key(b)).reverse());
+    /// assert_eq!(DROPS.load(Ordering::SeqCst), 0);
+    ///
+    /// let keys: Vec<u32> = values.as_slice().iter().map(key).collect();
+    /// assert_eq!(keys, [8, 7, 6, 5, 4, 3, 2, 1]);
+    ///
+    /// drop(values);
+    /// assert_eq!(DROPS.load(Ordering::SeqCst), 4);
+    /// ```
+    pub fn sort_by(
+        &mut self,
+        mut compare: impl FnMut(&StackAny<N>, &StackAny<N>) -> core::cmp::Ordering,
+    ) {
+        let mut is_less = |a: &StackAny<N>, b: &StackAny<N>| compare(a, b).is_lt();
+        let items = self.as_mut_slice();
+
+        for node in (0..items.len() / 2).rev() {
+            sift_down(items, node, &mut is_less);
+        }
+        for end in (1..items.len()).rev() {
+            items.swap(0, end);
+            sift_down(&mut items[..end], 0, &mut is_less);
+        }
+    }
+
+    /// Drops every element, leaving the vector empty.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::rc::Rc;
+    ///
+    /// let counter = Rc::new(());
+    ///
+    /// let mut values = stack_any::StackAnyVec::<4, 8>::new();
+    /// values.push_value(counter.clone()).unwrap();
+    /// values.push_value(counter.clone()).unwrap();
+    /// assert_eq!(Rc::strong_count(&counter), 3);
+    ///
+    /// values.clear();
+    /// assert!(values.is_empty());
+    /// assert_eq!(Rc::strong_count(&counter), 1);
+    /// ```
+    pub fn clear(&mut self) {
+        let len = core::mem::replace(&mut self.len, 0);
+
+        // Elements left behind by a panicking destructor are dropped with the vector.
+        self.items[..len].iter_mut().for_each(StackAny::clear);
+    }
+}
+
+impl<const CAP: usize, const N: usize> Default for StackAnyVec<CAP, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Formats the elements as a list, each of them as the name of its type with the `type-name` feature,
+/// followed by the value formatted by `Debug` captured by [`StackAnyBuilder::with_debug`](crate::StackAnyBuilder::with_debug),
+/// or `<opaque N bytes>` without the capability. `{:#?}` prints one element per line.
+///
+/// # Examples
+///
+/// ```
+/// use stack_any::{StackAny, StackAnyVec};
+///
+/// let mut values = StackAnyVec::<3, 8>::new();
+/// values.push(StackAny::builder(5i32).with_debug().build()).unwrap();
+/// values.push(StackAny::builder((1u8, 'x')).with_debug().build()).unwrap();
+/// values.push_value(1.5f64).unwrap();
+///
+/// #[cfg(feature = "type-name")]
+/// assert_eq!(
+///     format!("{:?}", values),
+///     "[i32: 5, (u8, char): (1, 'x'), f64: <opaque 8 bytes>]"
+/// );
+/// #[cfg(not(feature = "type-name"))]
+/// assert_eq!(format!("{:?}", values), "[5, (1, 'x'), <opaque 8 bytes>]");
+/// ```
+///
+/// ```
+/// use stack_any::{StackAny, StackAnyVec};
+///
+/// let mut values = StackAnyVec::<3, 8>::new();
+/// values.push(StackAny::builder(5i32).with_debug().build()).unwrap();
+/// values.push(StackAny::builder((1u8, 'x')).with_debug().build()).unwrap();
+/// values.push_value(1.5f64).unwrap();
+///
+/// #[cfg(feature = "type-name")]
+/// assert_eq!(
+///     format!("{:#?}", values),
+///     "[
+///     i32: 5,
+///     (u8, char): (
+///         1,
+///         'x',
+///     ),
+///     f64: <opaque 8 bytes>,
+/// ]"
+/// );
+/// #[cfg(not(feature = "type-name"))]
+/// assert_eq!(
+///     format!("{:#?}", values),
+///     "[
+///     5,
+///     (
+///         1,
+///         'x',
+///     ),
+///     <opaque 8 bytes>,
+/// ]"
+/// );
+/// ```
+impl<const CAP: usize, const N: usize> core::fmt::Debug for StackAnyVec<CAP, N> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_list()
+            .entries(self.as_slice().iter().map(DebugElement))
+            .finish()
+    }
+}
+
+/// Appends every stack of the iterator to the back of the vector.
+///
+/// # Panics
+///
+/// Panics if the vector becomes full before the iterator ends.
+/// Use [`StackAnyVec::try_extend`] to handle it instead.
+///
+/// # Examples
+///
+/// ```
+/// use stack_any::{StackAny, StackAnyVec};
+///
+/// let mut values = StackAnyVec::<3, 8>::new();
+/// values.extend([StackAny::new(1i32), StackAny::new('x')]);
+/// values.extend(std::iter::empty());
+/// assert_eq!(values.len(), 2);
+/// ```
+///
+/// ```should_panic
+/// use stack_any::{StackAny, StackAnyVec};
+///
+/// let mut values = StackAnyVec::<1, 8>::new();
+/// values.extend([StackAny::new(1i32), StackAny::new('x')]);
+/// ```
+impl<const CAP: usize, const N: usize> Extend<StackAny<N>> for StackAnyVec<CAP, N> {
+    fn extend<I>(&mut self, iter: I)
+    where
+        I: IntoIterator<Item = StackAny<N>>,
+    {
+        if self.try_extend(iter).is_err() {
+            panic!("StackAnyVec capacity (is {CAP}) exceeded");
+        }
+    }
+}
+
+/// Collects the stacks of the iterator into a vector.
+///
+/// # Panics
+///
+/// Panics if the iterator yields more than `CAP` stacks, rather than dropping the rest silently.
+/// Use [`StackAnyVec::try_extend`] on an empty vector to handle it instead.
+///
+/// # Examples
+///
+/// ```
+/// use stack_any::{StackAny, StackAnyVec};
+///
+/// let values: StackAnyVec<4, 8> = (0..4).map(StackAny::new).collect();
+/// assert!(values.is_full());
+///
+/// let values: StackAnyVec<4, 8> = std::iter::empty().collect();
+/// assert!(values.is_empty());
+/// ```
+///
+/// ```should_panic
+/// use stack_any::{StackAny, StackAnyVec};
+///
+/// let values: StackAnyVec<4, 8> = (0..5).map(StackAny::new).collect();
+/// ```
+impl<const CAP: usize, const N: usize> FromIterator<StackAny<N>> for StackAnyVec<CAP, N> {
+    fn from_iter<I>(iter: I) -> Self
+    where
+        I: IntoIterator<Item = StackAny<N>>,
+    {
+        let mut vec = Self::new();
+        vec.extend(iter);
+        vec
+    }
+}
+
+/// Moves the element at `node` down the max-heap `items` by swapping it with the greater child.
+fn sift_down<T>(items: &mut [T], mut node: usize, is_less: &mut impl FnMut(&T, &T) -> bool) {
+    loop {
+        let mut child = 2 * node + 1;
+        if child >= items.len() {
+            break;
+        }
+        if child + 1 < items.len() && is_less(&items[child], &items[child + 1]) {
+            child += 1;
+        }
+        if !is_less(&items[node], &items[child]) {
+            break;
+        }
+
+        items.swap(node, child);
+        node = child;
+    }
+}
+
+/// Restores the length of [`StackAnyVec`] on the way out of [`StackAnyVec::retain`], even if it panics.
+struct RetainGuard<'a, const CAP: usize, const N: usize> {
+    vec: &'a mut StackAnyVec<CAP, N>,
+    len: usize,
+    processed: usize,
+    retained: usize,
+}
+
+impl<const CAP: usize, const N: usize> Drop for RetainGuard<'_, CAP, N> {
+    fn drop(&mut self) {
+        // Moves the elements not processed yet next to the retained ones, over the empty ones.
+        let holes = self.processed - self.retained;
+        self.vec.items[self.retained..self.len].rotate_left(holes);
+        self.vec.len = self.len - holes;
+    }
+}
+
+/// An iterator over the elements removed from [`StackAnyVec`], returned by [`StackAnyVec::drain`].
+pub struct Drain<'a, const CAP: usize, const N: usize> {
+    vec: &'a mut StackAnyVec<CAP, N>,
+    start: usize,
+    end: usize,
+    front: usize,
+    back: usize,
+    len: usize,
+}
+
+impl<const CAP: usize, const N: usize> Iterator for Drain<'_, CAP, N> {
+    type Item = StackAny<N>;
+
+    fn next(&mut self) -> Option<StackAny<N>> {
+        if self.front == self.back {
+            return None;
+        }
+
+        self.front += 1;
+        Some(core::mem::take(&mut self.vec.items[self.front - 1]))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.back - self.front;
+        (len, Some(len))
+    }
+}
+
+impl<const CAP: usize, const N: usize> DoubleEndedIterator for Drain<'_, CAP, N> {
+    fn next_back(&mut self) -> Option<StackAny<N>> {
+        if self.front == self.back {
+            return None;
+        }
+
+        self.back -= 1;
+        Some(core::mem::take(&mut self.vec.items[self.back]))
+    }
+}
+
+impl<const CAP: usize, const N: usize> ExactSizeIterator for Drain<'_, CAP, N> {}
+
+impl<const CAP: usize, const N: usize> core::iter::FusedIterator for Drain<'_, CAP, N> {}
+
+impl<const CAP: usize, const N: usize> core::fmt::Debug for Drain<'_, CAP, N> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_list()
+            .entries(
+                self.vec.items[self.front..self.back]
+                    .iter()
+                    .map(DebugElement),
+            )
+            .finish()
+    }
+}
+
+impl<const CAP: usize, const N: usize> Drop for Drain<'_, CAP, N> {
+    fn drop(&mut self) {
+        // Moves the elements after the range forward first, so that the vector is valid
+        // even if the destructor of an element not yielded panics.
+        let removed = self.end - self.start;
+        self.vec.items[self.start..self.len].rotate_left(removed);
+        self.vec.len = self.len - removed;
+
+        // Elements left behind by a panicking destructor are dropped with the vector.
+        let vec_len = self.vec.len;
+        self.vec.items[vec_len..self.len]
+            .iter_mut()
+            .for_each(StackAny::clear);
+    }
+}
+
+/// Creates a [`StackAnyVec`] containing the given values of the given types.
+///
+/// Each element is as large as the largest of the types. `stack_any_vec![CAP; type => value, ...]`
+/// sets the capacity explicitly, and `stack_any_vec![type => value, ...]` takes the number of the values.
+/// Compilation fails if a value does not fit or if there are more values than the capacity.
+///
+/// # Examples
+///
+/// ```
+/// let mut values = stack_any::stack_any_vec![
+///     Vec<i32> => vec![],
+///     Vec<char> => vec![],
+/// ];
+///
+/// values.get_mut(0).unwrap().downcast_mut::<Vec<i32>>().unwrap().push(5);
+/// values.get_mut(1).unwrap().downcast_mut::<Vec<char>>().unwrap().push('x');
+///
+/// assert_eq!(values.get(0).unwrap().downcast_ref(), Some(&vec![5]));
+/// assert_eq!(values.get(1).unwrap().downcast_ref(), Some(&vec!['x']));
+/// assert!(values.is_full());
+/// ```
+///
+/// ```
+/// let mut values = stack_any::stack_any_vec![4; u8 => 5, [u64; 2] => [1, 2]];
+/// assert_eq!(values.len(), 2);
+/// assert_eq!(values.capacity(), 4);
+/// assert_eq!(values.get(0).unwrap().capacity(), 16);
+///
+/// values.push_value(7u64).unwrap();
+///
+/// const CAP: usize = 3;
+/// let values = stack_any::stack_any_vec![CAP; char => 'x'];
+/// assert_eq!(values.capacity(), 3);
+/// ```
+///
+/// ```
+/// const N: usize = 4;
+/// const LEN: usize = 7;
+///
+/// let values = stack_any::stack_any_vec![N; u8 => 1, usize => LEN];
+/// assert_eq!(values.capacity(), 4);
+/// assert_eq!(values.get(1).unwrap().downcast_ref::<usize>(), Some(&7));
+/// ```
+///
+/// ```compile_fail
+/// let values = stack_any::stack_any_vec![1; u8 => 5, u16 => 7];
+/// ```
+///
+/// ```compile_fail
+/// #[repr(align(16))]
+/// struct Aligned(u8);
+///
+/// let values = stack_any::stack_any_vec![u8 => 5, Aligned => Aligned(7)];
+/// ```
+#[macro_export]
+macro_rules! stack_any_vec {
+    ($($type:ty => $init:expr),* $(,)?) => {
+        $crate::stack_any_vec![{ <[&str]>::len(&[$(::core::stringify!($type)),*]) }; $($type => $init),*]
+    };
+    ($cap:expr; $($type:ty => $init:expr),* $(,)?) => {{
+        const {
+            ::core::assert!(
+                <[&str]>::len(&[$(::core::stringify!($type)),*]) <= $cap,
+                "more values than the capacity"
+            )
+        };
+
+        let mut vec = $crate::StackAnyVec::<{ $cap }, { $crate::max_size_of!($($type),*) }>::new();
+        $(
+            if vec.push_value::<$type>($init).is_err() {
+                ::core::unreachable!();
+            }
+        )*
+        vec
+    }};
+}
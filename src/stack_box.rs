@@ -0,0 +1,431 @@
+use crate::{any_ptr, Buffer};
+
+/// A type that owns a stack allocation of `N` size containing a value used as `Dyn`, e.g. a trait object.
+///
+/// Unlike [`StackAny`](crate::StackAny), the value is not downcast but used through `Deref<Target = Dyn>`,
+/// so its methods can be called directly. Besides the allocation, it only holds the metadata of `Dyn`,
+/// such as the vtable of a trait object.
+///
+/// # Examples
+///
+/// ```
+/// use stack_any::StackBox;
+///
+/// trait Handler {
+///     fn handle(&mut self, event: u32) -> u32;
+/// }
+///
+/// struct Add(u32);
+///
+/// impl Handler for Add {
+///     fn handle(&mut self, event: u32) -> u32 {
+///         event + self.0
+///     }
+/// }
+///
+/// struct Count(usize);
+///
+/// impl Handler for Count {
+///     fn handle(&mut self, event: u32) -> u32 {
+///         self.0 += 1;
+///         event
+///     }
+/// }
+///
+/// let mut handlers: [StackBox<dyn Handler, 8>; 2] = [
+///     StackBox::new_with(Add(5), |v| v as &mut dyn Handler),
+///     StackBox::new_with(Count(0), |v| v as &mut dyn Handler),
+/// ];
+///
+/// let results: Vec<u32> = handlers.iter_mut().map(|handler| handler.handle(1)).collect();
+/// assert_eq!(results, [6, 1]);
+/// ```
+///
+/// `StackBox` is `Send` or `Sync` only if `Dyn` is.
+///
+/// ```compile_fail
+/// fn assert_send<T: Send>() {}
+/// assert_send::<stack_any::StackBox<dyn core::fmt::Debug, 8>>();
+/// ```
+///
+/// ```
+/// fn assert_send<T: Send>() {}
+/// assert_send::<stack_any::StackBox<dyn core::fmt::Debug + Send, 8>>();
+/// ```
+pub struct StackBox<Dyn, const N: usize>
+where
+    Dyn: ?Sized,
+{
+    bytes: Buffer<N>,
+    meta: *mut Dyn,
+    _marker: core::marker::PhantomData<Dyn>,
+}
+
+// SAFETY: `StackBox` owns a value used as `Dyn`, which is `Send`.
+unsafe impl<Dyn, const N: usize> Send for StackBox<Dyn, N> where Dyn: ?Sized + Send {}
+
+// SAFETY: `StackBox` owns a value used as `Dyn`, which is `Sync`.
+unsafe impl<Dyn, const N: usize> Sync for StackBox<Dyn, N> where Dyn: ?Sized + Sync {}
+
+impl<Dyn, const N: usize> StackBox<Dyn, N>
+where
+    Dyn: ?Sized,
+{
+    /// The alignment of the allocation in bytes.
+    pub const ALIGN: usize = core::mem::align_of::<Buffer<N>>();
+
+    /// Returns true if a value of `T` can be placed into N-size memory.
+    ///
+    /// Unlike [`StackAny::fits`](crate::StackAny::fits), zero-sized types are rejected
+    /// if their alignment is larger than [`StackBox::ALIGN`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// assert!(stack_any::StackBox::<dyn std::fmt::Debug, 8>::fits::<u64>());
+    /// assert!(!stack_any::StackBox::<dyn std::fmt::Debug, 4>::fits::<u64>());
+    /// ```
+    pub const fn fits<T>() -> bool {
+        core::mem::size_of::<T>() <= N && core::mem::align_of::<T>() <= Self::ALIGN
+    }
+
+    /// Allocates N-size memory on the stack and then places `value` into it, used as `Dyn` by the unsizing coercion.
+    ///
+    /// Requires the nightly-only `unsize` feature. On stable, use [`StackBox::new_with`] or [`stack_box!`](crate::stack_box).
+    /// Whether `T` fits is checked at compile time, as in [`StackAny::new`](crate::StackAny::new).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::fmt::Display;
+    ///
+    /// let values: [stack_any::StackBox<dyn Display, 24>; 2] = [
+    ///     stack_any::StackBox::new(42u8),
+    ///     stack_any::StackBox::new(String::from("x")),
+    /// ];
+    /// assert_eq!(values[0].to_string(), "42");
+    /// assert_eq!(values[1].to_string(), "x");
+    /// ```
+    ///
+    /// ```compile_fail
+    /// let five: stack_any::StackBox<dyn std::fmt::Display, 4> = stack_any::StackBox::new(5u64);
+    /// ```
+    #[cfg(feature = "unsize")]
+    pub fn new<T>(value: T) -> Self
+    where
+        T: core::marker::Unsize<Dyn>,
+    {
+        Self::new_with(value, |value| value)
+    }
+
+    /// Allocates N-size memory on the stack and then places `value` into it, used as `Dyn` by `coerce`.
+    ///
+    /// Whether `T` fits is checked at compile time, as in [`StackAny::new`](crate::StackAny::new).
+    /// See [`StackBox::try_new_with`] for `coerce`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let five = stack_any::StackBox::<dyn std::fmt::Display, 4>::new_with(5i32, |v| v as _);
+    /// assert_eq!(five.to_string(), "5");
+    /// ```
+    ///
+    /// ```compile_fail
+    /// let five = stack_any::StackBox::<dyn std::fmt::Display, 4>::new_with(5i64, |v| v as _);
+    /// ```
+    pub fn new_with<T>(value: T, coerce: impl FnOnce(&mut T) -> &mut Dyn) -> Self {
+        const { assert!(Self::fits::<T>(), "value does not fit in the stack") };
+
+        match Self::try_new_with(value, coerce) {
+            Some(stack) => stack,
+            None => unreachable!(),
+        }
+    }
+
+    /// Allocates N-size memory on the stack and then places `value` into it, used as `Dyn` by `coerce`.
+    /// Returns None if `T` size is larger than N or `T` alignment is larger than [`StackBox::ALIGN`].
+    ///
+    /// `coerce` is given the placed value and must return it as `Dyn`, which an unsizing coercion such as
+    /// `|v| v as &mut dyn Trait` does.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `coerce` returns anything but the given value, which is dropped beforehand.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let five = stack_any::StackBox::<dyn std::fmt::Debug, 8>::try_new_with(5i32, |v| v as _);
+    /// assert_eq!(format!("{:?}", five.unwrap()), "5");
+    ///
+    /// let bytes = stack_any::StackBox::<dyn std::fmt::Debug, 8>::try_new_with([0u8; 16], |v| v as _);
+    /// assert!(bytes.is_none());
+    /// ```
+    ///
+    /// ```should_panic
+    /// static mut OTHER: i32 = 7;
+    ///
+    /// let _ = stack_any::StackBox::<dyn std::fmt::Debug, 8>::try_new_with(5i32, |_| unsafe {
+    ///     &mut *std::ptr::addr_of_mut!(OTHER) as _
+    /// });
+    /// ```
+    pub fn try_new_with<T>(value: T, coerce: impl FnOnce(&mut T) -> &mut Dyn) -> Option<Self> {
+        if !Self::fits::<T>() {
+            return None;
+        }
+
+        let mut bytes = Buffer([core::mem::MaybeUninit::uninit(); N]);
+
+        let ptr = bytes.0.as_mut_ptr() as *mut T;
+        unsafe { core::ptr::write(ptr, value) };
+
+        let meta = coerce(unsafe { &mut *ptr }) as *mut Dyn;
+
+        // The returned pointer must share the address, size and alignment of the value,
+        // so that dropping it as `Dyn` drops the value.
+        let same = unsafe {
+            core::ptr::addr_eq(meta, ptr)
+                && core::mem::size_of_val(&*meta) == core::mem::size_of::<T>()
+                && core::mem::align_of_val(&*meta) == core::mem::align_of::<T>()
+        };
+        if !same {
+            unsafe { core::ptr::drop_in_place(ptr) };
+            panic!("coercion does not return the given value");
+        }
+
+        Some(Self {
+            bytes,
+            meta,
+            _marker: core::marker::PhantomData,
+        })
+    }
+
+    /// Moves the contained value onto the heap, keeping it usable as `Dyn`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::rc::Rc;
+    ///
+    /// trait Shape {
+    ///     fn area(&self) -> u32;
+    /// }
+    ///
+    /// struct Square(u32, Rc<()>);
+    ///
+    /// impl Shape for Square {
+    ///     fn area(&self) -> u32 {
+    ///         self.0 * self.0
+    ///     }
+    /// }
+    ///
+    /// let counter = Rc::new(());
+    /// let shape = stack_any::stack_box!(dyn Shape; 16, Square(3, counter.clone()));
+    /// assert_eq!(shape.area(), 9);
+    ///
+    /// let boxed: Box<dyn Shape> = shape.into_box();
+    /// assert_eq!(boxed.area(), 9);
+    /// assert_eq!(Rc::strong_count(&counter), 2);
+    ///
+    /// drop(boxed);
+    /// assert_eq!(Rc::strong_count(&counter), 1);
+    /// ```
+    #[cfg(feature = "alloc")]
+    pub fn into_box(self) -> alloc::boxed::Box<Dyn> {
+        let this = core::mem::ManuallyDrop::new(self);
+
+        let layout = core::alloc::Layout::for_value(&**this);
+        if layout.size() == 0 {
+            return unsafe {
+                alloc::boxed::Box::from_raw(any_ptr(
+                    this.meta,
+                    1,
+                    core::ptr::without_provenance_mut(layout.align()),
+                ))
+            };
+        }
+
+        let dst = unsafe { alloc::alloc::alloc(layout) };
+        if dst.is_null() {
+            alloc::alloc::handle_alloc_error(layout);
+        }
+
+        let src = this.bytes.0.as_ptr();
+        unsafe { core::ptr::copy_nonoverlapping(src, dst.cast(), layout.size()) };
+        unsafe { alloc::boxed::Box::from_raw(any_ptr(this.meta, 1, dst.cast())) }
+    }
+
+    /// Moves the boxed value onto the stack, keeping it usable as `Dyn`.
+    /// Returns the box back if the value size is larger than N or its alignment is larger than [`StackBox::ALIGN`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::rc::Rc;
+    ///
+    /// trait Shape {
+    ///     fn area(&self) -> u32;
+    /// }
+    ///
+    /// struct Square(u32, Rc<()>);
+    ///
+    /// impl Shape for Square {
+    ///     fn area(&self) -> u32 {
+    ///         self.0 * self.0
+    ///     }
+    /// }
+    ///
+    /// let counter = Rc::new(());
+    /// let boxed: Box<dyn Shape> = Box::new(Square(3, counter.clone()));
+    ///
+    /// let boxed = stack_any::StackBox::<dyn Shape, 8>::try_from_box(boxed).err().unwrap();
+    /// let shape = stack_any::StackBox::<dyn Shape, 16>::try_from_box(boxed).ok().unwrap();
+    /// assert_eq!(shape.area(), 9);
+    /// assert_eq!(Rc::strong_count(&counter), 2);
+    ///
+    /// drop(shape);
+    /// assert_eq!(Rc::strong_count(&counter), 1);
+    /// ```
+    #[cfg(feature = "alloc")]
+    pub fn try_from_box(boxed: alloc::boxed::Box<Dyn>) -> Result<Self, alloc::boxed::Box<Dyn>> {
+        let layout = core::alloc::Layout::for_value(&*boxed);
+        if layout.size() > N || layout.align() > Self::ALIGN {
+            return Err(boxed);
+        }
+
+        let mut bytes = Buffer([core::mem::MaybeUninit::uninit(); N]);
+
+        let meta = alloc::boxed::Box::into_raw(boxed);
+        let src = meta as *const u8;
+        unsafe { core::ptr::copy_nonoverlapping(src, bytes.0.as_mut_ptr().cast(), layout.size()) };
+        if layout.size() != 0 {
+            unsafe { alloc::alloc::dealloc(meta.cast(), layout) };
+        }
+
+        Ok(Self {
+            bytes,
+            meta,
+            _marker: core::marker::PhantomData,
+        })
+    }
+
+    /// Returns the pointer to the contained value as `Dyn`.
+    fn as_dyn_ptr(&self) -> *const Dyn {
+        any_ptr(self.meta, 1, self.bytes.0.as_ptr() as *mut _)
+    }
+
+    /// Returns the mutable pointer to the contained value as `Dyn`.
+    fn as_dyn_ptr_mut(&mut self) -> *mut Dyn {
+        any_ptr(self.meta, 1, self.bytes.0.as_mut_ptr())
+    }
+}
+
+impl<Dyn, const N: usize> core::ops::Deref for StackBox<Dyn, N>
+where
+    Dyn: ?Sized,
+{
+    type Target = Dyn;
+
+    fn deref(&self) -> &Dyn {
+        unsafe { &*self.as_dyn_ptr() }
+    }
+}
+
+impl<Dyn, const N: usize> core::ops::DerefMut for StackBox<Dyn, N>
+where
+    Dyn: ?Sized,
+{
+    fn deref_mut(&mut self) -> &mut Dyn {
+        unsafe { &mut *self.as_dyn_ptr_mut() }
+    }
+}
+
+impl<Dyn, const N: usize> core::fmt::Debug for StackBox<Dyn, N>
+where
+    Dyn: ?Sized + core::fmt::Debug,
+{
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        core::fmt::Debug::fmt(&**self, f)
+    }
+}
+
+impl<Dyn, const N: usize> core::fmt::Display for StackBox<Dyn, N>
+where
+    Dyn: ?Sized + core::fmt::Display,
+{
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        core::fmt::Display::fmt(&**self, f)
+    }
+}
+
+/// Drops the contained value through `Dyn`, e.g. by the destructor in the vtable of a trait object.
+///
+/// # Examples
+///
+/// ```
+/// use std::rc::Rc;
+///
+/// trait Shape {}
+///
+/// struct Circle(Rc<()>);
+///
+/// impl Shape for Circle {}
+///
+/// let counter = Rc::new(());
+/// let shape = stack_any::StackBox::<dyn Shape, 8>::new_with(Circle(counter.clone()), |v| v as _);
+/// assert_eq!(Rc::strong_count(&counter), 2);
+///
+/// drop(shape);
+/// assert_eq!(Rc::strong_count(&counter), 1);
+/// ```
+impl<Dyn, const N: usize> Drop for StackBox<Dyn, N>
+where
+    Dyn: ?Sized,
+{
+    fn drop(&mut self) {
+        unsafe { core::ptr::drop_in_place(self.as_dyn_ptr_mut()) };
+    }
+}
+
+/// Allocates memory on the stack and then places value used as the given trait object type.
+///
+/// `stack_box!(dyn Trait, value)` takes its size from the annotated `StackBox<dyn Trait, N>`,
+/// and `stack_box!(dyn Trait; N, value)` sets it explicitly.
+/// Compilation fails if the value does not fit in either form.
+///
+/// # Examples
+///
+/// ```
+/// use stack_any::{stack_box, StackBox};
+///
+/// let offset = 10;
+/// let factor = 3;
+///
+/// let table: [StackBox<dyn Fn(i32) -> i32, 24>; 4] = [
+///     stack_box!(dyn Fn(i32) -> i32, |x| x),
+///     stack_box!(dyn Fn(i32) -> i32, move |x| x + offset),
+///     stack_box!(dyn Fn(i32) -> i32, move |x| x * factor),
+///     stack_box!(dyn Fn(i32) -> i32, move |x| x * factor + offset),
+/// ];
+///
+/// let results: Vec<i32> = table.iter().map(|f| f(2)).collect();
+/// assert_eq!(results, [2, 12, 6, 16]);
+/// ```
+///
+/// ```
+/// let debug = stack_any::stack_box!(dyn std::fmt::Debug + Send; 8, 5i32);
+/// std::thread::spawn(move || assert_eq!(format!("{:?}", debug), "5"));
+/// ```
+///
+/// ```compile_fail
+/// let debug = stack_any::stack_box!(dyn std::fmt::Debug; 4, 5i64);
+/// ```
+#[macro_export]
+macro_rules! stack_box {
+    ($dyn:ty, $init:expr) => {
+        $crate::StackBox::<$dyn, _>::new_with($init, |value| value as &mut $dyn)
+    };
+    ($dyn:ty; $size:expr, $init:expr) => {
+        $crate::StackBox::<$dyn, { $size }>::new_with($init, |value| value as &mut $dyn)
+    };
+}
@@ -0,0 +1,428 @@
+use crate::{CapacityError, StackBox};
+
+/// A type that owns a stack allocation of `N` size containing an error.
+///
+/// Any `E: core::error::Error + Send + Sync + 'static` is erased without boxing.
+/// An error which does not fit is rejected explicitly: at compile time by [`StackError::new`],
+/// or at runtime by [`StackError::try_new`] and [`StackError::try_new_checked`].
+///
+/// Like `anyhow::Error`, `StackError` does not implement `Error` itself, so that any error can be converted into it.
+/// Instead, it dereferences to `dyn Error`, which provides [`source`](core::error::Error::source).
+/// The alternate format `{:#}` prints the whole chain of sources.
+///
+/// # Examples
+///
+/// ```
+/// use std::error::Error;
+/// use std::fmt;
+///
+/// #[derive(Debug)]
+/// struct NotFound;
+///
+/// impl fmt::Display for NotFound {
+///     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+///         write!(f, "not found")
+///     }
+/// }
+///
+/// impl Error for NotFound {}
+///
+/// #[derive(Debug)]
+/// struct LoadError(NotFound);
+///
+/// impl fmt::Display for LoadError {
+///     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+///         write!(f, "failed to load")
+///     }
+/// }
+///
+/// impl Error for LoadError {
+///     fn source(&self) -> Option<&(dyn Error + 'static)> {
+///         Some(&self.0)
+///     }
+/// }
+///
+/// let err = stack_any::StackError::<8>::new(LoadError(NotFound));
+///
+/// assert_eq!(err.to_string(), "failed to load");
+/// assert_eq!(format!("{:#}", err), "failed to load: not found");
+/// assert!(err.source().unwrap().is::<NotFound>());
+/// assert!(err.downcast_ref::<LoadError>().is_some());
+/// ```
+pub struct StackError<const N: usize> {
+    error: StackBox<dyn core::error::Error + Send + Sync + 'static, N>,
+}
+
+impl<const N: usize> StackError<N> {
+    /// Allocates N-size memory on the stack and then places `error` into it.
+    ///
+    /// Whether `E` fits is checked at compile time, as in [`StackAny::new`](crate::StackAny::new).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let err = stack_any::StackError::<8>::new(std::fmt::Error);
+    /// assert_eq!(err.to_string(), "an error occurred when formatting an argument");
+    /// ```
+    ///
+    /// ```compile_fail
+    /// let err = stack_any::StackError::<4>::new(std::io::Error::other("x"));
+    /// ```
+    pub fn new<E>(error: E) -> Self
+    where
+        E: core::error::Error + Send + Sync + 'static,
+    {
+        Self {
+            error: StackBox::new_with(error, |error| error as _),
+        }
+    }
+
+    /// Allocates N-size memory on the stack and then places `error` into it.
+    /// Returns None if `E` size is larger than N or `E` alignment is larger than [`StackBox::ALIGN`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let err = stack_any::StackError::<4>::try_new(std::io::Error::other("x"));
+    /// assert!(err.is_none());
+    /// ```
+    pub fn try_new<E>(error: E) -> Option<Self>
+    where
+        E: core::error::Error + Send + Sync + 'static,
+    {
+        Self::try_new_checked(error).ok()
+    }
+
+    /// Allocates N-size memory on the stack and then places `error` into it.
+    /// Returns [`CapacityError`] carrying `error` back if `E` size is larger than N
+    /// or `E` alignment is larger than [`StackBox::ALIGN`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let err = stack_any::StackError::<4>::try_new_checked(std::io::Error::other("x")).unwrap_err();
+    /// assert_eq!(err.into_value().to_string(), "x");
+    /// ```
+    pub fn try_new_checked<E>(error: E) -> Result<Self, CapacityError<E>>
+    where
+        E: core::error::Error + Send + Sync + 'static,
+    {
+        type Dyn = dyn core::error::Error + Send + Sync + 'static;
+
+        if !StackBox::<Dyn, N>::fits::<E>() {
+            return Err(CapacityError::new(error, N, StackBox::<Dyn, N>::ALIGN));
+        }
+
+        match StackBox::try_new_with(error, |error| error as _) {
+            Some(error) => Ok(Self { error }),
+            None => unreachable!(),
+        }
+    }
+
+    /// Returns true if the contained error type is equal to `E`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let err = stack_any::StackError::<8>::new(std::fmt::Error);
+    /// assert!(err.is::<std::fmt::Error>());
+    /// assert!(!err.is::<std::io::Error>());
+    /// ```
+    pub fn is<E>(&self) -> bool
+    where
+        E: core::error::Error + 'static,
+    {
+        self.error.is::<E>()
+    }
+
+    /// Attempt to return reference to the inner error as a concrete type.
+    /// Returns None if `E` is not equal to contained error type.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let err = stack_any::StackError::<8>::new(std::io::Error::other("x"));
+    /// assert_eq!(err.downcast_ref::<std::io::Error>().map(|err| err.kind()), Some(std::io::ErrorKind::Other));
+    /// assert!(err.downcast_ref::<std::fmt::Error>().is_none());
+    /// ```
+    pub fn downcast_ref<E>(&self) -> Option<&E>
+    where
+        E: core::error::Error + 'static,
+    {
+        self.error.downcast_ref()
+    }
+
+    /// Attempt to return mutable reference to the inner error as a concrete type.
+    /// Returns None if `E` is not equal to contained error type.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// #[derive(Debug)]
+    /// struct Retry(u32);
+    ///
+    /// impl std::fmt::Display for Retry {
+    ///     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    ///         write!(f, "retried {} times", self.0)
+    ///     }
+    /// }
+    ///
+    /// impl std::error::Error for Retry {}
+    ///
+    /// let mut err = stack_any::StackError::<8>::new(Retry(1));
+    /// err.downcast_mut::<Retry>().unwrap().0 += 1;
+    /// assert_eq!(err.to_string(), "retried 2 times");
+    /// ```
+    pub fn downcast_mut<E>(&mut self) -> Option<&mut E>
+    where
+        E: core::error::Error + 'static,
+    {
+        self.error.downcast_mut()
+    }
+}
+
+impl<const N: usize> core::ops::Deref for StackError<N> {
+    type Target = dyn core::error::Error + Send + Sync + 'static;
+
+    fn deref(&self) -> &Self::Target {
+        &*self.error
+    }
+}
+
+impl<const N: usize> core::ops::DerefMut for StackError<N> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut *self.error
+    }
+}
+
+impl<const N: usize> AsRef<dyn core::error::Error + Send + Sync + 'static> for StackError<N> {
+    fn as_ref(&self) -> &(dyn core::error::Error + Send + Sync + 'static) {
+        &*self.error
+    }
+}
+
+impl<const N: usize> AsRef<dyn core::error::Error + 'static> for StackError<N> {
+    fn as_ref(&self) -> &(dyn core::error::Error + 'static) {
+        &*self.error
+    }
+}
+
+/// Moves the contained error onto the heap.
+///
+/// # Examples
+///
+/// ```
+/// let err = stack_any::StackError::<8>::new(std::fmt::Error);
+///
+/// let boxed: Box<dyn std::error::Error + Send + Sync> = err.into();
+/// assert!(boxed.is::<std::fmt::Error>());
+/// ```
+#[cfg(feature = "alloc")]
+impl<const N: usize> From<StackError<N>>
+    for alloc::boxed::Box<dyn core::error::Error + Send + Sync + 'static>
+{
+    fn from(value: StackError<N>) -> Self {
+        value.error.into_box()
+    }
+}
+
+impl<const N: usize> core::fmt::Debug for StackError<N> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        core::fmt::Debug::fmt(&*self.error, f)
+    }
+}
+
+impl<const N: usize> core::fmt::Display for StackError<N> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        core::fmt::Display::fmt(&*self.error, f)?;
+
+        if f.alternate() {
+            let mut source = self.error.source();
+            while let Some(error) = source {
+                write!(f, ": {}", error)?;
+                source = error.source();
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Converts any error into `StackError` by [`StackError::new`], so that `?` converts it implicitly.
+///
+/// An error which does not fit fails to compile at the conversion, since its size is known there.
+/// Convert it by [`StackError::try_new_checked`] instead to handle it at runtime.
+///
+/// # Examples
+///
+/// ```
+/// fn parse(text: &str) -> Result<i32, stack_any::StackError<16>> {
+///     let value: i32 = text.parse()?;
+///     Ok(value)
+/// }
+///
+/// assert_eq!(parse("5").ok(), Some(5));
+///
+/// let err = parse("x").unwrap_err();
+/// assert!(err.is::<std::num::ParseIntError>());
+/// assert_eq!(err.to_string(), "invalid digit found in string");
+/// ```
+///
+/// ```compile_fail
+/// fn read() -> Result<(), stack_any::StackError<4>> {
+///     Err(std::io::Error::other("x"))?
+/// }
+///
+/// let _ = read();
+/// ```
+impl<E, const N: usize> From<E> for StackError<N>
+where
+    E: core::error::Error + Send + Sync + 'static,
+{
+    fn from(value: E) -> Self {
+        Self::new(value)
+    }
+}
+
+/// Provides [`Context::context`] and [`Context::with_context`] on `Result`, like `anyhow::Context`.
+///
+/// The context is stored inline next to the error, and the error becomes its source.
+/// Since a [`StackError`] is stored as a whole, giving it context needs capacity for it as well as for the context.
+///
+/// # Examples
+///
+/// ```
+/// use stack_any::{Context, StackError};
+///
+/// fn parse(text: &str) -> Result<i32, StackError<32>> {
+///     text.parse::<i32>().context("failed to parse")
+/// }
+///
+/// fn load(text: &str) -> Result<i32, StackError<64>> {
+///     parse(text).with_context(|| "failed to load")
+/// }
+///
+/// let err = load("x").unwrap_err();
+/// assert_eq!(err.to_string(), "failed to load");
+/// assert_eq!(format!("{:#}", err), "failed to load: failed to parse: invalid digit found in string");
+///
+/// let source = err.source().unwrap();
+/// assert_eq!(source.to_string(), "failed to parse");
+/// assert!(source.source().unwrap().is::<std::num::ParseIntError>());
+/// ```
+///
+/// ```compile_fail
+/// use stack_any::{Context, StackError};
+///
+/// fn load(text: &str) -> Result<i32, StackError<32>> {
+///     let value: Result<i32, StackError<32>> = text.parse::<i32>().context("failed to parse");
+///     value.context("failed to load")
+/// }
+///
+/// let _ = load("x");
+/// ```
+pub trait Context<T> {
+    /// Wraps the error with `context`.
+    fn context<C, const N: usize>(self, context: C) -> Result<T, StackError<N>>
+    where
+        C: core::fmt::Display + core::fmt::Debug + Send + Sync + 'static;
+
+    /// Wraps the error with the context returned by `f`, which is only called on error.
+    fn with_context<C, F, const N: usize>(self, f: F) -> Result<T, StackError<N>>
+    where
+        C: core::fmt::Display + core::fmt::Debug + Send + Sync + 'static,
+        F: FnOnce() -> C;
+}
+
+impl<T, E> Context<T> for Result<T, E>
+where
+    E: core::error::Error + Send + Sync + 'static,
+{
+    fn context<C, const N: usize>(self, context: C) -> Result<T, StackError<N>>
+    where
+        C: core::fmt::Display + core::fmt::Debug + Send + Sync + 'static,
+    {
+        self.map_err(|error| StackError::new(ContextError { context, error }))
+    }
+
+    fn with_context<C, F, const N: usize>(self, f: F) -> Result<T, StackError<N>>
+    where
+        C: core::fmt::Display + core::fmt::Debug + Send + Sync + 'static,
+        F: FnOnce() -> C,
+    {
+        self.map_err(|error| {
+            StackError::new(ContextError {
+                context: f(),
+                error,
+            })
+        })
+    }
+}
+
+impl<T, const M: usize> Context<T> for Result<T, StackError<M>> {
+    fn context<C, const N: usize>(self, context: C) -> Result<T, StackError<N>>
+    where
+        C: core::fmt::Display + core::fmt::Debug + Send + Sync + 'static,
+    {
+        self.map_err(|error| StackError::new(ContextError { context, error }))
+    }
+
+    fn with_context<C, F, const N: usize>(self, f: F) -> Result<T, StackError<N>>
+    where
+        C: core::fmt::Display + core::fmt::Debug + Send + Sync + 'static,
+        F: FnOnce() -> C,
+    {
+        self.map_err(|error| {
+            StackError::new(ContextError {
+                context: f(),
+                error,
+            })
+        })
+    }
+}
+
+/// An error that displays `context` and has `error` as its source.
+#[derive(Debug)]
+struct ContextError<C, E> {
+    context: C,
+    error: E,
+}
+
+/// An error which can be the source of [`ContextError`], i.e. a concrete error or [`StackError`].
+trait Source: core::fmt::Debug {
+    fn as_source(&self) -> &(dyn core::error::Error + 'static);
+}
+
+impl<E> Source for E
+where
+    E: core::error::Error + 'static,
+{
+    fn as_source(&self) -> &(dyn core::error::Error + 'static) {
+        self
+    }
+}
+
+impl<const N: usize> Source for StackError<N> {
+    fn as_source(&self) -> &(dyn core::error::Error + 'static) {
+        self.as_ref()
+    }
+}
+
+impl<C, E> core::fmt::Display for ContextError<C, E>
+where
+    C: core::fmt::Display,
+{
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        core::fmt::Display::fmt(&self.context, f)
+    }
+}
+
+impl<C, E> core::error::Error for ContextError<C, E>
+where
+    C: core::fmt::Display + core::fmt::Debug,
+    E: Source,
+{
+    fn source(&self) -> Option<&(dyn core::error::Error + 'static)> {
+        Some(self.error.as_source())
+    }
+}
@@ -0,0 +1,558 @@
+use crate::{value_ptr, Buffer, CapacityError, StackAny};
+
+/// A type that owns a stack allocation of `N` size containing a closure called by `&self`.
+///
+/// The closure takes `Args` as a single argument, so multiple arguments are passed as a tuple.
+/// Besides the allocation, it only holds the functions to call and drop the closure,
+/// which are captured at construction.
+///
+/// # Examples
+///
+/// ```
+/// use stack_any::StackFn;
+///
+/// let offset = 10;
+///
+/// let table: [StackFn<(i32, i32), i32, 8>; 3] = [
+///     StackFn::new(|(a, b)| a + b),
+///     StackFn::new(|(a, b)| a * b),
+///     StackFn::new(move |(a, b)| a - b + offset),
+/// ];
+///
+/// let results: Vec<i32> = table.iter().map(|f| f.call((3, 2))).collect();
+/// assert_eq!(results, [5, 6, 11]);
+/// ```
+pub struct StackFn<Args, Out, const N: usize> {
+    bytes: Buffer<N>,
+    call_fn: unsafe fn(*mut core::mem::MaybeUninit<u8>, Args) -> Out,
+    drop_fn: Option<unsafe fn(*mut core::mem::MaybeUninit<u8>)>,
+    _marker: core::marker::PhantomData<*mut ()>,
+}
+
+impl<Args, Out, const N: usize> StackFn<Args, Out, N> {
+    /// Allocates N-size memory on the stack and then places `f` into it.
+    ///
+    /// Whether `F` fits is checked at compile time, as in [`StackAny::new`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let add_one = stack_any::StackFn::<i32, i32, 0>::new(|x| x + 1);
+    /// assert_eq!(add_one.call(5), 6);
+    /// ```
+    ///
+    /// ```compile_fail
+    /// let offset = 5i64;
+    /// let add = stack_any::StackFn::<i64, i64, 4>::new(move |x| x + offset);
+    /// ```
+    pub fn new<F>(f: F) -> Self
+    where
+        F: Fn(Args) -> Out + 'static,
+    {
+        const {
+            assert!(
+                StackAny::<N>::fits::<F>(),
+                "value does not fit in the stack"
+            )
+        };
+
+        match Self::try_new_checked(f) {
+            Ok(stack) => stack,
+            Err(_) => unreachable!(),
+        }
+    }
+
+    /// Allocates N-size memory on the stack and then places `f` into it.
+    /// Returns None if `F` size is larger than N or `F` alignment is larger than [`StackAny::ALIGN`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let name = String::from("x");
+    ///
+    /// let greet = stack_any::StackFn::<(), String, 8>::try_new(move |()| name.clone());
+    /// assert!(greet.is_none());
+    /// ```
+    pub fn try_new<F>(f: F) -> Option<Self>
+    where
+        F: Fn(Args) -> Out + 'static,
+    {
+        Self::try_new_checked(f).ok()
+    }
+
+    /// Allocates N-size memory on the stack and then places `f` into it.
+    /// Returns [`CapacityError`] carrying `f` back if `F` size is larger than N
+    /// or `F` alignment is larger than [`StackAny::ALIGN`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let offset = 5i64;
+    ///
+    /// let err = stack_any::StackFn::<i64, i64, 4>::try_new_checked(move |x| x + offset).unwrap_err();
+    /// assert_eq!((err.into_value())(1), 6);
+    /// ```
+    pub fn try_new_checked<F>(f: F) -> Result<Self, CapacityError<F>>
+    where
+        F: Fn(Args) -> Out + 'static,
+    {
+        if !StackAny::<N>::fits::<F>() {
+            return Err(CapacityError::new(f, N, StackAny::<N>::ALIGN));
+        }
+
+        let mut bytes = Buffer([core::mem::MaybeUninit::uninit(); N]);
+
+        let ptr = value_ptr::<F>(bytes.0.as_mut_ptr());
+        unsafe { core::ptr::write(ptr, f) };
+
+        Ok(Self {
+            bytes,
+            call_fn: |ptr, args| unsafe { (*value_ptr::<F>(ptr))(args) },
+            drop_fn: drop_fn::<F>(),
+            _marker: core::marker::PhantomData,
+        })
+    }
+
+    /// Calls the contained closure with `args`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let add = stack_any::StackFn::<(i32, i32), i32, 0>::new(|(a, b)| a + b);
+    /// assert_eq!(add.call((2, 3)), 5);
+    /// ```
+    pub fn call(&self, args: Args) -> Out {
+        unsafe { (self.call_fn)(self.bytes.0.as_ptr() as *mut _, args) }
+    }
+}
+
+impl<Args, Out, const N: usize> core::fmt::Debug for StackFn<Args, Out, N> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "StackFn<{}>", N)
+    }
+}
+
+/// Drops the contained closure together with its captures.
+///
+/// # Examples
+///
+/// ```
+/// use std::rc::Rc;
+///
+/// let counter = Rc::new(());
+/// let captured = counter.clone();
+///
+/// let count = stack_any::StackFn::<(), usize, 8>::new(move |()| Rc::strong_count(&captured));
+/// assert_eq!(count.call(()), 2);
+///
+/// drop(count);
+/// assert_eq!(Rc::strong_count(&counter), 1);
+/// ```
+impl<Args, Out, const N: usize> Drop for StackFn<Args, Out, N> {
+    fn drop(&mut self) {
+        if let Some(drop_fn) = self.drop_fn {
+            unsafe { drop_fn(self.bytes.0.as_mut_ptr()) };
+        }
+    }
+}
+
+/// A type that owns a stack allocation of `N` size containing a closure called by `&mut self`.
+///
+/// Unlike [`StackFn`], the closure may mutate its captures.
+///
+/// # Examples
+///
+/// ```
+/// use stack_any::StackFnMut;
+///
+/// let mut total = 0;
+/// let mut sum = StackFnMut::<i32, i32, 4>::new(move |x| {
+///     total += x;
+///     total
+/// });
+///
+/// assert_eq!(sum.call_mut(2), 2);
+/// assert_eq!(sum.call_mut(3), 5);
+/// ```
+pub struct StackFnMut<Args, Out, const N: usize> {
+    bytes: Buffer<N>,
+    call_fn: unsafe fn(*mut core::mem::MaybeUninit<u8>, Args) -> Out,
+    drop_fn: Option<unsafe fn(*mut core::mem::MaybeUninit<u8>)>,
+    _marker: core::marker::PhantomData<*mut ()>,
+}
+
+impl<Args, Out, const N: usize> StackFnMut<Args, Out, N> {
+    /// Allocates N-size memory on the stack and then places `f` into it.
+    ///
+    /// Whether `F` fits is checked at compile time, as in [`StackAny::new`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut count = 0;
+    /// let mut next = stack_any::StackFnMut::<(), i32, 4>::new(move |()| {
+    ///     count += 1;
+    ///     count
+    /// });
+    /// assert_eq!(next.call_mut(()), 1);
+    /// ```
+    ///
+    /// ```compile_fail
+    /// let mut count = 0i64;
+    /// let next = stack_any::StackFnMut::<(), i64, 4>::new(move |()| {
+    ///     count += 1;
+    ///     count
+    /// });
+    /// ```
+    pub fn new<F>(f: F) -> Self
+    where
+        F: FnMut(Args) -> Out + 'static,
+    {
+        const {
+            assert!(
+                StackAny::<N>::fits::<F>(),
+                "value does not fit in the stack"
+            )
+        };
+
+        match Self::try_new_checked(f) {
+            Ok(stack) => stack,
+            Err(_) => unreachable!(),
+        }
+    }
+
+    /// Allocates N-size memory on the stack and then places `f` into it.
+    /// Returns None if `F` size is larger than N or `F` alignment is larger than [`StackAny::ALIGN`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut names = Vec::new();
+    ///
+    /// let push = stack_any::StackFnMut::<String, (), 8>::try_new(move |name| names.push(name));
+    /// assert!(push.is_none());
+    /// ```
+    pub fn try_new<F>(f: F) -> Option<Self>
+    where
+        F: FnMut(Args) -> Out + 'static,
+    {
+        Self::try_new_checked(f).ok()
+    }
+
+    /// Allocates N-size memory on the stack and then places `f` into it.
+    /// Returns [`CapacityError`] carrying `f` back if `F` size is larger than N
+    /// or `F` alignment is larger than [`StackAny::ALIGN`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut count = 0i64;
+    ///
+    /// let err = stack_any::StackFnMut::<(), i64, 4>::try_new_checked(move |()| {
+    ///     count += 1;
+    ///     count
+    /// });
+    /// assert_eq!((err.unwrap_err().into_value())(()), 1);
+    /// ```
+    pub fn try_new_checked<F>(f: F) -> Result<Self, CapacityError<F>>
+    where
+        F: FnMut(Args) -> Out + 'static,
+    {
+        if !StackAny::<N>::fits::<F>() {
+            return Err(CapacityError::new(f, N, StackAny::<N>::ALIGN));
+        }
+
+        let mut bytes = Buffer([core::mem::MaybeUninit::uninit(); N]);
+
+        let ptr = value_ptr::<F>(bytes.0.as_mut_ptr());
+        unsafe { core::ptr::write(ptr, f) };
+
+        Ok(Self {
+            bytes,
+            call_fn: |ptr, args| unsafe { (*value_ptr::<F>(ptr))(args) },
+            drop_fn: drop_fn::<F>(),
+            _marker: core::marker::PhantomData,
+        })
+    }
+
+    /// Calls the contained closure with `args`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut calls = 0;
+    /// let mut count = stack_any::StackFnMut::<(), (), 4>::new(move |()| calls += 1);
+    /// count.call_mut(());
+    /// ```
+    pub fn call_mut(&mut self, args: Args) -> Out {
+        unsafe { (self.call_fn)(self.bytes.0.as_mut_ptr(), args) }
+    }
+}
+
+/// Calls the closure of [`StackFn`] by `&mut self`.
+///
+/// # Examples
+///
+/// ```
+/// let add_one = stack_any::StackFn::<i32, i32, 0>::new(|x| x + 1);
+///
+/// let mut add_one = stack_any::StackFnMut::from(add_one);
+/// assert_eq!(add_one.call_mut(5), 6);
+/// ```
+impl<Args, Out, const N: usize> From<StackFn<Args, Out, N>> for StackFnMut<Args, Out, N> {
+    fn from(value: StackFn<Args, Out, N>) -> Self {
+        let value = core::mem::ManuallyDrop::new(value);
+
+        Self {
+            bytes: Buffer(value.bytes.0),
+            call_fn: value.call_fn,
+            drop_fn: value.drop_fn,
+            _marker: core::marker::PhantomData,
+        }
+    }
+}
+
+impl<Args, Out, const N: usize> core::fmt::Debug for StackFnMut<Args, Out, N> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "StackFnMut<{}>", N)
+    }
+}
+
+/// Drops the contained closure together with its captures.
+///
+/// # Examples
+///
+/// ```
+/// use std::rc::Rc;
+///
+/// let counter = Rc::new(());
+/// let mut captured = Some(counter.clone());
+///
+/// let release = stack_any::StackFnMut::<(), (), 8>::new(move |()| captured = None);
+/// drop(release);
+/// assert_eq!(Rc::strong_count(&counter), 1);
+/// ```
+impl<Args, Out, const N: usize> Drop for StackFnMut<Args, Out, N> {
+    fn drop(&mut self) {
+        if let Some(drop_fn) = self.drop_fn {
+            unsafe { drop_fn(self.bytes.0.as_mut_ptr()) };
+        }
+    }
+}
+
+/// A type that owns a stack allocation of `N` size containing a closure called once by `self`.
+///
+/// The closure may consume its captures. If it is never called, they are dropped with `StackFnOnce`.
+///
+/// # Examples
+///
+/// ```
+/// use stack_any::StackFnOnce;
+///
+/// let name = String::from("x");
+/// let done = StackFnOnce::<&str, String, 24>::new(move |suffix| name + suffix);
+///
+/// assert_eq!(done.call_once("y"), "xy");
+/// ```
+pub struct StackFnOnce<Args, Out, const N: usize> {
+    bytes: Buffer<N>,
+    call_fn: unsafe fn(*mut core::mem::MaybeUninit<u8>, Args) -> Out,
+    drop_fn: Option<unsafe fn(*mut core::mem::MaybeUninit<u8>)>,
+    _marker: core::marker::PhantomData<*mut ()>,
+}
+
+impl<Args, Out, const N: usize> StackFnOnce<Args, Out, N> {
+    /// Allocates N-size memory on the stack and then places `f` into it.
+    ///
+    /// Whether `F` fits is checked at compile time, as in [`StackAny::new`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let values = vec![1, 2];
+    /// let take = stack_any::StackFnOnce::<(), Vec<i32>, 24>::new(move |()| values);
+    /// assert_eq!(take.call_once(()), [1, 2]);
+    /// ```
+    ///
+    /// ```compile_fail
+    /// let values = vec![1, 2];
+    /// let take = stack_any::StackFnOnce::<(), Vec<i32>, 8>::new(move |()| values);
+    /// ```
+    pub fn new<F>(f: F) -> Self
+    where
+        F: FnOnce(Args) -> Out + 'static,
+    {
+        const {
+            assert!(
+                StackAny::<N>::fits::<F>(),
+                "value does not fit in the stack"
+            )
+        };
+
+        match Self::try_new_checked(f) {
+            Ok(stack) => stack,
+            Err(_) => unreachable!(),
+        }
+    }
+
+    /// Allocates N-size memory on the stack and then places `f` into it.
+    /// Returns None if `F` size is larger than N or `F` alignment is larger than [`StackAny::ALIGN`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let values = vec![1, 2];
+    ///
+    /// let take = stack_any::StackFnOnce::<(), Vec<i32>, 8>::try_new(move |()| values);
+    /// assert!(take.is_none());
+    /// ```
+    pub fn try_new<F>(f: F) -> Option<Self>
+    where
+        F: FnOnce(Args) -> Out + 'static,
+    {
+        Self::try_new_checked(f).ok()
+    }
+
+    /// Allocates N-size memory on the stack and then places `f` into it.
+    /// Returns [`CapacityError`] carrying `f` back if `F` size is larger than N
+    /// or `F` alignment is larger than [`StackAny::ALIGN`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let values = vec![1, 2];
+    ///
+    /// let err = stack_any::StackFnOnce::<(), Vec<i32>, 8>::try_new_checked(move |()| values);
+    /// assert_eq!((err.unwrap_err().into_value())(()), [1, 2]);
+    /// ```
+    pub fn try_new_checked<F>(f: F) -> Result<Self, CapacityError<F>>
+    where
+        F: FnOnce(Args) -> Out + 'static,
+    {
+        if !StackAny::<N>::fits::<F>() {
+            return Err(CapacityError::new(f, N, StackAny::<N>::ALIGN));
+        }
+
+        let mut bytes = Buffer([core::mem::MaybeUninit::uninit(); N]);
+
+        let ptr = value_ptr::<F>(bytes.0.as_mut_ptr());
+        unsafe { core::ptr::write(ptr, f) };
+
+        Ok(Self {
+            bytes,
+            call_fn: |ptr, args| unsafe { core::ptr::read(value_ptr::<F>(ptr))(args) },
+            drop_fn: drop_fn::<F>(),
+            _marker: core::marker::PhantomData,
+        })
+    }
+
+    /// Calls the contained closure with `args`, consuming it.
+    ///
+    /// The closure is moved out before it runs, so its captures are dropped exactly once
+    /// even if it panics.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::rc::Rc;
+    ///
+    /// let counter = Rc::new(());
+    /// let captured = counter.clone();
+    ///
+    /// let done = stack_any::StackFnOnce::<(), usize, 8>::new(move |()| Rc::strong_count(&captured));
+    /// assert_eq!(done.call_once(()), 2);
+    /// assert_eq!(Rc::strong_count(&counter), 1);
+    /// ```
+    ///
+    /// ```
+    /// use std::rc::Rc;
+    ///
+    /// let counter = Rc::new(());
+    /// let captured = counter.clone();
+    ///
+    /// let done = stack_any::StackFnOnce::<(), (), 8>::new(move |()| {
+    ///     let _captured = captured;
+    ///     panic!("failed");
+    /// });
+    ///
+    /// let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| done.call_once(())));
+    /// assert!(result.is_err());
+    /// assert_eq!(Rc::strong_count(&counter), 1);
+    /// ```
+    pub fn call_once(self, args: Args) -> Out {
+        let mut this = core::mem::ManuallyDrop::new(self);
+        unsafe { (this.call_fn)(this.bytes.0.as_mut_ptr(), args) }
+    }
+}
+
+impl<Args, Out, const N: usize> core::fmt::Debug for StackFnOnce<Args, Out, N> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "StackFnOnce<{}>", N)
+    }
+}
+
+/// Drops the contained closure together with its captures, unless it was called.
+///
+/// # Examples
+///
+/// ```
+/// use std::rc::Rc;
+///
+/// let counter = Rc::new(());
+/// let captured = counter.clone();
+///
+/// let done = stack_any::StackFnOnce::<(), Rc<()>, 8>::new(move |()| captured);
+/// assert_eq!(Rc::strong_count(&counter), 2);
+///
+/// drop(done);
+/// assert_eq!(Rc::strong_count(&counter), 1);
+/// ```
+impl<Args, Out, const N: usize> Drop for StackFnOnce<Args, Out, N> {
+    fn drop(&mut self) {
+        if let Some(drop_fn) = self.drop_fn {
+            unsafe { drop_fn(self.bytes.0.as_mut_ptr()) };
+        }
+    }
+}
+
+/// Returns the function dropping `F` placed at the given pointer, or None if `F` needs no drop.
+fn drop_fn<F>() -> Option<unsafe fn(*mut core::mem::MaybeUninit<u8>)> {
+    match core::mem::needs_drop::<F>() {
+        true => Some(|ptr| unsafe { core::ptr::drop_in_place(value_ptr::<F>(ptr)) }),
+        false => None,
+    }
+}
+
+/// Allocates memory on the stack and then places the given closure as [`StackFn`].
+///
+/// `stack_fn!(f)` takes its size from the annotated `StackFn<Args, Out, N>`,
+/// and `stack_fn!(N, f)` sets it explicitly.
+/// Compilation fails if the closure does not fit in either form.
+///
+/// # Examples
+///
+/// ```
+/// use stack_any::{stack_fn, StackFn};
+///
+/// let offset = 10;
+///
+/// let table: [StackFn<i32, i32, 4>; 2] = [stack_fn!(|x| x * 2), stack_fn!(move |x| x + offset)];
+/// assert_eq!(table.iter().map(|f| f.call(1)).collect::<Vec<_>>(), [2, 11]);
+///
+/// let add = stack_fn!(8, move |x: i64| x + 1);
+/// assert_eq!(add.call(1), 2);
+/// ```
+///
+/// ```compile_fail
+/// let offset = 10i64;
+/// let add = stack_any::stack_fn!(4, move |x: i64| x + offset);
+/// ```
+#[macro_export]
+macro_rules! stack_fn {
+    ($f:expr) => {
+        $crate::StackFn::<_, _, _>::new($f)
+    };
+    ($size:expr, $f:expr) => {
+        $crate::StackFn::<_, _, { $size }>::new($f)
+    };
+}
@@ -0,0 +1,227 @@
+use crate::{value_ptr, Buffer, CapacityError, StackAny};
+
+/// A type that owns a stack allocation of `N` size containing a future, and is a future itself.
+///
+/// Polling is delegated to the contained future through the function captured at construction.
+/// `StackFuture` is never `Unpin`, so it is polled through `Pin` and the contained future is never moved
+/// once it is polled. If it is dropped before completion, the contained future is dropped in place.
+///
+/// # Examples
+///
+/// ```
+/// use std::future::Future;
+/// use std::pin::pin;
+/// use std::task::{Context, Poll, Waker};
+///
+/// use stack_any::StackFuture;
+///
+/// struct YieldNow(bool);
+///
+/// impl Future for YieldNow {
+///     type Output = ();
+///
+///     fn poll(mut self: std::pin::Pin<&mut Self>, _: &mut Context<'_>) -> Poll<()> {
+///         if std::mem::replace(&mut self.0, true) {
+///             return Poll::Ready(());
+///         }
+///         Poll::Pending
+///     }
+/// }
+///
+/// async fn double(x: u32) -> u32 {
+///     YieldNow(false).await;
+///     x * 2
+/// }
+///
+/// async fn sum() -> u32 {
+///     let values = [1, 2, 3];
+///     let values = &values;
+///     YieldNow(false).await;
+///     values.iter().sum()
+/// }
+///
+/// fn block_on<F: Future>(future: F) -> F::Output {
+///     let mut future = pin!(future);
+///     let mut cx = Context::from_waker(Waker::noop());
+///     loop {
+///         if let Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+///             return output;
+///         }
+///     }
+/// }
+///
+/// let futures: [StackFuture<u32, 64>; 3] = [
+///     StackFuture::new(double(5)),
+///     StackFuture::new(sum()),
+///     StackFuture::new(async { 7 }),
+/// ];
+///
+/// let results: Vec<u32> = futures.into_iter().map(block_on).collect();
+/// assert_eq!(results, [10, 6, 7]);
+/// ```
+///
+/// A polled `StackFuture` can not be moved out of its pin.
+///
+/// ```compile_fail
+/// let mut future = std::pin::pin!(stack_any::StackFuture::<u32, 8>::new(async { 5 }));
+/// let future: &mut stack_any::StackFuture<u32, 8> = std::pin::Pin::get_mut(future.as_mut());
+/// ```
+pub struct StackFuture<Out, const N: usize> {
+    bytes: Buffer<N>,
+    poll_fn: unsafe fn(
+        *mut core::mem::MaybeUninit<u8>,
+        &mut core::task::Context<'_>,
+    ) -> core::task::Poll<Out>,
+    drop_fn: Option<unsafe fn(*mut core::mem::MaybeUninit<u8>)>,
+    _marker: core::marker::PhantomData<*mut ()>,
+    _pinned: core::marker::PhantomPinned,
+}
+
+impl<Out, const N: usize> StackFuture<Out, N> {
+    /// Allocates N-size memory on the stack and then places `future` into it.
+    ///
+    /// Whether `F` fits is checked at compile time, as in [`StackAny::new`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let five = stack_any::StackFuture::<i32, 8>::new(async { 5 });
+    /// ```
+    ///
+    /// ```compile_fail
+    /// let values = [0u8; 64];
+    /// let sum = stack_any::StackFuture::<u32, 8>::new(async move { values.len() as u32 });
+    /// ```
+    pub fn new<F>(future: F) -> Self
+    where
+        F: core::future::Future<Output = Out> + 'static,
+    {
+        const {
+            assert!(
+                StackAny::<N>::fits::<F>(),
+                "value does not fit in the stack"
+            )
+        };
+
+        match Self::try_new_checked(future) {
+            Ok(stack) => stack,
+            Err(_) => unreachable!(),
+        }
+    }
+
+    /// Allocates N-size memory on the stack and then places `future` into it.
+    /// Returns None if `F` size is larger than N or `F` alignment is larger than [`StackAny::ALIGN`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let values = [0u8; 64];
+    ///
+    /// let sum = stack_any::StackFuture::<u32, 8>::try_new(async move { values.len() as u32 });
+    /// assert!(sum.is_none());
+    /// ```
+    pub fn try_new<F>(future: F) -> Option<Self>
+    where
+        F: core::future::Future<Output = Out> + 'static,
+    {
+        Self::try_new_checked(future).ok()
+    }
+
+    /// Allocates N-size memory on the stack and then places `future` into it.
+    /// Returns [`CapacityError`] carrying `future` back if `F` size is larger than N
+    /// or `F` alignment is larger than [`StackAny::ALIGN`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let values = [0u8; 64];
+    ///
+    /// let err = stack_any::StackFuture::<u32, 8>::try_new_checked(async move { values.len() as u32 });
+    /// assert!(err.is_err());
+    /// ```
+    pub fn try_new_checked<F>(future: F) -> Result<Self, CapacityError<F>>
+    where
+        F: core::future::Future<Output = Out> + 'static,
+    {
+        if !StackAny::<N>::fits::<F>() {
+            return Err(CapacityError::new(future, N, StackAny::<N>::ALIGN));
+        }
+
+        let mut bytes = Buffer([core::mem::MaybeUninit::uninit(); N]);
+
+        let ptr = value_ptr::<F>(bytes.0.as_mut_ptr());
+        unsafe { core::ptr::write(ptr, future) };
+
+        let drop_fn: Option<unsafe fn(_)> = match core::mem::needs_drop::<F>() {
+            true => Some(|ptr| unsafe { core::ptr::drop_in_place(value_ptr::<F>(ptr)) }),
+            false => None,
+        };
+
+        Ok(Self {
+            bytes,
+            // SAFETY: the contained future is structurally pinned.
+            poll_fn: |ptr, cx| unsafe {
+                let future = core::pin::Pin::new_unchecked(&mut *value_ptr::<F>(ptr));
+                core::future::Future::poll(future, cx)
+            },
+            drop_fn,
+            _marker: core::marker::PhantomData,
+            _pinned: core::marker::PhantomPinned,
+        })
+    }
+}
+
+impl<Out, const N: usize> core::future::Future for StackFuture<Out, N> {
+    type Output = Out;
+
+    fn poll(
+        self: core::pin::Pin<&mut Self>,
+        cx: &mut core::task::Context<'_>,
+    ) -> core::task::Poll<Out> {
+        // SAFETY: `self` is not moved out of the pin, and the contained future is structurally pinned.
+        let this = unsafe { self.get_unchecked_mut() };
+
+        // The buffer is not borrowed as a whole, which would invalidate references into a self-referential future.
+        let ptr = core::ptr::addr_of_mut!(this.bytes).cast();
+        unsafe { (this.poll_fn)(ptr, cx) }
+    }
+}
+
+impl<Out, const N: usize> core::fmt::Debug for StackFuture<Out, N> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "StackFuture<{}>", N)
+    }
+}
+
+/// Drops the contained future in place, even if it is not finished.
+///
+/// # Examples
+///
+/// ```
+/// use std::future::{pending, Future};
+/// use std::rc::Rc;
+/// use std::task::{Context, Poll, Waker};
+///
+/// let counter = Rc::new(());
+/// let captured = counter.clone();
+///
+/// let mut future = Box::pin(stack_any::StackFuture::<(), 32>::new(async move {
+///     let _captured = captured;
+///     pending::<()>().await;
+/// }));
+///
+/// let mut cx = Context::from_waker(Waker::noop());
+/// assert_eq!(future.as_mut().poll(&mut cx), Poll::Pending);
+/// assert_eq!(Rc::strong_count(&counter), 2);
+///
+/// drop(future);
+/// assert_eq!(Rc::strong_count(&counter), 1);
+/// ```
+impl<Out, const N: usize> Drop for StackFuture<Out, N> {
+    fn drop(&mut self) {
+        if let Some(drop_fn) = self.drop_fn {
+            let ptr = core::ptr::addr_of_mut!(self.bytes).cast();
+            unsafe { drop_fn(ptr) };
+        }
+    }
+}
@@ -0,0 +1,237 @@
+use crate::{CapacityError, StackBox};
+
+/// A type that owns a stack allocation of `N` size containing a hasher, and is a hasher itself.
+///
+/// Any `H: core::hash::Hasher + 'static` is erased without boxing, and every method of `Hasher`
+/// is delegated to the contained hasher, so the hash is identical to the one of `H`.
+///
+/// # Examples
+///
+/// ```
+/// use std::collections::hash_map::DefaultHasher;
+/// use std::hash::{Hash, Hasher};
+///
+/// use stack_any::StackHasher;
+///
+/// /// Counts the written bytes instead of hashing them.
+/// #[derive(Default)]
+/// struct CountHasher(u64);
+///
+/// impl Hasher for CountHasher {
+///     fn finish(&self) -> u64 {
+///         self.0
+///     }
+///
+///     fn write(&mut self, bytes: &[u8]) {
+///         self.0 += bytes.len() as u64;
+///     }
+/// }
+///
+/// fn hasher(count: bool) -> StackHasher<72> {
+///     match count {
+///         true => StackHasher::new(CountHasher::default()),
+///         false => StackHasher::new(DefaultHasher::new()),
+///     }
+/// }
+///
+/// let mut counted = hasher(true);
+/// (5u32, "xy").hash(&mut counted);
+/// assert_eq!(counted.finish(), 4 + 2 + 1);
+///
+/// let mut concrete = DefaultHasher::new();
+/// (5u32, "xy").hash(&mut concrete);
+///
+/// let mut erased = hasher(false);
+/// (5u32, "xy").hash(&mut erased);
+/// assert_eq!(erased.finish(), concrete.finish());
+/// ```
+pub struct StackHasher<const N: usize> {
+    hasher: StackBox<dyn core::hash::Hasher, N>,
+}
+
+impl<const N: usize> StackHasher<N> {
+    /// Allocates N-size memory on the stack and then places `hasher` into it.
+    ///
+    /// Whether `H` fits is checked at compile time, as in [`StackAny::new`](crate::StackAny::new).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let hasher = stack_any::StackHasher::<72>::new(std::collections::hash_map::DefaultHasher::new());
+    /// ```
+    ///
+    /// ```compile_fail
+    /// let hasher = stack_any::StackHasher::<64>::new(std::collections::hash_map::DefaultHasher::new());
+    /// ```
+    pub fn new<H>(hasher: H) -> Self
+    where
+        H: core::hash::Hasher + 'static,
+    {
+        Self {
+            hasher: StackBox::new_with(hasher, |hasher| hasher as _),
+        }
+    }
+
+    /// Allocates N-size memory on the stack and then places `hasher` into it.
+    /// Returns None if `H` size is larger than N or `H` alignment is larger than [`StackBox::ALIGN`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let hasher = stack_any::StackHasher::<64>::try_new(std::collections::hash_map::DefaultHasher::new());
+    /// assert!(hasher.is_none());
+    /// ```
+    pub fn try_new<H>(hasher: H) -> Option<Self>
+    where
+        H: core::hash::Hasher + 'static,
+    {
+        Self::try_new_checked(hasher).ok()
+    }
+
+    /// Allocates N-size memory on the stack and then places `hasher` into it.
+    /// Returns [`CapacityError`] carrying `hasher` back if `H` size is larger than N
+    /// or `H` alignment is larger than [`StackBox::ALIGN`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::hash::Hasher;
+    ///
+    /// let hasher = std::collections::hash_map::DefaultHasher::new();
+    /// let expected = hasher.finish();
+    ///
+    /// let err = stack_any::StackHasher::<64>::try_new_checked(hasher).unwrap_err();
+    /// assert_eq!(err.into_value().finish(), expected);
+    /// ```
+    pub fn try_new_checked<H>(hasher: H) -> Result<Self, CapacityError<H>>
+    where
+        H: core::hash::Hasher + 'static,
+    {
+        type Dyn = dyn core::hash::Hasher;
+
+        if !StackBox::<Dyn, N>::fits::<H>() {
+            return Err(CapacityError::new(hasher, N, StackBox::<Dyn, N>::ALIGN));
+        }
+
+        match StackBox::try_new_with(hasher, |hasher| hasher as _) {
+            Some(hasher) => Ok(Self { hasher }),
+            None => unreachable!(),
+        }
+    }
+
+    /// Allocates N-size memory on the stack and then places the hasher built by `build_hasher` into it.
+    ///
+    /// Whether the hasher fits is checked at compile time, as in [`StackHasher::new`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::collections::hash_map::RandomState;
+    /// use std::hash::{BuildHasher, Hash, Hasher};
+    ///
+    /// let state = RandomState::new();
+    ///
+    /// let mut hasher = stack_any::StackHasher::<72>::from_build_hasher(&state);
+    /// "x".hash(&mut hasher);
+    /// assert_eq!(hasher.finish(), state.hash_one("x"));
+    /// ```
+    pub fn from_build_hasher<B>(build_hasher: &B) -> Self
+    where
+        B: core::hash::BuildHasher,
+        B::Hasher: 'static,
+    {
+        Self::new(build_hasher.build_hasher())
+    }
+}
+
+/// Delegates every method to the contained hasher, including the ones with a default implementation.
+///
+/// # Examples
+///
+/// ```
+/// use std::collections::hash_map::DefaultHasher;
+/// use std::hash::Hasher;
+///
+/// fn feed(hasher: &mut impl Hasher) -> u64 {
+///     hasher.write(b"x");
+///     hasher.write_u8(1);
+///     hasher.write_u16(2);
+///     hasher.write_u32(3);
+///     hasher.write_u64(4);
+///     hasher.write_u128(5);
+///     hasher.write_usize(6);
+///     hasher.write_i8(-1);
+///     hasher.write_i16(-2);
+///     hasher.write_i32(-3);
+///     hasher.write_i64(-4);
+///     hasher.write_i128(-5);
+///     hasher.write_isize(-6);
+///     hasher.finish()
+/// }
+///
+/// let mut hasher = stack_any::StackHasher::<72>::new(DefaultHasher::new());
+/// assert_eq!(feed(&mut hasher), feed(&mut DefaultHasher::new()));
+/// ```
+impl<const N: usize> core::hash::Hasher for StackHasher<N> {
+    fn finish(&self) -> u64 {
+        self.hasher.finish()
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        self.hasher.write(bytes)
+    }
+
+    fn write_u8(&mut self, i: u8) {
+        self.hasher.write_u8(i)
+    }
+
+    fn write_u16(&mut self, i: u16) {
+        self.hasher.write_u16(i)
+    }
+
+    fn write_u32(&mut self, i: u32) {
+        self.hasher.write_u32(i)
+    }
+
+    fn write_u64(&mut self, i: u64) {
+        self.hasher.write_u64(i)
+    }
+
+    fn write_u128(&mut self, i: u128) {
+        self.hasher.write_u128(i)
+    }
+
+    fn write_usize(&mut self, i: usize) {
+        self.hasher.write_usize(i)
+    }
+
+    fn write_i8(&mut self, i: i8) {
+        self.hasher.write_i8(i)
+    }
+
+    fn write_i16(&mut self, i: i16) {
+        self.hasher.write_i16(i)
+    }
+
+    fn write_i32(&mut self, i: i32) {
+        self.hasher.write_i32(i)
+    }
+
+    fn write_i64(&mut self, i: i64) {
+        self.hasher.write_i64(i)
+    }
+
+    fn write_i128(&mut self, i: i128) {
+        self.hasher.write_i128(i)
+    }
+
+    fn write_isize(&mut self, i: isize) {
+        self.hasher.write_isize(i)
+    }
+}
+
+impl<const N: usize> core::fmt::Debug for StackHasher<N> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "StackHasher<{}>", N)
+    }
+}
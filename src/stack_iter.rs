@@ -0,0 +1,168 @@
+use crate::{value_ptr, Buffer, CapacityError, StackAny};
+
+/// A type that owns a stack allocation of `N` size containing an iterator, and is an iterator itself.
+///
+/// `next` and `size_hint` are delegated to the contained iterator through the functions captured at construction.
+///
+/// # Examples
+///
+/// ```
+/// use stack_any::StackIter;
+///
+/// fn numbers(odd: bool, limit: u32) -> StackIter<u32, 16> {
+///     match odd {
+///         true => StackIter::new((0..limit).filter(|x| x % 2 == 1)),
+///         false => StackIter::new((0..limit).map(|x| x * 2)),
+///     }
+/// }
+///
+/// assert_eq!(numbers(true, 6).collect::<Vec<_>>(), [1, 3, 5]);
+/// assert_eq!(numbers(false, 3).collect::<Vec<_>>(), [0, 2, 4]);
+///
+/// let sum: u32 = numbers(false, 4).skip(1).take(2).chain(numbers(true, 4)).sum();
+/// assert_eq!(sum, 2 + 4 + 1 + 3);
+/// ```
+pub struct StackIter<Item, const N: usize> {
+    bytes: Buffer<N>,
+    next_fn: unsafe fn(*mut core::mem::MaybeUninit<u8>) -> Option<Item>,
+    size_hint_fn: unsafe fn(*mut core::mem::MaybeUninit<u8>) -> (usize, Option<usize>),
+    drop_fn: Option<unsafe fn(*mut core::mem::MaybeUninit<u8>)>,
+    _marker: core::marker::PhantomData<*mut ()>,
+}
+
+impl<Item, const N: usize> StackIter<Item, N> {
+    /// Allocates N-size memory on the stack and then places `iter` into it.
+    ///
+    /// Whether `I` fits is checked at compile time, as in [`StackAny::new`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let chars = stack_any::StackIter::<char, 16>::new("ab".chars());
+    /// assert_eq!(chars.collect::<String>(), "ab");
+    /// ```
+    ///
+    /// ```compile_fail
+    /// let values = stack_any::StackIter::<u32, 16>::new(vec![1, 2].into_iter());
+    /// ```
+    pub fn new<I>(iter: I) -> Self
+    where
+        I: Iterator<Item = Item> + 'static,
+    {
+        const {
+            assert!(
+                StackAny::<N>::fits::<I>(),
+                "value does not fit in the stack"
+            )
+        };
+
+        match Self::try_new_checked(iter) {
+            Ok(stack) => stack,
+            Err(_) => unreachable!(),
+        }
+    }
+
+    /// Allocates N-size memory on the stack and then places `iter` into it.
+    /// Returns None if `I` size is larger than N or `I` alignment is larger than [`StackAny::ALIGN`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let values = stack_any::StackIter::<u32, 16>::try_new(vec![1, 2].into_iter());
+    /// assert!(values.is_none());
+    /// ```
+    pub fn try_new<I>(iter: I) -> Option<Self>
+    where
+        I: Iterator<Item = Item> + 'static,
+    {
+        Self::try_new_checked(iter).ok()
+    }
+
+    /// Allocates N-size memory on the stack and then places `iter` into it.
+    /// Returns [`CapacityError`] carrying `iter` back if `I` size is larger than N
+    /// or `I` alignment is larger than [`StackAny::ALIGN`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let err = stack_any::StackIter::<u32, 16>::try_new_checked(vec![1, 2].into_iter()).unwrap_err();
+    /// assert_eq!(err.into_value().collect::<Vec<_>>(), [1, 2]);
+    /// ```
+    pub fn try_new_checked<I>(iter: I) -> Result<Self, CapacityError<I>>
+    where
+        I: Iterator<Item = Item> + 'static,
+    {
+        if !StackAny::<N>::fits::<I>() {
+            return Err(CapacityError::new(iter, N, StackAny::<N>::ALIGN));
+        }
+
+        let mut bytes = Buffer([core::mem::MaybeUninit::uninit(); N]);
+
+        let ptr = value_ptr::<I>(bytes.0.as_mut_ptr());
+        unsafe { core::ptr::write(ptr, iter) };
+
+        let drop_fn: Option<unsafe fn(_)> = match core::mem::needs_drop::<I>() {
+            true => Some(|ptr| unsafe { core::ptr::drop_in_place(value_ptr::<I>(ptr)) }),
+            false => None,
+        };
+
+        Ok(Self {
+            bytes,
+            next_fn: |ptr| unsafe { (*value_ptr::<I>(ptr)).next() },
+            size_hint_fn: |ptr| unsafe { (*value_ptr::<I>(ptr)).size_hint() },
+            drop_fn,
+            _marker: core::marker::PhantomData,
+        })
+    }
+}
+
+impl<Item, const N: usize> Iterator for StackIter<Item, N> {
+    type Item = Item;
+
+    fn next(&mut self) -> Option<Item> {
+        unsafe { (self.next_fn)(self.bytes.0.as_mut_ptr()) }
+    }
+
+    /// Returns the bounds of the contained iterator.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let values = stack_any::StackIter::<u32, 16>::new(0..5);
+    /// assert_eq!(values.size_hint(), (5, Some(5)));
+    /// ```
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        unsafe { (self.size_hint_fn)(self.bytes.0.as_ptr() as *mut _) }
+    }
+}
+
+impl<Item, const N: usize> core::fmt::Debug for StackIter<Item, N> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "StackIter<{}>", N)
+    }
+}
+
+/// Drops the contained iterator, even if it is partially consumed.
+///
+/// # Examples
+///
+/// ```
+/// use std::rc::Rc;
+///
+/// let counter = Rc::new(());
+/// let captured = counter.clone();
+///
+/// let mut values = stack_any::StackIter::<usize, 24>::new((0..3).map(move |x| x + Rc::strong_count(&captured)));
+/// assert_eq!(values.next(), Some(2));
+/// assert_eq!(Rc::strong_count(&counter), 2);
+///
+/// drop(values);
+/// assert_eq!(Rc::strong_count(&counter), 1);
+/// ```
+impl<Item, const N: usize> Drop for StackIter<Item, N> {
+    fn drop(&mut self) {
+        if let Some(drop_fn) = self.drop_fn {
+            unsafe { drop_fn(self.bytes.0.as_mut_ptr()) };
+        }
+    }
+}
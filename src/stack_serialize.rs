@@ -0,0 +1,226 @@
+use crate::{CapacityError, StackAny};
+
+/// A convertible type that owns a stack allocation of `N` size containing a serializable value.
+///
+/// Unlike the tagged serialization of [`StackAny`], the contained value is serialized as it is,
+/// and the caller drives any serializer through [`StackSerialize::as_erased_serialize`] without naming the type.
+///
+/// # Examples
+///
+/// ```
+/// use stack_any::StackSerialize;
+///
+/// #[derive(serde::Serialize)]
+/// struct Position {
+///     x: i32,
+///     y: i32,
+/// }
+///
+/// // values from plugins unknown to the host
+/// let values = [
+///     StackSerialize::<24>::new(Position { x: 1, y: -2 }),
+///     StackSerialize::<24>::new(String::from("x")),
+/// ];
+///
+/// let json: Vec<String> = values
+///     .iter()
+///     .map(|value| serde_json::to_string(value.as_erased_serialize()).unwrap())
+///     .collect();
+/// assert_eq!(json, [r#"{"x":1,"y":-2}"#, r#""x""#]);
+///
+/// let bytes: Vec<Vec<u8>> = values
+///     .iter()
+///     .map(|value| bincode::serialize(value.as_erased_serialize()).unwrap())
+///     .collect();
+/// assert_eq!(bytes[0], bincode::serialize(&Position { x: 1, y: -2 }).unwrap());
+/// assert_eq!(bytes[1], bincode::serialize("x").unwrap());
+/// ```
+pub struct StackSerialize<const N: usize> {
+    stack: StackAny<N>,
+    as_serialize_fn: fn(&StackAny<N>) -> &dyn erased_serde::Serialize,
+}
+
+impl<const N: usize> StackSerialize<N> {
+    /// Allocates N-size memory on the stack and then places `value` into it.
+    ///
+    /// Whether `T` fits is checked at compile time, as in [`StackAny::new`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let value = stack_any::StackSerialize::<24>::new(vec![1, 2]);
+    /// ```
+    ///
+    /// ```compile_fail
+    /// let value = stack_any::StackSerialize::<8>::new(vec![1, 2]);
+    /// ```
+    pub fn new<T>(value: T) -> Self
+    where
+        T: serde::Serialize + 'static,
+    {
+        Self::from_stack_any::<T>(StackAny::new(value))
+    }
+
+    /// Allocates N-size memory on the stack and then places `value` into it.
+    /// Returns None if `T` size is larger than N or `T` alignment is larger than [`StackAny::ALIGN`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let value = stack_any::StackSerialize::<8>::try_new(vec![1, 2]);
+    /// assert!(value.is_none());
+    /// ```
+    pub fn try_new<T>(value: T) -> Option<Self>
+    where
+        T: serde::Serialize + 'static,
+    {
+        StackAny::try_new(value).map(Self::from_stack_any::<T>)
+    }
+
+    /// Allocates N-size memory on the stack and then places `value` into it.
+    /// Returns [`CapacityError`] carrying `value` back if `T` size is larger than N
+    /// or `T` alignment is larger than [`StackAny::ALIGN`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let err = stack_any::StackSerialize::<8>::try_new_checked(vec![1, 2]).unwrap_err();
+    /// assert_eq!(err.into_value(), [1, 2]);
+    /// ```
+    pub fn try_new_checked<T>(value: T) -> Result<Self, CapacityError<T>>
+    where
+        T: serde::Serialize + 'static,
+    {
+        StackAny::try_new_checked(value).map(Self::from_stack_any::<T>)
+    }
+
+    /// Captures the function serializing `T` contained by `stack`.
+    fn from_stack_any<T>(stack: StackAny<N>) -> Self
+    where
+        T: serde::Serialize + 'static,
+    {
+        Self {
+            stack,
+            as_serialize_fn: |stack| unsafe { stack.downcast_ref_unchecked::<T>() },
+        }
+    }
+
+    /// Returns the contained value as an erased serializable trait object.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let value = stack_any::StackSerialize::<16>::new((5u8, 'x'));
+    ///
+    /// let mut json = Vec::new();
+    /// let mut serializer = serde_json::Serializer::new(&mut json);
+    /// erased_serde::serialize(value.as_erased_serialize(), &mut serializer).unwrap();
+    /// assert_eq!(json, br#"[5,"x"]"#);
+    /// ```
+    pub fn as_erased_serialize(&self) -> &dyn erased_serde::Serialize {
+        (self.as_serialize_fn)(&self.stack)
+    }
+
+    /// Returns true if the contained value type is equal to `T`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let value = stack_any::StackSerialize::<16>::new(5i32);
+    /// assert!(value.is::<i32>());
+    /// assert!(!value.is::<u32>());
+    /// ```
+    pub fn is<T>(&self) -> bool
+    where
+        T: serde::Serialize + 'static,
+    {
+        self.stack.is::<T>()
+    }
+
+    /// Attempt to return reference to the inner value as a concrete type.
+    /// Returns None if `T` is not equal to contained value type.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let value = stack_any::StackSerialize::<16>::new(5i32);
+    /// assert_eq!(value.downcast_ref::<i32>(), Some(&5));
+    /// assert_eq!(value.downcast_ref::<u32>(), None);
+    /// ```
+    pub fn downcast_ref<T>(&self) -> Option<&T>
+    where
+        T: serde::Serialize + 'static,
+    {
+        self.stack.downcast_ref()
+    }
+
+    /// Attempt to return mutable reference to the inner value as a concrete type.
+    /// Returns None if `T` is not equal to contained value type.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut value = stack_any::StackSerialize::<16>::new(5i32);
+    /// *value.downcast_mut::<i32>().unwrap() += 1;
+    /// assert_eq!(value.downcast_ref::<i32>(), Some(&6));
+    /// ```
+    pub fn downcast_mut<T>(&mut self) -> Option<&mut T>
+    where
+        T: serde::Serialize + 'static,
+    {
+        self.stack.downcast_mut()
+    }
+
+    /// Attempt to downcast to a concrete type, recovering the value.
+    /// Returns the value back if `T` is not equal to contained value type.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let value = stack_any::StackSerialize::<16>::new(5i32);
+    ///
+    /// let value = value.downcast::<u32>().unwrap_err();
+    /// assert_eq!(value.downcast::<i32>().ok(), Some(5));
+    /// ```
+    pub fn downcast<T>(self) -> Result<T, Self>
+    where
+        T: serde::Serialize + 'static,
+    {
+        let Self {
+            stack,
+            as_serialize_fn,
+        } = self;
+
+        stack.downcast().map_err(|stack| Self {
+            stack,
+            as_serialize_fn,
+        })
+    }
+}
+
+/// Serializes the contained value as it is, without the type name.
+///
+/// # Examples
+///
+/// ```
+/// let values = [
+///     stack_any::StackSerialize::<24>::new(5i32),
+///     stack_any::StackSerialize::<24>::new(vec!['x', 'y']),
+/// ];
+///
+/// assert_eq!(serde_json::to_string(&values).unwrap(), r#"[5,["x","y"]]"#);
+/// ```
+impl<const N: usize> serde::Serialize for StackSerialize<N> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        self.as_erased_serialize().serialize(serializer)
+    }
+}
+
+impl<const N: usize> core::fmt::Debug for StackSerialize<N> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_tuple("StackSerialize").field(&self.stack).finish()
+    }
+}
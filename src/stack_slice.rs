@@ -0,0 +1,288 @@
+use crate::{value_ptr, Buffer, SliceCapacityError, StackAny};
+
+/// A type that owns a stack allocation of `N` size containing a slice of elements of a single type,
+/// decided at construction.
+///
+/// The stack records the type, the number and the stride of the elements, and drops every element
+/// when dropped.
+///
+/// # Examples
+///
+/// ```
+/// #[derive(Clone, Copy, Debug, PartialEq)]
+/// struct Vertex {
+///     position: [f32; 3],
+/// }
+///
+/// #[derive(Clone, Copy, Debug, PartialEq)]
+/// struct TexturedVertex {
+///     position: [f32; 3],
+///     uv: [f32; 2],
+/// }
+///
+/// let vertices = [Vertex { position: [0.0; 3] }; 16];
+/// let buffer = stack_any::StackSlice::<256>::try_from_slice(&vertices).unwrap();
+/// assert_eq!(buffer.downcast_slice::<Vertex>(), Some(&vertices[..]));
+/// assert!(buffer.downcast_slice::<TexturedVertex>().is_none());
+///
+/// let vertices = [TexturedVertex { position: [0.0; 3], uv: [1.0; 2] }; 16];
+/// let err = stack_any::StackSlice::<256>::try_from_slice(&vertices).unwrap_err();
+/// assert_eq!(err.max_len(), 12);
+/// assert!(stack_any::StackSlice::<256>::try_from_slice(&vertices[..8]).is_ok());
+/// ```
+pub struct StackSlice<const N: usize> {
+    bytes: Buffer<N>,
+    type_id: core::any::TypeId,
+    len: usize,
+    stride: usize,
+    drop_fn: Option<unsafe fn(*mut core::mem::MaybeUninit<u8>, usize)>,
+    _marker: core::marker::PhantomData<*mut ()>,
+}
+
+impl<const N: usize> StackSlice<N> {
+    /// Returns the number of elements of `T` that fit in the stack.
+    /// Returns 0 if `T` alignment is larger than [`StackAny::ALIGN`], and `usize::MAX` if `T` is zero-sized.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// assert_eq!(stack_any::StackSlice::<16>::max_len::<u32>(), 4);
+    /// assert_eq!(stack_any::StackSlice::<16>::max_len::<[u8; 3]>(), 5);
+    /// assert_eq!(stack_any::StackSlice::<16>::max_len::<()>(), usize::MAX);
+    /// ```
+    pub const fn max_len<T>() -> usize {
+        if core::mem::align_of::<T>() > StackAny::<N>::ALIGN {
+            return 0;
+        }
+
+        match N.checked_div(core::mem::size_of::<T>()) {
+            Some(len) => len,
+            None => usize::MAX,
+        }
+    }
+
+    /// Allocates N-size memory on the stack and then copies `slice` into it.
+    /// Returns [`SliceCapacityError`] reporting how many elements would have fit if `slice` does not fit.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let numbers = stack_any::StackSlice::<16>::try_from_slice(&[1u32, 2, 3]).unwrap();
+    /// assert_eq!(numbers.downcast_slice::<u32>(), Some(&[1, 2, 3][..]));
+    ///
+    /// let err = stack_any::StackSlice::<16>::try_from_slice(&[0u32; 5]).unwrap_err();
+    /// assert_eq!(err.requested_len(), 5);
+    /// assert_eq!(err.max_len(), 4);
+    /// ```
+    pub fn try_from_slice<T>(slice: &[T]) -> Result<Self, SliceCapacityError>
+    where
+        T: core::any::Any + Copy,
+    {
+        Self::try_from_iter(slice.iter().copied())
+    }
+
+    /// Allocates N-size memory on the stack and then places the elements of `iter` into it.
+    /// Returns [`SliceCapacityError`] reporting how many elements would have fit if the elements do not fit,
+    /// in which case no element is taken from `iter`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let names = ["alice", "bob"].map(String::from);
+    /// let names = stack_any::StackSlice::<48>::try_from_iter(names).unwrap();
+    /// assert_eq!(names.downcast_slice::<String>().unwrap(), ["alice", "bob"]);
+    ///
+    /// let mut numbers = (0..10).map(|i| i.to_string());
+    /// let err = stack_any::StackSlice::<48>::try_from_iter(numbers.by_ref()).unwrap_err();
+    /// assert_eq!(err.max_len(), 2);
+    /// assert_eq!(numbers.len(), 10);
+    /// ```
+    pub fn try_from_iter<T, I>(iter: I) -> Result<Self, SliceCapacityError>
+    where
+        T: core::any::Any,
+        I: IntoIterator<Item = T>,
+        I::IntoIter: ExactSizeIterator,
+    {
+        let iter = iter.into_iter();
+        let len = iter.len();
+        if Self::max_len::<T>() < len {
+            return Err(SliceCapacityError::new(len, Self::max_len::<T>()));
+        }
+
+        let drop_fn: Option<unsafe fn(_, _)> = match core::mem::needs_drop::<T>() {
+            true => Some(|ptr, len| unsafe {
+                let slice = core::ptr::slice_from_raw_parts_mut(value_ptr::<T>(ptr), len);
+                core::ptr::drop_in_place(slice)
+            }),
+            false => None,
+        };
+
+        let mut stack = Self {
+            bytes: Buffer([core::mem::MaybeUninit::uninit(); N]),
+            type_id: core::any::TypeId::of::<T>(),
+            len: 0,
+            stride: core::mem::size_of::<T>(),
+            drop_fn,
+            _marker: core::marker::PhantomData,
+        };
+
+        // The number is counted as each element is placed, so that the placed elements are dropped
+        // if the iterator panics, and an iterator reporting a wrong length never overflows the stack.
+        for value in iter.take(len) {
+            let ptr = value_ptr::<T>(stack.bytes.0.as_mut_ptr());
+            unsafe { core::ptr::write(ptr.wrapping_add(stack.len), value) };
+            stack.len += 1;
+        }
+
+        Ok(stack)
+    }
+
+    /// Returns the number of elements.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let numbers = stack_any::StackSlice::<16>::try_from_slice(&[1u32, 2, 3]).unwrap();
+    /// assert_eq!(numbers.len(), 3);
+    /// ```
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns true if the slice has no elements.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let numbers = stack_any::StackSlice::<16>::try_from_slice::<u32>(&[]).unwrap();
+    /// assert!(numbers.is_empty());
+    /// ```
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the distance in bytes between the starts of consecutive elements.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let numbers = stack_any::StackSlice::<16>::try_from_slice(&[[1u8; 3], [2u8; 3]]).unwrap();
+    /// assert_eq!(numbers.stride(), 3);
+    /// ```
+    pub fn stride(&self) -> usize {
+        self.stride
+    }
+
+    /// Returns true if the element type is `T`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let numbers = stack_any::StackSlice::<16>::try_from_slice(&[1u32, 2, 3]).unwrap();
+    /// assert!(numbers.is::<u32>());
+    /// assert!(!numbers.is::<i32>());
+    /// ```
+    pub fn is<T>(&self) -> bool
+    where
+        T: core::any::Any,
+    {
+        self.type_id == core::any::TypeId::of::<T>()
+    }
+
+    /// Attempt to return the elements as a slice of a concrete type.
+    /// Returns None if `T` is not equal to the element type.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let numbers = stack_any::StackSlice::<16>::try_from_slice(&[1u32, 2, 3]).unwrap();
+    /// assert_eq!(numbers.downcast_slice::<u32>(), Some(&[1, 2, 3][..]));
+    /// assert_eq!(numbers.downcast_slice::<i32>(), None);
+    /// ```
+    pub fn downcast_slice<T>(&self) -> Option<&[T]>
+    where
+        T: core::any::Any,
+    {
+        if !self.is::<T>() {
+            return None;
+        }
+
+        let ptr = value_ptr::<T>(self.bytes.0.as_ptr() as *mut _);
+        Some(unsafe { core::slice::from_raw_parts(ptr, self.len) })
+    }
+
+    /// Attempt to return the elements as a mutable slice of a concrete type.
+    /// Returns None if `T` is not equal to the element type.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut numbers = stack_any::StackSlice::<16>::try_from_slice(&[3u32, 1, 2]).unwrap();
+    /// numbers.downcast_slice_mut::<u32>().unwrap().sort();
+    /// assert_eq!(numbers.downcast_slice::<u32>(), Some(&[1, 2, 3][..]));
+    /// assert!(numbers.downcast_slice_mut::<i32>().is_none());
+    /// ```
+    pub fn downcast_slice_mut<T>(&mut self) -> Option<&mut [T]>
+    where
+        T: core::any::Any,
+    {
+        if !self.is::<T>() {
+            return None;
+        }
+
+        let ptr = value_ptr::<T>(self.bytes.0.as_mut_ptr());
+        Some(unsafe { core::slice::from_raw_parts_mut(ptr, self.len) })
+    }
+}
+
+/// Formats the metadata of the elements.
+///
+/// # Examples
+///
+/// ```
+/// let numbers = stack_any::StackSlice::<16>::try_from_slice(&[1u32, 2, 3]).unwrap();
+/// assert_eq!(format!("{:?}", numbers), "StackSlice { capacity: 16, len: 3, stride: 4 }");
+/// ```
+impl<const N: usize> core::fmt::Debug for StackSlice<N> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("StackSlice")
+            .field("capacity", &N)
+            .field("len", &self.len)
+            .field("stride", &self.stride)
+            .finish()
+    }
+}
+
+/// Drops every element, or does nothing if the element type does not need to be dropped.
+///
+/// # Examples
+///
+/// ```
+/// use std::rc::Rc;
+///
+/// let counter = Rc::new(());
+///
+/// let counters = (0..3).map(|_| counter.clone());
+/// let counters = stack_any::StackSlice::<24>::try_from_iter(counters).unwrap();
+/// assert_eq!(Rc::strong_count(&counter), 4);
+///
+/// drop(counters);
+/// assert_eq!(Rc::strong_count(&counter), 1);
+/// ```
+///
+/// ```
+/// let names = ["alice", "bob", "carol"].map(String::from);
+/// let names = stack_any::StackSlice::<72>::try_from_iter(names).unwrap();
+/// assert_eq!(names.downcast_slice::<String>().unwrap().concat(), "alicebobcarol");
+/// drop(names);
+///
+/// let numbers = stack_any::StackSlice::<12>::try_from_slice(&[1u32, 2, 3]).unwrap();
+/// drop(numbers);
+/// ```
+impl<const N: usize> Drop for StackSlice<N> {
+    fn drop(&mut self) {
+        if let Some(drop_fn) = self.drop_fn {
+            unsafe { drop_fn(self.bytes.0.as_mut_ptr(), self.len) };
+        }
+    }
+}
@@ -0,0 +1,203 @@
+use futures_core::Stream;
+
+use crate::{value_ptr, Buffer, CapacityError, StackAny};
+
+/// A type that owns a stack allocation of `N` size containing a stream, and is a stream itself.
+///
+/// `poll_next` and `size_hint` are delegated to the contained stream through the functions captured at construction.
+/// As [`StackFuture`](crate::StackFuture), `StackStream` is never `Unpin`, so the contained stream is never moved
+/// once it is polled. If it is dropped before completion, the contained stream is dropped in place.
+///
+/// # Examples
+///
+/// ```
+/// use futures::channel::mpsc;
+/// use futures::executor::block_on;
+/// use futures::stream::{self, StreamExt};
+///
+/// use stack_any::StackStream;
+///
+/// let (tx, rx) = mpsc::unbounded();
+/// for event in [3, 4] {
+///     tx.unbounded_send(event).unwrap();
+/// }
+/// drop(tx);
+///
+/// let streams: [StackStream<u32, 32>; 2] = [
+///     StackStream::new(stream::iter([1, 2])),
+///     StackStream::new(rx),
+/// ];
+///
+/// let events: Vec<u32> = block_on(stream::iter(streams).flatten().collect());
+/// assert_eq!(events, [1, 2, 3, 4]);
+/// ```
+pub struct StackStream<Item, const N: usize> {
+    bytes: Buffer<N>,
+    poll_next_fn: unsafe fn(
+        *mut core::mem::MaybeUninit<u8>,
+        &mut core::task::Context<'_>,
+    ) -> core::task::Poll<Option<Item>>,
+    size_hint_fn: unsafe fn(*mut core::mem::MaybeUninit<u8>) -> (usize, Option<usize>),
+    drop_fn: Option<unsafe fn(*mut core::mem::MaybeUninit<u8>)>,
+    _marker: core::marker::PhantomData<*mut ()>,
+    _pinned: core::marker::PhantomPinned,
+}
+
+impl<Item, const N: usize> StackStream<Item, N> {
+    /// Allocates N-size memory on the stack and then places `stream` into it.
+    ///
+    /// Whether `S` fits is checked at compile time, as in [`StackAny::new`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let numbers = stack_any::StackStream::<u32, 32>::new(futures::stream::iter([1, 2]));
+    /// ```
+    ///
+    /// ```compile_fail
+    /// let numbers = stack_any::StackStream::<u32, 8>::new(futures::stream::iter([1, 2, 3, 4]));
+    /// ```
+    pub fn new<S>(stream: S) -> Self
+    where
+        S: Stream<Item = Item> + 'static,
+    {
+        const {
+            assert!(
+                StackAny::<N>::fits::<S>(),
+                "value does not fit in the stack"
+            )
+        };
+
+        match Self::try_new_checked(stream) {
+            Ok(stack) => stack,
+            Err(_) => unreachable!(),
+        }
+    }
+
+    /// Allocates N-size memory on the stack and then places `stream` into it.
+    /// Returns None if `S` size is larger than N or `S` alignment is larger than [`StackAny::ALIGN`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let numbers = stack_any::StackStream::<u32, 8>::try_new(futures::stream::iter([1, 2, 3, 4]));
+    /// assert!(numbers.is_none());
+    /// ```
+    pub fn try_new<S>(stream: S) -> Option<Self>
+    where
+        S: Stream<Item = Item> + 'static,
+    {
+        Self::try_new_checked(stream).ok()
+    }
+
+    /// Allocates N-size memory on the stack and then places `stream` into it.
+    /// Returns [`CapacityError`] carrying `stream` back if `S` size is larger than N
+    /// or `S` alignment is larger than [`StackAny::ALIGN`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let numbers = futures::stream::iter([1u32, 2, 3, 4]);
+    ///
+    /// let err = stack_any::StackStream::<u32, 8>::try_new_checked(numbers);
+    /// assert!(err.is_err());
+    /// ```
+    pub fn try_new_checked<S>(stream: S) -> Result<Self, CapacityError<S>>
+    where
+        S: Stream<Item = Item> + 'static,
+    {
+        if !StackAny::<N>::fits::<S>() {
+            return Err(CapacityError::new(stream, N, StackAny::<N>::ALIGN));
+        }
+
+        let mut bytes = Buffer([core::mem::MaybeUninit::uninit(); N]);
+
+        let ptr = value_ptr::<S>(bytes.0.as_mut_ptr());
+        unsafe { core::ptr::write(ptr, stream) };
+
+        let drop_fn: Option<unsafe fn(_)> = match core::mem::needs_drop::<S>() {
+            true => Some(|ptr| unsafe { core::ptr::drop_in_place(value_ptr::<S>(ptr)) }),
+            false => None,
+        };
+
+        Ok(Self {
+            bytes,
+            // SAFETY: the contained stream is structurally pinned.
+            poll_next_fn: |ptr, cx| unsafe {
+                let stream = core::pin::Pin::new_unchecked(&mut *value_ptr::<S>(ptr));
+                stream.poll_next(cx)
+            },
+            size_hint_fn: |ptr| unsafe { (*value_ptr::<S>(ptr)).size_hint() },
+            drop_fn,
+            _marker: core::marker::PhantomData,
+            _pinned: core::marker::PhantomPinned,
+        })
+    }
+}
+
+impl<Item, const N: usize> Stream for StackStream<Item, N> {
+    type Item = Item;
+
+    fn poll_next(
+        self: core::pin::Pin<&mut Self>,
+        cx: &mut core::task::Context<'_>,
+    ) -> core::task::Poll<Option<Item>> {
+        // SAFETY: `self` is not moved out of the pin, and the contained stream is structurally pinned.
+        let this = unsafe { self.get_unchecked_mut() };
+
+        // The buffer is not borrowed as a whole, which would invalidate references into a self-referential stream.
+        let ptr = core::ptr::addr_of_mut!(this.bytes).cast();
+        unsafe { (this.poll_next_fn)(ptr, cx) }
+    }
+
+    /// Returns the bounds of the contained stream.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use futures::Stream;
+    ///
+    /// let numbers = stack_any::StackStream::<u32, 32>::new(futures::stream::iter([1, 2]));
+    /// assert_eq!(numbers.size_hint(), (2, Some(2)));
+    /// ```
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let ptr = core::ptr::addr_of!(self.bytes) as *mut _;
+        unsafe { (self.size_hint_fn)(ptr) }
+    }
+}
+
+impl<Item, const N: usize> core::fmt::Debug for StackStream<Item, N> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "StackStream<{}>", N)
+    }
+}
+
+/// Drops the contained stream in place, even if it is partially consumed.
+///
+/// # Examples
+///
+/// ```
+/// use std::rc::Rc;
+///
+/// use futures::executor::block_on;
+/// use futures::stream::{self, StreamExt};
+///
+/// let counter = Rc::new(());
+/// let captured = counter.clone();
+///
+/// let numbers = stream::iter(0..3).map(move |x| x + Rc::strong_count(&captured));
+/// let mut numbers = Box::pin(stack_any::StackStream::<usize, 32>::new(numbers));
+/// assert_eq!(block_on(numbers.next()), Some(2));
+/// assert_eq!(Rc::strong_count(&counter), 2);
+///
+/// drop(numbers);
+/// assert_eq!(Rc::strong_count(&counter), 1);
+/// ```
+impl<Item, const N: usize> Drop for StackStream<Item, N> {
+    fn drop(&mut self) {
+        if let Some(drop_fn) = self.drop_fn {
+            let ptr = core::ptr::addr_of_mut!(self.bytes).cast();
+            unsafe { drop_fn(ptr) };
+        }
+    }
+}
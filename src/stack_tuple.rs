@@ -0,0 +1,387 @@
+use crate::{value_ptr, Buffer, StackAny};
+
+/// The type, the place and the destructor of a value in a [`StackTuple`].
+#[derive(Clone, Copy)]
+struct Member {
+    type_id: core::any::TypeId,
+    offset: usize,
+    drop_fn: Option<unsafe fn(*mut core::mem::MaybeUninit<u8>)>,
+}
+
+/// A type that packs up to `CAP` values of different types one after another into a single stack allocation of `N` size,
+/// like an erased tuple.
+///
+/// Each value is placed at the end of the previous one, padded to the alignment of its type,
+/// and is referred to by the index of its insertion. Values are dropped in the order of insertion,
+/// as the fields of a tuple.
+///
+/// # Examples
+///
+/// ```
+/// let mut args = stack_any::StackTuple::<24>::new();
+/// assert_eq!(args.push(7u8), Ok(0));
+/// assert_eq!(args.push(1.5f64), Ok(1));
+/// assert_eq!(args.push('x'), Ok(2));
+///
+/// assert_eq!(args.get::<u8>(0), Some(&7));
+/// assert_eq!(args.get::<f64>(1), Some(&1.5));
+/// assert_eq!(args.get::<char>(2), Some(&'x'));
+/// assert_eq!(args.get::<u32>(2), None);
+///
+/// // `u64` does not fit in the rest of the stack.
+/// assert_eq!(args.push(5u64), Err(5));
+/// ```
+pub struct StackTuple<const N: usize, const CAP: usize = 8> {
+    bytes: Buffer<N>,
+    members: [Option<Member>; CAP],
+    len: usize,
+    end: usize,
+    _marker: core::marker::PhantomData<*mut ()>,
+}
+
+impl<const N: usize, const CAP: usize> StackTuple<N, CAP> {
+    /// Creates an empty tuple.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// const ARGS: stack_any::StackTuple<16> = stack_any::StackTuple::new();
+    /// assert!(ARGS.is_empty());
+    /// ```
+    pub const fn new() -> Self {
+        Self {
+            bytes: Buffer([core::mem::MaybeUninit::uninit(); N]),
+            members: [None; CAP],
+            len: 0,
+            end: 0,
+            _marker: core::marker::PhantomData,
+        }
+    }
+
+    /// Returns the number of values.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut args = stack_any::StackTuple::<16>::new();
+    /// args.push(5u32).unwrap();
+    /// args.push(7u32).unwrap();
+    /// assert_eq!(args.len(), 2);
+    /// ```
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns true if the tuple contains no values.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let args = stack_any::StackTuple::<16>::new();
+    /// assert!(args.is_empty());
+    /// ```
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Places `value` after the last value, padded to the alignment of `T`, and returns its index.
+    /// Returns `value` back if the tuple already has `CAP` values, if `value` does not fit in the rest of
+    /// the stack, or if `T` alignment is larger than [`StackAny::ALIGN`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut args = stack_any::StackTuple::<12>::new();
+    /// args.push(1u8).unwrap();
+    ///
+    /// // `u32` is placed at the offset 4 after 3 bytes of padding.
+    /// args.push(2u32).unwrap();
+    /// assert_eq!(args.offset(1), Some(4));
+    ///
+    /// // `u64` does not fit in the rest 4 bytes, while `u16` does.
+    /// assert_eq!(args.push(3u64), Err(3));
+    ///
+    /// args.push(4u16).unwrap();
+    /// assert_eq!(args.offset(2), Some(8));
+    /// ```
+    ///
+    /// ```
+    /// let mut args = stack_any::StackTuple::<16, 1>::new();
+    /// args.push(1u8).unwrap();
+    /// assert_eq!(args.push(2u8), Err(2));
+    /// ```
+    pub fn push<T>(&mut self, value: T) -> Result<usize, T>
+    where
+        T: core::any::Any,
+    {
+        let size = core::mem::size_of::<T>();
+        let align = core::mem::align_of::<T>();
+        if self.len == CAP || align > StackAny::<N>::ALIGN {
+            return Err(value);
+        }
+
+        let offset = self.end.next_multiple_of(align);
+        if offset + size > N {
+            return Err(value);
+        }
+
+        let ptr = unsafe { self.bytes.0.as_mut_ptr().add(offset) };
+        unsafe { core::ptr::write(value_ptr::<T>(ptr), value) };
+
+        let drop_fn: Option<unsafe fn(_)> = match core::mem::needs_drop::<T>() {
+            true => Some(|ptr| unsafe { core::ptr::drop_in_place(value_ptr::<T>(ptr)) }),
+            false => None,
+        };
+
+        self.members[self.len] = Some(Member {
+            type_id: core::any::TypeId::of::<T>(),
+            offset,
+            drop_fn,
+        });
+        self.len += 1;
+        self.end = offset + size;
+        Ok(self.len - 1)
+    }
+
+    /// Returns the member at `index` if its type is `T`.
+    fn member<T>(&self, index: usize) -> Option<Member>
+    where
+        T: core::any::Any,
+    {
+        let member = (*self.members.get(index)?)?;
+        (member.type_id == core::any::TypeId::of::<T>()).then_some(member)
+    }
+
+    /// Returns reference to the value at `index` as a concrete type.
+    /// Returns None if `index` is out of bounds, or if `T` is not equal to its type.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut args = stack_any::StackTuple::<16>::new();
+    /// args.push(5i32).unwrap();
+    ///
+    /// assert_eq!(args.get::<i32>(0), Some(&5));
+    /// assert_eq!(args.get::<u32>(0), None);
+    /// assert_eq!(args.get::<i32>(1), None);
+    /// ```
+    pub fn get<T>(&self, index: usize) -> Option<&T>
+    where
+        T: core::any::Any,
+    {
+        let member = self.member::<T>(index)?;
+        let ptr = unsafe { self.bytes.0.as_ptr().add(member.offset) };
+        Some(unsafe { &*value_ptr::<T>(ptr as *mut _) })
+    }
+
+    /// Returns mutable reference to the value at `index` as a concrete type.
+    /// Returns None if `index` is out of bounds, or if `T` is not equal to its type.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut args = stack_any::StackTuple::<16>::new();
+    /// args.push(5i32).unwrap();
+    ///
+    /// *args.get_mut::<i32>(0).unwrap() += 1;
+    /// assert_eq!(args.get::<i32>(0), Some(&6));
+    /// assert_eq!(args.get_mut::<u32>(0), None);
+    /// ```
+    pub fn get_mut<T>(&mut self, index: usize) -> Option<&mut T>
+    where
+        T: core::any::Any,
+    {
+        let member = self.member::<T>(index)?;
+        let ptr = unsafe { self.bytes.0.as_mut_ptr().add(member.offset) };
+        Some(unsafe { &mut *value_ptr::<T>(ptr) })
+    }
+
+    /// Returns the offset in bytes of the value at `index` from the start of the stack.
+    /// Returns None if `index` is out of bounds.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut args = stack_any::StackTuple::<24>::new();
+    /// args.push(1u8).unwrap();
+    /// args.push(2u16).unwrap();
+    /// args.push(3u8).unwrap();
+    /// args.push(4u64).unwrap();
+    ///
+    /// assert_eq!(args.offset(0), Some(0));
+    /// assert_eq!(args.offset(1), Some(2));
+    /// assert_eq!(args.offset(2), Some(4));
+    /// assert_eq!(args.offset(3), Some(8));
+    /// assert_eq!(args.offset(4), None);
+    /// ```
+    pub fn offset(&self, index: usize) -> Option<usize> {
+        Some((*self.members.get(index)?)?.offset)
+    }
+}
+
+impl<const N: usize, const CAP: usize> Default for StackTuple<N, CAP> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize, const CAP: usize> core::fmt::Debug for StackTuple<N, CAP> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "StackTuple<{}> {{ len: {} }}", N, self.len)
+    }
+}
+
+/// Drops the values in the order of insertion.
+/// If a destructor panics, the following values are still dropped, as the fields of a tuple.
+///
+/// # Examples
+///
+/// ```
+/// use std::cell::RefCell;
+///
+/// thread_local! {
+///     static LOG: RefCell<Vec<&'static str>> = RefCell::new(Vec::new());
+/// }
+///
+/// struct Logged(&'static str);
+///
+/// impl Drop for Logged {
+///     fn drop(&mut self) {
+///         LOG.with(|log| log.borrow_mut().push(self.0));
+///     }
+/// }
+///
+/// let mut args = stack_any::StackTuple::<56>::new();
+/// args.push(Logged("a")).unwrap_or_else(|_| unreachable!());
+/// args.push(5u8).unwrap();
+/// args.push(Logged("b")).unwrap_or_else(|_| unreachable!());
+/// args.push(Logged("c")).unwrap_or_else(|_| unreachable!());
+/// drop(args);
+///
+/// LOG.with(|log| assert_eq!(*log.borrow(), ["a", "b", "c"]));
+///
+/// // The same order as a tuple.
+/// LOG.with(|log| log.borrow_mut().clear());
+/// drop((Logged("a"), 5u8, Logged("b"), Logged("c")));
+/// LOG.with(|log| assert_eq!(*log.borrow(), ["a", "b", "c"]));
+/// ```
+///
+/// ```
+/// use std::rc::Rc;
+///
+/// struct Bomb;
+///
+/// impl Drop for Bomb {
+///     fn drop(&mut self) {
+///         panic!("boom");
+///     }
+/// }
+///
+/// let counter = Rc::new(());
+///
+/// let mut args = stack_any::StackTuple::<16>::new();
+/// args.push(Bomb).unwrap_or_else(|_| unreachable!());
+/// args.push(counter.clone()).unwrap();
+///
+/// let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| drop(args)));
+/// assert!(result.is_err());
+/// assert_eq!(Rc::strong_count(&counter), 1);
+/// ```
+impl<const N: usize, const CAP: usize> Drop for StackTuple<N, CAP> {
+    fn drop(&mut self) {
+        /// Drops the rest of the values if a destructor panics.
+        struct Guard<'a, const N: usize, const CAP: usize>(&'a mut StackTuple<N, CAP>);
+
+        impl<const N: usize, const CAP: usize> Drop for Guard<'_, N, CAP> {
+            fn drop(&mut self) {
+                self.0.drop_members();
+            }
+        }
+
+        let guard = Guard(self);
+        guard.0.drop_members();
+        core::mem::forget(guard);
+    }
+}
+
+impl<const N: usize, const CAP: usize> StackTuple<N, CAP> {
+    /// Drops the values not dropped yet in the order of insertion.
+    fn drop_members(&mut self) {
+        for index in 0..self.len {
+            // The member is taken first, so that it is not dropped again after its destructor panics.
+            let Some(member) = self.members[index].take() else {
+                continue;
+            };
+
+            if let Some(drop_fn) = member.drop_fn {
+                let ptr = unsafe { self.bytes.0.as_mut_ptr().add(member.offset) };
+                unsafe { drop_fn(ptr) };
+            }
+        }
+    }
+}
+
+/// Creates a [`StackTuple`] of the given values, with the size of the stack and the capacity
+/// computed at compile time from the listed types, including the padding between them.
+///
+/// # Examples
+///
+/// ```
+/// let args = stack_any::stack_tuple!(u8 => 1, u32 => 2, String => String::from("x"));
+/// assert_eq!(args.len(), 3);
+/// assert_eq!(args.get::<u8>(0), Some(&1));
+/// assert_eq!(args.get::<u32>(1), Some(&2));
+/// assert_eq!(args.get::<String>(2).unwrap(), "x");
+///
+/// // `u32` follows 3 bytes of padding, and `String` follows no padding.
+/// assert_eq!(args.offset(1), Some(4));
+/// assert_eq!(args.offset(2), Some(8));
+///
+/// let args: stack_any::StackTuple<32, 3> = args;
+/// ```
+///
+/// ```
+/// let args = stack_any::stack_tuple!();
+/// assert!(args.is_empty());
+///
+/// const N: usize = 4;
+/// const CAP: usize = 16;
+///
+/// let args = stack_any::stack_tuple!(u64 => N as u64, [u8; CAP] => [0; CAP]);
+/// assert_eq!(args.get::<u64>(0), Some(&4));
+/// let args: stack_any::StackTuple<24, 2> = args;
+/// ```
+///
+/// ```compile_fail
+/// #[repr(align(16))]
+/// struct Aligned(u8);
+///
+/// let args = stack_any::stack_tuple!(u8 => 5, Aligned => Aligned(7));
+/// ```
+#[macro_export]
+macro_rules! stack_tuple {
+    (@size $($type:ty),*) => {
+        $crate::packed_size(&[$((::core::mem::size_of::<$type>(), ::core::mem::align_of::<$type>())),*])
+    };
+    ($($type:ty => $init:expr),* $(,)?) => {{
+        const {
+            ::core::assert!(
+                $crate::max_align_of!($($type),*)
+                    <= $crate::StackAny::<{ $crate::stack_tuple!(@size $($type),*) }>::ALIGN,
+                "value does not fit in the stack"
+            )
+        };
+
+        #[allow(unused_mut)]
+        let mut tuple = $crate::StackTuple::<
+            { $crate::stack_tuple!(@size $($type),*) },
+            { <[&str]>::len(&[$(::core::stringify!($type)),*]) },
+        >::new();
+        $(
+            if tuple.push::<$type>($init).is_err() {
+                ::core::unreachable!();
+            }
+        )*
+        tuple
+    }};
+}
@@ -0,0 +1,184 @@
+use crate::{CapacityError, StackAny, StackAnySend};
+
+/// A convertible type that owns a stack allocation of `N` size and can be shared across threads.
+///
+/// Only `Send + Sync` values can be placed into it, so it is `Send + Sync` itself.
+///
+/// # Examples
+///
+/// ```
+/// let stack = stack_any::stack_any_sync!(Vec<i32>, vec![5]);
+///
+/// std::thread::scope(|s| {
+///     for _ in 0..4 {
+///         s.spawn(|| assert_eq!(stack.downcast_ref::<Vec<i32>>(), Some(&vec![5])));
+///     }
+/// });
+/// ```
+///
+/// ```compile_fail
+/// let stack = stack_any::StackAnySync::<8>::try_new(std::cell::Cell::new(5));
+/// ```
+#[derive(Debug)]
+pub struct StackAnySync<const N: usize>(StackAny<N>);
+
+// SAFETY: every constructor requires the contained value to be `Send`.
+unsafe impl<const N: usize> Send for StackAnySync<N> {}
+
+// SAFETY: every constructor requires the contained value to be `Sync`.
+unsafe impl<const N: usize> Sync for StackAnySync<N> {}
+
+impl<const N: usize> StackAnySync<N> {
+    /// Allocates N-size memory on the stack and then places `value` into it.
+    ///
+    /// Whether `T` fits is checked at compile time, so this never fails at runtime.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let five = stack_any::StackAnySync::<8>::new(5u64);
+    /// assert_eq!(five.downcast_ref::<u64>(), Some(&5));
+    /// ```
+    ///
+    /// ```compile_fail
+    /// let five = stack_any::StackAnySync::<4>::new(5u64);
+    /// ```
+    pub fn new<T>(value: T) -> Self
+    where
+        T: core::any::Any + Send + Sync,
+    {
+        Self(StackAny::new(value))
+    }
+
+    /// Allocates N-size memory on the stack and then places `value` into it.
+    /// Returns None if `T` size is larger than N or `T` alignment is larger than [`StackAny::ALIGN`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let five = stack_any::StackAnySync::<{ std::mem::size_of::<i32>() }>::try_new(5);
+    /// ```
+    pub fn try_new<T>(value: T) -> Option<Self>
+    where
+        T: core::any::Any + Send + Sync,
+    {
+        StackAny::try_new(value).map(Self)
+    }
+
+    /// Allocates N-size memory on the stack and then places `value` into it.
+    /// Returns [`CapacityError`] carrying `value` back if `T` size is larger than N
+    /// or `T` alignment is larger than [`StackAny::ALIGN`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let err = stack_any::StackAnySync::<4>::try_new_checked(5i64).unwrap_err();
+    /// assert_eq!(err.to_string(), "value of type i64 needs 8 bytes but capacity is 4");
+    /// ```
+    pub fn try_new_checked<T>(value: T) -> Result<Self, CapacityError<T>>
+    where
+        T: core::any::Any + Send + Sync,
+    {
+        StackAny::try_new_checked(value).map(Self)
+    }
+
+    /// Attempt to return reference to the inner value as a concrete type.
+    /// Returns None if `T` is not equal to contained value type.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let five = stack_any::stack_any_sync!(i32, 5);
+    /// assert_eq!(five.downcast_ref::<i32>(), Some(&5));
+    /// assert_eq!(five.downcast_ref::<i64>(), None);
+    /// ```
+    pub fn downcast_ref<T>(&self) -> Option<&T>
+    where
+        T: core::any::Any,
+    {
+        self.0.downcast_ref()
+    }
+
+    /// Attempt to return mutable reference to the inner value as a concrete type.
+    /// Returns None if `T` is not equal to contained value type.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut five = stack_any::stack_any_sync!(i32, 5);
+    /// assert_eq!(five.downcast_mut::<i32>(), Some(&mut 5));
+    /// assert_eq!(five.downcast_mut::<i64>(), None);
+    /// ```
+    pub fn downcast_mut<T>(&mut self) -> Option<&mut T>
+    where
+        T: core::any::Any,
+    {
+        self.0.downcast_mut()
+    }
+
+    /// Attempt to downcast the stack to a concrete type.
+    /// Returns the stack back if `T` is not equal to contained value type.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let five = stack_any::stack_any_sync!(i32, 5);
+    /// assert_eq!(five.downcast::<i32>().ok(), Some(5));
+    /// ```
+    pub fn downcast<T>(self) -> Result<T, Self>
+    where
+        T: core::any::Any,
+    {
+        self.0.downcast().map_err(Self)
+    }
+
+    /// Converts into the plain [`StackAny`], forgetting that the contained value is `Send + Sync`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let five = stack_any::stack_any_sync!(i32, 5).into_stack_any();
+    /// assert_eq!(five.downcast_ref::<i32>(), Some(&5));
+    /// ```
+    pub fn into_stack_any(self) -> StackAny<N> {
+        self.0
+    }
+
+    /// Converts into the [`StackAnySend`], forgetting that the contained value is `Sync`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let five = stack_any::stack_any_sync!(i32, 5).into_stack_any_send();
+    /// assert_eq!(five.downcast_ref::<i32>(), Some(&5));
+    /// ```
+    pub fn into_stack_any_send(self) -> StackAnySend<N> {
+        StackAnySend(self.0)
+    }
+}
+
+impl<const N: usize> From<StackAnySync<N>> for StackAny<N> {
+    fn from(value: StackAnySync<N>) -> Self {
+        value.into_stack_any()
+    }
+}
+
+impl<const N: usize> From<StackAnySync<N>> for StackAnySend<N> {
+    fn from(value: StackAnySync<N>) -> Self {
+        value.into_stack_any_send()
+    }
+}
+
+/// Allocates memory on the stack and then places `Send + Sync` value based on given type and value.
+///
+/// # Examples
+///
+/// ```
+/// let five = stack_any::stack_any_sync!(i32, 5);
+/// ```
+#[macro_export]
+macro_rules! stack_any_sync {
+    ($type:ty, $init:expr) => {
+        $crate::StackAnySync::<{ ::core::mem::size_of::<$type>() }>::new::<$type>($init)
+    };
+}
@@ -0,0 +1,261 @@
+use crate::{CapacityError, StackAny};
+
+/// A convertible type that owns a stack allocation of `N` size together with a tag of `Tag`.
+///
+/// The tag is a plain value stored next to the contained value, e.g. a kind or a priority,
+/// so that it does not have to be kept in a parallel array.
+///
+/// [`StackAny`] has no padding to spare, so the tag adds its size rounded up to [`StackAny::ALIGN`].
+///
+/// ```
+/// use std::mem::size_of;
+///
+/// let size = size_of::<stack_any::StackAny<8>>() + stack_any::StackAny::<8>::ALIGN;
+/// assert_eq!(size_of::<stack_any::TaggedStackAny<8>>(), size);
+/// assert_eq!(size_of::<stack_any::TaggedStackAny<8, u8>>(), size);
+/// assert_eq!(size_of::<stack_any::TaggedStackAny<8, ()>>(), size_of::<stack_any::StackAny<8>>());
+/// ```
+///
+/// # Examples
+///
+/// ```
+/// use stack_any::TaggedStackAny;
+///
+/// let mut ops = [
+///     TaggedStackAny::<24>::new(2, 'x'),
+///     TaggedStackAny::<24>::new(0, 5i32),
+///     TaggedStackAny::<24>::new(1, String::from("y")),
+///     TaggedStackAny::<24>::new(0, 7i32),
+/// ];
+///
+/// ops.sort_by_key(|op| *op.tag());
+/// let tags: Vec<u32> = ops.iter().map(|op| *op.tag()).collect();
+/// assert_eq!(tags, [0, 0, 1, 2]);
+///
+/// let sum: i32 = ops
+///     .iter()
+///     .filter(|op| *op.tag() == 0)
+///     .filter_map(|op| op.downcast_ref::<i32>())
+///     .sum();
+/// assert_eq!(sum, 12);
+/// ```
+pub struct TaggedStackAny<const N: usize, Tag = u32> {
+    stack: StackAny<N>,
+    tag: Tag,
+}
+
+impl<const N: usize, Tag> TaggedStackAny<N, Tag> {
+    /// Allocates N-size memory on the stack and then places `value` into it, tagged by `tag`.
+    ///
+    /// Whether `T` fits is checked at compile time, as in [`StackAny::new`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let five = stack_any::TaggedStackAny::<4, u8>::new(1, 5i32);
+    /// assert_eq!(five.tag(), &1);
+    /// assert_eq!(five.downcast_ref::<i32>(), Some(&5));
+    /// ```
+    ///
+    /// ```compile_fail
+    /// let five = stack_any::TaggedStackAny::<4>::new(1, 5u64);
+    /// ```
+    pub fn new<T>(tag: Tag, value: T) -> Self
+    where
+        T: core::any::Any,
+    {
+        Self::from_stack_any(tag, StackAny::new(value))
+    }
+
+    /// Allocates N-size memory on the stack and then places `value` into it, tagged by `tag`.
+    /// Returns None if `T` size is larger than N or `T` alignment is larger than [`StackAny::ALIGN`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let five = stack_any::TaggedStackAny::<{ std::mem::size_of::<i32>() }>::try_new(1, 5);
+    /// assert!(five.is_some());
+    /// ```
+    pub fn try_new<T>(tag: Tag, value: T) -> Option<Self>
+    where
+        T: core::any::Any,
+    {
+        StackAny::try_new(value).map(|stack| Self::from_stack_any(tag, stack))
+    }
+
+    /// Allocates N-size memory on the stack and then places `value` into it, tagged by `tag`.
+    /// Returns [`CapacityError`] carrying `value` back if `T` size is larger than N
+    /// or `T` alignment is larger than [`StackAny::ALIGN`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let err = stack_any::TaggedStackAny::<4>::try_new_checked(1, 5i64).unwrap_err();
+    /// assert_eq!(err.into_value(), 5);
+    /// ```
+    pub fn try_new_checked<T>(tag: Tag, value: T) -> Result<Self, CapacityError<T>>
+    where
+        T: core::any::Any,
+    {
+        StackAny::try_new_checked(value).map(|stack| Self::from_stack_any(tag, stack))
+    }
+
+    /// Tags the stack by `tag`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let five = stack_any::StackAny::<4>::builder(5i32).with_clone().build();
+    /// let five = stack_any::TaggedStackAny::from_stack_any(1u8, five);
+    /// assert!(five.as_stack_any().is_cloneable());
+    /// ```
+    pub fn from_stack_any(tag: Tag, stack: StackAny<N>) -> Self {
+        Self { stack, tag }
+    }
+
+    /// Returns reference to the tag.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let five = stack_any::TaggedStackAny::<4>::new(1, 5i32);
+    /// assert_eq!(five.tag(), &1);
+    /// ```
+    pub fn tag(&self) -> &Tag {
+        &self.tag
+    }
+
+    /// Replaces the tag with `tag`, returning the old tag.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut five = stack_any::TaggedStackAny::<4>::new(1, 5i32);
+    /// assert_eq!(five.set_tag(2), 1);
+    /// assert_eq!(five.tag(), &2);
+    /// ```
+    pub fn set_tag(&mut self, tag: Tag) -> Tag {
+        core::mem::replace(&mut self.tag, tag)
+    }
+
+    /// Returns true if the contained value type is equal to `T`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let five = stack_any::TaggedStackAny::<4>::new(1, 5i32);
+    /// assert!(five.is::<i32>());
+    /// assert!(!five.is::<i64>());
+    /// ```
+    pub fn is<T>(&self) -> bool
+    where
+        T: core::any::Any,
+    {
+        self.stack.is::<T>()
+    }
+
+    /// Attempt to return reference to the inner value as a concrete type.
+    /// Returns None if `T` is not equal to contained value type.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let five = stack_any::TaggedStackAny::<4>::new(1, 5i32);
+    /// assert_eq!(five.downcast_ref::<i32>(), Some(&5));
+    /// assert_eq!(five.downcast_ref::<i64>(), None);
+    /// ```
+    pub fn downcast_ref<T>(&self) -> Option<&T>
+    where
+        T: core::any::Any,
+    {
+        self.stack.downcast_ref()
+    }
+
+    /// Attempt to return mutable reference to the inner value as a concrete type.
+    /// Returns None if `T` is not equal to contained value type.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut five = stack_any::TaggedStackAny::<4>::new(1, 5i32);
+    /// assert_eq!(five.downcast_mut::<i32>(), Some(&mut 5));
+    /// assert_eq!(five.downcast_mut::<i64>(), None);
+    /// ```
+    pub fn downcast_mut<T>(&mut self) -> Option<&mut T>
+    where
+        T: core::any::Any,
+    {
+        self.stack.downcast_mut()
+    }
+
+    /// Attempt to downcast to a concrete type, dropping the tag.
+    /// Returns the stack back if `T` is not equal to contained value type.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let five = stack_any::TaggedStackAny::<4>::new(1, 5i32);
+    /// let five = five.downcast::<char>().unwrap_err();
+    /// assert_eq!(five.downcast::<i32>().ok(), Some(5));
+    /// ```
+    pub fn downcast<T>(self) -> Result<T, Self>
+    where
+        T: core::any::Any,
+    {
+        let tag = self.tag;
+        self.stack
+            .downcast()
+            .map_err(|stack| Self::from_stack_any(tag, stack))
+    }
+
+    /// Returns reference to the untagged stack, which provides the rest of the methods.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let five = stack_any::TaggedStackAny::<4>::new(1, 5i32);
+    /// assert_eq!(five.as_stack_any().value_size(), 4);
+    /// ```
+    pub fn as_stack_any(&self) -> &StackAny<N> {
+        &self.stack
+    }
+
+    /// Returns mutable reference to the untagged stack, which provides the rest of the methods.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut five = stack_any::TaggedStackAny::<4>::new(1, 5i32);
+    /// assert_eq!(five.as_stack_any_mut().take::<i32>(), Some(5));
+    /// assert!(five.as_stack_any().is_empty());
+    /// assert_eq!(five.tag(), &1);
+    /// ```
+    pub fn as_stack_any_mut(&mut self) -> &mut StackAny<N> {
+        &mut self.stack
+    }
+
+    /// Splits into the tag and the untagged stack.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let (tag, five) = stack_any::TaggedStackAny::<4>::new(1, 5i32).into_parts();
+    /// assert_eq!(tag, 1);
+    /// assert_eq!(five.downcast_ref::<i32>(), Some(&5));
+    /// ```
+    pub fn into_parts(self) -> (Tag, StackAny<N>) {
+        (self.tag, self.stack)
+    }
+}
+
+impl<const N: usize, Tag> core::fmt::Debug for TaggedStackAny<N, Tag>
+where
+    Tag: core::fmt::Debug,
+{
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("TaggedStackAny")
+            .field("tag", &self.tag)
+            .field("stack", &self.stack)
+            .finish()
+    }
+}
@@ -0,0 +1,246 @@
+use crate::{any_ptr, value_ptr, Buffer, CapacityError, RawParts, StackAny, VTable};
+
+/// A type that owns a stack allocation of `N` size without keeping the `TypeId` of its value.
+///
+/// It only holds the bytes and the [`VTable`] needed to drop the value, so it is smaller than
+/// [`StackAny`], while downcasts are `unsafe` and the caller must know the contained value type.
+///
+/// On 64-bit targets, `UncheckedStackAny<8>` is half the size of `StackAny<8>`.
+///
+/// ```
+/// # #[cfg(target_pointer_width = "64")]
+/// const _: () = {
+///     assert!(std::mem::size_of::<stack_any::UncheckedStackAny<8>>() == 16);
+///     assert!(std::mem::size_of::<stack_any::StackAny<8>>() == 32);
+/// };
+/// ```
+///
+/// # Examples
+///
+/// ```
+/// let events = [
+///     stack_any::UncheckedStackAny::<24>::new(String::from("open")),
+///     stack_any::UncheckedStackAny::<24>::new(String::from("close")),
+/// ];
+///
+/// let names: Vec<String> = events
+///     .into_iter()
+///     .map(|event| unsafe { event.downcast::<String>() })
+///     .collect();
+/// assert_eq!(names, ["open", "close"]);
+/// ```
+pub struct UncheckedStackAny<const N: usize> {
+    bytes: Buffer<N>,
+    vtable: &'static VTable,
+    _marker: core::marker::PhantomData<*mut ()>,
+}
+
+impl<const N: usize> UncheckedStackAny<N> {
+    /// Allocates N-size memory on the stack and then places `value` into it.
+    ///
+    /// Whether `T` fits is checked at compile time, as in [`StackAny::new`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let five = stack_any::UncheckedStackAny::<4>::new(5i32);
+    /// assert_eq!(unsafe { five.downcast_ref::<i32>() }, &5);
+    /// ```
+    ///
+    /// ```compile_fail
+    /// let five = stack_any::UncheckedStackAny::<4>::new(5u64);
+    /// ```
+    pub fn new<T>(value: T) -> Self
+    where
+        T: core::any::Any,
+    {
+        Self::from(StackAny::new(value))
+    }
+
+    /// Allocates N-size memory on the stack and then places `value` into it.
+    /// Returns None if `T` size is larger than N or `T` alignment is larger than [`StackAny::ALIGN`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let five = stack_any::UncheckedStackAny::<{ std::mem::size_of::<i32>() }>::try_new(5);
+    /// ```
+    pub fn try_new<T>(value: T) -> Option<Self>
+    where
+        T: core::any::Any,
+    {
+        StackAny::try_new(value).map(Self::from)
+    }
+
+    /// Allocates N-size memory on the stack and then places `value` into it.
+    /// Returns [`CapacityError`] carrying `value` back if `T` size is larger than N
+    /// or `T` alignment is larger than [`StackAny::ALIGN`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let err = stack_any::UncheckedStackAny::<4>::try_new_checked(5i64).unwrap_err();
+    /// assert_eq!(err.into_value(), 5);
+    /// ```
+    pub fn try_new_checked<T>(value: T) -> Result<Self, CapacityError<T>>
+    where
+        T: core::any::Any,
+    {
+        StackAny::try_new_checked(value).map(Self::from)
+    }
+
+    /// Returns reference to the inner value as a concrete type.
+    ///
+    /// # Safety
+    ///
+    /// The contained value type must be `T`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let five = stack_any::UncheckedStackAny::<4>::new(5i32);
+    /// assert_eq!(unsafe { five.downcast_ref::<i32>() }, &5);
+    /// ```
+    pub unsafe fn downcast_ref<T>(&self) -> &T
+    where
+        T: core::any::Any,
+    {
+        let ptr = value_ptr::<T>(self.bytes.0.as_ptr() as *mut _);
+        unsafe { &*ptr }
+    }
+
+    /// Returns mutable reference to the inner value as a concrete type.
+    ///
+    /// # Safety
+    ///
+    /// The contained value type must be `T`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut five = stack_any::UncheckedStackAny::<4>::new(5i32);
+    /// unsafe { *five.downcast_mut::<i32>() = 7 };
+    /// assert_eq!(unsafe { five.downcast_ref::<i32>() }, &7);
+    /// ```
+    pub unsafe fn downcast_mut<T>(&mut self) -> &mut T
+    where
+        T: core::any::Any,
+    {
+        let ptr = value_ptr::<T>(self.bytes.0.as_mut_ptr());
+        unsafe { &mut *ptr }
+    }
+
+    /// Moves the inner value out as a concrete type.
+    ///
+    /// # Safety
+    ///
+    /// The contained value type must be `T`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let strings = stack_any::UncheckedStackAny::<24>::new(vec![String::from("x")]);
+    /// assert_eq!(unsafe { strings.downcast::<Vec<String>>() }, ["x"]);
+    /// ```
+    pub unsafe fn downcast<T>(self) -> T
+    where
+        T: core::any::Any,
+    {
+        let mut this = core::mem::ManuallyDrop::new(self);
+
+        let ptr = value_ptr::<T>(this.bytes.0.as_mut_ptr());
+        let value = unsafe { core::ptr::read(ptr) };
+
+        this.scrub();
+        value
+    }
+
+    /// Converts into the checked [`StackAny`] by supplying the `TypeId` of the contained value.
+    ///
+    /// # Safety
+    ///
+    /// `type_id` must be the `TypeId` of the contained value type.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let five = stack_any::UncheckedStackAny::<4>::new(5i32);
+    ///
+    /// let five = unsafe { five.into_stack_any(std::any::TypeId::of::<i32>()) };
+    /// assert_eq!(five.downcast_ref::<i32>(), Some(&5));
+    /// ```
+    pub unsafe fn into_stack_any(self, type_id: core::any::TypeId) -> StackAny<N> {
+        let this = core::mem::ManuallyDrop::new(self);
+
+        let parts = RawParts {
+            type_id,
+            bytes: this.bytes.0,
+            vtable: this.vtable,
+        };
+        unsafe { StackAny::from_raw_parts(parts) }
+    }
+
+    /// Overwrites the bytes of the value, which was dropped or moved out, with zeros
+    /// if the `zeroize` feature is enabled.
+    fn scrub(&mut self) {
+        #[cfg(feature = "zeroize")]
+        zeroize::Zeroize::zeroize(&mut self.bytes.0[..self.vtable.size]);
+    }
+}
+
+/// Forgets the `TypeId` of the contained value, which is free.
+///
+/// # Examples
+///
+/// ```
+/// let five = stack_any::stack_any!(i32, 5);
+///
+/// let five = stack_any::UncheckedStackAny::from(five);
+/// assert_eq!(unsafe { five.downcast_ref::<i32>() }, &5);
+/// ```
+impl<const N: usize> From<StackAny<N>> for UncheckedStackAny<N> {
+    fn from(value: StackAny<N>) -> Self {
+        let parts = value.into_raw_parts();
+        Self {
+            bytes: Buffer(parts.bytes),
+            vtable: parts.vtable,
+            _marker: core::marker::PhantomData,
+        }
+    }
+}
+
+impl<const N: usize> core::fmt::Debug for UncheckedStackAny<N> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("UncheckedStackAny")
+            .field("vtable", self.vtable)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Drops the contained value.
+///
+/// # Examples
+///
+/// ```
+/// use std::rc::Rc;
+///
+/// let counter = Rc::new(());
+/// let stack = stack_any::UncheckedStackAny::<8>::new(counter.clone());
+/// assert_eq!(Rc::strong_count(&counter), 2);
+///
+/// drop(stack);
+/// assert_eq!(Rc::strong_count(&counter), 1);
+/// ```
+impl<const N: usize> Drop for UncheckedStackAny<N> {
+    fn drop(&mut self) {
+        if self.vtable.needs_drop {
+            let ptr = any_ptr(
+                self.vtable.meta,
+                self.vtable.size,
+                self.bytes.0.as_mut_ptr(),
+            );
+            unsafe { core::ptr::drop_in_place(ptr) };
+        }
+        self.scrub();
+    }
+}
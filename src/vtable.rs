@@ -0,0 +1,280 @@
+use crate::value_ptr;
+
+/// A function that clones the value placed at the first pointer into the second pointer.
+type CloneFn = unsafe fn(*const core::mem::MaybeUninit<u8>, *mut core::mem::MaybeUninit<u8>);
+
+/// A function that compares the values placed at both pointers for equality.
+type EqFn = unsafe fn(*const core::mem::MaybeUninit<u8>, *const core::mem::MaybeUninit<u8>) -> bool;
+
+/// A function that compares the values placed at both pointers.
+type CmpFn = unsafe fn(
+    *const core::mem::MaybeUninit<u8>,
+    *const core::mem::MaybeUninit<u8>,
+) -> core::cmp::Ordering;
+
+/// A function that feeds the value placed at the pointer into the hasher.
+type HashFn = unsafe fn(*const core::mem::MaybeUninit<u8>, &mut dyn core::hash::Hasher);
+
+/// A function that formats the value placed at the pointer.
+type FmtFn = unsafe fn(
+    *const core::mem::MaybeUninit<u8>,
+    &mut core::fmt::Formatter<'_>,
+) -> core::fmt::Result;
+
+/// A function that returns the name and the pointer of the value placed at the pointer for serialization.
+#[cfg(feature = "serde")]
+type SerializeFn = unsafe fn(
+    *const core::mem::MaybeUninit<u8>,
+) -> (&'static str, *const dyn erased_serde::Serialize);
+
+/// The metadata and the functions of a value type, shared by every stack containing a value of the type.
+///
+/// A [`StackAny`](crate::StackAny) refers to a single `&'static VTable`, so capturing more
+/// capabilities of the type does not make the stack larger.
+pub struct VTable {
+    pub(crate) size: usize,
+    pub(crate) align: usize,
+    #[cfg(feature = "type-name")]
+    pub(crate) type_name: fn() -> &'static str,
+    pub(crate) meta: *mut dyn core::any::Any,
+    pub(crate) needs_drop: bool,
+    pub(crate) clone_fn: Option<CloneFn>,
+    pub(crate) eq_fn: Option<EqFn>,
+    pub(crate) cmp_fn: Option<CmpFn>,
+    pub(crate) hash_fn: Option<HashFn>,
+    pub(crate) debug_fn: Option<FmtFn>,
+    pub(crate) display_fn: Option<FmtFn>,
+    #[cfg(feature = "serde")]
+    pub(crate) serialize_fn: Option<SerializeFn>,
+}
+
+// SAFETY: `meta` is a dangling pointer, only its metadata is used.
+unsafe impl Sync for VTable {}
+
+// SAFETY: `meta` is a dangling pointer, only its metadata is used.
+unsafe impl Send for VTable {}
+
+impl VTable {
+    /// Creates the vtable of `T` without any capability.
+    pub(crate) const fn new<T>() -> Self
+    where
+        T: core::any::Any,
+    {
+        Self {
+            size: core::mem::size_of::<T>(),
+            align: core::mem::align_of::<T>(),
+            #[cfg(feature = "type-name")]
+            type_name: core::any::type_name::<T>,
+            meta: core::ptr::NonNull::<T>::dangling().as_ptr(),
+            needs_drop: core::mem::needs_drop::<T>(),
+            clone_fn: None,
+            eq_fn: None,
+            cmp_fn: None,
+            hash_fn: None,
+            debug_fn: None,
+            display_fn: None,
+            #[cfg(feature = "serde")]
+            serialize_fn: None,
+        }
+    }
+
+    /// Returns the size of the value type in bytes.
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// Returns the alignment of the value type in bytes.
+    pub fn align(&self) -> usize {
+        self.align
+    }
+
+    /// Returns true if dropping a value of the type runs any code.
+    ///
+    /// Otherwise dropping the stack skips the call to the destructor altogether.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::rc::Rc;
+    ///
+    /// let parts = stack_any::stack_any!(u64, 5).into_raw_parts();
+    /// assert!(!parts.vtable.needs_drop());
+    ///
+    /// let counter = Rc::new(());
+    /// let stack = stack_any::stack_any!(Rc<()>, counter.clone());
+    /// assert_eq!(Rc::strong_count(&counter), 2);
+    ///
+    /// drop(stack);
+    /// assert_eq!(Rc::strong_count(&counter), 1);
+    /// ```
+    pub fn needs_drop(&self) -> bool {
+        self.needs_drop
+    }
+
+    /// Returns the name of the value type.
+    #[cfg(feature = "type-name")]
+    pub fn type_name(&self) -> &'static str {
+        (self.type_name)()
+    }
+}
+
+impl core::fmt::Debug for VTable {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let mut debug = f.debug_struct("VTable");
+        #[cfg(feature = "type-name")]
+        debug.field("type", &self.type_name());
+        debug
+            .field("size", &self.size)
+            .field("align", &self.align)
+            .finish_non_exhaustive()
+    }
+}
+
+/// A set of capabilities of `T` that produces the vtable of `T`.
+#[doc(hidden)]
+pub trait Capture<T> {
+    /// The vtable of `T` with the capabilities.
+    const VTABLE: VTable;
+
+    /// The reference to the vtable, which lives as long as the program.
+    const REF: &'static VTable = &Self::VTABLE;
+}
+
+macro_rules! impl_capture {
+    ($(#[$attr:meta])* $name:ident: $bound:path => $field:ident = $glue:expr) => {
+        #[doc(hidden)]
+        $(#[$attr])*
+        pub struct $name<V>(core::marker::PhantomData<V>);
+
+        $(#[$attr])*
+        impl<T, V> Capture<T> for $name<V>
+        where
+            T: core::any::Any + $bound,
+            V: Capture<T>,
+        {
+            const VTABLE: VTable = VTable {
+                $field: Some($glue),
+                ..V::VTABLE
+            };
+        }
+    };
+}
+
+/// No capability.
+#[doc(hidden)]
+pub struct Plain;
+
+impl<T> Capture<T> for Plain
+where
+    T: core::any::Any,
+{
+    const VTABLE: VTable = VTable::new::<T>();
+}
+
+impl_capture!(WithClone: Clone => clone_fn = |src, dst| unsafe {
+    let value = (*value_ptr::<T>(src as *mut _)).clone();
+    core::ptr::write(value_ptr::<T>(dst), value);
+});
+
+impl_capture!(WithEq: PartialEq => eq_fn = |lhs, rhs| unsafe {
+    *value_ptr::<T>(lhs as *mut _) == *value_ptr::<T>(rhs as *mut _)
+});
+
+impl_capture!(WithOrd: Ord => cmp_fn = |lhs, rhs| unsafe {
+    (*value_ptr::<T>(lhs as *mut _)).cmp(&*value_ptr::<T>(rhs as *mut _))
+});
+
+impl_capture!(WithHash: core::hash::Hash => hash_fn = |ptr, mut state| unsafe {
+    (*value_ptr::<T>(ptr as *mut _)).hash(&mut state)
+});
+
+impl_capture!(WithDebug: core::fmt::Debug => debug_fn = |ptr, f| unsafe {
+    core::fmt::Debug::fmt(&*value_ptr::<T>(ptr as *mut _), f)
+});
+
+impl_capture!(WithDisplay: core::fmt::Display => display_fn = |ptr, f| unsafe {
+    core::fmt::Display::fmt(&*value_ptr::<T>(ptr as *mut _), f)
+});
+
+impl_capture!(#[cfg(feature = "serde")] WithSerialize: serde::Serialize => serialize_fn = |ptr| {
+    let value = value_ptr::<T>(ptr as *mut _) as *const dyn erased_serde::Serialize;
+    (core::any::type_name::<T>(), value)
+});
+
+/// A vtable interned by [`VTable::of_boxed`].
+#[cfg(feature = "alloc")]
+struct Interned {
+    type_id: core::any::TypeId,
+    vtable: VTable,
+    next: *mut Interned,
+}
+
+/// The list of the interned vtables, one per type, which are leaked and never removed.
+#[cfg(feature = "alloc")]
+static INTERNED: core::sync::atomic::AtomicPtr<Interned> =
+    core::sync::atomic::AtomicPtr::new(core::ptr::null_mut());
+
+#[cfg(feature = "alloc")]
+impl VTable {
+    /// Returns the vtable of the type of the boxed value without any capability.
+    ///
+    /// The type is only known at runtime, so the vtable is allocated once per type and then reused.
+    pub(crate) fn of_boxed(value: &dyn core::any::Any) -> &'static VTable {
+        use core::sync::atomic::Ordering;
+
+        let type_id = value.type_id();
+        let mut head = INTERNED.load(Ordering::Acquire);
+
+        if let Some(vtable) = Self::find_interned(type_id, head, core::ptr::null_mut()) {
+            return vtable;
+        }
+
+        let layout = core::alloc::Layout::for_value(value);
+        let ptr = value as *const dyn core::any::Any as *mut dyn core::any::Any;
+        let dangling = core::ptr::without_provenance_mut(layout.align());
+        let interned = alloc::boxed::Box::into_raw(alloc::boxed::Box::new(Interned {
+            type_id,
+            vtable: VTable {
+                size: layout.size(),
+                align: layout.align(),
+                #[cfg(feature = "type-name")]
+                type_name: core::any::type_name::<dyn core::any::Any>,
+                meta: crate::any_ptr(ptr, 1, dangling),
+                needs_drop: true,
+                ..VTable::new::<()>()
+            },
+            next: head,
+        }));
+
+        // A racing thread may have interned the same type meanwhile, so only the nodes pushed since
+        // are searched again, and then the allocation is freed, keeping a single vtable per type.
+        while let Err(current) =
+            INTERNED.compare_exchange_weak(head, interned, Ordering::AcqRel, Ordering::Acquire)
+        {
+            if let Some(vtable) = Self::find_interned(type_id, current, head) {
+                drop(unsafe { alloc::boxed::Box::from_raw(interned) });
+                return vtable;
+            }
+            head = current;
+            unsafe { (*interned).next = head };
+        }
+
+        unsafe { &(*interned).vtable }
+    }
+
+    /// Returns the interned vtable of `type_id` among the nodes from `node` until `end`.
+    fn find_interned(
+        type_id: core::any::TypeId,
+        mut node: *mut Interned,
+        end: *mut Interned,
+    ) -> Option<&'static VTable> {
+        while node != end {
+            let interned = unsafe { &*node };
+            if interned.type_id == type_id {
+                return Some(&interned.vtable);
+            }
+            node = interned.next;
+        }
+        None
+    }
+}
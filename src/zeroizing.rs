@@ -0,0 +1,69 @@
+use crate::StackAny;
+
+/// A [`StackAny`] that implements `Zeroize` and `ZeroizeOnDrop`.
+///
+/// With the `zeroize` feature, every [`StackAny`] overwrites the bytes with zeros
+/// once the contained value is dropped or moved out. This wrapper makes the guarantee visible
+/// to the traits of the `zeroize` crate, and wipes the whole allocation on `zeroize`.
+///
+/// # Examples
+///
+/// ```
+/// let mut key = stack_any::ZeroizingStackAny::new(stack_any::stack_any!([u8; 32], [0xa5; 32]));
+/// assert_eq!(key.downcast_ref::<[u8; 32]>(), Some(&[0xa5; 32]));
+///
+/// zeroize::Zeroize::zeroize(&mut key);
+/// assert!(key.is_empty());
+///
+/// let bytes = key.into_inner().into_inner_bytes();
+/// assert!(bytes.iter().all(|byte| unsafe { byte.assume_init() } == 0));
+/// ```
+pub struct ZeroizingStackAny<const N: usize>(StackAny<N>);
+
+impl<const N: usize> ZeroizingStackAny<N> {
+    /// Wraps the stack.
+    pub fn new(stack: StackAny<N>) -> Self {
+        Self(stack)
+    }
+
+    /// Returns the wrapped stack.
+    pub fn into_inner(self) -> StackAny<N> {
+        self.0
+    }
+}
+
+impl<const N: usize> core::fmt::Debug for ZeroizingStackAny<N> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_tuple("ZeroizingStackAny").field(&self.0).finish()
+    }
+}
+
+impl<const N: usize> core::ops::Deref for ZeroizingStackAny<N> {
+    type Target = StackAny<N>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<const N: usize> core::ops::DerefMut for ZeroizingStackAny<N> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+impl<const N: usize> From<StackAny<N>> for ZeroizingStackAny<N> {
+    fn from(value: StackAny<N>) -> Self {
+        Self::new(value)
+    }
+}
+
+/// Drops the contained value, and then overwrites the whole allocation with zeros.
+impl<const N: usize> zeroize::Zeroize for ZeroizingStackAny<N> {
+    fn zeroize(&mut self) {
+        self.0.clear();
+        zeroize::Zeroize::zeroize(&mut self.0.bytes.0[..]);
+    }
+}
+
+impl<const N: usize> zeroize::ZeroizeOnDrop for ZeroizingStackAny<N> {}
@@ -0,0 +1,34 @@
+//! Exercises the library as a `no_std` crate, e.g. with `cargo test --no-default-features --test no_std`.
+
+#![no_std]
+
+use stack_any::StackAny;
+
+#[derive(Debug, PartialEq)]
+struct Point {
+    x: i32,
+    y: i32,
+}
+
+#[test]
+fn try_new_downcast() {
+    let stack = StackAny::<8>::try_new(Point { x: 2, y: 3 }).unwrap();
+    assert!(stack.is::<Point>());
+    assert_eq!(stack.downcast_ref::<Point>(), Some(&Point { x: 2, y: 3 }));
+
+    let stack = stack.downcast::<u64>().unwrap_err();
+    assert_eq!(stack.downcast::<Point>().ok(), Some(Point { x: 2, y: 3 }));
+}
+
+#[test]
+fn try_new_too_large() {
+    assert!(StackAny::<4>::try_new(5u64).is_none());
+    assert!(StackAny::<8>::try_new([0u8; 9]).is_none());
+}
+
+#[test]
+fn macro_downcast_mut() {
+    let mut stack = stack_any::stack_any!(u32, 5);
+    *stack.downcast_mut::<u32>().unwrap() += 1;
+    assert_eq!(stack.downcast::<u32>().ok(), Some(6));
+}